@@ -16,6 +16,7 @@
 pub(crate) trait ByteOperations {
     fn read_a_byte(&mut self) -> Option<u8>;
     fn read_bytes(&mut self, len: usize) -> Vec<u8>;
+    fn peek_bytes(&self, len: usize) -> Vec<u8>;
     fn write_a_byte(&mut self, byte: u8);
     fn write_bytes(&mut self, bytes: &[u8]);
     fn bytes_len(&self) -> usize;
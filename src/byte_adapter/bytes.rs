@@ -0,0 +1,76 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::byte_adapter::byte_operations::ByteOperations;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+/// Reading splits off the front of the shared buffer without copying it, so a clone taken
+/// before decoding still holds every byte. Writing copies the buffer into a new one.
+impl ByteOperations for Bytes {
+    fn read_a_byte(&mut self) -> Option<u8> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.get_u8())
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Vec<u8> {
+        let take_size = self.available_size(len);
+        self.split_to(take_size).to_vec()
+    }
+
+    fn peek_bytes(&self, len: usize) -> Vec<u8> {
+        self[..len.min(self.len())].to_vec()
+    }
+
+    fn write_a_byte(&mut self, byte: u8) {
+        let mut bytes = BytesMut::from(&self[..]);
+        bytes.put_u8(byte);
+        *self = bytes.freeze();
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        let mut written = BytesMut::from(&self[..]);
+        written.extend_from_slice(bytes);
+        *self = written.freeze();
+    }
+
+    fn bytes_len(&self) -> usize {
+        self.len()
+    }
+}
+
+#[cfg(test)]
+mod bytes_ops_tests {
+    use crate::byte_adapter::byte_operations::ByteOperations;
+    use bytes::Bytes;
+
+    #[test]
+    fn bytes_should_read_without_touching_a_clone() {
+        let frame = Bytes::from_static(&[0x01, 0x02, 0x03]);
+        let mut bytes = frame.clone();
+        assert_eq!(bytes.read_a_byte(), Some(0x01));
+        assert_eq!(bytes.peek_bytes(5), vec![0x02, 0x03]);
+        assert_eq!(bytes.read_bytes(5), vec![0x02, 0x03]);
+        assert_eq!(bytes.read_a_byte(), None);
+        assert_eq!(frame.bytes_len(), 3);
+    }
+
+    #[test]
+    fn bytes_should_append_written_bytes() {
+        let mut bytes = Bytes::from_static(&[0x01]);
+        bytes.write_a_byte(0x02);
+        bytes.write_bytes(&[0x03, 0x04]);
+        assert_eq!(bytes.read_bytes(4), vec![0x01, 0x02, 0x03, 0x04]);
+    }
+}
@@ -28,6 +28,10 @@ impl ByteOperations for BytesMut {
         self.split_to(take_size).to_vec()
     }
 
+    fn peek_bytes(&self, len: usize) -> Vec<u8> {
+        self[..len.min(self.len())].to_vec()
+    }
+
     fn write_a_byte(&mut self, byte: u8) {
         self.put_u8(byte);
     }
@@ -69,6 +73,15 @@ mod bytes_mut_ops_tests {
         assert_eq!(byte, Some(0xCD));
     }
 
+    #[test]
+    fn bytes_mut_can_peek_bytes_without_consuming_them() {
+        let mut bytes_mut = BytesMut::new();
+        bytes_mut.write_bytes(&[0x01, 0x02, 0x03]);
+        assert_eq!(bytes_mut.peek_bytes(2), vec![0x01, 0x02]);
+        assert_eq!(bytes_mut.peek_bytes(5), vec![0x01, 0x02, 0x03]);
+        assert_eq!(bytes_mut.bytes_len(), 3);
+    }
+
     #[test]
     fn bytes_must_can_read_bytes() {
         let mut bytes_mut = BytesMut::new();
@@ -108,7 +121,7 @@ mod bytes_mut_ops_tests {
         let mut bytes_mut = BytesMut::new();
         let bytes = bytes_mut.read_bytes(5);
         assert!(bytes.is_empty());
-        assert_eq!(bytes, vec![]);
+        assert_eq!(bytes, Vec::<u8>::new());
     }
 
     #[test]
@@ -13,4 +13,5 @@
 // limitations under the License.
 
 pub(crate) mod byte_operations;
+mod bytes;
 mod bytes_mut;
@@ -0,0 +1,35 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[allow(dead_code)]
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum CaptureError {
+    #[error("from io::Error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Capture file format is not pcap or pcapng, magic number: {0:#010x}")]
+    UnknownFormat(u32),
+
+    #[error("Capture file is truncated at offset {0}")]
+    Truncated(usize),
+
+    #[error("pcap version {0}.{1} is not supported")]
+    UnsupportedVersion(u16, u16),
+
+    #[error("Invalid pcapng block at offset {0}: {1}")]
+    InvalidBlock(usize, &'static str),
+
+    #[error("Packet block references unknown interface {0}")]
+    UnknownInterface(u32),
+}
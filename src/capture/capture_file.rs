@@ -0,0 +1,153 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::capture::capture_error::CaptureError;
+use crate::capture::{pcap, pcapng};
+use std::time::Duration;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ByteOrder {
+    Little,
+    Big,
+}
+
+#[allow(dead_code)]
+impl ByteOrder {
+    pub(crate) fn u16(&self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        match self {
+            ByteOrder::Little => u16::from_le_bytes(bytes),
+            ByteOrder::Big => u16::from_be_bytes(bytes),
+        }
+    }
+
+    pub(crate) fn u32(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        match self {
+            ByteOrder::Little => u32::from_le_bytes(bytes),
+            ByteOrder::Big => u32::from_be_bytes(bytes),
+        }
+    }
+
+    pub(crate) fn u64(&self, bytes: &[u8]) -> u64 {
+        let mut buffer = [0u8; 8];
+        buffer.copy_from_slice(&bytes[..8]);
+        match self {
+            ByteOrder::Little => u64::from_le_bytes(buffer),
+            ByteOrder::Big => u64::from_be_bytes(buffer),
+        }
+    }
+}
+
+/// One link-layer frame as stored in the capture file, stamped with the time since the epoch.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CapturedFrame {
+    timestamp: Duration,
+    link_type: u32,
+    data: Vec<u8>,
+}
+
+#[allow(dead_code)]
+impl CapturedFrame {
+    pub fn new(timestamp: Duration, link_type: u32, data: Vec<u8>) -> Self {
+        CapturedFrame {
+            timestamp,
+            link_type,
+            data,
+        }
+    }
+
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    pub fn link_type(&self) -> u32 {
+        self.link_type
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Reads every frame of a pcap or pcapng file, picking the format from the magic number.
+#[allow(dead_code)]
+pub(crate) fn read_frames(data: &[u8]) -> Result<Vec<CapturedFrame>, CaptureError> {
+    let magic = slice(data, 0, 4)?;
+    let magic = ByteOrder::Big.u32(magic);
+    if pcap::is_pcap_magic(magic) {
+        return pcap::read_frames(data);
+    }
+    if magic == pcapng::SECTION_HEADER_BLOCK {
+        return pcapng::read_frames(data);
+    }
+    Err(CaptureError::UnknownFormat(magic))
+}
+
+/// Bounds checked sub-slice, reporting the offset where the file ran out.
+pub(crate) fn slice(data: &[u8], offset: usize, length: usize) -> Result<&[u8], CaptureError> {
+    offset
+        .checked_add(length)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(CaptureError::Truncated(offset))
+}
+
+/// Converts a timestamp counted in `units_per_second` ticks into a `Duration`.
+pub(crate) fn timestamp_from_units(units: u64, units_per_second: u64) -> Duration {
+    let seconds = units / units_per_second;
+    let nanos = (units % units_per_second) as u128 * 1_000_000_000 / units_per_second as u128;
+    Duration::new(seconds, nanos as u32)
+}
+
+#[cfg(test)]
+mod capture_file_tests {
+    use crate::capture::capture_error::CaptureError;
+    use crate::capture::capture_file::{self, ByteOrder};
+    use std::time::Duration;
+
+    #[test]
+    fn byte_order_should_read_both_endians() {
+        assert_eq!(ByteOrder::Little.u16(&[0x01, 0x02]), 0x0201);
+        assert_eq!(ByteOrder::Big.u32(&[0x01, 0x02, 0x03, 0x04]), 0x01020304);
+    }
+
+    #[test]
+    fn read_frames_should_reject_unknown_magic() {
+        let result = capture_file::read_frames(&[0xDE, 0xAD, 0xBE, 0xEF]);
+        assert!(matches!(
+            result,
+            Err(CaptureError::UnknownFormat(0xDEADBEEF))
+        ));
+    }
+
+    #[test]
+    fn read_frames_should_report_empty_file_as_truncated() {
+        let result = capture_file::read_frames(&[]);
+        assert!(matches!(result, Err(CaptureError::Truncated(0))));
+    }
+
+    #[test]
+    fn timestamp_from_units_should_keep_sub_second_precision() {
+        assert_eq!(
+            capture_file::timestamp_from_units(1_500_000, 1_000_000),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(
+            capture_file::timestamp_from_units(3, 2),
+            Duration::from_millis(1500)
+        );
+    }
+}
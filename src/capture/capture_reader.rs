@@ -0,0 +1,341 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::capture::capture_error::CaptureError;
+use crate::capture::capture_file;
+use crate::capture::mqtt_conversation::{DecodedStream, MqttConversation};
+use crate::capture::tcp_reassembly::TcpStreamReassembler;
+use crate::capture::tcp_segment::TcpSegment;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+
+pub(crate) const DEFAULT_MQTT_PORT: u16 = 1883;
+
+#[derive(Default)]
+struct Connection {
+    client_to_server: TcpStreamReassembler,
+    server_to_client: TcpStreamReassembler,
+    closed: bool,
+}
+
+#[allow(dead_code)]
+impl Connection {
+    fn is_used(&self) -> bool {
+        self.closed
+            || !self.client_to_server.data().is_empty()
+            || !self.server_to_client.data().is_empty()
+    }
+}
+
+/// Pulls the MQTT conversations out of a pcap or pcapng capture. Only TCP traffic to or from
+/// the broker port is kept, and nothing beyond the capture itself is needed.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub(crate) struct CaptureReader {
+    port: u16,
+}
+
+#[allow(dead_code)]
+impl Default for CaptureReader {
+    fn default() -> Self {
+        CaptureReader::new(DEFAULT_MQTT_PORT)
+    }
+}
+
+#[allow(dead_code)]
+impl CaptureReader {
+    pub fn new(port: u16) -> Self {
+        CaptureReader { port }
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn read_file(&self, path: impl AsRef<Path>) -> Result<Vec<MqttConversation>, CaptureError> {
+        let data = std::fs::read(path)?;
+        self.read_bytes(&data)
+    }
+
+    /// Conversations come back in the order their first segment was captured.
+    pub fn read_bytes(&self, data: &[u8]) -> Result<Vec<MqttConversation>, CaptureError> {
+        let frames = capture_file::read_frames(data)?;
+
+        let mut connections: Vec<((SocketAddr, SocketAddr), Connection)> = Vec::new();
+        let mut open_connections: HashMap<(SocketAddr, SocketAddr), usize> = HashMap::new();
+
+        for frame in frames {
+            let Some(segment) = TcpSegment::parse(frame.link_type(), frame.data()) else {
+                continue;
+            };
+            let (key, from_client) = if segment.destination().port() == self.port {
+                ((segment.source(), segment.destination()), true)
+            } else if segment.source().port() == self.port {
+                ((segment.destination(), segment.source()), false)
+            } else {
+                continue;
+            };
+
+            // a new SYN on a finished 4-tuple is a new connection reusing the client port
+            let is_client_syn = from_client && segment.is_syn() && !segment.is_ack();
+            let index = match open_connections.get(&key) {
+                Some(index) if !(is_client_syn && connections[*index].1.is_used()) => *index,
+                _ => {
+                    connections.push((key, Connection::default()));
+                    open_connections.insert(key, connections.len() - 1);
+                    connections.len() - 1
+                }
+            };
+
+            let connection = &mut connections[index].1;
+            let stream = if from_client {
+                &mut connection.client_to_server
+            } else {
+                &mut connection.server_to_client
+            };
+            if segment.is_syn() {
+                stream.push_syn(segment.sequence_number());
+            } else {
+                stream.push(
+                    frame.timestamp(),
+                    segment.sequence_number(),
+                    segment.payload(),
+                );
+            }
+            if segment.is_fin() || segment.is_rst() {
+                connection.closed = true;
+            }
+        }
+
+        Ok(connections
+            .into_iter()
+            .map(|((client, server), connection)| {
+                MqttConversation::new(
+                    client,
+                    server,
+                    DecodedStream::decode(&connection.client_to_server),
+                    DecodedStream::decode(&connection.server_to_client),
+                )
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod capture_reader_tests {
+    use crate::capture::capture_error::CaptureError;
+    use crate::capture::capture_reader::CaptureReader;
+    use crate::capture::mqtt_conversation::Direction;
+    use crate::capture::test_frames::{self, TCP_ACK, TCP_FIN, TCP_PSH, TCP_SYN};
+    use crate::protocol::codec::Encoder;
+    use crate::protocol::common::control_packet_type::ControlPacketType;
+    use crate::protocol::common::protocol_level::ProtocolLevel;
+    use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::common::return_code::ReturnCode;
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
+    use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+    use crate::protocol::mqtt4::payload_parser::connect_parser::payload::ConnectPayload;
+    use crate::protocol::mqtt4::variable_header_parser::conn_ack_parser::variable_header::ConnAckVariableHeader;
+    use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::{
+        ConnectFlags, ConnectVariableHeader,
+    };
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    fn client() -> SocketAddr {
+        "10.0.0.2:40000".parse().unwrap()
+    }
+
+    fn server() -> SocketAddr {
+        "10.0.0.1:1883".parse().unwrap()
+    }
+
+    fn connect_bytes() -> Vec<u8> {
+        let mut packet = Packet::Connect {
+            fixed: FixedHeader::new(ControlPacketType::Connect, FixedHeaderFlags::Connect),
+            variable: ConnectVariableHeader::new(
+                ProtocolLevel::Mqtt3_1_1,
+                ConnectFlags::new(false, false, false, QoSCode::Qos0, false, true).unwrap(),
+                30,
+            ),
+            payload: ConnectPayload::new("device42".to_string(), None, None, None, None),
+        };
+        packet.encode().unwrap()
+    }
+
+    fn conn_ack_bytes() -> Vec<u8> {
+        let mut packet = Packet::ConnAck {
+            fixed: FixedHeader::new(ControlPacketType::ConnAck, FixedHeaderFlags::ConnAck),
+            variable: ConnAckVariableHeader::new(false, ReturnCode::ConnectionAccepted),
+        };
+        packet.encode().unwrap()
+    }
+
+    fn segment(
+        seconds: u64,
+        from_client: bool,
+        sequence_number: u32,
+        flags: u8,
+        payload: &[u8],
+    ) -> (Duration, Vec<u8>) {
+        let (source, destination) = if from_client {
+            (client(), server())
+        } else {
+            (server(), client())
+        };
+        let frame =
+            test_frames::ethernet_tcp_frame(source, destination, sequence_number, flags, payload);
+        (Duration::from_secs(seconds), frame)
+    }
+
+    fn handshake_frames() -> Vec<(Duration, Vec<u8>)> {
+        let connect = connect_bytes();
+        let (first_half, second_half) = connect.split_at(5);
+        vec![
+            segment(1, true, 1000, TCP_SYN, &[]),
+            segment(1, false, 5000, TCP_SYN | TCP_ACK, &[]),
+            // the CONNECT arrives in two segments, the second one first
+            segment(2, true, 1001 + 5, TCP_PSH | TCP_ACK, second_half),
+            segment(3, true, 1001, TCP_PSH | TCP_ACK, first_half),
+            segment(4, false, 5001, TCP_PSH | TCP_ACK, &conn_ack_bytes()),
+            // retransmission of the CONNACK
+            segment(5, false, 5001, TCP_PSH | TCP_ACK, &conn_ack_bytes()),
+        ]
+    }
+
+    #[test]
+    fn capture_reader_should_decode_both_directions_of_a_pcap() {
+        let file = test_frames::pcap_file(1, &handshake_frames());
+        let conversations = CaptureReader::default().read_bytes(&file).unwrap();
+
+        assert_eq!(conversations.len(), 1);
+        let conversation = &conversations[0];
+        assert_eq!(conversation.client(), client());
+        assert_eq!(conversation.server(), server());
+
+        let to_server: Vec<_> = conversation
+            .stream(Direction::ClientToServer)
+            .packets()
+            .collect();
+        assert_eq!(to_server.len(), 1);
+        assert_eq!(to_server[0].timestamp(), Duration::from_secs(3));
+        let Packet::Connect { payload, .. } = to_server[0].packet() else {
+            panic!("Decoded packet is not of type Connect");
+        };
+        assert_eq!(payload.client_id(), "device42");
+
+        let to_client = conversation.stream(Direction::ServerToClient);
+        assert_eq!(to_client.packets().count(), 1);
+        assert!(matches!(
+            to_client.packets().next().unwrap().packet(),
+            Packet::ConnAck { .. }
+        ));
+        assert_eq!(to_client.undecoded_bytes(), 0);
+    }
+
+    #[test]
+    fn capture_reader_should_read_pcapng() {
+        let file = test_frames::pcapng_file(1, None, &handshake_frames());
+        let conversations = CaptureReader::default().read_bytes(&file).unwrap();
+        assert_eq!(
+            conversations[0]
+                .stream(Direction::ClientToServer)
+                .packets()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn capture_reader_should_only_follow_the_chosen_port() {
+        let file = test_frames::pcap_file(1, &handshake_frames());
+        assert!(
+            CaptureReader::new(8883)
+                .read_bytes(&file)
+                .unwrap()
+                .is_empty()
+        );
+
+        let other_server: SocketAddr = "10.0.0.1:8883".parse().unwrap();
+        let frame = test_frames::ethernet_tcp_frame(
+            client(),
+            other_server,
+            1,
+            TCP_PSH | TCP_ACK,
+            &[0b1100_0000, 0x00],
+        );
+        let file = test_frames::pcap_file(1, &[(Duration::ZERO, frame)]);
+        let conversations = CaptureReader::new(8883).read_bytes(&file).unwrap();
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0].server(), other_server);
+    }
+
+    #[test]
+    fn capture_reader_should_report_decode_error_with_stream_offset() {
+        let mut stream = vec![0b1100_0000, 0x00];
+        // a CONNECT with protocol level 9
+        let mut connect = connect_bytes();
+        connect[8] = 9;
+        stream.extend_from_slice(&connect);
+        let file = test_frames::pcap_file(1, &[segment(1, true, 7, TCP_PSH | TCP_ACK, &stream)]);
+
+        let conversations = CaptureReader::default().read_bytes(&file).unwrap();
+        let to_server = conversations[0].stream(Direction::ClientToServer);
+        let error = to_server.errors().next().unwrap();
+
+        assert_eq!(error.stream_offset(), 2);
+        assert_eq!(error.timestamp(), Duration::from_secs(1));
+        assert!(matches!(
+            error.error(),
+            MqttProtocolError::ProtocolLevelNoSupport(9)
+        ));
+    }
+
+    #[test]
+    fn capture_reader_should_split_reused_client_port_into_new_conversation() {
+        let mut frames = handshake_frames();
+        frames.push(segment(6, true, 1001 + 18, TCP_FIN | TCP_ACK, &[]));
+        frames.push(segment(7, true, 9000, TCP_SYN, &[]));
+        frames.push(segment(
+            7,
+            true,
+            9001,
+            TCP_PSH | TCP_ACK,
+            &[0b1100_0000, 0x00],
+        ));
+        let file = test_frames::pcap_file(1, &frames);
+
+        let conversations = CaptureReader::default().read_bytes(&file).unwrap();
+
+        assert_eq!(conversations.len(), 2);
+        assert!(matches!(
+            conversations[1]
+                .stream(Direction::ClientToServer)
+                .packets()
+                .next()
+                .unwrap()
+                .packet(),
+            Packet::PingReq { .. }
+        ));
+    }
+
+    #[test]
+    fn capture_reader_should_fail_on_missing_file() {
+        let result = CaptureReader::default().read_file("/nonexistent/capture.pcap");
+        assert!(matches!(result, Err(CaptureError::Io(_))));
+    }
+}
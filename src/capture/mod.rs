@@ -0,0 +1,24 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub(crate) mod capture_error;
+pub(crate) mod capture_file;
+pub(crate) mod capture_reader;
+pub(crate) mod mqtt_conversation;
+pub(crate) mod pcap;
+pub(crate) mod pcapng;
+pub(crate) mod tcp_reassembly;
+pub(crate) mod tcp_segment;
+#[cfg(test)]
+mod test_frames;
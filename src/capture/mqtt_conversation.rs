@@ -0,0 +1,272 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::capture::tcp_reassembly::TcpStreamReassembler;
+use crate::protocol::codec::Decoder;
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use bytes::BytesMut;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Direction {
+    ClientToServer,
+    ServerToClient,
+}
+
+/// A control packet decoded from a capture. `stream_offset` is where its first byte sits in
+/// the reassembled stream of its direction.
+#[allow(dead_code)]
+pub(crate) struct CapturedPacket {
+    timestamp: Duration,
+    stream_offset: u64,
+    packet: Packet,
+}
+
+#[allow(dead_code)]
+impl CapturedPacket {
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    pub fn stream_offset(&self) -> u64 {
+        self.stream_offset
+    }
+
+    pub fn packet(&self) -> &Packet {
+        &self.packet
+    }
+
+    pub fn into_packet(self) -> Packet {
+        self.packet
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, thiserror::Error)]
+#[error("MQTT decode error at stream offset {stream_offset}: {error}")]
+pub(crate) struct StreamDecodeError {
+    timestamp: Duration,
+    stream_offset: u64,
+    error: MqttProtocolError,
+}
+
+#[allow(dead_code)]
+impl StreamDecodeError {
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
+    pub fn stream_offset(&self) -> u64 {
+        self.stream_offset
+    }
+
+    pub fn error(&self) -> &MqttProtocolError {
+        &self.error
+    }
+}
+
+/// Everything decoded from one direction of a connection, in stream order.
+#[allow(dead_code)]
+#[derive(Default)]
+pub(crate) struct DecodedStream {
+    entries: Vec<Result<CapturedPacket, StreamDecodeError>>,
+    undecoded_bytes: usize,
+    has_gap: bool,
+}
+
+#[allow(dead_code)]
+impl DecodedStream {
+    /// Cuts the stream into packets with `Packet::frame_length` and decodes each one. A packet
+    /// that fails to decode is reported and skipped, since its length is still known; a broken
+    /// Remaining Length leaves no way to find the next packet, so decoding stops there.
+    pub fn decode(stream: &TcpStreamReassembler) -> DecodedStream {
+        let data = stream.data();
+        // every reassembled byte has an arrival time, so the default is never used
+        let arrival = |offset: usize| stream.timestamp_at(offset as u64).unwrap_or_default();
+        let mut entries = Vec::new();
+        let mut offset = 0;
+
+        while offset < data.len() {
+            let frame_length = match Packet::frame_length(&data[offset..]) {
+                Ok(Some(frame_length)) => frame_length,
+                Ok(None) => break,
+                Err(error) => {
+                    entries.push(Err(StreamDecodeError {
+                        timestamp: arrival(offset),
+                        stream_offset: offset as u64,
+                        error,
+                    }));
+                    offset = data.len();
+                    break;
+                }
+            };
+
+            // a packet counts as received once its last byte has arrived
+            let timestamp = arrival(offset + frame_length - 1);
+            let mut frame = BytesMut::from(&data[offset..offset + frame_length]);
+            let entry = match Packet::decode(&mut frame) {
+                Ok(packet) => Ok(CapturedPacket {
+                    timestamp,
+                    stream_offset: offset as u64,
+                    packet,
+                }),
+                Err(error) => Err(StreamDecodeError {
+                    timestamp,
+                    stream_offset: offset as u64,
+                    error,
+                }),
+            };
+            entries.push(entry);
+            offset += frame_length;
+        }
+
+        DecodedStream {
+            entries,
+            undecoded_bytes: data.len() - offset,
+            has_gap: stream.has_gap(),
+        }
+    }
+
+    pub fn entries(&self) -> &[Result<CapturedPacket, StreamDecodeError>] {
+        &self.entries
+    }
+
+    pub fn packets(&self) -> impl Iterator<Item = &CapturedPacket> {
+        self.entries.iter().filter_map(|entry| entry.as_ref().ok())
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &StreamDecodeError> {
+        self.entries.iter().filter_map(|entry| entry.as_ref().err())
+    }
+
+    /// Bytes at the end of the stream that do not make up a whole packet.
+    pub fn undecoded_bytes(&self) -> usize {
+        self.undecoded_bytes
+    }
+
+    /// True when the capture lost segments, so the stream ends at the first missing byte.
+    pub fn has_gap(&self) -> bool {
+        self.has_gap
+    }
+}
+
+/// One TCP connection to the broker port, decoded in both directions.
+#[allow(dead_code)]
+pub(crate) struct MqttConversation {
+    client: SocketAddr,
+    server: SocketAddr,
+    client_to_server: DecodedStream,
+    server_to_client: DecodedStream,
+}
+
+#[allow(dead_code)]
+impl MqttConversation {
+    pub(crate) fn new(
+        client: SocketAddr,
+        server: SocketAddr,
+        client_to_server: DecodedStream,
+        server_to_client: DecodedStream,
+    ) -> Self {
+        MqttConversation {
+            client,
+            server,
+            client_to_server,
+            server_to_client,
+        }
+    }
+
+    pub fn client(&self) -> SocketAddr {
+        self.client
+    }
+
+    pub fn server(&self) -> SocketAddr {
+        self.server
+    }
+
+    pub fn stream(&self, direction: Direction) -> &DecodedStream {
+        match direction {
+            Direction::ClientToServer => &self.client_to_server,
+            Direction::ServerToClient => &self.server_to_client,
+        }
+    }
+}
+
+#[cfg(test)]
+mod mqtt_conversation_tests {
+    use crate::capture::mqtt_conversation::DecodedStream;
+    use crate::capture::tcp_reassembly::TcpStreamReassembler;
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+    use std::time::Duration;
+
+    #[test]
+    fn decoded_stream_should_time_packets_by_their_last_byte() {
+        let mut stream = TcpStreamReassembler::new();
+        stream.push(Duration::from_secs(1), 0, &[0b1100_0000]);
+        stream.push(Duration::from_secs(2), 1, &[0x00, 0b1101_0000, 0x00]);
+
+        let decoded = DecodedStream::decode(&stream);
+        let packets: Vec<_> = decoded.packets().collect();
+
+        assert_eq!(packets.len(), 2);
+        assert!(matches!(packets[0].packet(), Packet::PingReq { .. }));
+        assert_eq!(packets[0].timestamp(), Duration::from_secs(2));
+        assert_eq!(packets[1].stream_offset(), 2);
+        assert!(matches!(packets[1].packet(), Packet::PingResp { .. }));
+    }
+
+    #[test]
+    fn decoded_stream_should_report_error_offset_and_continue() {
+        let mut stream = TcpStreamReassembler::new();
+        // PINGREQ with reserved flags set, then a valid PINGREQ and half a packet
+        stream.push(
+            Duration::ZERO,
+            0,
+            &[0b1100_0001, 0x00, 0b1100_0000, 0x00, 0b0011_0000],
+        );
+
+        let decoded = DecodedStream::decode(&stream);
+
+        assert_eq!(decoded.entries().len(), 2);
+        let error = decoded.errors().next().unwrap();
+        assert_eq!(error.stream_offset(), 0);
+        assert!(matches!(
+            error.error(),
+            MqttProtocolError::InvalidFixedHeaderFlags
+        ));
+        assert_eq!(decoded.packets().next().unwrap().stream_offset(), 2);
+        assert_eq!(decoded.undecoded_bytes(), 1);
+    }
+
+    #[test]
+    fn decoded_stream_should_stop_at_malformed_remaining_length() {
+        let mut stream = TcpStreamReassembler::new();
+        stream.push(Duration::ZERO, 0, &[0b1100_0000, 0x00]);
+        stream.push(Duration::ZERO, 2, &[0x30, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]);
+
+        let decoded = DecodedStream::decode(&stream);
+
+        assert_eq!(decoded.packets().count(), 1);
+        let error = decoded.errors().next().unwrap();
+        assert_eq!(error.stream_offset(), 2);
+        assert!(matches!(
+            error.error(),
+            MqttProtocolError::MalformedRemainingLength
+        ));
+        assert_eq!(decoded.undecoded_bytes(), 0);
+    }
+}
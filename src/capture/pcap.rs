@@ -0,0 +1,144 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::capture::capture_error::CaptureError;
+use crate::capture::capture_file::{self, ByteOrder, CapturedFrame};
+
+const MAGIC_MICROSECONDS: u32 = 0xA1B2_C3D4;
+const MAGIC_NANOSECONDS: u32 = 0xA1B2_3C4D;
+const GLOBAL_HEADER_LENGTH: usize = 24;
+const RECORD_HEADER_LENGTH: usize = 16;
+
+#[allow(dead_code)]
+pub(crate) fn is_pcap_magic(magic: u32) -> bool {
+    [MAGIC_MICROSECONDS, MAGIC_NANOSECONDS]
+        .iter()
+        .any(|expect| magic == *expect || magic == expect.swap_bytes())
+}
+
+/// Reads a classic libpcap file. A record cut short at the end of the file, which is what a
+/// killed tcpdump leaves behind, ends the capture instead of failing it.
+#[allow(dead_code)]
+pub(crate) fn read_frames(data: &[u8]) -> Result<Vec<CapturedFrame>, CaptureError> {
+    let header = capture_file::slice(data, 0, GLOBAL_HEADER_LENGTH)?;
+    let magic = ByteOrder::Big.u32(&header[0..4]);
+    if !is_pcap_magic(magic) {
+        return Err(CaptureError::UnknownFormat(magic));
+    }
+    let (byte_order, magic) = if magic == MAGIC_MICROSECONDS || magic == MAGIC_NANOSECONDS {
+        (ByteOrder::Big, magic)
+    } else {
+        (ByteOrder::Little, magic.swap_bytes())
+    };
+    let units_per_second = if magic == MAGIC_NANOSECONDS {
+        1_000_000_000
+    } else {
+        1_000_000
+    };
+
+    let version_major = byte_order.u16(&header[4..6]);
+    let version_minor = byte_order.u16(&header[6..8]);
+    if version_major != 2 {
+        return Err(CaptureError::UnsupportedVersion(
+            version_major,
+            version_minor,
+        ));
+    }
+    let link_type = byte_order.u32(&header[20..24]) & 0x0FFF_FFFF;
+
+    let mut frames = Vec::new();
+    let mut offset = GLOBAL_HEADER_LENGTH;
+    while offset < data.len() {
+        let Ok(record) = capture_file::slice(data, offset, RECORD_HEADER_LENGTH) else {
+            break;
+        };
+        let seconds = byte_order.u32(&record[0..4]) as u64;
+        let fraction = byte_order.u32(&record[4..8]) as u64;
+        let captured_length = byte_order.u32(&record[8..12]) as usize;
+        let Ok(frame) = capture_file::slice(data, offset + RECORD_HEADER_LENGTH, captured_length)
+        else {
+            break;
+        };
+
+        let timestamp = capture_file::timestamp_from_units(
+            seconds * units_per_second + fraction,
+            units_per_second,
+        );
+        frames.push(CapturedFrame::new(timestamp, link_type, frame.to_vec()));
+        offset += RECORD_HEADER_LENGTH + captured_length;
+    }
+
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod pcap_tests {
+    use crate::capture::capture_error::CaptureError;
+    use crate::capture::pcap;
+    use crate::capture::test_frames;
+    use std::time::Duration;
+
+    #[test]
+    fn pcap_should_read_little_endian_microsecond_file() {
+        let file = test_frames::pcap_file(
+            1,
+            &[
+                (Duration::new(10, 250_000_000), vec![1, 2, 3]),
+                (Duration::new(11, 0), vec![4]),
+            ],
+        );
+        let frames = pcap::read_frames(&file).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].timestamp(), Duration::new(10, 250_000_000));
+        assert_eq!(frames[0].link_type(), 1);
+        assert_eq!(frames[0].data(), &[1, 2, 3]);
+        assert_eq!(frames[1].data(), &[4]);
+    }
+
+    #[test]
+    fn pcap_should_read_big_endian_nanosecond_file() {
+        let mut file = vec![0xA1, 0xB2, 0x3C, 0x4D, 0, 2, 0, 4];
+        file.extend_from_slice(&[0; 12]);
+        file.extend_from_slice(&[0, 0, 0, 101]);
+        file.extend_from_slice(&[0, 0, 0, 5, 0, 0, 0, 7, 0, 0, 0, 1, 0, 0, 0, 1, 0xAA]);
+
+        let frames = pcap::read_frames(&file).unwrap();
+
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].timestamp(), Duration::new(5, 7));
+        assert_eq!(frames[0].link_type(), 101);
+        assert_eq!(frames[0].data(), &[0xAA]);
+    }
+
+    #[test]
+    fn pcap_should_stop_at_truncated_last_record() {
+        let mut file = test_frames::pcap_file(1, &[(Duration::ZERO, vec![1, 2, 3])]);
+        file.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 9, 0, 0, 0, 1]);
+
+        let frames = pcap::read_frames(&file).unwrap();
+        assert_eq!(frames.len(), 1);
+    }
+
+    #[test]
+    fn pcap_should_reject_unsupported_version() {
+        let mut file = test_frames::pcap_file(1, &[]);
+        file[4] = 1;
+        let result = pcap::read_frames(&file);
+        assert!(matches!(
+            result,
+            Err(CaptureError::UnsupportedVersion(1, 4))
+        ));
+    }
+}
@@ -0,0 +1,306 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::capture::capture_error::CaptureError;
+use crate::capture::capture_file::{self, ByteOrder, CapturedFrame};
+use std::time::Duration;
+
+pub(crate) const SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const OBSOLETE_PACKET_BLOCK: u32 = 0x0000_0002;
+const SIMPLE_PACKET_BLOCK: u32 = 0x0000_0003;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+const OPTION_END: u16 = 0;
+const OPTION_IF_TSRESOL: u16 = 9;
+const OPTION_IF_TSOFFSET: u16 = 14;
+
+#[derive(Debug, Clone, Copy)]
+struct Interface {
+    link_type: u32,
+    units_per_second: u64,
+    /// if_tsoffset, which may move timestamps back as well as forward.
+    offset_seconds: i64,
+}
+
+#[allow(dead_code)]
+impl Interface {
+    /// `None` when if_tsoffset moves the timestamp outside what a `Duration` can hold.
+    fn timestamp(&self, high: u32, low: u32) -> Option<Duration> {
+        let units = ((high as u64) << 32) | low as u64;
+        let timestamp = capture_file::timestamp_from_units(units, self.units_per_second);
+        let offset = Duration::from_secs(self.offset_seconds.unsigned_abs());
+        if self.offset_seconds < 0 {
+            timestamp.checked_sub(offset)
+        } else {
+            timestamp.checked_add(offset)
+        }
+    }
+}
+
+/// Reads a pcapng file section by section. Blocks other than interface descriptions and
+/// packets are skipped, and a block cut short at the end of the file ends the capture.
+#[allow(dead_code)]
+pub(crate) fn read_frames(data: &[u8]) -> Result<Vec<CapturedFrame>, CaptureError> {
+    let mut frames = Vec::new();
+    let mut byte_order = ByteOrder::Little;
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut offset = 0;
+
+    while offset < data.len() {
+        let Ok(block_header) = capture_file::slice(data, offset, 12) else {
+            break;
+        };
+        let block_type = byte_order.u32(&block_header[0..4]);
+        if block_type == SECTION_HEADER_BLOCK {
+            byte_order = section_byte_order(&block_header[8..12], offset)?;
+            interfaces.clear();
+        }
+
+        let block_length = byte_order.u32(&block_header[4..8]) as usize;
+        if block_length < 12 || !block_length.is_multiple_of(4) {
+            return Err(CaptureError::InvalidBlock(offset, "bad block total length"));
+        }
+        let Ok(block) = capture_file::slice(data, offset, block_length) else {
+            break;
+        };
+        let body = &block[8..block_length - 4];
+
+        match block_type {
+            INTERFACE_DESCRIPTION_BLOCK => {
+                interfaces.push(read_interface(body, byte_order, offset)?);
+            }
+            ENHANCED_PACKET_BLOCK => {
+                let fields = capture_file::slice(body, 0, 20)
+                    .map_err(|_| CaptureError::InvalidBlock(offset, "short enhanced packet"))?;
+                let interface = find_interface(&interfaces, byte_order.u32(&fields[0..4]))?;
+                let captured_length = byte_order.u32(&fields[12..16]) as usize;
+                let frame = capture_file::slice(body, 20, captured_length)
+                    .map_err(|_| CaptureError::InvalidBlock(offset, "packet data overflows"))?;
+                frames.push(CapturedFrame::new(
+                    interface
+                        .timestamp(
+                            byte_order.u32(&fields[4..8]),
+                            byte_order.u32(&fields[8..12]),
+                        )
+                        .ok_or(CaptureError::InvalidBlock(offset, "timestamp out of range"))?,
+                    interface.link_type,
+                    frame.to_vec(),
+                ));
+            }
+            OBSOLETE_PACKET_BLOCK => {
+                let fields = capture_file::slice(body, 0, 20)
+                    .map_err(|_| CaptureError::InvalidBlock(offset, "short packet block"))?;
+                let interface = find_interface(&interfaces, byte_order.u16(&fields[0..2]) as u32)?;
+                let captured_length = byte_order.u32(&fields[12..16]) as usize;
+                let frame = capture_file::slice(body, 20, captured_length)
+                    .map_err(|_| CaptureError::InvalidBlock(offset, "packet data overflows"))?;
+                frames.push(CapturedFrame::new(
+                    interface
+                        .timestamp(
+                            byte_order.u32(&fields[4..8]),
+                            byte_order.u32(&fields[8..12]),
+                        )
+                        .ok_or(CaptureError::InvalidBlock(offset, "timestamp out of range"))?,
+                    interface.link_type,
+                    frame.to_vec(),
+                ));
+            }
+            SIMPLE_PACKET_BLOCK => {
+                // simple packets carry no timestamp and always belong to the first interface
+                let fields = capture_file::slice(body, 0, 4)
+                    .map_err(|_| CaptureError::InvalidBlock(offset, "short simple packet"))?;
+                let interface = find_interface(&interfaces, 0)?;
+                let original_length = byte_order.u32(fields) as usize;
+                let captured_length = original_length.min(body.len() - 4);
+                frames.push(CapturedFrame::new(
+                    Duration::ZERO,
+                    interface.link_type,
+                    body[4..4 + captured_length].to_vec(),
+                ));
+            }
+            _ => {}
+        }
+
+        offset += block_length;
+    }
+
+    Ok(frames)
+}
+
+fn section_byte_order(magic: &[u8], offset: usize) -> Result<ByteOrder, CaptureError> {
+    if ByteOrder::Little.u32(magic) == BYTE_ORDER_MAGIC {
+        return Ok(ByteOrder::Little);
+    }
+    if ByteOrder::Big.u32(magic) == BYTE_ORDER_MAGIC {
+        return Ok(ByteOrder::Big);
+    }
+    Err(CaptureError::InvalidBlock(offset, "bad byte-order magic"))
+}
+
+fn find_interface(interfaces: &[Interface], interface_id: u32) -> Result<Interface, CaptureError> {
+    interfaces
+        .get(interface_id as usize)
+        .copied()
+        .ok_or(CaptureError::UnknownInterface(interface_id))
+}
+
+fn read_interface(
+    body: &[u8],
+    byte_order: ByteOrder,
+    offset: usize,
+) -> Result<Interface, CaptureError> {
+    let fields = capture_file::slice(body, 0, 8)
+        .map_err(|_| CaptureError::InvalidBlock(offset, "short interface description"))?;
+    let mut interface = Interface {
+        link_type: byte_order.u16(&fields[0..2]) as u32,
+        units_per_second: 1_000_000,
+        offset_seconds: 0,
+    };
+
+    let mut option_offset = 8;
+    while let Ok(option_header) = capture_file::slice(body, option_offset, 4) {
+        let code = byte_order.u16(&option_header[0..2]);
+        let length = byte_order.u16(&option_header[2..4]) as usize;
+        if code == OPTION_END {
+            break;
+        }
+        let value = capture_file::slice(body, option_offset + 4, length)
+            .map_err(|_| CaptureError::InvalidBlock(offset, "option overflows block"))?;
+        match code {
+            OPTION_IF_TSRESOL if length == 1 => {
+                interface.units_per_second = units_per_second(value[0])
+                    .ok_or(CaptureError::InvalidBlock(offset, "bad if_tsresol"))?;
+            }
+            OPTION_IF_TSOFFSET if length == 8 => {
+                interface.offset_seconds = byte_order.u64(value) as i64;
+            }
+            _ => {}
+        }
+        option_offset += 4 + length.div_ceil(4) * 4;
+    }
+
+    Ok(interface)
+}
+
+/// if_tsresol: the low seven bits are a negative power of ten, or of two when the top bit is set.
+fn units_per_second(tsresol: u8) -> Option<u64> {
+    let exponent = (tsresol & 0x7F) as u32;
+    if tsresol & 0x80 == 0 {
+        10u64.checked_pow(exponent)
+    } else {
+        1u64.checked_shl(exponent).filter(|_| exponent < 64)
+    }
+}
+
+#[cfg(test)]
+mod pcapng_tests {
+    use crate::capture::capture_error::CaptureError;
+    use crate::capture::pcapng;
+    use crate::capture::test_frames;
+    use std::time::Duration;
+
+    #[test]
+    fn pcapng_should_read_enhanced_packets_with_default_resolution() {
+        let file = test_frames::pcapng_file(
+            1,
+            None,
+            &[(Duration::new(3, 500_000_000), vec![1, 2, 3, 4, 5])],
+        );
+        let frames = pcapng::read_frames(&file).unwrap();
+
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].timestamp(), Duration::new(3, 500_000_000));
+        assert_eq!(frames[0].link_type(), 1);
+        assert_eq!(frames[0].data(), &[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn pcapng_should_honour_nanosecond_tsresol() {
+        let file = test_frames::pcapng_file(101, Some(9), &[(Duration::new(1, 7), vec![0xAA])]);
+        let frames = pcapng::read_frames(&file).unwrap();
+
+        assert_eq!(frames[0].timestamp(), Duration::new(1, 7));
+        assert_eq!(frames[0].link_type(), 101);
+    }
+
+    #[test]
+    fn pcapng_should_read_simple_packets_and_skip_unknown_blocks() {
+        let mut file = test_frames::pcapng_file(1, None, &[]);
+        // a name resolution block, which the reader does not care about
+        file.extend_from_slice(&[4, 0, 0, 0, 16, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0]);
+        // a simple packet block with three bytes of data padded to four
+        file.extend_from_slice(&[3, 0, 0, 0, 20, 0, 0, 0, 3, 0, 0, 0, 7, 8, 9, 0, 20, 0, 0, 0]);
+
+        let frames = pcapng::read_frames(&file).unwrap();
+
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].data(), &[7, 8, 9]);
+        assert_eq!(frames[0].timestamp(), Duration::ZERO);
+    }
+
+    #[test]
+    fn pcapng_should_reject_bad_byte_order_magic() {
+        let mut file = test_frames::pcapng_file(1, None, &[]);
+        file[8] = 0;
+        let result = pcapng::read_frames(&file);
+        assert!(matches!(result, Err(CaptureError::InvalidBlock(0, _))));
+    }
+
+    #[test]
+    fn pcapng_should_reject_packet_for_unknown_interface() {
+        let mut file = test_frames::pcapng_file(1, None, &[(Duration::ZERO, vec![1])]);
+        let enhanced_packet_offset = file.len() - 36;
+        file[enhanced_packet_offset + 8] = 5;
+        let result = pcapng::read_frames(&file);
+        assert!(matches!(result, Err(CaptureError::UnknownInterface(5))));
+    }
+
+    #[test]
+    fn pcapng_should_apply_a_negative_tsoffset() {
+        let file = test_frames::pcapng_file_with_tsoffset(
+            1,
+            None,
+            Some(-3600),
+            &[(Duration::from_secs(3700), vec![1])],
+        );
+        let frames = pcapng::read_frames(&file).unwrap();
+        assert_eq!(frames[0].timestamp(), Duration::from_secs(100));
+
+        let file = test_frames::pcapng_file_with_tsoffset(
+            1,
+            None,
+            Some(-3600),
+            &[(Duration::from_secs(1), vec![1])],
+        );
+        let result = pcapng::read_frames(&file);
+        assert!(matches!(
+            result,
+            Err(CaptureError::InvalidBlock(_, "timestamp out of range"))
+        ));
+
+        let file = test_frames::pcapng_file_with_tsoffset(
+            1,
+            Some(0),
+            Some(i64::MAX),
+            &[(Duration::from_secs(u64::MAX), vec![1])],
+        );
+        let result = pcapng::read_frames(&file);
+        assert!(matches!(
+            result,
+            Err(CaptureError::InvalidBlock(_, "timestamp out of range"))
+        ));
+    }
+}
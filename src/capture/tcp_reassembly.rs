@@ -0,0 +1,162 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Rebuilds the byte stream of one direction of a TCP connection. Segments may arrive out of
+/// order or be retransmitted; bytes already in the stream are dropped and segments past a hole
+/// wait until the hole is filled.
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+pub(crate) struct TcpStreamReassembler {
+    next_sequence_number: Option<u32>,
+    data: Vec<u8>,
+    // end offset of each appended segment and the time it became readable in order
+    arrivals: Vec<(u64, Duration)>,
+    pending: BTreeMap<u64, (Duration, Vec<u8>)>,
+}
+
+#[allow(dead_code)]
+impl TcpStreamReassembler {
+    pub fn new() -> Self {
+        TcpStreamReassembler::default()
+    }
+
+    /// A SYN consumes one sequence number; the stream starts right after it.
+    pub fn push_syn(&mut self, sequence_number: u32) {
+        if self.next_sequence_number.is_none() {
+            self.next_sequence_number = Some(sequence_number.wrapping_add(1));
+        }
+    }
+
+    pub fn push(&mut self, timestamp: Duration, sequence_number: u32, payload: &[u8]) {
+        if payload.is_empty() {
+            return;
+        }
+        // a capture started mid-connection has no SYN, so the first segment sets the origin
+        let next_sequence_number = *self.next_sequence_number.get_or_insert(sequence_number);
+
+        // sequence numbers wrap, so compare them through the signed distance
+        let distance = sequence_number.wrapping_sub(next_sequence_number) as i32 as i64;
+        let offset = self.data.len() as i64 + distance;
+        let end = offset + payload.len() as i64;
+        if end <= self.data.len() as i64 {
+            return;
+        }
+        // bytes from before the first captured segment can never be placed
+        let payload = &payload[(-offset).max(0) as usize..];
+        let offset = offset.max(0) as u64;
+
+        match self.pending.get(&offset) {
+            Some((_, buffered)) if buffered.len() >= payload.len() => {}
+            _ => {
+                self.pending.insert(offset, (timestamp, payload.to_vec()));
+            }
+        }
+        self.drain_pending(timestamp);
+    }
+
+    /// Segments held back by a hole only become readable once the hole is filled at `now`.
+    fn drain_pending(&mut self, now: Duration) {
+        while let Some(entry) = self.pending.first_entry() {
+            let stream_end = self.data.len() as u64;
+            let offset = *entry.key();
+            if offset > stream_end {
+                break;
+            }
+            let (timestamp, payload) = entry.remove();
+            let skip = (stream_end - offset) as usize;
+            if skip >= payload.len() {
+                continue;
+            }
+            self.data.extend_from_slice(&payload[skip..]);
+            self.arrivals
+                .push((self.data.len() as u64, timestamp.max(now)));
+            let appended = (payload.len() - skip) as u32;
+            self.next_sequence_number = self
+                .next_sequence_number
+                .map(|next| next.wrapping_add(appended));
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// True while some captured segments sit beyond bytes the capture never saw.
+    pub fn has_gap(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Time at which the byte at `offset` became readable in stream order.
+    pub fn timestamp_at(&self, offset: u64) -> Option<Duration> {
+        let index = self.arrivals.partition_point(|(end, _)| *end <= offset);
+        self.arrivals.get(index).map(|(_, timestamp)| *timestamp)
+    }
+}
+
+#[cfg(test)]
+mod tcp_reassembly_tests {
+    use crate::capture::tcp_reassembly::TcpStreamReassembler;
+    use std::time::Duration;
+
+    #[test]
+    fn reassembler_should_append_in_order_segments_after_syn() {
+        let mut stream = TcpStreamReassembler::new();
+        stream.push_syn(99);
+        stream.push(Duration::from_secs(1), 100, b"hello ");
+        stream.push(Duration::from_secs(2), 106, b"world");
+
+        assert_eq!(stream.data(), b"hello world");
+        assert_eq!(stream.timestamp_at(0), Some(Duration::from_secs(1)));
+        assert_eq!(stream.timestamp_at(5), Some(Duration::from_secs(1)));
+        assert_eq!(stream.timestamp_at(6), Some(Duration::from_secs(2)));
+        assert_eq!(stream.timestamp_at(11), None);
+    }
+
+    #[test]
+    fn reassembler_should_reorder_out_of_order_segments() {
+        let mut stream = TcpStreamReassembler::new();
+        stream.push_syn(0);
+        stream.push(Duration::from_secs(2), 4, b"def");
+        assert!(stream.data().is_empty());
+        assert!(stream.has_gap());
+
+        stream.push(Duration::from_secs(3), 1, b"abc");
+        assert_eq!(stream.data(), b"abcdef");
+        assert!(!stream.has_gap());
+        assert_eq!(stream.timestamp_at(5), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn reassembler_should_drop_retransmitted_and_trim_overlapping_bytes() {
+        let mut stream = TcpStreamReassembler::new();
+        stream.push(Duration::ZERO, 10, b"abcd");
+        stream.push(Duration::ZERO, 10, b"abcd");
+        stream.push(Duration::ZERO, 12, b"cdef");
+
+        assert_eq!(stream.data(), b"abcdef");
+    }
+
+    #[test]
+    fn reassembler_should_follow_sequence_number_wraparound() {
+        let mut stream = TcpStreamReassembler::new();
+        stream.push_syn(u32::MAX - 2);
+        stream.push(Duration::ZERO, u32::MAX - 1, b"ab");
+        stream.push(Duration::ZERO, 0, b"cd");
+
+        assert_eq!(stream.data(), b"abcd");
+    }
+}
@@ -0,0 +1,269 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+pub(crate) const LINK_TYPE_NULL: u32 = 0;
+pub(crate) const LINK_TYPE_ETHERNET: u32 = 1;
+pub(crate) const LINK_TYPE_RAW: u32 = 101;
+pub(crate) const LINK_TYPE_LOOP: u32 = 108;
+pub(crate) const LINK_TYPE_LINUX_SLL: u32 = 113;
+pub(crate) const LINK_TYPE_IPV4: u32 = 228;
+pub(crate) const LINK_TYPE_IPV6: u32 = 229;
+pub(crate) const LINK_TYPE_LINUX_SLL2: u32 = 276;
+
+const ETHER_TYPE_IPV4: u16 = 0x0800;
+const ETHER_TYPE_IPV6: u16 = 0x86DD;
+const ETHER_TYPE_VLAN: u16 = 0x8100;
+const ETHER_TYPE_QINQ: u16 = 0x88A8;
+const IP_PROTOCOL_TCP: u8 = 6;
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_RST: u8 = 0x04;
+const TCP_ACK: u8 = 0x10;
+
+/// The parts of a captured TCP segment needed to put the byte stream back together.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TcpSegment {
+    source: SocketAddr,
+    destination: SocketAddr,
+    sequence_number: u32,
+    flags: u8,
+    payload: Vec<u8>,
+}
+
+#[allow(dead_code)]
+impl TcpSegment {
+    /// Unwraps the link layer and IP header of a frame. Anything that is not an unfragmented
+    /// TCP segment, or is too short to be one, gives `None` and is left out of the reassembly.
+    pub fn parse(link_type: u32, frame: &[u8]) -> Option<TcpSegment> {
+        let ip_packet = match link_type {
+            LINK_TYPE_ETHERNET => Self::strip_ethernet(frame)?,
+            // the 4 byte address family is in the byte order of the capturing host
+            LINK_TYPE_NULL | LINK_TYPE_LOOP => frame.get(4..)?,
+            LINK_TYPE_RAW | LINK_TYPE_IPV4 | LINK_TYPE_IPV6 => frame,
+            LINK_TYPE_LINUX_SLL => Self::ip_payload(u16_at(frame, 14)?, frame.get(16..)?)?,
+            LINK_TYPE_LINUX_SLL2 => Self::ip_payload(u16_at(frame, 0)?, frame.get(20..)?)?,
+            _ => return None,
+        };
+
+        match ip_packet.first()? >> 4 {
+            4 => Self::parse_ipv4(ip_packet),
+            6 => Self::parse_ipv6(ip_packet),
+            _ => None,
+        }
+    }
+
+    fn strip_ethernet(frame: &[u8]) -> Option<&[u8]> {
+        let mut ether_type = u16_at(frame, 12)?;
+        let mut offset = 14;
+        while ether_type == ETHER_TYPE_VLAN || ether_type == ETHER_TYPE_QINQ {
+            ether_type = u16_at(frame, offset + 2)?;
+            offset += 4;
+        }
+        Self::ip_payload(ether_type, frame.get(offset..)?)
+    }
+
+    fn ip_payload(ether_type: u16, payload: &[u8]) -> Option<&[u8]> {
+        match ether_type {
+            ETHER_TYPE_IPV4 | ETHER_TYPE_IPV6 => Some(payload),
+            _ => None,
+        }
+    }
+
+    fn parse_ipv4(packet: &[u8]) -> Option<TcpSegment> {
+        let header_length = ((packet.first()? & 0x0F) as usize) * 4;
+        let total_length = u16_at(packet, 2)? as usize;
+        let fragment = u16_at(packet, 6)?;
+        // more fragments set or a non-zero fragment offset
+        if fragment & 0x3FFF != 0 || packet.get(9)? != &IP_PROTOCOL_TCP {
+            return None;
+        }
+        let source = Ipv4Addr::from(<[u8; 4]>::try_from(packet.get(12..16)?).ok()?);
+        let destination = Ipv4Addr::from(<[u8; 4]>::try_from(packet.get(16..20)?).ok()?);
+        // the frame may carry Ethernet padding past the end of the IP packet
+        let segment = packet.get(header_length..total_length.min(packet.len()))?;
+        Self::parse_tcp(source.into(), destination.into(), segment)
+    }
+
+    fn parse_ipv6(packet: &[u8]) -> Option<TcpSegment> {
+        let payload_length = u16_at(packet, 4)? as usize;
+        let source = Ipv6Addr::from(<[u8; 16]>::try_from(packet.get(8..24)?).ok()?);
+        let destination = Ipv6Addr::from(<[u8; 16]>::try_from(packet.get(24..40)?).ok()?);
+        let payload = packet.get(40..(40 + payload_length).min(packet.len()))?;
+
+        let mut next_header = *packet.get(6)?;
+        let mut offset = 0;
+        loop {
+            match next_header {
+                IP_PROTOCOL_TCP => break,
+                // hop-by-hop, routing and destination options
+                0 | 43 | 60 => {
+                    next_header = *payload.get(offset)?;
+                    offset += (*payload.get(offset + 1)? as usize + 1) * 8;
+                }
+                _ => return None,
+            }
+        }
+        Self::parse_tcp(source.into(), destination.into(), payload.get(offset..)?)
+    }
+
+    fn parse_tcp(source: IpAddr, destination: IpAddr, segment: &[u8]) -> Option<TcpSegment> {
+        let source_port = u16_at(segment, 0)?;
+        let destination_port = u16_at(segment, 2)?;
+        let sequence_number = u32::from_be_bytes(segment.get(4..8)?.try_into().ok()?);
+        let data_offset = ((segment.get(12)? >> 4) as usize) * 4;
+        let flags = *segment.get(13)?;
+        Some(TcpSegment {
+            source: SocketAddr::new(source, source_port),
+            destination: SocketAddr::new(destination, destination_port),
+            sequence_number,
+            flags,
+            payload: segment.get(data_offset..)?.to_vec(),
+        })
+    }
+
+    pub fn source(&self) -> SocketAddr {
+        self.source
+    }
+
+    pub fn destination(&self) -> SocketAddr {
+        self.destination
+    }
+
+    pub fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    pub fn is_syn(&self) -> bool {
+        self.flags & TCP_SYN != 0
+    }
+
+    pub fn is_ack(&self) -> bool {
+        self.flags & TCP_ACK != 0
+    }
+
+    pub fn is_fin(&self) -> bool {
+        self.flags & TCP_FIN != 0
+    }
+
+    pub fn is_rst(&self) -> bool {
+        self.flags & TCP_RST != 0
+    }
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tcp_segment_tests {
+    use crate::capture::tcp_segment::{
+        LINK_TYPE_ETHERNET, LINK_TYPE_LINUX_SLL, LINK_TYPE_NULL, LINK_TYPE_RAW, TcpSegment,
+    };
+    use crate::capture::test_frames::{self, TCP_ACK, TCP_PSH, TCP_SYN};
+    use std::net::SocketAddr;
+
+    fn client() -> SocketAddr {
+        "192.168.1.10:50000".parse().unwrap()
+    }
+
+    fn server() -> SocketAddr {
+        "192.168.1.1:1883".parse().unwrap()
+    }
+
+    #[test]
+    fn tcp_segment_should_parse_ethernet_ipv4_frame() {
+        let frame =
+            test_frames::ethernet_tcp_frame(client(), server(), 1000, TCP_PSH | TCP_ACK, b"abc");
+        let segment = TcpSegment::parse(LINK_TYPE_ETHERNET, &frame).unwrap();
+
+        assert_eq!(segment.source(), client());
+        assert_eq!(segment.destination(), server());
+        assert_eq!(segment.sequence_number(), 1000);
+        assert_eq!(segment.payload(), b"abc");
+        assert!(segment.is_ack());
+        assert!(!segment.is_syn());
+    }
+
+    #[test]
+    fn tcp_segment_should_parse_ethernet_ipv6_frame() {
+        let client: SocketAddr = "[fe80::1]:50000".parse().unwrap();
+        let server: SocketAddr = "[fe80::2]:1883".parse().unwrap();
+        let frame = test_frames::ethernet_tcp_frame(client, server, 7, TCP_SYN, &[]);
+        let segment = TcpSegment::parse(LINK_TYPE_ETHERNET, &frame).unwrap();
+
+        assert_eq!(segment.source(), client);
+        assert_eq!(segment.destination(), server);
+        assert!(segment.is_syn());
+        assert!(segment.payload().is_empty());
+    }
+
+    #[test]
+    fn tcp_segment_should_skip_vlan_tags() {
+        let frame = test_frames::ethernet_tcp_frame(client(), server(), 1, TCP_ACK, b"x");
+        let mut tagged = frame[..12].to_vec();
+        tagged.extend_from_slice(&[0x81, 0x00, 0x00, 0x2A]);
+        tagged.extend_from_slice(&frame[12..]);
+
+        let segment = TcpSegment::parse(LINK_TYPE_ETHERNET, &tagged).unwrap();
+        assert_eq!(segment.payload(), b"x");
+    }
+
+    #[test]
+    fn tcp_segment_should_parse_raw_null_and_linux_cooked_frames() {
+        let packet = test_frames::ip_tcp_packet(client(), server(), 1, TCP_ACK, b"x");
+        assert!(TcpSegment::parse(LINK_TYPE_RAW, &packet).is_some());
+
+        let mut null_frame = vec![2, 0, 0, 0];
+        null_frame.extend_from_slice(&packet);
+        assert!(TcpSegment::parse(LINK_TYPE_NULL, &null_frame).is_some());
+
+        let mut cooked_frame = vec![0; 14];
+        cooked_frame.extend_from_slice(&[0x08, 0x00]);
+        cooked_frame.extend_from_slice(&packet);
+        assert!(TcpSegment::parse(LINK_TYPE_LINUX_SLL, &cooked_frame).is_some());
+    }
+
+    #[test]
+    fn tcp_segment_should_ignore_ethernet_padding() {
+        let mut frame = test_frames::ethernet_tcp_frame(client(), server(), 1, TCP_ACK, &[]);
+        frame.extend_from_slice(&[0; 6]);
+        let segment = TcpSegment::parse(LINK_TYPE_ETHERNET, &frame).unwrap();
+        assert!(segment.payload().is_empty());
+    }
+
+    #[test]
+    fn tcp_segment_should_reject_non_tcp_and_fragmented_packets() {
+        let mut udp = test_frames::ethernet_tcp_frame(client(), server(), 1, TCP_ACK, b"x");
+        udp[14 + 9] = 17;
+        assert!(TcpSegment::parse(LINK_TYPE_ETHERNET, &udp).is_none());
+
+        let mut fragment = test_frames::ethernet_tcp_frame(client(), server(), 1, TCP_ACK, b"x");
+        fragment[14 + 6] = 0x20;
+        assert!(TcpSegment::parse(LINK_TYPE_ETHERNET, &fragment).is_none());
+
+        let mut arp = test_frames::ethernet_tcp_frame(client(), server(), 1, TCP_ACK, b"x");
+        arp[12..14].copy_from_slice(&[0x08, 0x06]);
+        assert!(TcpSegment::parse(LINK_TYPE_ETHERNET, &arp).is_none());
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+pub(crate) const TCP_FIN: u8 = 0x01;
+pub(crate) const TCP_SYN: u8 = 0x02;
+pub(crate) const TCP_PSH: u8 = 0x08;
+pub(crate) const TCP_ACK: u8 = 0x10;
+
+pub(crate) fn pcap_file(link_type: u32, frames: &[(Duration, Vec<u8>)]) -> Vec<u8> {
+    let mut file = Vec::new();
+    file.extend_from_slice(&0xA1B2_C3D4u32.to_le_bytes());
+    file.extend_from_slice(&2u16.to_le_bytes());
+    file.extend_from_slice(&4u16.to_le_bytes());
+    file.extend_from_slice(&[0; 8]);
+    file.extend_from_slice(&65535u32.to_le_bytes());
+    file.extend_from_slice(&link_type.to_le_bytes());
+    for (timestamp, frame) in frames {
+        file.extend_from_slice(&(timestamp.as_secs() as u32).to_le_bytes());
+        file.extend_from_slice(&timestamp.subsec_micros().to_le_bytes());
+        file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        file.extend_from_slice(frame);
+    }
+    file
+}
+
+/// A little-endian pcapng file with one section and one interface. `tsresol` is the power of
+/// ten of the timestamp unit, microseconds when absent.
+pub(crate) fn pcapng_file(
+    link_type: u16,
+    tsresol: Option<u8>,
+    frames: &[(Duration, Vec<u8>)],
+) -> Vec<u8> {
+    pcapng_file_with_tsoffset(link_type, tsresol, None, frames)
+}
+
+/// `pcapng_file` whose interface carries an if_tsoffset option when `tsoffset` is given.
+pub(crate) fn pcapng_file_with_tsoffset(
+    link_type: u16,
+    tsresol: Option<u8>,
+    tsoffset: Option<i64>,
+    frames: &[(Duration, Vec<u8>)],
+) -> Vec<u8> {
+    let mut file = Vec::new();
+
+    let mut section_body = Vec::new();
+    section_body.extend_from_slice(&0x1A2B_3C4Du32.to_le_bytes());
+    section_body.extend_from_slice(&1u16.to_le_bytes());
+    section_body.extend_from_slice(&0u16.to_le_bytes());
+    section_body.extend_from_slice(&u64::MAX.to_le_bytes());
+    push_block(&mut file, 0x0A0D_0D0A, &section_body);
+
+    let mut interface_body = Vec::new();
+    interface_body.extend_from_slice(&link_type.to_le_bytes());
+    interface_body.extend_from_slice(&0u16.to_le_bytes());
+    interface_body.extend_from_slice(&65535u32.to_le_bytes());
+    if let Some(tsresol) = tsresol {
+        interface_body.extend_from_slice(&9u16.to_le_bytes());
+        interface_body.extend_from_slice(&1u16.to_le_bytes());
+        interface_body.extend_from_slice(&[tsresol, 0, 0, 0]);
+    }
+    if let Some(tsoffset) = tsoffset {
+        interface_body.extend_from_slice(&14u16.to_le_bytes());
+        interface_body.extend_from_slice(&8u16.to_le_bytes());
+        interface_body.extend_from_slice(&tsoffset.to_le_bytes());
+    }
+    if tsresol.is_some() || tsoffset.is_some() {
+        interface_body.extend_from_slice(&[0, 0, 0, 0]);
+    }
+    push_block(&mut file, 1, &interface_body);
+
+    let units_per_second = 10u128.pow(tsresol.unwrap_or(6) as u32);
+    for (timestamp, frame) in frames {
+        let units = (timestamp.as_nanos() * units_per_second / 1_000_000_000) as u64;
+        let mut packet_body = Vec::new();
+        packet_body.extend_from_slice(&0u32.to_le_bytes());
+        packet_body.extend_from_slice(&((units >> 32) as u32).to_le_bytes());
+        packet_body.extend_from_slice(&(units as u32).to_le_bytes());
+        packet_body.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        packet_body.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        packet_body.extend_from_slice(frame);
+        push_block(&mut file, 6, &packet_body);
+    }
+
+    file
+}
+
+fn push_block(file: &mut Vec<u8>, block_type: u32, body: &[u8]) {
+    let padding = body.len().div_ceil(4) * 4 - body.len();
+    let block_length = (12 + body.len() + padding) as u32;
+    file.extend_from_slice(&block_type.to_le_bytes());
+    file.extend_from_slice(&block_length.to_le_bytes());
+    file.extend_from_slice(body);
+    file.extend_from_slice(&vec![0; padding]);
+    file.extend_from_slice(&block_length.to_le_bytes());
+}
+
+/// An Ethernet frame carrying a TCP segment over IPv4 or IPv6, depending on the addresses.
+pub(crate) fn ethernet_tcp_frame(
+    source: SocketAddr,
+    destination: SocketAddr,
+    sequence_number: u32,
+    flags: u8,
+    payload: &[u8],
+) -> Vec<u8> {
+    let ip_packet = ip_tcp_packet(source, destination, sequence_number, flags, payload);
+    let ether_type: u16 = if source.is_ipv4() { 0x0800 } else { 0x86DD };
+    let mut frame = vec![0x02, 0, 0, 0, 0, 1, 0x02, 0, 0, 0, 0, 2];
+    frame.extend_from_slice(&ether_type.to_be_bytes());
+    frame.extend_from_slice(&ip_packet);
+    frame
+}
+
+pub(crate) fn ip_tcp_packet(
+    source: SocketAddr,
+    destination: SocketAddr,
+    sequence_number: u32,
+    flags: u8,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut segment = Vec::new();
+    segment.extend_from_slice(&source.port().to_be_bytes());
+    segment.extend_from_slice(&destination.port().to_be_bytes());
+    segment.extend_from_slice(&sequence_number.to_be_bytes());
+    segment.extend_from_slice(&0u32.to_be_bytes());
+    segment.extend_from_slice(&[0x50, flags]);
+    segment.extend_from_slice(&[0xFF, 0xFF, 0, 0, 0, 0]);
+    segment.extend_from_slice(payload);
+
+    let mut packet = Vec::new();
+    match (source, destination) {
+        (SocketAddr::V4(source), SocketAddr::V4(destination)) => {
+            packet.extend_from_slice(&[0x45, 0]);
+            packet.extend_from_slice(&((20 + segment.len()) as u16).to_be_bytes());
+            packet.extend_from_slice(&[0, 0, 0x40, 0, 64, 6, 0, 0]);
+            packet.extend_from_slice(&source.ip().octets());
+            packet.extend_from_slice(&destination.ip().octets());
+        }
+        (SocketAddr::V6(source), SocketAddr::V6(destination)) => {
+            packet.extend_from_slice(&[0x60, 0, 0, 0]);
+            packet.extend_from_slice(&(segment.len() as u16).to_be_bytes());
+            packet.extend_from_slice(&[6, 64]);
+            packet.extend_from_slice(&source.ip().octets());
+            packet.extend_from_slice(&destination.ip().octets());
+        }
+        _ => panic!("source and destination must use the same IP version"),
+    }
+    packet.extend_from_slice(&segment);
+    packet
+}
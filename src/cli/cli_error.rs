@@ -0,0 +1,56 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "serde")]
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::packet_parser::decode_error::DecodeError;
+
+#[allow(dead_code)]
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum CliError {
+    #[error("{0}")]
+    Usage(String),
+
+    #[error("from io::Error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid hex digit {1:?} at input position {0}")]
+    InvalidHex(usize, char),
+
+    #[error("Hex input has an odd number of digits")]
+    OddHexLength,
+
+    #[error("Invalid base64 character {1:?} at input position {0}")]
+    InvalidBase64(usize, char),
+
+    #[error("Base64 input ends with a partial byte")]
+    TruncatedBase64,
+
+    #[error("{0}")]
+    Decode(DecodeError),
+
+    #[error("{0} conformance statements failed")]
+    Conformance(usize),
+
+    #[cfg(feature = "serde")]
+    #[error("Invalid JSON packet: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[cfg(feature = "serde")]
+    #[error("{error} while encoding JSON packet {index}")]
+    Encode {
+        index: usize,
+        error: MqttProtocolError,
+    },
+}
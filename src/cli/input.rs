@@ -0,0 +1,183 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cli::cli_error::CliError;
+use crate::utils::hex::hex_handler;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InputFormat {
+    Hex,
+    Base64,
+    Raw,
+}
+
+#[allow(dead_code)]
+impl InputFormat {
+    pub(crate) fn parse(name: &str) -> Result<InputFormat, CliError> {
+        match name {
+            "hex" => Ok(InputFormat::Hex),
+            "base64" => Ok(InputFormat::Base64),
+            "raw" => Ok(InputFormat::Raw),
+            _ => Err(CliError::Usage(format!("unknown input format: {}", name))),
+        }
+    }
+
+    pub(crate) fn decode(&self, input: &[u8]) -> Result<Vec<u8>, CliError> {
+        match self {
+            InputFormat::Raw => Ok(input.to_vec()),
+            InputFormat::Hex => decode_hex(&String::from_utf8_lossy(input)),
+            InputFormat::Base64 => decode_base64(&String::from_utf8_lossy(input)),
+        }
+    }
+}
+
+fn is_hex_separator(character: char) -> bool {
+    character.is_whitespace() || matches!(character, ':' | ',' | '-')
+}
+
+/// Accepts what log lines usually contain: digits in either case, optionally split by
+/// whitespace, `:`, `,` or `-`, with an optional `0x` before each group.
+pub(crate) fn decode_hex(text: &str) -> Result<Vec<u8>, CliError> {
+    let characters: Vec<(usize, char)> = text.char_indices().collect();
+    let mut nibbles = Vec::new();
+    let mut index = 0;
+
+    while index < characters.len() {
+        let (position, character) = characters[index];
+        if is_hex_separator(character) {
+            index += 1;
+            continue;
+        }
+        let at_group_start = index == 0 || is_hex_separator(characters[index - 1].1);
+        let prefix_follows = matches!(characters.get(index + 1), Some((_, 'x' | 'X')));
+        if character == '0' && at_group_start && prefix_follows {
+            index += 2;
+            continue;
+        }
+        let nibble = character
+            .to_digit(16)
+            .ok_or(CliError::InvalidHex(position, character))?;
+        nibbles.push(nibble as u8);
+        index += 1;
+    }
+
+    if !nibbles.len().is_multiple_of(2) {
+        return Err(CliError::OddHexLength);
+    }
+    Ok(nibbles
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect())
+}
+
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    hex_handler::encode(bytes)
+}
+
+/// Standard or URL-safe alphabet; whitespace is ignored and padding is optional.
+pub(crate) fn decode_base64(text: &str) -> Result<Vec<u8>, CliError> {
+    let mut bytes = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut buffered_bits = 0;
+
+    for (position, character) in text.char_indices() {
+        if character.is_whitespace() {
+            continue;
+        }
+        if character == '=' {
+            break;
+        }
+        let value = match character {
+            'A'..='Z' => character as u32 - 'A' as u32,
+            'a'..='z' => character as u32 - 'a' as u32 + 26,
+            '0'..='9' => character as u32 - '0' as u32 + 52,
+            '+' | '-' => 62,
+            '/' | '_' => 63,
+            _ => return Err(CliError::InvalidBase64(position, character)),
+        };
+        buffer = (buffer << 6) | value;
+        buffered_bits += 6;
+        if buffered_bits >= 8 {
+            buffered_bits -= 8;
+            bytes.push((buffer >> buffered_bits) as u8);
+            buffer &= (1 << buffered_bits) - 1;
+        }
+    }
+
+    // a single leftover character only carries six bits, not enough for a byte
+    if buffered_bits >= 6 {
+        return Err(CliError::TruncatedBase64);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod input_tests {
+    use crate::cli::cli_error::CliError;
+    use crate::cli::input::{self, InputFormat};
+
+    #[test]
+    fn decode_hex_should_accept_common_log_layouts() {
+        let expect = vec![0x10, 0x0C, 0xAB];
+        assert_eq!(input::decode_hex("100cab").unwrap(), expect);
+        assert_eq!(input::decode_hex("10 0C AB\n").unwrap(), expect);
+        assert_eq!(input::decode_hex("0x10, 0x0c, 0xab").unwrap(), expect);
+        assert_eq!(input::decode_hex("10:0c:ab").unwrap(), expect);
+        assert_eq!(input::encode_hex(&expect), "100cab");
+    }
+
+    #[test]
+    fn decode_hex_should_report_invalid_digit_position() {
+        let result = input::decode_hex("10 0g");
+        assert!(matches!(result, Err(CliError::InvalidHex(4, 'g'))));
+        assert!(matches!(
+            input::decode_hex("100"),
+            Err(CliError::OddHexLength)
+        ));
+    }
+
+    #[test]
+    fn decode_base64_should_decode_with_and_without_padding() {
+        assert_eq!(input::decode_base64("wAA=").unwrap(), vec![0xC0, 0x00]);
+        assert_eq!(input::decode_base64("wAA").unwrap(), vec![0xC0, 0x00]);
+        assert_eq!(
+            input::decode_base64("EAw r").unwrap(),
+            vec![0x10, 0x0C, 0x2B]
+        );
+        assert_eq!(input::decode_base64("__8").unwrap(), vec![0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn decode_base64_should_reject_invalid_input() {
+        assert!(matches!(
+            input::decode_base64("wA*A"),
+            Err(CliError::InvalidBase64(2, '*'))
+        ));
+        assert!(matches!(
+            input::decode_base64("wAAAw"),
+            Err(CliError::TruncatedBase64)
+        ));
+    }
+
+    #[test]
+    fn input_format_raw_should_pass_bytes_through() {
+        let bytes = InputFormat::Raw.decode(&[0xC0, 0x00]).unwrap();
+        assert_eq!(bytes, vec![0xC0, 0x00]);
+        assert!(matches!(
+            InputFormat::parse("binary"),
+            Err(CliError::Usage(_))
+        ));
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+/// Just enough of JSON to print decoded packets. Object keys keep their insertion order so the
+/// output is stable.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(u64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(&'static str, JsonValue)>),
+}
+
+#[allow(dead_code)]
+impl JsonValue {
+    pub(crate) fn string(value: &str) -> JsonValue {
+        JsonValue::String(value.to_string())
+    }
+
+    pub(crate) fn optional_string(value: Option<&str>) -> JsonValue {
+        value.map_or(JsonValue::Null, JsonValue::string)
+    }
+}
+
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonValue::Null => f.write_str("null"),
+            JsonValue::Bool(value) => write!(f, "{}", value),
+            JsonValue::Number(value) => write!(f, "{}", value),
+            JsonValue::String(value) => write_escaped(f, value),
+            JsonValue::Array(values) => {
+                f.write_str("[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_str("]")
+            }
+            JsonValue::Object(fields) => {
+                f.write_str("{")?;
+                for (index, (key, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_escaped(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+fn write_escaped(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for character in value.chars() {
+        match character {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            character if (character as u32) < 0x20 => write!(f, "\\u{:04x}", character as u32)?,
+            character => write!(f, "{}", character)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+#[cfg(test)]
+mod json_tests {
+    use crate::cli::json::JsonValue;
+
+    #[test]
+    fn json_value_should_print_compact_json_in_insertion_order() {
+        let value = JsonValue::Object(vec![
+            ("type", JsonValue::string("PUBLISH")),
+            ("qos", JsonValue::Number(1)),
+            ("dup", JsonValue::Bool(false)),
+            ("packet_identifier", JsonValue::Null),
+            (
+                "codes",
+                JsonValue::Array(vec![JsonValue::Number(0), JsonValue::Number(128)]),
+            ),
+        ]);
+        assert_eq!(
+            value.to_string(),
+            r#"{"type":"PUBLISH","qos":1,"dup":false,"packet_identifier":null,"codes":[0,128]}"#
+        );
+    }
+
+    #[test]
+    fn json_value_should_escape_strings() {
+        let value = JsonValue::string("a\"b\\c\nd\u{1}");
+        assert_eq!(value.to_string(), r#""a\"b\\c\nd\u0001""#);
+    }
+}
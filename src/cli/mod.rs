@@ -0,0 +1,483 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod cli_error;
+mod input;
+mod json;
+pub(crate) mod packet_json;
+mod packet_text;
+
+use crate::cli::cli_error::CliError;
+use crate::cli::input::InputFormat;
+use crate::cli::json::JsonValue;
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::conformance::conformance_report::{ConformanceReport, StatementStatus};
+use crate::protocol::mqtt4::decoder_config::DecoderConfig;
+use crate::protocol::mqtt4::packet_parser::decode_error::DecodeError;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use bytes::BytesMut;
+use std::io::{Read, Write};
+
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_MALFORMED_PACKET: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+const USAGE: &str = "\
+Usage: mqtt-decode [OPTIONS] [FILE]
+
+Decodes every MQTT 3.1.1 control packet in FILE, or stdin when FILE is missing or `-`.
+
+Options:
+  -i, --input <hex|base64|raw>   Input encoding [default: hex]
+  -o, --output <text|json>       Output format; json prints one object per line [default: text]
+  -e, --encode                   Read JSON packets instead and print each one encoded as a hex
+                                 line; accepts the objects `-o json` prints, bare or in arrays
+  -l, --lenient                  Decode packets that break protocol rules and print the
+                                 violations as warnings instead of failing
+  -m, --max-packet-size <bytes>  Fail on packets announcing more bytes than this
+  -c, --conformance              Ignore the input and print which MQTT 3.1.1 normative
+                                 statements the codec passes
+  -h, --help                     Print this help
+
+Exit status is 0 when every byte decoded, 1 on a malformed packet or a failed conformance
+statement and 2 on bad usage or input.
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Options {
+    input_format: InputFormat,
+    output_format: OutputFormat,
+    path: Option<String>,
+    encode: bool,
+    lenient: bool,
+    max_packet_size: Option<usize>,
+    conformance: bool,
+    help: bool,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Options, CliError> {
+        let mut options = Options {
+            input_format: InputFormat::Hex,
+            output_format: OutputFormat::Text,
+            path: None,
+            encode: false,
+            lenient: false,
+            max_packet_size: None,
+            conformance: false,
+            help: false,
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => options.help = true,
+                "-e" | "--encode" if cfg!(feature = "serde") => options.encode = true,
+                "-e" | "--encode" => {
+                    return Err(CliError::Usage(format!(
+                        "{} needs mqtt-decode built with the serde feature",
+                        arg
+                    )));
+                }
+                "-l" | "--lenient" => options.lenient = true,
+                "-c" | "--conformance" => options.conformance = true,
+                "-m" | "--max-packet-size" => {
+                    let value = args.next().ok_or_else(|| missing_value(arg))?;
+                    let max_packet_size = value.parse().map_err(|_| {
+                        CliError::Usage(format!("invalid maximum packet size: {}", value))
+                    })?;
+                    options.max_packet_size = Some(max_packet_size);
+                }
+                "-i" | "--input" => {
+                    let value = args.next().ok_or_else(|| missing_value(arg))?;
+                    options.input_format = InputFormat::parse(value)?;
+                }
+                "-o" | "--output" => {
+                    let value = args.next().ok_or_else(|| missing_value(arg))?;
+                    options.output_format = match value.as_str() {
+                        "text" => OutputFormat::Text,
+                        "json" => OutputFormat::Json,
+                        _ => {
+                            return Err(CliError::Usage(format!(
+                                "unknown output format: {}",
+                                value
+                            )));
+                        }
+                    };
+                }
+                "-" => options.path = None,
+                flag if flag.starts_with('-') => {
+                    return Err(CliError::Usage(format!("unknown option: {}", flag)));
+                }
+                path if options.path.is_none() => options.path = Some(path.to_string()),
+                path => {
+                    return Err(CliError::Usage(format!("unexpected argument: {}", path)));
+                }
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn missing_value(flag: &str) -> CliError {
+    CliError::Usage(format!("{} needs a value", flag))
+}
+
+/// Entry point of the `mqtt-decode` binary. Returns the process exit status.
+pub fn run(
+    args: &[String],
+    stdin: &mut impl Read,
+    stdout: &mut impl Write,
+    stderr: &mut impl Write,
+) -> i32 {
+    match try_run(args, stdin, stdout) {
+        Ok(()) => EXIT_SUCCESS,
+        Err(error) => {
+            let _ = writeln!(stderr, "mqtt-decode: {}", error);
+            match error {
+                CliError::Decode(_) | CliError::Conformance(_) => EXIT_MALFORMED_PACKET,
+                #[cfg(feature = "serde")]
+                CliError::Json(_) | CliError::Encode { .. } => EXIT_MALFORMED_PACKET,
+                CliError::Usage(_) => {
+                    let _ = write!(stderr, "\n{}", USAGE);
+                    EXIT_USAGE
+                }
+                _ => EXIT_USAGE,
+            }
+        }
+    }
+}
+
+fn try_run(
+    args: &[String],
+    stdin: &mut impl Read,
+    stdout: &mut impl Write,
+) -> Result<(), CliError> {
+    let options = Options::parse(args)?;
+    if options.help {
+        stdout.write_all(USAGE.as_bytes())?;
+        return Ok(());
+    }
+    if options.conformance {
+        let report = ConformanceReport::generate();
+        write!(stdout, "{}", report)?;
+        return match report.count(StatementStatus::Failed) {
+            0 => Ok(()),
+            failed => Err(CliError::Conformance(failed)),
+        };
+    }
+
+    let mut input = Vec::new();
+    match &options.path {
+        Some(path) => input = std::fs::read(path)?,
+        None => {
+            stdin.read_to_end(&mut input)?;
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    if options.encode {
+        return encode_packets(&input, stdout);
+    }
+
+    let bytes = options.input_format.decode(&input)?;
+    let mut config = if options.lenient {
+        DecoderConfig::lenient()
+    } else {
+        DecoderConfig::strict()
+    };
+    if let Some(max_packet_size) = options.max_packet_size {
+        config = config.with_max_packet_size(max_packet_size);
+    }
+
+    let mut offset = 0;
+    let mut index = 0;
+    while offset < bytes.len() {
+        let frame_length =
+            match Packet::frame_length_with_max_size(&bytes[offset..], config.max_packet_size()) {
+                Ok(Some(frame_length)) => frame_length,
+                Ok(None) => {
+                    let error = DecodeError::from(MqttProtocolError::PacketTooShort);
+                    return Err(CliError::Decode(error.with_base_offset(offset)));
+                }
+                Err(error) => {
+                    let error = DecodeError::from(error);
+                    return Err(CliError::Decode(error.with_base_offset(offset)));
+                }
+            };
+        let mut frame = BytesMut::from(&bytes[offset..offset + frame_length]);
+        let decoded = Packet::decode_with_config(&mut frame, &config)
+            .map_err(|error| CliError::Decode(error.with_base_offset(offset)))?;
+
+        let fields = packet_json::packet_to_json(decoded.packet());
+        match options.output_format {
+            OutputFormat::Json => {
+                let mut line = vec![
+                    ("offset", JsonValue::Number(offset as u64)),
+                    ("length", JsonValue::Number(frame_length as u64)),
+                    ("packet", fields),
+                ];
+                if !decoded.is_clean() {
+                    let warnings = decoded
+                        .warnings()
+                        .iter()
+                        .map(|warning| JsonValue::String(warning.to_string()))
+                        .collect();
+                    line.push(("warnings", JsonValue::Array(warnings)));
+                }
+                writeln!(stdout, "{}", JsonValue::Object(line))?;
+            }
+            OutputFormat::Text => {
+                writeln!(
+                    stdout,
+                    "packet {} at byte offset {}, {} bytes",
+                    index, offset, frame_length
+                )?;
+                for warning in decoded.warnings() {
+                    writeln!(stdout, "  warning: {}", warning)?;
+                }
+                write!(stdout, "{}", packet_text::render(&fields, 2))?;
+            }
+        }
+
+        offset += frame_length;
+        index += 1;
+    }
+
+    Ok(())
+}
+
+/// Encodes every JSON packet in `input`. Values may be packets, `{"packet": ...}` wrappers as
+/// printed by `-o json`, or arrays of either.
+#[cfg(feature = "serde")]
+fn encode_packets(input: &[u8], stdout: &mut impl Write) -> Result<(), CliError> {
+    use crate::protocol::codec::Encoder;
+    use serde_json::Value;
+
+    fn collect(value: Value, packets: &mut Vec<Value>) {
+        match value {
+            Value::Array(values) => values.into_iter().for_each(|value| collect(value, packets)),
+            Value::Object(mut object) if object.contains_key("packet") => {
+                packets.push(object.remove("packet").unwrap_or_default())
+            }
+            value => packets.push(value),
+        }
+    }
+
+    let mut packets = Vec::new();
+    for value in serde_json::Deserializer::from_slice(input).into_iter::<Value>() {
+        collect(value?, &mut packets);
+    }
+
+    for (index, value) in packets.into_iter().enumerate() {
+        let mut packet: Packet = serde_json::from_value(value)?;
+        let bytes = packet
+            .encode()
+            .map_err(|error| CliError::Encode { index, error })?;
+        writeln!(stdout, "{}", input::encode_hex(&bytes))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use crate::cli::{self, EXIT_MALFORMED_PACKET, EXIT_SUCCESS, EXIT_USAGE};
+
+    fn run(args: &[&str], stdin: &[u8]) -> (i32, String, String) {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let code = cli::run(&args, &mut &stdin[..], &mut stdout, &mut stderr);
+        (
+            code,
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        )
+    }
+
+    #[test]
+    fn run_should_print_every_packet_as_text() {
+        let (code, stdout, stderr) = run(&[], b"c0 00\n82 08 00 01 00 03 61 2f 62 01");
+
+        assert_eq!(code, EXIT_SUCCESS, "{}", stderr);
+        assert_eq!(
+            stdout,
+            concat!(
+                "packet 0 at byte offset 0, 2 bytes\n",
+                "  type: \"PINGREQ\"\n",
+                "  fixed_header:\n",
+                "    flags: 0\n",
+                "    remaining_length: 0\n",
+                "packet 1 at byte offset 2, 10 bytes\n",
+                "  type: \"SUBSCRIBE\"\n",
+                "  fixed_header:\n",
+                "    flags: 2\n",
+                "    remaining_length: 8\n",
+                "  variable_header:\n",
+                "    packet_identifier: 1\n",
+                "  payload:\n",
+                "    subscriptions:\n",
+                "      [0]:\n",
+                "        topic_filter: \"a/b\"\n",
+                "        qos: 1\n",
+            )
+        );
+    }
+
+    #[test]
+    fn run_should_print_json_lines_from_base64() {
+        let (code, stdout, _) = run(&["--input", "base64", "-o", "json"], b"wADQAA==");
+
+        assert_eq!(code, EXIT_SUCCESS);
+        assert_eq!(
+            stdout,
+            concat!(
+                r#"{"offset":0,"length":2,"packet":{"type":"PINGREQ","fixed_header":{"flags":0,"remaining_length":0}}}"#,
+                "\n",
+                r#"{"offset":2,"length":2,"packet":{"type":"PINGRESP","fixed_header":{"flags":0,"remaining_length":0}}}"#,
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn run_should_read_raw_bytes() {
+        let (code, stdout, _) = run(&["-i", "raw"], &[0xE0, 0x00]);
+        assert_eq!(code, EXIT_SUCCESS);
+        assert!(stdout.contains("DISCONNECT"));
+    }
+
+    #[test]
+    fn run_should_fail_with_error_and_offset_on_malformed_packet() {
+        let (code, stdout, stderr) = run(&[], b"c0 00 c1 00");
+
+        assert_eq!(code, EXIT_MALFORMED_PACKET);
+        assert!(stdout.contains("PINGREQ"));
+        assert_eq!(
+            stderr,
+            concat!(
+                "mqtt-decode: This Control Packet type reserved flag is invalid in ",
+                "pingreq.fixed_header.flags at byte offset 2 (bytes c1)\n"
+            )
+        );
+    }
+
+    #[test]
+    fn run_should_print_warnings_for_violations_when_lenient() {
+        let (code, stdout, stderr) = run(&["--lenient"], b"c1 00");
+        assert_eq!(code, EXIT_SUCCESS, "{}", stderr);
+        assert!(stdout.starts_with(concat!(
+            "packet 0 at byte offset 0, 2 bytes\n",
+            "  warning: This Control Packet type reserved flag is invalid\n",
+            "  type: \"PINGREQ\"\n",
+        )));
+
+        let (code, stdout, _) = run(&["-l", "-o", "json"], b"c1 00");
+        assert_eq!(code, EXIT_SUCCESS);
+        assert!(stdout.ends_with(concat!(
+            r#""warnings":["This Control Packet type reserved flag is invalid"]}"#,
+            "\n"
+        )));
+    }
+
+    #[test]
+    fn run_should_fail_on_packets_over_the_maximum_size() {
+        let (code, stdout, stderr) = run(&["-m", "4"], b"c0 00 30 ff ff ff 7f");
+
+        assert_eq!(code, EXIT_MALFORMED_PACKET);
+        assert!(stdout.contains("PINGREQ"));
+        assert_eq!(
+            stderr,
+            concat!(
+                "mqtt-decode: Packet announces 268435460 bytes, more than the maximum packet ",
+                "size of 4 bytes at byte offset 2\n"
+            )
+        );
+        assert_eq!(run(&["--max-packet-size", "big"], b"").0, EXIT_USAGE);
+    }
+
+    #[test]
+    fn run_should_fail_on_incomplete_packet() {
+        let (code, _, stderr) = run(&[], b"30 05 00");
+        assert_eq!(code, EXIT_MALFORMED_PACKET);
+        assert_eq!(
+            stderr,
+            "mqtt-decode: Packet does not have enough bytes at byte offset 0\n"
+        );
+    }
+
+    #[test]
+    fn run_should_reject_bad_usage_and_input() {
+        assert_eq!(run(&["--output", "xml"], b"").0, EXIT_USAGE);
+        assert_eq!(run(&["a", "b"], b"").0, EXIT_USAGE);
+        assert_eq!(run(&[], b"zz").0, EXIT_USAGE);
+        assert_eq!(run(&["/nonexistent/input.hex"], b"").0, EXIT_USAGE);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn run_should_encode_json_packets_back_to_hex() {
+        let (_, json, _) = run(&["-o", "json"], b"c0 00 82 08 00 01 00 03 61 2f 62 01");
+
+        let (code, stdout, stderr) = run(&["--encode"], json.as_bytes());
+        assert_eq!(code, EXIT_SUCCESS, "{}", stderr);
+        assert_eq!(stdout, "c000\n820800010003612f6201\n");
+
+        let (code, stdout, _) = run(
+            &["-e"],
+            br#"[{"type":"PUBACK","variable_header":{"packet_identifier":5}},{"type":"DISCONNECT"}]"#,
+        );
+        assert_eq!(code, EXIT_SUCCESS);
+        assert_eq!(stdout, "40020005\ne000\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn run_should_reject_invalid_json_packets() {
+        let (code, _, stderr) = run(&["-e"], br#"{"type":"PUBLISH","fixed_header":{"qos":1},"variable_header":{"topic_name":"t"},"payload":{"application_message":""}}"#);
+        assert_eq!(code, EXIT_MALFORMED_PACKET);
+        assert!(stderr.contains("packet_identifier"), "{}", stderr);
+
+        assert_eq!(run(&["-e"], b"{").0, EXIT_MALFORMED_PACKET);
+    }
+
+    #[cfg(not(feature = "serde"))]
+    #[test]
+    fn run_should_reject_encode_without_serde() {
+        assert_eq!(run(&["--encode"], b"").0, EXIT_USAGE);
+    }
+
+    #[test]
+    fn run_should_print_the_conformance_report() {
+        let (code, stdout, _) = run(&["--conformance"], b"");
+        assert_eq!(code, EXIT_SUCCESS);
+        assert!(stdout.starts_with("MQTT 3.1.1 conformance: "));
+        assert!(stdout.contains("\npass  MQTT-3.1.2-3 "));
+        assert!(stdout.contains("\ntodo  MQTT-3.3.1-2 "));
+    }
+
+    #[test]
+    fn run_should_print_help() {
+        let (code, stdout, _) = run(&["--help"], b"");
+        assert_eq!(code, EXIT_SUCCESS);
+        assert!(stdout.starts_with("Usage: mqtt-decode"));
+    }
+}
@@ -0,0 +1,278 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cli::input;
+use crate::cli::json::JsonValue;
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use crate::protocol::mqtt4::payload_parser::connect_parser::payload::ConnectPayload;
+use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::ConnectVariableHeader;
+
+/// The field tree printed for a packet, shared by the text and the JSON output. Binary fields
+/// are lower case hex strings.
+pub(crate) fn packet_to_json(packet: &Packet) -> JsonValue {
+    let fixed = packet.fixed_header();
+    let mut fields = vec![
+        (
+            "type",
+            JsonValue::string(fixed.control_packet_type().name()),
+        ),
+        ("fixed_header", fixed_header_to_json(fixed)),
+    ];
+
+    match packet {
+        Packet::Connect {
+            variable, payload, ..
+        } => {
+            fields.push(("variable_header", connect_variable_header_to_json(variable)));
+            fields.push(("payload", connect_payload_to_json(payload)));
+        }
+        Packet::ConnAck { variable, .. } => {
+            fields.push((
+                "variable_header",
+                JsonValue::Object(vec![
+                    (
+                        "session_present",
+                        JsonValue::Bool(variable.session_present()),
+                    ),
+                    (
+                        "return_code",
+                        JsonValue::Number(variable.return_code().as_u8() as u64),
+                    ),
+                ]),
+            ));
+        }
+        Packet::Publish {
+            variable, payload, ..
+        } => {
+            fields.push((
+                "variable_header",
+                JsonValue::Object(vec![
+                    ("topic_name", JsonValue::string(variable.topic_name())),
+                    (
+                        "packet_identifier",
+                        variable
+                            .packet_identifier()
+                            .map_or(JsonValue::Null, |id| JsonValue::Number(id.get() as u64)),
+                    ),
+                ]),
+            ));
+            fields.push((
+                "payload",
+                JsonValue::Object(vec![(
+                    "application_message",
+                    JsonValue::String(input::encode_hex(payload.application_message())),
+                )]),
+            ));
+        }
+        Packet::PubAck { variable, .. } | Packet::UnsubAck { variable, .. } => {
+            fields.push(packet_identifier_json(variable.packet_identifier()));
+        }
+        Packet::PubRec { variable, .. } => {
+            fields.push(packet_identifier_json(variable.packet_identifier()));
+        }
+        Packet::PubRel { variable, .. } => {
+            fields.push(packet_identifier_json(variable.packet_identifier()));
+        }
+        Packet::PubComp { variable, .. } => {
+            fields.push(packet_identifier_json(variable.packet_identifier()));
+        }
+        Packet::Subscribe {
+            variable, payload, ..
+        } => {
+            fields.push(packet_identifier_json(variable.packet_identifier()));
+            let subscriptions = payload
+                .subscription_and_qos_tuples()
+                .iter()
+                .map(|(topic_filter, qos)| {
+                    JsonValue::Object(vec![
+                        ("topic_filter", JsonValue::string(topic_filter)),
+                        ("qos", JsonValue::Number(qos.as_u8() as u64)),
+                    ])
+                })
+                .collect();
+            fields.push((
+                "payload",
+                JsonValue::Object(vec![("subscriptions", JsonValue::Array(subscriptions))]),
+            ));
+        }
+        Packet::SubAck {
+            variable, payload, ..
+        } => {
+            fields.push(packet_identifier_json(variable.packet_identifier()));
+            let return_codes = payload
+                .return_codes()
+                .iter()
+                .map(|code| JsonValue::Number(code.as_u8() as u64))
+                .collect();
+            fields.push((
+                "payload",
+                JsonValue::Object(vec![("return_codes", JsonValue::Array(return_codes))]),
+            ));
+        }
+        Packet::Unsubscribe {
+            variable, payload, ..
+        } => {
+            fields.push(packet_identifier_json(variable.packet_identifier()));
+            let topics = payload
+                .topics()
+                .iter()
+                .map(|topic| JsonValue::string(topic))
+                .collect();
+            fields.push((
+                "payload",
+                JsonValue::Object(vec![("topic_filters", JsonValue::Array(topics))]),
+            ));
+        }
+        Packet::PingReq { .. } | Packet::PingResp { .. } | Packet::Disconnect { .. } => {}
+    }
+
+    JsonValue::Object(fields)
+}
+
+fn fixed_header_to_json(fixed: &FixedHeader) -> JsonValue {
+    let flags = fixed.fixed_header_reserved_flags();
+    let mut fields = vec![("flags", JsonValue::Number((flags.encode() & 0x0F) as u64))];
+    if let FixedHeaderFlags::Publish { dup, qos, retain } = flags {
+        fields.push(("dup", JsonValue::Bool(*dup)));
+        fields.push(("qos", JsonValue::Number(qos.as_u8() as u64)));
+        fields.push(("retain", JsonValue::Bool(*retain)));
+    }
+    fields.push((
+        "remaining_length",
+        JsonValue::Number(fixed.remaining_length() as u64),
+    ));
+    JsonValue::Object(fields)
+}
+
+fn packet_identifier_json(packet_identifier: PacketId) -> (&'static str, JsonValue) {
+    (
+        "variable_header",
+        JsonValue::Object(vec![(
+            "packet_identifier",
+            JsonValue::Number(packet_identifier.get() as u64),
+        )]),
+    )
+}
+
+fn connect_variable_header_to_json(variable: &ConnectVariableHeader) -> JsonValue {
+    let flags = variable.connect_flags();
+    JsonValue::Object(vec![
+        ("protocol_name", JsonValue::string(variable.protocol_name())),
+        (
+            "protocol_level",
+            JsonValue::Number(variable.protocol_level().as_u8() as u64),
+        ),
+        (
+            "connect_flags",
+            JsonValue::Object(vec![
+                ("username_flag", JsonValue::Bool(flags.username_flag())),
+                ("password_flag", JsonValue::Bool(flags.password_flag())),
+                ("will_retain", JsonValue::Bool(flags.will_retain())),
+                (
+                    "will_qos",
+                    JsonValue::Number(flags.will_qos().as_u8() as u64),
+                ),
+                ("will_flag", JsonValue::Bool(flags.will_flag())),
+                ("clean_session", JsonValue::Bool(flags.clean_session())),
+            ]),
+        ),
+        (
+            "keep_alive",
+            JsonValue::Number(variable.keep_alive() as u64),
+        ),
+    ])
+}
+
+fn connect_payload_to_json(payload: &ConnectPayload) -> JsonValue {
+    JsonValue::Object(vec![
+        ("client_id", JsonValue::string(payload.client_id())),
+        (
+            "will_topic",
+            JsonValue::optional_string(payload.will_topic()),
+        ),
+        (
+            "will_message",
+            payload.will_message().map_or(JsonValue::Null, |message| {
+                JsonValue::String(input::encode_hex(message))
+            }),
+        ),
+        ("username", JsonValue::optional_string(payload.username())),
+        ("password", JsonValue::optional_string(payload.password())),
+    ])
+}
+
+#[cfg(test)]
+mod packet_json_tests {
+    use crate::cli::packet_json;
+    use crate::protocol::codec::Decoder;
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+    use bytes::BytesMut;
+
+    fn decode(bytes: &[u8]) -> Packet {
+        Packet::decode(&mut BytesMut::from(bytes)).unwrap()
+    }
+
+    #[test]
+    fn packet_to_json_should_describe_connect() {
+        // CONNECT, clean session, keep alive 60, client id "c1", will "w" -> [0x01, 0xFF]
+        let packet = decode(&[
+            0x10, 0x15, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x0E, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1', 0x00, 0x01, b'w', 0x00, 0x02, 0x01, 0xFF,
+        ]);
+        assert_eq!(
+            packet_json::packet_to_json(&packet).to_string(),
+            concat!(
+                r#"{"type":"CONNECT","fixed_header":{"flags":0,"remaining_length":21},"#,
+                r#""variable_header":{"protocol_name":"MQTT","protocol_level":4,"#,
+                r#""connect_flags":{"username_flag":false,"password_flag":false,"#,
+                r#""will_retain":false,"will_qos":1,"will_flag":true,"clean_session":true},"#,
+                r#""keep_alive":60},"payload":{"client_id":"c1","will_topic":"w","#,
+                r#""will_message":"01ff","username":null,"password":null}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn packet_to_json_should_describe_publish_flags() {
+        // PUBLISH dup, QoS 1, retain to "a/b" with packet id 7 and message "hi"
+        let packet = decode(&[
+            0x3B, 0x09, 0x00, 0x03, b'a', b'/', b'b', 0x00, 0x07, b'h', b'i',
+        ]);
+        assert_eq!(
+            packet_json::packet_to_json(&packet).to_string(),
+            concat!(
+                r#"{"type":"PUBLISH","fixed_header":{"flags":11,"dup":true,"qos":1,"#,
+                r#""retain":true,"remaining_length":9},"variable_header":{"#,
+                r#""topic_name":"a/b","packet_identifier":7},"#,
+                r#""payload":{"application_message":"6869"}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn packet_to_json_should_describe_suback() {
+        let packet = decode(&[0x90, 0x04, 0x00, 0x0A, 0x01, 0x80]);
+        assert_eq!(
+            packet_json::packet_to_json(&packet).to_string(),
+            concat!(
+                r#"{"type":"SUBACK","fixed_header":{"flags":0,"remaining_length":4},"#,
+                r#""variable_header":{"packet_identifier":10},"#,
+                r#""payload":{"return_codes":[1,128]}}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::cli::json::JsonValue;
+use std::fmt::Write;
+
+/// Renders the field tree of a packet as indented `key: value` lines. Scalars and arrays of
+/// scalars stay on one line; objects open an indented block.
+pub(crate) fn render(value: &JsonValue, indent: usize) -> String {
+    let mut text = String::new();
+    if let JsonValue::Object(fields) = value {
+        for (key, value) in fields {
+            render_field(&mut text, key, value, indent);
+        }
+    }
+    text
+}
+
+fn render_field(text: &mut String, key: &str, value: &JsonValue, indent: usize) {
+    let padding = " ".repeat(indent);
+    match value {
+        JsonValue::Object(fields) => {
+            let _ = writeln!(text, "{}{}:", padding, key);
+            for (key, value) in fields {
+                render_field(text, key, value, indent + 2);
+            }
+        }
+        JsonValue::Array(values) if values.iter().any(is_object) => {
+            let _ = writeln!(text, "{}{}:", padding, key);
+            for (index, value) in values.iter().enumerate() {
+                render_field(text, &format!("[{}]", index), value, indent + 2);
+            }
+        }
+        value => {
+            let _ = writeln!(text, "{}{}: {}", padding, key, scalar(value));
+        }
+    }
+}
+
+fn is_object(value: &JsonValue) -> bool {
+    matches!(value, JsonValue::Object(_))
+}
+
+fn scalar(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Null => "-".to_string(),
+        JsonValue::Array(values) => {
+            let values: Vec<String> = values.iter().map(scalar).collect();
+            format!("[{}]", values.join(", "))
+        }
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod packet_text_tests {
+    use crate::cli::json::JsonValue;
+    use crate::cli::packet_text;
+
+    #[test]
+    fn render_should_indent_nested_objects_and_list_arrays() {
+        let value = JsonValue::Object(vec![
+            (
+                "fixed_header",
+                JsonValue::Object(vec![("remaining_length", JsonValue::Number(2))]),
+            ),
+            (
+                "payload",
+                JsonValue::Object(vec![
+                    (
+                        "subscriptions",
+                        JsonValue::Array(vec![JsonValue::Object(vec![(
+                            "topic_filter",
+                            JsonValue::string("a/#"),
+                        )])]),
+                    ),
+                    (
+                        "return_codes",
+                        JsonValue::Array(vec![JsonValue::Number(0), JsonValue::Number(128)]),
+                    ),
+                    ("username", JsonValue::Null),
+                ]),
+            ),
+        ]);
+
+        assert_eq!(
+            packet_text::render(&value, 2),
+            concat!(
+                "  fixed_header:\n",
+                "    remaining_length: 2\n",
+                "  payload:\n",
+                "    subscriptions:\n",
+                "      [0]:\n",
+                "        topic_filter: \"a/#\"\n",
+                "    return_codes: [0, 128]\n",
+                "    username: -\n",
+            )
+        );
+    }
+}
@@ -13,6 +13,8 @@
 // limitations under the License.
 
 pub(crate) mod byte_adapter;
+pub(crate) mod capture;
+pub mod cli;
 pub(crate) mod protocol;
 pub(crate) mod protocol_error;
 pub(crate) mod utils;
@@ -17,6 +17,11 @@ use crate::utils::radix::radix_handler;
 
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "&'static str")
+)]
 pub enum ControlPacketType {
     Connect = 1,
     ConnAck = 2,
@@ -77,6 +82,84 @@ impl ControlPacketType {
         };
         value << 4
     }
+
+    /// The upper case name the specification uses for the packet, e.g. `CONNACK`.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ControlPacketType::Connect => "CONNECT",
+            ControlPacketType::ConnAck => "CONNACK",
+            ControlPacketType::Publish => "PUBLISH",
+            ControlPacketType::PubAck => "PUBACK",
+            ControlPacketType::PubRec => "PUBREC",
+            ControlPacketType::PubRel => "PUBREL",
+            ControlPacketType::PubComp => "PUBCOMP",
+            ControlPacketType::Subscribe => "SUBSCRIBE",
+            ControlPacketType::SubAck => "SUBACK",
+            ControlPacketType::Unsubscribe => "UNSUBSCRIBE",
+            ControlPacketType::UnsubAck => "UNSUBACK",
+            ControlPacketType::PingReq => "PINGREQ",
+            ControlPacketType::PingResp => "PINGRESP",
+            ControlPacketType::Disconnect => "DISCONNECT",
+        }
+    }
+
+    /// Whether a client may send this packet to a server. A server receiving anything else
+    /// closes the network connection [MQTT-4.8.0-1].
+    pub(crate) fn sent_by_client(&self) -> bool {
+        !matches!(
+            self,
+            ControlPacketType::ConnAck
+                | ControlPacketType::SubAck
+                | ControlPacketType::UnsubAck
+                | ControlPacketType::PingResp
+        )
+    }
+
+    /// Whether a server may send this packet to a client.
+    pub(crate) fn sent_by_server(&self) -> bool {
+        !matches!(
+            self,
+            ControlPacketType::Connect
+                | ControlPacketType::Subscribe
+                | ControlPacketType::Unsubscribe
+                | ControlPacketType::PingReq
+                | ControlPacketType::Disconnect
+        )
+    }
+
+    pub(crate) fn from_name(name: &str) -> Result<ControlPacketType, MqttProtocolError> {
+        match name {
+            "CONNECT" => Ok(ControlPacketType::Connect),
+            "CONNACK" => Ok(ControlPacketType::ConnAck),
+            "PUBLISH" => Ok(ControlPacketType::Publish),
+            "PUBACK" => Ok(ControlPacketType::PubAck),
+            "PUBREC" => Ok(ControlPacketType::PubRec),
+            "PUBREL" => Ok(ControlPacketType::PubRel),
+            "PUBCOMP" => Ok(ControlPacketType::PubComp),
+            "SUBSCRIBE" => Ok(ControlPacketType::Subscribe),
+            "SUBACK" => Ok(ControlPacketType::SubAck),
+            "UNSUBSCRIBE" => Ok(ControlPacketType::Unsubscribe),
+            "UNSUBACK" => Ok(ControlPacketType::UnsubAck),
+            "PINGREQ" => Ok(ControlPacketType::PingReq),
+            "PINGRESP" => Ok(ControlPacketType::PingResp),
+            "DISCONNECT" => Ok(ControlPacketType::Disconnect),
+            _ => Err(MqttProtocolError::InvalidPacketType),
+        }
+    }
+}
+
+impl TryFrom<String> for ControlPacketType {
+    type Error = MqttProtocolError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        ControlPacketType::from_name(&value)
+    }
+}
+
+impl From<ControlPacketType> for &'static str {
+    fn from(value: ControlPacketType) -> Self {
+        value.name()
+    }
 }
 
 #[cfg(test)]
@@ -194,4 +277,36 @@ mod control_packet_type_tests {
         assert!(result.is_err());
         assert!(matches!(result, Err(MqttProtocolError::InvalidPacketType)));
     }
+
+    #[test]
+    fn control_packet_type_name_should_match_specification() {
+        assert_eq!(ControlPacketType::ConnAck.name(), "CONNACK");
+        assert_eq!(ControlPacketType::Unsubscribe.name(), "UNSUBSCRIBE");
+        assert_eq!(
+            ControlPacketType::from_name("PINGRESP").unwrap(),
+            ControlPacketType::PingResp
+        );
+        assert!(matches!(
+            ControlPacketType::from_name("pingresp"),
+            Err(MqttProtocolError::InvalidPacketType)
+        ));
+    }
+
+    #[test]
+    fn control_packet_type_direction_should_match_specification() {
+        let client_only = [
+            "CONNECT",
+            "SUBSCRIBE",
+            "UNSUBSCRIBE",
+            "PINGREQ",
+            "DISCONNECT",
+        ];
+        let server_only = ["CONNACK", "SUBACK", "UNSUBACK", "PINGRESP"];
+        for value in 1..=14u8 {
+            let packet_type = ControlPacketType::parse(value << 4).unwrap();
+            let name = packet_type.name();
+            assert_eq!(packet_type.sent_by_client(), !server_only.contains(&name));
+            assert_eq!(packet_type.sent_by_server(), !client_only.contains(&name));
+        }
+    }
 }
@@ -13,6 +13,10 @@
 // limitations under the License.
 
 pub mod control_packet_type;
+pub mod packet_id;
 pub mod protocol_level;
 pub mod qos;
 pub mod return_code;
+pub mod topic_error;
+pub mod topic_filter;
+pub mod topic_name;
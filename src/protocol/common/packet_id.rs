@@ -0,0 +1,108 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use std::fmt;
+use std::num::NonZeroU16;
+
+/// A Packet Identifier. The spec reserves 0, so every value is in 1..=65535.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u16", into = "u16")
+)]
+pub struct PacketId(NonZeroU16);
+
+#[allow(dead_code)]
+impl PacketId {
+    pub const MIN: PacketId = PacketId(NonZeroU16::MIN);
+    pub const MAX: PacketId = PacketId(NonZeroU16::MAX);
+
+    pub fn new(value: u16) -> Option<PacketId> {
+        NonZeroU16::new(value).map(PacketId)
+    }
+
+    pub(crate) fn parse(value: u16) -> Result<PacketId, MqttProtocolError> {
+        Self::new(value).ok_or(MqttProtocolError::ZeroPacketIdentifier)
+    }
+
+    pub fn get(&self) -> u16 {
+        self.0.get()
+    }
+
+    pub fn to_be_bytes(self) -> [u8; 2] {
+        self.get().to_be_bytes()
+    }
+}
+
+impl From<NonZeroU16> for PacketId {
+    fn from(value: NonZeroU16) -> Self {
+        PacketId(value)
+    }
+}
+
+impl TryFrom<u16> for PacketId {
+    type Error = MqttProtocolError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        PacketId::parse(value)
+    }
+}
+
+impl From<PacketId> for u16 {
+    fn from(value: PacketId) -> Self {
+        value.get()
+    }
+}
+
+impl From<PacketId> for NonZeroU16 {
+    fn from(value: PacketId) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for PacketId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod packet_id_tests {
+    use crate::protocol::common::packet_id::PacketId;
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+
+    #[test]
+    fn packet_id_should_reject_zero() {
+        assert_eq!(PacketId::new(0), None);
+        assert!(matches!(
+            PacketId::try_from(0),
+            Err(MqttProtocolError::ZeroPacketIdentifier)
+        ));
+    }
+
+    #[test]
+    fn packet_id_should_keep_every_non_zero_value() {
+        for value in [1, 0x1234, u16::MAX] {
+            let packet_id = PacketId::try_from(value).unwrap();
+            assert_eq!(packet_id.get(), value);
+            assert_eq!(u16::from(packet_id), value);
+            assert_eq!(packet_id.to_be_bytes(), value.to_be_bytes());
+        }
+        assert_eq!(PacketId::MIN.get(), 1);
+        assert_eq!(PacketId::MAX.to_string(), "65535");
+    }
+}
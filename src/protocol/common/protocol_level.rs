@@ -16,6 +16,11 @@ use crate::protocol::mqtt_protocol_error::MqttProtocolError;
 
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u8", into = "u8")
+)]
 pub enum ProtocolLevel {
     Mqtt3 = 3,
     Mqtt3_1_1 = 4,
@@ -24,7 +29,7 @@ pub enum ProtocolLevel {
 
 #[allow(dead_code)]
 impl ProtocolLevel {
-    pub(in crate::protocol) fn parse(level: u8) -> Result<ProtocolLevel, MqttProtocolError> {
+    pub(crate) fn parse(level: u8) -> Result<ProtocolLevel, MqttProtocolError> {
         match level {
             3 => Ok(ProtocolLevel::Mqtt3),
             4 => Ok(ProtocolLevel::Mqtt3_1_1),
@@ -33,7 +38,7 @@ impl ProtocolLevel {
         }
     }
 
-    pub(in crate::protocol) fn as_u8(&self) -> u8 {
+    pub(crate) fn as_u8(&self) -> u8 {
         match self {
             ProtocolLevel::Mqtt3 => 3,
             ProtocolLevel::Mqtt3_1_1 => 4,
@@ -42,6 +47,20 @@ impl ProtocolLevel {
     }
 }
 
+impl TryFrom<u8> for ProtocolLevel {
+    type Error = MqttProtocolError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        ProtocolLevel::parse(value)
+    }
+}
+
+impl From<ProtocolLevel> for u8 {
+    fn from(value: ProtocolLevel) -> Self {
+        value.as_u8()
+    }
+}
+
 #[cfg(test)]
 mod protocol_level_tests {
     use crate::protocol::common::protocol_level::ProtocolLevel;
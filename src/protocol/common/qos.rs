@@ -15,8 +15,14 @@
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
 use std::convert::TryFrom;
 
+/// Ordered by delivery guarantee, so the lower of two QoS levels is their `min`.
 #[allow(dead_code)]
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u8", into = "u8")
+)]
 pub enum QoSCode {
     Qos0 = 0,
     Qos1 = 1,
@@ -25,7 +31,7 @@ pub enum QoSCode {
 
 #[allow(dead_code)]
 impl QoSCode {
-    pub(in crate::protocol) fn parse(byte: u8) -> Result<QoSCode, MqttProtocolError> {
+    pub(crate) fn parse(byte: u8) -> Result<QoSCode, MqttProtocolError> {
         match byte {
             0 => Ok(QoSCode::Qos0),
             1 => Ok(QoSCode::Qos1),
@@ -33,7 +39,7 @@ impl QoSCode {
             _ => Err(MqttProtocolError::QoSLevelNotSupported(byte)),
         }
     }
-    pub(in crate::protocol) fn as_u8(&self) -> u8 {
+    pub(crate) fn as_u8(&self) -> u8 {
         match self {
             QoSCode::Qos0 => 0,
             QoSCode::Qos1 => 1,
@@ -50,6 +56,12 @@ impl TryFrom<u8> for QoSCode {
     }
 }
 
+impl From<QoSCode> for u8 {
+    fn from(value: QoSCode) -> Self {
+        value.as_u8()
+    }
+}
+
 #[cfg(test)]
 mod qos_code_tests {
     use crate::protocol::common::qos::QoSCode;
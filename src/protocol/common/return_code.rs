@@ -15,6 +15,11 @@
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u8", into = "u8")
+)]
 pub enum ReturnCode {
     ConnectionAccepted = 0,
     UnacceptableProtocolVersion = 1,
@@ -50,6 +55,20 @@ impl ReturnCode {
     }
 }
 
+impl TryFrom<u8> for ReturnCode {
+    type Error = MqttProtocolError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        ReturnCode::parse(value)
+    }
+}
+
+impl From<ReturnCode> for u8 {
+    fn from(value: ReturnCode) -> Self {
+        value.as_u8()
+    }
+}
+
 #[cfg(test)]
 mod return_code_tests {
     use crate::protocol::common::return_code::ReturnCode;
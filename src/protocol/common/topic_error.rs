@@ -0,0 +1,37 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Why a string is not a valid topic name or topic filter. Positions are byte offsets into the
+/// string.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq, Clone, thiserror::Error)]
+pub enum TopicError {
+    #[error("topic must be at least one character long")]
+    Empty,
+
+    #[error("topic is {0} bytes long, the limit is 65535")]
+    TooLong(usize),
+
+    #[error("topic contains U+0000 at byte {0}")]
+    NullCharacter(usize),
+
+    #[error("topic name contains the wildcard {1:?} at byte {0}")]
+    WildcardInTopicName(usize, char),
+
+    #[error("wildcard {1:?} at byte {0} does not occupy an entire level")]
+    WildcardNotWholeLevel(usize, char),
+
+    #[error("multi-level wildcard at byte {0} is not the last level")]
+    MultiLevelWildcardNotLast(usize),
+}
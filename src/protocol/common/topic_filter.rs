@@ -0,0 +1,174 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::topic_error::TopicError;
+use crate::protocol::common::topic_name::{
+    MULTI_LEVEL_WILDCARD, SINGLE_LEVEL_WILDCARD, TOPIC_LEVEL_SEPARATOR, verify_topic_string,
+};
+use std::fmt;
+
+/// A topic filter as carried by SUBSCRIBE and UNSUBSCRIBE: the topic name rules, except that `+`
+/// may fill a whole level and `#` may fill the last one.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct TopicFilter(String);
+
+#[allow(dead_code)]
+impl TopicFilter {
+    pub fn new(topic_filter: impl Into<String>) -> Result<TopicFilter, TopicError> {
+        let topic_filter = topic_filter.into();
+        Self::verify(&topic_filter)?;
+        Ok(TopicFilter(topic_filter))
+    }
+
+    pub(crate) fn verify(topic_filter: &str) -> Result<(), TopicError> {
+        verify_topic_string(topic_filter)?;
+
+        let mut level_start = 0;
+        let mut levels = topic_filter.split(TOPIC_LEVEL_SEPARATOR).peekable();
+        while let Some(level) = levels.next() {
+            if let Some(offset) = level.find([SINGLE_LEVEL_WILDCARD, MULTI_LEVEL_WILDCARD]) {
+                let position = level_start + offset;
+                let wildcard = level[offset..].chars().next().unwrap_or_default();
+                if level.len() != 1 {
+                    return Err(TopicError::WildcardNotWholeLevel(position, wildcard));
+                }
+                if wildcard == MULTI_LEVEL_WILDCARD && levels.peek().is_some() {
+                    return Err(TopicError::MultiLevelWildcardNotLast(position));
+                }
+            }
+            level_start += level.len() + TOPIC_LEVEL_SEPARATOR.len_utf8();
+        }
+        Ok(())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn levels(&self) -> impl Iterator<Item = &str> {
+        self.0.split(TOPIC_LEVEL_SEPARATOR)
+    }
+
+    pub fn has_wildcards(&self) -> bool {
+        self.0
+            .contains([SINGLE_LEVEL_WILDCARD, MULTI_LEVEL_WILDCARD])
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl TryFrom<String> for TopicFilter {
+    type Error = TopicError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        TopicFilter::new(value)
+    }
+}
+
+impl TryFrom<&str> for TopicFilter {
+    type Error = TopicError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        TopicFilter::new(value)
+    }
+}
+
+impl From<TopicFilter> for String {
+    fn from(value: TopicFilter) -> Self {
+        value.0
+    }
+}
+
+impl AsRef<str> for TopicFilter {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for TopicFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod topic_filter_tests {
+    use crate::protocol::common::topic_error::TopicError;
+    use crate::protocol::common::topic_filter::TopicFilter;
+
+    #[test]
+    fn topic_filter_should_accept_valid_filters() {
+        for filter in [
+            "#",
+            "+",
+            "a/b",
+            "sport/tennis/#",
+            "sport/+",
+            "+/+",
+            "/+",
+            "+/tennis/#",
+            "a//+",
+            "$SYS/#",
+        ] {
+            assert_eq!(TopicFilter::new(filter).unwrap().as_str(), filter);
+        }
+        assert!(TopicFilter::new("a/+").unwrap().has_wildcards());
+        assert!(!TopicFilter::new("a/b").unwrap().has_wildcards());
+    }
+
+    #[test]
+    fn topic_filter_should_reject_misplaced_multi_level_wildcard() {
+        assert_eq!(
+            TopicFilter::new("a/#/b"),
+            Err(TopicError::MultiLevelWildcardNotLast(2))
+        );
+        assert_eq!(
+            TopicFilter::new("sport/tennis#"),
+            Err(TopicError::WildcardNotWholeLevel(12, '#'))
+        );
+        assert_eq!(
+            TopicFilter::new("#/"),
+            Err(TopicError::MultiLevelWildcardNotLast(0))
+        );
+    }
+
+    #[test]
+    fn topic_filter_should_reject_partial_single_level_wildcard() {
+        assert_eq!(
+            TopicFilter::new("sport+"),
+            Err(TopicError::WildcardNotWholeLevel(5, '+'))
+        );
+        assert_eq!(
+            TopicFilter::new("a/++"),
+            Err(TopicError::WildcardNotWholeLevel(2, '+'))
+        );
+    }
+
+    #[test]
+    fn topic_filter_should_reject_empty_and_null() {
+        assert_eq!(TopicFilter::new(""), Err(TopicError::Empty));
+        assert_eq!(
+            TopicFilter::new("a\u{0}"),
+            Err(TopicError::NullCharacter(1))
+        );
+    }
+}
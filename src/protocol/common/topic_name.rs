@@ -0,0 +1,170 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::topic_error::TopicError;
+use std::fmt;
+
+pub(crate) const TOPIC_LEVEL_SEPARATOR: char = '/';
+pub(crate) const SINGLE_LEVEL_WILDCARD: char = '+';
+pub(crate) const MULTI_LEVEL_WILDCARD: char = '#';
+
+/// A topic name as carried by PUBLISH: non-empty, at most 65535 bytes, no U+0000 and no
+/// wildcard characters.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct TopicName(String);
+
+#[allow(dead_code)]
+impl TopicName {
+    pub fn new(topic_name: impl Into<String>) -> Result<TopicName, TopicError> {
+        let topic_name = topic_name.into();
+        Self::verify(&topic_name)?;
+        Ok(TopicName(topic_name))
+    }
+
+    pub(crate) fn verify(topic_name: &str) -> Result<(), TopicError> {
+        verify_topic_string(topic_name)?;
+        if let Some((position, wildcard)) = topic_name
+            .char_indices()
+            .find(|(_, c)| *c == SINGLE_LEVEL_WILDCARD || *c == MULTI_LEVEL_WILDCARD)
+        {
+            return Err(TopicError::WildcardInTopicName(position, wildcard));
+        }
+        Ok(())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn levels(&self) -> impl Iterator<Item = &str> {
+        self.0.split(TOPIC_LEVEL_SEPARATOR)
+    }
+
+    /// Topics starting with `$` are reserved for server use, e.g. `$SYS/...`.
+    pub fn is_system(&self) -> bool {
+        self.0.starts_with('$')
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+/// Rules shared by topic names and topic filters.
+pub(crate) fn verify_topic_string(topic: &str) -> Result<(), TopicError> {
+    if topic.is_empty() {
+        return Err(TopicError::Empty);
+    }
+    if topic.len() > u16::MAX as usize {
+        return Err(TopicError::TooLong(topic.len()));
+    }
+    if let Some(position) = topic.find('\u{0}') {
+        return Err(TopicError::NullCharacter(position));
+    }
+    Ok(())
+}
+
+impl TryFrom<String> for TopicName {
+    type Error = TopicError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        TopicName::new(value)
+    }
+}
+
+impl TryFrom<&str> for TopicName {
+    type Error = TopicError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        TopicName::new(value)
+    }
+}
+
+impl From<TopicName> for String {
+    fn from(value: TopicName) -> Self {
+        value.0
+    }
+}
+
+impl AsRef<str> for TopicName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for TopicName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod topic_name_tests {
+    use crate::protocol::common::topic_error::TopicError;
+    use crate::protocol::common::topic_name::TopicName;
+
+    #[test]
+    fn topic_name_should_accept_valid_names() {
+        for name in [
+            "a",
+            "/",
+            "a/b/c",
+            "/finance",
+            "a//b",
+            " ",
+            "$SYS/uptime",
+            "sport/tennis/",
+        ] {
+            assert_eq!(TopicName::new(name).unwrap().as_str(), name);
+        }
+        assert_eq!(
+            TopicName::new("a//b").unwrap().levels().collect::<Vec<_>>(),
+            vec!["a", "", "b"]
+        );
+    }
+
+    #[test]
+    fn topic_name_should_reject_empty_and_null() {
+        assert_eq!(TopicName::new(""), Err(TopicError::Empty));
+        assert_eq!(TopicName::new("a/\u{0}"), Err(TopicError::NullCharacter(2)));
+        assert_eq!(
+            TopicName::new("a".repeat(65536)),
+            Err(TopicError::TooLong(65536))
+        );
+    }
+
+    #[test]
+    fn topic_name_should_reject_wildcards() {
+        assert_eq!(
+            TopicName::new("a/+/c"),
+            Err(TopicError::WildcardInTopicName(2, '+'))
+        );
+        assert_eq!(
+            TopicName::new("sport#"),
+            Err(TopicError::WildcardInTopicName(5, '#'))
+        );
+    }
+
+    #[test]
+    fn topic_name_should_report_system_topics() {
+        assert!(TopicName::new("$SYS/broker").unwrap().is_system());
+        assert!(!TopicName::new("SYS/$broker").unwrap().is_system());
+    }
+}
@@ -12,7 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-mod codec;
+pub(crate) mod codec;
 pub(crate) mod common;
 pub(crate) mod mqtt4;
 pub(crate) mod mqtt_protocol_error;
+pub(crate) mod sparkplug_b;
+pub(crate) mod topic_matching;
@@ -0,0 +1,1103 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::common::return_code::ReturnCode;
+use crate::protocol::common::topic_filter::TopicFilter;
+use crate::protocol::common::topic_name::TopicName;
+use crate::protocol::mqtt4::client::client_error::ClientError;
+use crate::protocol::mqtt4::client::client_event::ClientEvent;
+use crate::protocol::mqtt4::delivery::at_least_once::AtLeastOnceTracker;
+use crate::protocol::mqtt4::delivery::exactly_once::{ExactlyOnceReceiver, ExactlyOnceSender};
+use crate::protocol::mqtt4::delivery::packet_id_allocator::PacketIdAllocator;
+use crate::protocol::mqtt4::delivery::session_resume::SessionResume;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
+use crate::protocol::mqtt4::keep_alive::clock::Clock;
+use crate::protocol::mqtt4::keep_alive::keep_alive_supervisor::{
+    KeepAliveAction, KeepAliveSupervisor,
+};
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use crate::protocol::mqtt4::payload_parser::connect_parser::payload::ConnectPayload;
+use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::ConnectVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+    PublishQoS, PublishVariableHeader,
+};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Where a `ClientConnection` is in its life.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientState {
+    /// No CONNECT has been sent, or the last connection is closed.
+    Disconnected,
+    /// The CONNECT is sent and its CONNACK has not arrived.
+    Connecting,
+    /// The server accepted the CONNECT.
+    Connected,
+}
+
+/// The client side of an MQTT 3.1.1 connection without any I/O.
+///
+/// The caller owns the transport: it feeds every decoded packet to `handle_packet`, calls
+/// `handle_tick` once `next_deadline` has passed, writes whatever `poll_transmit` returns and
+/// hands `poll_event` results to the application. Time only comes from the clock the engine is
+/// built with, so tests can step a `ManualClock` instead of sleeping.
+#[allow(dead_code)]
+pub(crate) struct ClientConnection<C: Clock + Clone> {
+    clock: C,
+    state: ClientState,
+    clean_session: bool,
+    ping_timeout: Option<Duration>,
+    keep_alive: Option<KeepAliveSupervisor<C>>,
+    packet_identifiers: PacketIdAllocator,
+    pending_subscribes: HashMap<PacketId, Vec<String>>,
+    pending_unsubscribes: HashMap<PacketId, Vec<String>>,
+    at_least_once: AtLeastOnceTracker,
+    exactly_once_sender: ExactlyOnceSender,
+    exactly_once_receiver: ExactlyOnceReceiver,
+    publish_order: VecDeque<PacketId>,
+    transmits: VecDeque<Packet>,
+    events: VecDeque<ClientEvent>,
+}
+
+/// How many QoS 1 and how many QoS 2 messages `ClientConnection::new` lets wait for their acks.
+pub(crate) const DEFAULT_MAX_INFLIGHT: usize = u16::MAX as usize;
+
+#[allow(dead_code)]
+impl<C: Clock + Clone> ClientConnection<C> {
+    pub(crate) fn new(clock: C) -> Self {
+        Self::with_max_inflight(clock, DEFAULT_MAX_INFLIGHT)
+    }
+
+    /// An engine that lets at most `max_inflight` QoS 1 and `max_inflight` QoS 2 messages wait
+    /// for their acks.
+    pub(crate) fn with_max_inflight(clock: C, max_inflight: usize) -> Self {
+        ClientConnection {
+            clock,
+            state: ClientState::Disconnected,
+            clean_session: true,
+            ping_timeout: None,
+            keep_alive: None,
+            packet_identifiers: PacketIdAllocator::new(),
+            pending_subscribes: HashMap::new(),
+            pending_unsubscribes: HashMap::new(),
+            at_least_once: AtLeastOnceTracker::new(max_inflight),
+            exactly_once_sender: ExactlyOnceSender::new(max_inflight),
+            exactly_once_receiver: ExactlyOnceReceiver::new(),
+            publish_order: VecDeque::new(),
+            transmits: VecDeque::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// How long to wait for a PINGRESP before giving up on the connection, instead of one Keep
+    /// Alive period. Applies from the next `connect`.
+    pub(crate) fn with_ping_timeout(mut self, ping_timeout: Duration) -> Self {
+        self.ping_timeout = Some(ping_timeout);
+        self
+    }
+
+    pub(crate) fn state(&self) -> ClientState {
+        self.state
+    }
+
+    /// Queues the CONNECT. Its Keep Alive drives the PINGREQ schedule once the server accepts.
+    pub(crate) fn connect(
+        &mut self,
+        variable: ConnectVariableHeader,
+        payload: ConnectPayload,
+    ) -> Result<(), ClientError> {
+        if self.state != ClientState::Disconnected {
+            return Err(ClientError::AlreadyConnected);
+        }
+        self.clean_session = variable.connect_flags().clean_session();
+        // messages still waiting for an ack belong to the session
+        if self.clean_session {
+            self.packet_identifiers = PacketIdAllocator::new();
+            self.at_least_once = AtLeastOnceTracker::new(self.at_least_once.max_inflight());
+            self.exactly_once_sender =
+                ExactlyOnceSender::new(self.exactly_once_sender.max_inflight());
+            self.exactly_once_receiver = ExactlyOnceReceiver::new();
+            self.publish_order.clear();
+        }
+        let keep_alive = KeepAliveSupervisor::client(self.clock.clone(), variable.keep_alive());
+        self.keep_alive = Some(match self.ping_timeout {
+            Some(ping_timeout) => keep_alive.with_ping_timeout(ping_timeout),
+            None => keep_alive,
+        });
+        self.state = ClientState::Connecting;
+        self.send(Packet::connect(variable, payload));
+        Ok(())
+    }
+
+    /// Queues a PUBLISH. QoS 1 and QoS 2 messages get a packet identifier, returned here and
+    /// in the `Published` event once the server has acknowledged them.
+    pub(crate) fn publish(
+        &mut self,
+        topic_name: String,
+        qos: QoSCode,
+        retain: bool,
+        payload: PublishPayload,
+    ) -> Result<Option<PacketId>, ClientError> {
+        self.ensure_connected()?;
+        TopicName::verify(&topic_name).map_err(ClientError::InvalidTopicName)?;
+        if qos == QoSCode::Qos0 {
+            let variable = PublishVariableHeader::new(topic_name, PublishQoS::AtMostOnce);
+            self.send(Packet::publish(false, retain, variable, payload));
+            return Ok(None);
+        }
+
+        let packet_identifier = self.packet_identifiers.allocate()?;
+        let result = if qos == QoSCode::Qos1 {
+            let variable =
+                PublishVariableHeader::new(topic_name, PublishQoS::AtLeastOnce(packet_identifier));
+            self.at_least_once.publish(retain, variable, payload)
+        } else {
+            let variable =
+                PublishVariableHeader::new(topic_name, PublishQoS::ExactlyOnce(packet_identifier));
+            self.exactly_once_sender.publish(retain, variable, payload)
+        };
+        match result {
+            Ok(packet) => {
+                self.send(packet);
+                self.publish_order.push_back(packet_identifier);
+                Ok(Some(packet_identifier))
+            }
+            Err(error) => {
+                self.packet_identifiers.release(packet_identifier);
+                Err(error.into())
+            }
+        }
+    }
+
+    /// Queues a SUBSCRIBE and returns the packet identifier its SUBACK will carry.
+    pub(crate) fn subscribe(
+        &mut self,
+        subscriptions: Vec<(String, QoSCode)>,
+    ) -> Result<PacketId, ClientError> {
+        self.ensure_connected()?;
+        // [MQTT-3.8.3-3]
+        if subscriptions.is_empty() {
+            return Err(ClientError::NoTopicFilters("SUBSCRIBE"));
+        }
+        let topic_filters = subscriptions
+            .iter()
+            .map(|(topic_filter, _)| Self::verify_topic_filter(topic_filter))
+            .collect::<Result<Vec<_>, _>>()?;
+        let packet_identifier = self.packet_identifiers.allocate()?;
+        self.pending_subscribes
+            .insert(packet_identifier, topic_filters);
+        self.send(Packet::subscribe(packet_identifier, subscriptions));
+        Ok(packet_identifier)
+    }
+
+    /// Queues an UNSUBSCRIBE and returns the packet identifier its UNSUBACK will carry.
+    pub(crate) fn unsubscribe(
+        &mut self,
+        topic_filters: Vec<String>,
+    ) -> Result<PacketId, ClientError> {
+        self.ensure_connected()?;
+        // [MQTT-3.10.3-2]
+        if topic_filters.is_empty() {
+            return Err(ClientError::NoTopicFilters("UNSUBSCRIBE"));
+        }
+        for topic_filter in &topic_filters {
+            Self::verify_topic_filter(topic_filter)?;
+        }
+        let packet_identifier = self.packet_identifiers.allocate()?;
+        self.pending_unsubscribes
+            .insert(packet_identifier, topic_filters.clone());
+        self.send(Packet::unsubscribe(packet_identifier, topic_filters));
+        Ok(packet_identifier)
+    }
+
+    /// Queues a DISCONNECT and closes the connection; the transport should close the network
+    /// connection once the DISCONNECT is written [MQTT-3.14.4-1].
+    pub(crate) fn disconnect(&mut self) -> Result<(), ClientError> {
+        if self.state == ClientState::Disconnected {
+            return Err(ClientError::NotConnected);
+        }
+        self.send(Packet::disconnect());
+        self.close();
+        Ok(())
+    }
+
+    /// Tells the engine the transport lost the network connection.
+    pub(crate) fn connection_lost(&mut self) {
+        self.close();
+    }
+
+    /// Processes a packet received from the server. An error closes the connection.
+    pub(crate) fn handle_packet(&mut self, packet: Packet) -> Result<(), ClientError> {
+        if let Some(keep_alive) = self.keep_alive.as_mut() {
+            keep_alive.packet_received(packet.fixed_header().control_packet_type());
+        }
+        let result = match self.state {
+            ClientState::Disconnected => return Err(ClientError::NotConnected),
+            ClientState::Connecting => self.handle_conn_ack(packet),
+            ClientState::Connected => self.handle_connected_packet(packet),
+        };
+        if result.is_err() {
+            self.close();
+        }
+        result
+    }
+
+    /// Sends a PINGREQ when nothing has been sent for a whole Keep Alive period
+    /// [MQTT-3.1.2-23], and closes the connection when its PINGRESP has not arrived within the
+    /// ping timeout.
+    pub(crate) fn handle_tick(&mut self) -> Result<(), ClientError> {
+        if self.state != ClientState::Connected {
+            return Ok(());
+        }
+        match self
+            .keep_alive
+            .as_mut()
+            .and_then(|keep_alive| keep_alive.poll())
+        {
+            Some(KeepAliveAction::SendPingReq) => self.send(Packet::ping_req()),
+            Some(KeepAliveAction::Close(_)) => {
+                self.close();
+                return Err(ClientError::PingRespMissing);
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// When `handle_tick` has something to do next, if ever.
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        match self.state {
+            ClientState::Connected => self.keep_alive.as_ref()?.next_deadline(),
+            ClientState::Connecting | ClientState::Disconnected => None,
+        }
+    }
+
+    pub(crate) fn poll_transmit(&mut self) -> Option<Packet> {
+        self.transmits.pop_front()
+    }
+
+    pub(crate) fn poll_event(&mut self) -> Option<ClientEvent> {
+        self.events.pop_front()
+    }
+
+    fn handle_conn_ack(&mut self, packet: Packet) -> Result<(), ClientError> {
+        // the first packet from the server must be a CONNACK [MQTT-3.2.0-1]
+        let variable = match packet {
+            Packet::ConnAck { variable, .. } => variable,
+            other => return Err(ClientError::UnexpectedPacket(Self::packet_name(&other))),
+        };
+        let session_present = variable.session_present();
+        if *variable.return_code() != ReturnCode::ConnectionAccepted {
+            // [MQTT-3.2.2-4]
+            if session_present {
+                return Err(ClientError::SessionPresentOnRefusal);
+            }
+            return Err(ClientError::ConnectionRefused(
+                variable.return_code().clone(),
+            ));
+        }
+        // [MQTT-3.2.2-1]
+        if session_present && self.clean_session {
+            return Err(ClientError::SessionPresentOnCleanSession);
+        }
+        self.state = ClientState::Connected;
+        self.events
+            .push_back(ClientEvent::Connected { session_present });
+        if !self.clean_session {
+            let resume = SessionResume::capture(
+                self.publish_order.iter().copied(),
+                &self.at_least_once,
+                &self.exactly_once_sender,
+            );
+            for packet in resume.retransmissions() {
+                self.send(packet);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_connected_packet(&mut self, packet: Packet) -> Result<(), ClientError> {
+        match packet {
+            Packet::Publish {
+                fixed,
+                variable,
+                payload,
+            } => {
+                let (dup, retain) = match fixed.fixed_header_reserved_flags() {
+                    FixedHeaderFlags::Publish { dup, retain, .. } => (*dup, *retain),
+                    _ => (false, false),
+                };
+                let deliver = match variable.qos() {
+                    QoSCode::Qos0 => true,
+                    QoSCode::Qos1 => {
+                        let pub_ack = AtLeastOnceTracker::pub_ack_for(&variable)?;
+                        self.send(pub_ack);
+                        true
+                    }
+                    QoSCode::Qos2 => {
+                        let received = self.exactly_once_receiver.handle_publish(&variable)?;
+                        self.send(received.pub_rec);
+                        received.deliver
+                    }
+                };
+                if deliver {
+                    self.events.push_back(ClientEvent::Message {
+                        dup,
+                        retain,
+                        variable,
+                        payload,
+                    });
+                }
+            }
+            Packet::SubAck {
+                variable, payload, ..
+            } => {
+                let packet_identifier = variable.packet_identifier();
+                let topic_filters = self.pending_subscribes.remove(&packet_identifier).ok_or(
+                    ClientError::UnknownPacketIdentifier("SUBACK", packet_identifier),
+                )?;
+                self.packet_identifiers.release(packet_identifier);
+                let return_codes = payload.return_codes();
+                // [MQTT-3.9.3-1]
+                if return_codes.len() != topic_filters.len() {
+                    return Err(ClientError::SubAckLengthMismatch(
+                        topic_filters.len(),
+                        return_codes.len(),
+                    ));
+                }
+                let results = topic_filters
+                    .into_iter()
+                    .zip(return_codes.iter().copied())
+                    .collect();
+                self.events.push_back(ClientEvent::Subscribed {
+                    packet_identifier,
+                    results,
+                });
+            }
+            Packet::UnsubAck { variable, .. } => {
+                let packet_identifier = variable.packet_identifier();
+                let topic_filters = self.pending_unsubscribes.remove(&packet_identifier).ok_or(
+                    ClientError::UnknownPacketIdentifier("UNSUBACK", packet_identifier),
+                )?;
+                self.packet_identifiers.release(packet_identifier);
+                self.events.push_back(ClientEvent::Unsubscribed {
+                    packet_identifier,
+                    topic_filters,
+                });
+            }
+            // the keep alive supervisor has seen it already
+            Packet::PingResp { .. } => {}
+            Packet::PubAck { variable, .. } => {
+                let message = self.at_least_once.acknowledge(&variable)?;
+                self.published(message.packet_identifier());
+            }
+            Packet::PubRec { variable, .. } => {
+                let pub_rel = self.exactly_once_sender.handle_pub_rec(&variable)?;
+                self.send(pub_rel);
+            }
+            Packet::PubRel { variable, .. } => {
+                let pub_comp = self.exactly_once_receiver.handle_pub_rel(&variable);
+                self.send(pub_comp);
+            }
+            Packet::PubComp { variable, .. } => {
+                let packet_identifier = self.exactly_once_sender.handle_pub_comp(&variable)?;
+                self.published(packet_identifier);
+            }
+            // a second CONNACK and every packet only a client sends
+            other => return Err(ClientError::UnexpectedPacket(Self::packet_name(&other))),
+        }
+        Ok(())
+    }
+
+    fn ensure_connected(&self) -> Result<(), ClientError> {
+        match self.state {
+            ClientState::Connected => Ok(()),
+            _ => Err(ClientError::NotConnected),
+        }
+    }
+
+    fn verify_topic_filter(topic_filter: &str) -> Result<String, ClientError> {
+        TopicFilter::verify(topic_filter).map_err(ClientError::InvalidTopicFilter)?;
+        Ok(topic_filter.to_string())
+    }
+
+    fn published(&mut self, packet_identifier: PacketId) {
+        self.packet_identifiers.release(packet_identifier);
+        self.publish_order
+            .retain(|published| *published != packet_identifier);
+        self.events
+            .push_back(ClientEvent::Published { packet_identifier });
+    }
+
+    fn send(&mut self, packet: Packet) {
+        self.transmits.push_back(packet);
+        if let Some(keep_alive) = self.keep_alive.as_mut() {
+            keep_alive.packet_sent();
+        }
+    }
+
+    fn close(&mut self) {
+        self.state = ClientState::Disconnected;
+        self.keep_alive = None;
+        // SUBSCRIBE and UNSUBSCRIBE are not sent again on a new connection
+        let pending = self
+            .pending_subscribes
+            .drain()
+            .chain(self.pending_unsubscribes.drain());
+        for (packet_identifier, _) in pending {
+            self.packet_identifiers.release(packet_identifier);
+        }
+    }
+
+    fn packet_name(packet: &Packet) -> &'static str {
+        packet.fixed_header().control_packet_type().name()
+    }
+}
+
+#[cfg(test)]
+mod client_connection_tests {
+    use crate::protocol::codec::Encoder;
+    use crate::protocol::common::control_packet_type::ControlPacketType;
+    use crate::protocol::common::packet_id::PacketId;
+    use crate::protocol::common::protocol_level::ProtocolLevel;
+    use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::common::return_code::ReturnCode;
+    use crate::protocol::mqtt4::client::client_connection::{ClientConnection, ClientState};
+    use crate::protocol::mqtt4::client::client_error::ClientError;
+    use crate::protocol::mqtt4::client::client_event::ClientEvent;
+    use crate::protocol::mqtt4::delivery::delivery_error::DeliveryError;
+    use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
+    use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
+    use crate::protocol::mqtt4::keep_alive::clock::{Clock, ManualClock};
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+    use crate::protocol::mqtt4::payload_parser::connect_parser::payload::ConnectPayload;
+    use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+    use crate::protocol::mqtt4::payload_parser::sub_ack_parser::payload::{
+        SubAckPayload, SubAckReturnCode,
+    };
+    use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::{
+        ConnectFlags, ConnectVariableHeader,
+    };
+    use crate::protocol::mqtt4::variable_header_parser::pub_ack_parser::variable_header::PubAckVariableHeader;
+    use crate::protocol::mqtt4::variable_header_parser::pub_rel_parser::variable_header::PubRelVariableHeader;
+    use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+        PublishQoS, PublishVariableHeader,
+    };
+    use crate::protocol::mqtt4::variable_header_parser::sub_ack_parser::variable_header::SubAckVariableHeader;
+    use bytes::Bytes;
+    use std::time::Duration;
+
+    fn connect(client: &mut Client, keep_alive: u16, clean_session: bool) {
+        let flags =
+            ConnectFlags::new(false, false, false, QoSCode::Qos0, false, clean_session).unwrap();
+        let variable = ConnectVariableHeader::new(ProtocolLevel::Mqtt3_1_1, flags, keep_alive);
+        let payload = ConnectPayload::new("client".to_string(), None, None, None, None);
+        client.connect(variable, payload).unwrap();
+    }
+
+    type Client = ClientConnection<ManualClock>;
+
+    fn connected(clock: &ManualClock, keep_alive: u16) -> Client {
+        let mut client = ClientConnection::new(clock.clone());
+        connect(&mut client, keep_alive, true);
+        client
+            .handle_packet(Packet::conn_ack(false, ReturnCode::ConnectionAccepted))
+            .unwrap();
+        transmitted(&mut client);
+        client.poll_event();
+        client
+    }
+
+    fn transmitted(client: &mut Client) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+        while let Some(mut packet) = client.poll_transmit() {
+            packets.push(packet.encode().unwrap());
+        }
+        packets
+    }
+
+    fn sub_ack(packet_identifier: u16, return_codes: Vec<SubAckReturnCode>) -> Packet {
+        Packet::SubAck {
+            fixed: FixedHeader::new(ControlPacketType::SubAck, FixedHeaderFlags::SubAck),
+            variable: SubAckVariableHeader::new(PacketId::new(packet_identifier).unwrap()),
+            payload: SubAckPayload::new(return_codes),
+        }
+    }
+
+    fn unsub_ack(packet_identifier: u16) -> Packet {
+        Packet::UnsubAck {
+            fixed: FixedHeader::new(ControlPacketType::UnsubAck, FixedHeaderFlags::UnsubAck),
+            variable: PubAckVariableHeader::new(PacketId::new(packet_identifier).unwrap()),
+        }
+    }
+
+    fn ping_resp() -> Packet {
+        Packet::PingResp {
+            fixed: FixedHeader::new(ControlPacketType::PingResp, FixedHeaderFlags::PingResp),
+        }
+    }
+
+    #[test]
+    fn connect_should_send_connect_and_report_the_conn_ack() {
+        let clock = ManualClock::new();
+        let mut client = ClientConnection::new(clock.clone());
+        connect(&mut client, 30, true);
+
+        let packets = transmitted(&mut client);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0][0], 0x10);
+        assert_eq!(client.state(), ClientState::Connecting);
+        assert_eq!(
+            client.subscribe(vec![("a".to_string(), QoSCode::Qos0)]),
+            Err(ClientError::NotConnected)
+        );
+
+        client
+            .handle_packet(Packet::conn_ack(false, ReturnCode::ConnectionAccepted))
+            .unwrap();
+        assert_eq!(client.state(), ClientState::Connected);
+        assert_eq!(
+            client.poll_event(),
+            Some(ClientEvent::Connected {
+                session_present: false
+            })
+        );
+        assert_eq!(client.poll_event(), None);
+    }
+
+    #[test]
+    fn conn_ack_should_be_validated() {
+        let clock = ManualClock::new();
+
+        let mut client = ClientConnection::new(clock.clone());
+        connect(&mut client, 30, true);
+        assert_eq!(
+            client.handle_packet(Packet::conn_ack(false, ReturnCode::NotAuthorized)),
+            Err(ClientError::ConnectionRefused(ReturnCode::NotAuthorized))
+        );
+        assert_eq!(client.state(), ClientState::Disconnected);
+
+        let mut client = ClientConnection::new(clock.clone());
+        connect(&mut client, 30, true);
+        assert_eq!(
+            client.handle_packet(Packet::conn_ack(true, ReturnCode::ConnectionAccepted)),
+            Err(ClientError::SessionPresentOnCleanSession)
+        );
+
+        let mut client = ClientConnection::new(clock.clone());
+        connect(&mut client, 30, false);
+        assert_eq!(
+            client.handle_packet(Packet::conn_ack(true, ReturnCode::ServerUnavailable)),
+            Err(ClientError::SessionPresentOnRefusal)
+        );
+
+        let mut client = ClientConnection::new(clock.clone());
+        connect(&mut client, 30, false);
+        assert_eq!(
+            client.handle_packet(ping_resp()),
+            Err(ClientError::UnexpectedPacket("PINGRESP"))
+        );
+        assert_eq!(client.state(), ClientState::Disconnected);
+        assert_eq!(
+            client.handle_packet(Packet::conn_ack(false, ReturnCode::ConnectionAccepted)),
+            Err(ClientError::NotConnected)
+        );
+
+        let mut client = connected(&clock, 30);
+        assert_eq!(
+            client.handle_packet(Packet::conn_ack(false, ReturnCode::ConnectionAccepted)),
+            Err(ClientError::UnexpectedPacket("CONNACK"))
+        );
+    }
+
+    #[test]
+    fn tick_should_send_pingreq_after_a_keep_alive_period_of_silence() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        let mut client = connected(&clock, 10);
+        assert_eq!(
+            client.next_deadline(),
+            Some(start + Duration::from_secs(10))
+        );
+
+        // sending anything restarts the period
+        clock.advance(Duration::from_secs(4));
+        client
+            .subscribe(vec![("a".to_string(), QoSCode::Qos0)])
+            .unwrap();
+        transmitted(&mut client);
+        clock.advance(Duration::from_secs(6));
+        client.handle_tick().unwrap();
+        assert!(transmitted(&mut client).is_empty());
+        assert_eq!(
+            client.next_deadline(),
+            Some(start + Duration::from_secs(14))
+        );
+
+        clock.advance(Duration::from_secs(4));
+        client.handle_tick().unwrap();
+        assert_eq!(transmitted(&mut client), vec![vec![0xC0, 0x00]]);
+        // the PINGRESP is due one Keep Alive period after the PINGREQ
+        assert_eq!(
+            client.next_deadline(),
+            Some(start + Duration::from_secs(24))
+        );
+
+        clock.advance(Duration::from_secs(1));
+        client.handle_packet(ping_resp()).unwrap();
+        assert_eq!(
+            client.next_deadline(),
+            Some(start + Duration::from_secs(24))
+        );
+        clock.advance(Duration::from_secs(5));
+        client.handle_tick().unwrap();
+        assert!(transmitted(&mut client).is_empty());
+        assert_eq!(client.state(), ClientState::Connected);
+    }
+
+    #[test]
+    fn zero_keep_alive_should_never_ping() {
+        let clock = ManualClock::new();
+        let mut client = connected(&clock, 0);
+        assert_eq!(client.next_deadline(), None);
+        clock.advance(Duration::from_secs(100_000));
+        client.handle_tick().unwrap();
+        assert!(transmitted(&mut client).is_empty());
+    }
+
+    #[test]
+    fn missing_pingresp_should_close_the_connection() {
+        let clock = ManualClock::new();
+        let mut client = connected(&clock, 10);
+        clock.advance(Duration::from_secs(10));
+        client.handle_tick().unwrap();
+        assert_eq!(transmitted(&mut client), vec![vec![0xC0, 0x00]]);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(client.handle_tick(), Err(ClientError::PingRespMissing));
+        assert_eq!(client.state(), ClientState::Disconnected);
+        assert_eq!(client.next_deadline(), None);
+    }
+
+    #[test]
+    fn ping_timeout_should_bound_the_wait_for_pingresp() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        let mut client =
+            ClientConnection::new(clock.clone()).with_ping_timeout(Duration::from_secs(3));
+        connect(&mut client, 10, true);
+        client
+            .handle_packet(Packet::conn_ack(false, ReturnCode::ConnectionAccepted))
+            .unwrap();
+        transmitted(&mut client);
+
+        clock.advance(Duration::from_secs(10));
+        client.handle_tick().unwrap();
+        assert_eq!(transmitted(&mut client), vec![vec![0xC0, 0x00]]);
+        assert_eq!(
+            client.next_deadline(),
+            Some(start + Duration::from_secs(13))
+        );
+        clock.advance(Duration::from_secs(2));
+        client.handle_tick().unwrap();
+        assert_eq!(client.state(), ClientState::Connected);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(client.handle_tick(), Err(ClientError::PingRespMissing));
+        assert_eq!(client.state(), ClientState::Disconnected);
+    }
+
+    #[test]
+    fn sub_ack_should_be_correlated_by_packet_identifier() {
+        let clock = ManualClock::new();
+        let mut client = connected(&clock, 0);
+        let first = client
+            .subscribe(vec![
+                ("a/+".to_string(), QoSCode::Qos1),
+                ("b/#".to_string(), QoSCode::Qos2),
+            ])
+            .unwrap();
+        let second = client
+            .subscribe(vec![("c".to_string(), QoSCode::Qos0)])
+            .unwrap();
+        assert_eq!((first.get(), second.get()), (1, 2));
+        assert_eq!(
+            transmitted(&mut client),
+            vec![
+                vec![
+                    0x82, 0x0E, 0x00, 0x01, 0x00, 0x03, b'a', b'/', b'+', 0x01, 0x00, 0x03, b'b',
+                    b'/', b'#', 0x02
+                ],
+                vec![0x82, 0x06, 0x00, 0x02, 0x00, 0x01, b'c', 0x00],
+            ]
+        );
+
+        client
+            .handle_packet(sub_ack(2, vec![SubAckReturnCode::Qos0]))
+            .unwrap();
+        client
+            .handle_packet(sub_ack(
+                1,
+                vec![SubAckReturnCode::Qos1, SubAckReturnCode::Failure],
+            ))
+            .unwrap();
+        assert_eq!(
+            client.poll_event(),
+            Some(ClientEvent::Subscribed {
+                packet_identifier: second,
+                results: vec![("c".to_string(), SubAckReturnCode::Qos0)],
+            })
+        );
+        assert_eq!(
+            client.poll_event(),
+            Some(ClientEvent::Subscribed {
+                packet_identifier: first,
+                results: vec![
+                    ("a/+".to_string(), SubAckReturnCode::Qos1),
+                    ("b/#".to_string(), SubAckReturnCode::Failure),
+                ],
+            })
+        );
+
+        // the identifier is free again once acknowledged
+        assert_eq!(
+            client.handle_packet(sub_ack(1, vec![SubAckReturnCode::Qos1])),
+            Err(ClientError::UnknownPacketIdentifier(
+                "SUBACK",
+                PacketId::new(1).unwrap()
+            ))
+        );
+        assert_eq!(client.state(), ClientState::Disconnected);
+    }
+
+    #[test]
+    fn sub_ack_should_carry_one_return_code_per_topic_filter() {
+        let clock = ManualClock::new();
+        let mut client = connected(&clock, 0);
+        client
+            .subscribe(vec![
+                ("a".to_string(), QoSCode::Qos1),
+                ("b".to_string(), QoSCode::Qos1),
+            ])
+            .unwrap();
+        assert_eq!(
+            client.handle_packet(sub_ack(1, vec![SubAckReturnCode::Qos1])),
+            Err(ClientError::SubAckLengthMismatch(2, 1))
+        );
+    }
+
+    #[test]
+    fn unsub_ack_should_be_correlated_by_packet_identifier() {
+        let clock = ManualClock::new();
+        let mut client = connected(&clock, 0);
+        let packet_identifier = client.unsubscribe(vec!["a/b".to_string()]).unwrap();
+        assert_eq!(
+            transmitted(&mut client),
+            vec![vec![0xA2, 0x07, 0x00, 0x01, 0x00, 0x03, b'a', b'/', b'b']]
+        );
+
+        client.handle_packet(unsub_ack(1)).unwrap();
+        assert_eq!(
+            client.poll_event(),
+            Some(ClientEvent::Unsubscribed {
+                packet_identifier,
+                topic_filters: vec!["a/b".to_string()],
+            })
+        );
+        assert_eq!(
+            client.handle_packet(unsub_ack(1)),
+            Err(ClientError::UnknownPacketIdentifier(
+                "UNSUBACK",
+                packet_identifier
+            ))
+        );
+    }
+
+    #[test]
+    fn requests_should_be_checked_before_sending() {
+        let clock = ManualClock::new();
+        let mut client = connected(&clock, 0);
+        assert_eq!(
+            client.subscribe(Vec::new()),
+            Err(ClientError::NoTopicFilters("SUBSCRIBE"))
+        );
+        assert_eq!(
+            client.unsubscribe(Vec::new()),
+            Err(ClientError::NoTopicFilters("UNSUBSCRIBE"))
+        );
+        assert!(matches!(
+            client.subscribe(vec![("a/b#".to_string(), QoSCode::Qos0)]),
+            Err(ClientError::InvalidTopicFilter(_))
+        ));
+        assert!(transmitted(&mut client).is_empty());
+        assert_eq!(client.state(), ClientState::Connected);
+    }
+
+    #[test]
+    fn packet_identifiers_should_be_released_by_acks_and_closing() {
+        let clock = ManualClock::new();
+        let mut client = connected(&clock, 0);
+        let subscribed = client
+            .subscribe(vec![("a".to_string(), QoSCode::Qos0)])
+            .unwrap();
+        let unsubscribed = client.unsubscribe(vec!["a".to_string()]).unwrap();
+        assert!(client.packet_identifiers.is_in_use(subscribed));
+        assert!(client.packet_identifiers.is_in_use(unsubscribed));
+
+        client
+            .handle_packet(sub_ack(1, vec![SubAckReturnCode::Qos0]))
+            .unwrap();
+        assert!(!client.packet_identifiers.is_in_use(subscribed));
+
+        client.connection_lost();
+        assert_eq!(client.packet_identifiers.in_use_len(), 0);
+    }
+
+    #[test]
+    fn publish_should_be_tracked_until_acknowledged() {
+        let clock = ManualClock::new();
+        let mut client = connected(&clock, 0);
+        let payload = || PublishPayload::new(Bytes::from_static(b"x"));
+        assert_eq!(
+            client.publish("t".to_string(), QoSCode::Qos0, false, payload()),
+            Ok(None)
+        );
+        let qos1 = client
+            .publish("t".to_string(), QoSCode::Qos1, false, payload())
+            .unwrap()
+            .unwrap();
+        let qos2 = client
+            .publish("t".to_string(), QoSCode::Qos2, true, payload())
+            .unwrap()
+            .unwrap();
+        assert_eq!((qos1.get(), qos2.get()), (1, 2));
+        assert_eq!(
+            transmitted(&mut client),
+            vec![
+                vec![0x30, 0x04, 0x00, 0x01, b't', b'x'],
+                vec![0x32, 0x06, 0x00, 0x01, b't', 0x00, 0x01, b'x'],
+                vec![0x35, 0x06, 0x00, 0x01, b't', 0x00, 0x02, b'x'],
+            ]
+        );
+
+        client.handle_packet(Packet::pub_ack(qos1)).unwrap();
+        client.handle_packet(Packet::pub_rec(qos2)).unwrap();
+        assert_eq!(transmitted(&mut client), vec![vec![0x62, 0x02, 0x00, 0x02]]);
+        assert!(client.packet_identifiers.is_in_use(qos2));
+        client.handle_packet(Packet::pub_comp(qos2)).unwrap();
+        assert_eq!(
+            client.poll_event(),
+            Some(ClientEvent::Published {
+                packet_identifier: qos1
+            })
+        );
+        assert_eq!(
+            client.poll_event(),
+            Some(ClientEvent::Published {
+                packet_identifier: qos2
+            })
+        );
+        assert_eq!(client.packet_identifiers.in_use_len(), 0);
+
+        assert!(matches!(
+            client.publish("t/#".to_string(), QoSCode::Qos0, false, payload()),
+            Err(ClientError::InvalidTopicName(_))
+        ));
+        assert_eq!(
+            client.handle_packet(Packet::pub_ack(qos1)),
+            Err(ClientError::Delivery(DeliveryError::DuplicateAck(
+                "PUBACK", qos1
+            )))
+        );
+        assert_eq!(client.state(), ClientState::Disconnected);
+    }
+
+    #[test]
+    fn publish_should_release_the_identifier_when_the_window_is_full() {
+        let clock = ManualClock::new();
+        let mut client = ClientConnection::with_max_inflight(clock.clone(), 1);
+        connect(&mut client, 0, true);
+        client
+            .handle_packet(Packet::conn_ack(false, ReturnCode::ConnectionAccepted))
+            .unwrap();
+        let payload = || PublishPayload::new(Bytes::from_static(b"x"));
+        client
+            .publish("t".to_string(), QoSCode::Qos1, false, payload())
+            .unwrap();
+        assert_eq!(
+            client.publish("t".to_string(), QoSCode::Qos1, false, payload()),
+            Err(ClientError::Delivery(DeliveryError::InflightWindowFull(1)))
+        );
+        assert_eq!(client.packet_identifiers.in_use_len(), 1);
+    }
+
+    #[test]
+    fn publish_should_be_delivered_as_a_message() {
+        let clock = ManualClock::new();
+        let mut client = connected(&clock, 0);
+        let variable = PublishVariableHeader::new("a/b".to_string(), PublishQoS::AtMostOnce);
+        let payload = PublishPayload::new(Bytes::from_static(b"hi"));
+        client
+            .handle_packet(Packet::publish(
+                false,
+                true,
+                variable.clone(),
+                payload.clone(),
+            ))
+            .unwrap();
+        assert_eq!(
+            client.poll_event(),
+            Some(ClientEvent::Message {
+                dup: false,
+                retain: true,
+                variable,
+                payload,
+            })
+        );
+    }
+
+    #[test]
+    fn qos_1_publish_should_be_answered_with_puback() {
+        let clock = ManualClock::new();
+        let mut client = connected(&clock, 0);
+        let variable = PublishVariableHeader::new(
+            "a".to_string(),
+            PublishQoS::AtLeastOnce(PacketId::new(5).unwrap()),
+        );
+        client
+            .handle_packet(Packet::publish(
+                false,
+                false,
+                variable,
+                PublishPayload::new(Bytes::from_static(b"x")),
+            ))
+            .unwrap();
+        assert_eq!(transmitted(&mut client), vec![vec![0x40, 0x02, 0x00, 0x05]]);
+        assert!(matches!(
+            client.poll_event(),
+            Some(ClientEvent::Message { .. })
+        ));
+    }
+
+    #[test]
+    fn qos_2_publish_should_be_delivered_once_until_pubrel() {
+        let clock = ManualClock::new();
+        let mut client = connected(&clock, 0);
+        let publish = |dup| {
+            let variable = PublishVariableHeader::new(
+                "a".to_string(),
+                PublishQoS::ExactlyOnce(PacketId::new(5).unwrap()),
+            );
+            Packet::publish(
+                dup,
+                false,
+                variable,
+                PublishPayload::new(Bytes::from_static(b"x")),
+            )
+        };
+        client.handle_packet(publish(false)).unwrap();
+        client.handle_packet(publish(true)).unwrap();
+        assert_eq!(
+            transmitted(&mut client),
+            vec![vec![0x50, 0x02, 0x00, 0x05], vec![0x50, 0x02, 0x00, 0x05]]
+        );
+        assert!(matches!(
+            client.poll_event(),
+            Some(ClientEvent::Message { dup: false, .. })
+        ));
+        assert_eq!(client.poll_event(), None);
+
+        let pub_rel = Packet::PubRel {
+            fixed: FixedHeader::new(ControlPacketType::PubRel, FixedHeaderFlags::PubRel),
+            variable: PubRelVariableHeader::new(PacketId::new(5).unwrap()),
+        };
+        client.handle_packet(pub_rel).unwrap();
+        assert_eq!(transmitted(&mut client), vec![vec![0x70, 0x02, 0x00, 0x05]]);
+    }
+
+    #[test]
+    fn reconnecting_without_clean_session_should_resend_unacknowledged_messages() {
+        let clock = ManualClock::new();
+        let mut client = ClientConnection::new(clock.clone());
+        connect(&mut client, 0, false);
+        client
+            .handle_packet(Packet::conn_ack(false, ReturnCode::ConnectionAccepted))
+            .unwrap();
+        let payload = || PublishPayload::new(Bytes::from_static(b"x"));
+        for qos in [QoSCode::Qos1, QoSCode::Qos2, QoSCode::Qos1, QoSCode::Qos2] {
+            client
+                .publish("t".to_string(), qos, false, payload())
+                .unwrap();
+        }
+        client
+            .handle_packet(Packet::pub_ack(PacketId::new(1).unwrap()))
+            .unwrap();
+        client
+            .handle_packet(Packet::pub_rec(PacketId::new(4).unwrap()))
+            .unwrap();
+        client
+            .handle_packet(Packet::pub_rec(PacketId::new(2).unwrap()))
+            .unwrap();
+        transmitted(&mut client);
+        client.connection_lost();
+
+        connect(&mut client, 0, false);
+        client
+            .handle_packet(Packet::conn_ack(true, ReturnCode::ConnectionAccepted))
+            .unwrap();
+        let packets = transmitted(&mut client);
+        assert_eq!(packets[0][0], 0x10);
+        assert_eq!(
+            packets[1..],
+            [
+                vec![0x62, 0x02, 0x00, 0x04],
+                vec![0x3A, 0x06, 0x00, 0x01, b't', 0x00, 0x03, b'x'],
+                vec![0x62, 0x02, 0x00, 0x02],
+            ]
+        );
+
+        // a clean session drops them
+        client.connection_lost();
+        connect(&mut client, 0, true);
+        client
+            .handle_packet(Packet::conn_ack(false, ReturnCode::ConnectionAccepted))
+            .unwrap();
+        assert_eq!(transmitted(&mut client).len(), 1);
+    }
+
+    #[test]
+    fn disconnect_should_send_disconnect_and_close() {
+        let clock = ManualClock::new();
+        let mut client = connected(&clock, 0);
+        client
+            .subscribe(vec![("a".to_string(), QoSCode::Qos0)])
+            .unwrap();
+        transmitted(&mut client);
+
+        client.disconnect().unwrap();
+        assert_eq!(transmitted(&mut client), vec![vec![0xE0, 0x00]]);
+        assert_eq!(client.state(), ClientState::Disconnected);
+        assert_eq!(
+            client.handle_packet(sub_ack(1, vec![SubAckReturnCode::Qos0])),
+            Err(ClientError::NotConnected)
+        );
+        assert_eq!(client.disconnect(), Err(ClientError::NotConnected));
+
+        // the engine can open the next connection
+        connect(&mut client, 0, true);
+        assert_eq!(client.state(), ClientState::Connecting);
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::common::return_code::ReturnCode;
+use crate::protocol::common::topic_error::TopicError;
+use crate::protocol::mqtt4::delivery::delivery_error::DeliveryError;
+
+/// Why `ClientConnection` refused a request or gave up on the connection. Errors returned by
+/// `handle_packet` close the connection; the transport should drop the network connection too.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq, Clone, thiserror::Error)]
+pub enum ClientError {
+    #[error("a CONNECT has already been sent on this connection")]
+    AlreadyConnected,
+
+    #[error("the connection is not open")]
+    NotConnected,
+
+    #[error("the server refused the connection with return code {0:?}")]
+    ConnectionRefused(ReturnCode),
+
+    #[error("CONNACK sets Session Present although it refuses the connection")]
+    SessionPresentOnRefusal,
+
+    #[error("CONNACK sets Session Present although a clean session was requested")]
+    SessionPresentOnCleanSession,
+
+    #[error("{0} is not expected from the server here")]
+    UnexpectedPacket(&'static str),
+
+    #[error("{0} carries packet identifier {1}, which no request in flight uses")]
+    UnknownPacketIdentifier(&'static str, PacketId),
+
+    #[error("SUBACK carries {1} return codes for a SUBSCRIBE with {0} topic filters")]
+    SubAckLengthMismatch(usize, usize),
+
+    #[error("no PINGRESP arrived within the ping timeout of the PINGREQ")]
+    PingRespMissing,
+
+    #[error("{0} needs at least one topic filter")]
+    NoTopicFilters(&'static str),
+
+    #[error("Invalid topic name: {0}")]
+    InvalidTopicName(TopicError),
+
+    #[error("Invalid topic filter: {0}")]
+    InvalidTopicFilter(TopicError),
+
+    #[error("{0}")]
+    Delivery(#[from] DeliveryError),
+}
@@ -0,0 +1,47 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+use crate::protocol::mqtt4::payload_parser::sub_ack_parser::payload::SubAckReturnCode;
+use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::PublishVariableHeader;
+
+/// Something the application should hear about, taken from `ClientConnection::poll_event`.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone)]
+pub enum ClientEvent {
+    /// The server accepted the CONNECT.
+    Connected { session_present: bool },
+    /// The SUBACK for the SUBSCRIBE sent with `packet_identifier`, one return code per topic
+    /// filter in the order they were requested.
+    Subscribed {
+        packet_identifier: PacketId,
+        results: Vec<(String, SubAckReturnCode)>,
+    },
+    /// The UNSUBACK for the UNSUBSCRIBE sent with `packet_identifier`.
+    Unsubscribed {
+        packet_identifier: PacketId,
+        topic_filters: Vec<String>,
+    },
+    /// The server acknowledged the QoS 1 or QoS 2 PUBLISH sent with `packet_identifier`: a
+    /// PUBACK or a PUBCOMP arrived.
+    Published { packet_identifier: PacketId },
+    /// An application message published by the server.
+    Message {
+        dup: bool,
+        retain: bool,
+        variable: PublishVariableHeader,
+        payload: PublishPayload,
+    },
+}
@@ -0,0 +1,224 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::mqtt4::conformance::normative_statement::{
+    NORMATIVE_STATEMENTS, NormativeStatement,
+};
+use crate::protocol::mqtt4::conformance::test_vector::{Outcome, TestVector};
+use crate::protocol::mqtt4::conformance::test_vectors::TEST_VECTORS;
+use std::fmt;
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum StatementStatus {
+    /// Every vector of the statement passed.
+    Passed,
+    /// At least one vector of the statement failed.
+    Failed,
+    /// The statement has no vectors yet.
+    NotImplemented,
+}
+
+#[allow(dead_code)]
+impl StatementStatus {
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            StatementStatus::Passed => "pass",
+            StatementStatus::Failed => "FAIL",
+            StatementStatus::NotImplemented => "todo",
+        }
+    }
+}
+
+/// A normative statement together with the outcome of each of its vectors.
+#[allow(dead_code)]
+pub(crate) struct StatementResult {
+    statement: &'static NormativeStatement,
+    outcomes: Vec<(&'static TestVector, Outcome)>,
+}
+
+#[allow(dead_code)]
+impl StatementResult {
+    pub(crate) fn statement(&self) -> &'static NormativeStatement {
+        self.statement
+    }
+
+    pub(crate) fn outcomes(&self) -> &[(&'static TestVector, Outcome)] {
+        &self.outcomes
+    }
+
+    pub(crate) fn status(&self) -> StatementStatus {
+        if self.outcomes.is_empty() {
+            StatementStatus::NotImplemented
+        } else if self
+            .outcomes
+            .iter()
+            .all(|(_, outcome)| *outcome == Outcome::Passed)
+        {
+            StatementStatus::Passed
+        } else {
+            StatementStatus::Failed
+        }
+    }
+}
+
+/// Which normative statements the codec is covered for, and whether it passes them. Statements
+/// are listed in the order of the catalogue they come from.
+#[allow(dead_code)]
+pub(crate) struct ConformanceReport {
+    results: Vec<StatementResult>,
+}
+
+#[allow(dead_code)]
+impl ConformanceReport {
+    /// Runs `TEST_VECTORS` against `NORMATIVE_STATEMENTS`.
+    pub(crate) fn generate() -> Self {
+        Self::from_vectors(NORMATIVE_STATEMENTS, TEST_VECTORS)
+    }
+
+    /// Runs `vectors` and files each outcome under its statement. Vectors naming a statement
+    /// missing from `statements` are left out.
+    pub(crate) fn from_vectors(
+        statements: &'static [NormativeStatement],
+        vectors: &'static [TestVector],
+    ) -> Self {
+        let results = statements
+            .iter()
+            .map(|statement| StatementResult {
+                statement,
+                outcomes: vectors
+                    .iter()
+                    .filter(|vector| vector.statement() == statement.id())
+                    .map(|vector| (vector, vector.run()))
+                    .collect(),
+            })
+            .collect();
+        ConformanceReport { results }
+    }
+
+    pub(crate) fn results(&self) -> &[StatementResult] {
+        &self.results
+    }
+
+    pub(crate) fn count(&self, status: StatementStatus) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.status() == status)
+            .count()
+    }
+
+    /// True when no vector failed. Statements that are not implemented do not count against it.
+    pub(crate) fn is_conformant(&self) -> bool {
+        self.count(StatementStatus::Failed) == 0
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "MQTT 3.1.1 conformance: {} statements, {} passed, {} failed, {} not implemented",
+            self.results.len(),
+            self.count(StatementStatus::Passed),
+            self.count(StatementStatus::Failed),
+            self.count(StatementStatus::NotImplemented)
+        )?;
+        for result in &self.results {
+            let statement = result.statement();
+            writeln!(
+                f,
+                "{}  {:<14} {}",
+                result.status().label(),
+                statement.id(),
+                statement.requirement()
+            )?;
+            for (vector, outcome) in result.outcomes() {
+                if let Outcome::Failed(reason) = outcome {
+                    writeln!(f, "      {}: {}", vector.description(), reason)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod conformance_report_tests {
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::mqtt4::conformance::conformance_report::{
+        ConformanceReport, StatementStatus,
+    };
+    use crate::protocol::mqtt4::conformance::normative_statement::NormativeStatement;
+    use crate::protocol::mqtt4::conformance::test_vector::TestVector;
+
+    static STATEMENTS: &[NormativeStatement] = &[
+        NormativeStatement::new("MQTT-2.2.2-1", "Reserved flags have the listed values"),
+        NormativeStatement::new("MQTT-3.14.1-1", "DISCONNECT reserved bits are zero"),
+        NormativeStatement::new("MQTT-3.3.1-2", "DUP is 0 for QoS 0"),
+    ];
+
+    static VECTORS: &[TestVector] = &[
+        TestVector::round_trip("MQTT-2.2.2-1", "PINGREQ", &[0xC0, 0x00]),
+        TestVector::round_trip("MQTT-3.14.1-1", "DISCONNECT", &[0xE0, 0x00]),
+        TestVector::rejected(
+            "MQTT-3.14.1-1",
+            "DISCONNECT with flags 0001",
+            &[0xE0, 0x00],
+            |error| matches!(error, MqttProtocolError::InvalidFixedHeaderFlags),
+        ),
+        TestVector::round_trip("MQTT-9.9.9-9", "not in the catalogue", &[0xD0, 0x00]),
+    ];
+
+    #[test]
+    fn report_should_file_outcomes_under_their_statements() {
+        let report = ConformanceReport::from_vectors(STATEMENTS, VECTORS);
+        let statuses: Vec<StatementStatus> = report
+            .results()
+            .iter()
+            .map(|result| result.status())
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                StatementStatus::Passed,
+                StatementStatus::Failed,
+                StatementStatus::NotImplemented
+            ]
+        );
+        assert_eq!(report.results()[1].outcomes().len(), 2);
+        assert!(!report.is_conformant());
+    }
+
+    #[test]
+    fn report_should_list_every_statement_and_explain_failures() {
+        let report = ConformanceReport::from_vectors(STATEMENTS, VECTORS);
+        assert_eq!(
+            report.to_string(),
+            concat!(
+                "MQTT 3.1.1 conformance: 3 statements, 1 passed, 1 failed, 1 not implemented\n",
+                "pass  MQTT-2.2.2-1   Reserved flags have the listed values\n",
+                "FAIL  MQTT-3.14.1-1  DISCONNECT reserved bits are zero\n",
+                "      DISCONNECT with flags 0001: decoded as DISCONNECT instead of being rejected\n",
+                "todo  MQTT-3.3.1-2   DUP is 0 for QoS 0\n",
+            )
+        );
+    }
+
+    #[test]
+    fn codec_should_pass_every_implemented_statement() {
+        let report = ConformanceReport::generate();
+        assert!(report.is_conformant(), "{}", report);
+        assert!(report.count(StatementStatus::Passed) > 0);
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// A requirement of the MQTT 3.1.1 specification, identified by the label the specification
+/// puts after it, such as `MQTT-3.1.2-3`.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct NormativeStatement {
+    id: &'static str,
+    requirement: &'static str,
+}
+
+#[allow(dead_code)]
+impl NormativeStatement {
+    pub(crate) const fn new(id: &'static str, requirement: &'static str) -> Self {
+        NormativeStatement { id, requirement }
+    }
+
+    pub(crate) fn id(&self) -> &'static str {
+        self.id
+    }
+
+    pub(crate) fn requirement(&self) -> &'static str {
+        self.requirement
+    }
+
+    pub(crate) fn find(id: &str) -> Option<&'static NormativeStatement> {
+        NORMATIVE_STATEMENTS
+            .iter()
+            .find(|statement| statement.id == id)
+    }
+}
+
+/// The statements a codec can be held to, in specification order. Requirements on connection
+/// handling and session state are left out; they belong to whatever drives the codec.
+pub(crate) static NORMATIVE_STATEMENTS: &[NormativeStatement] = &[
+    NormativeStatement::new(
+        "MQTT-1.5.3-1",
+        "UTF-8 encoded strings must be well-formed and must not encode U+D800 to U+DFFF",
+    ),
+    NormativeStatement::new(
+        "MQTT-1.5.3-2",
+        "UTF-8 encoded strings must not include an encoding of U+0000",
+    ),
+    NormativeStatement::new(
+        "MQTT-1.5.3-3",
+        "A leading 0xEF 0xBB 0xBF is a ZERO WIDTH NO-BREAK SPACE and must not be stripped",
+    ),
+    NormativeStatement::new(
+        "MQTT-2.2.2-1",
+        "Reserved fixed header flags must be set to the value listed for the packet type",
+    ),
+    NormativeStatement::new(
+        "MQTT-2.2.2-2",
+        "A packet with invalid fixed header flags must be treated as a protocol violation",
+    ),
+    NormativeStatement::new(
+        "MQTT-2.3.1-1",
+        "SUBSCRIBE, UNSUBSCRIBE and PUBLISH with QoS above 0 must carry a non-zero Packet Identifier",
+    ),
+    NormativeStatement::new(
+        "MQTT-2.3.1-5",
+        "A PUBLISH with QoS 0 must not contain a Packet Identifier",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.2-1",
+        "A CONNECT whose Protocol Name is not \"MQTT\" may be rejected",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.2-2",
+        "A CONNECT with an unsupported Protocol Level must be rejected",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.2-3",
+        "The reserved CONNECT flag must be 0, otherwise the CONNECT is rejected",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.2-9",
+        "With the Will Flag set, the Will Topic and Will Message must be present in the payload",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.2-11",
+        "With the Will Flag clear, Will QoS and Will Retain must be 0 and the will fields absent",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.2-13",
+        "With the Will Flag clear, the Will QoS must be 0",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.2-14",
+        "With the Will Flag set, the Will QoS can be 0, 1 or 2 but not 3",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.2-15",
+        "With the Will Flag clear, Will Retain must be 0",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.2-19",
+        "With the User Name Flag set, a user name must be present in the payload",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.2-21",
+        "With the Password Flag set, a password must be present in the payload",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.2-22",
+        "With the User Name Flag clear, the Password Flag must be clear",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.3-4",
+        "The Client Identifier must be a UTF-8 encoded string",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.3-5",
+        "Client Identifiers of 1 to 23 characters from 0-9, a-z and A-Z must be accepted",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.1.3-8",
+        "A zero-byte Client Identifier with CleanSession 0 must be rejected",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.2.2-4",
+        "A CONNACK with a non-zero return code must have Session Present set to 0",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.3.1-2",
+        "The DUP flag must be 0 for all QoS 0 messages",
+    ),
+    NormativeStatement::new("MQTT-3.3.1-4", "A PUBLISH must not have both QoS bits set"),
+    NormativeStatement::new(
+        "MQTT-3.3.2-1",
+        "The Topic Name must be present as the first field of the PUBLISH variable header and be a UTF-8 encoded string",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.3.2-2",
+        "The Topic Name of a PUBLISH must not contain wildcard characters",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.6.1-1",
+        "Bits 3 to 0 of the PUBREL fixed header must be 0010",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.8.1-1",
+        "Bits 3 to 0 of the SUBSCRIBE fixed header must be 0010",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.8.3-1",
+        "SUBSCRIBE Topic Filters must be UTF-8 encoded strings",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.8.3-3",
+        "A SUBSCRIBE payload must contain at least one Topic Filter and QoS pair",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.8.3-4",
+        "A SUBSCRIBE with non-zero reserved bits or a requested QoS above 2 is malformed",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.9.3-2",
+        "SUBACK return codes other than 0x00, 0x01, 0x02 and 0x80 are reserved and must not be used",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.10.1-1",
+        "Bits 3 to 0 of the UNSUBSCRIBE fixed header must be 0010",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.10.3-2",
+        "An UNSUBSCRIBE payload must contain at least one Topic Filter",
+    ),
+    NormativeStatement::new(
+        "MQTT-3.14.1-1",
+        "The reserved bits of a DISCONNECT fixed header must be zero",
+    ),
+    NormativeStatement::new(
+        "MQTT-4.7.1-2",
+        "The multi-level wildcard must be the last character of a Topic Filter",
+    ),
+    NormativeStatement::new(
+        "MQTT-4.7.1-3",
+        "The single-level wildcard must occupy an entire level of a Topic Filter",
+    ),
+    NormativeStatement::new(
+        "MQTT-4.7.3-1",
+        "Topic Names and Topic Filters must be at least one character long",
+    ),
+    NormativeStatement::new(
+        "MQTT-4.7.3-2",
+        "Topic Names and Topic Filters must not include U+0000",
+    ),
+];
+
+#[cfg(test)]
+mod normative_statement_tests {
+    use crate::protocol::mqtt4::conformance::normative_statement::{
+        NORMATIVE_STATEMENTS, NormativeStatement,
+    };
+    use std::collections::HashSet;
+
+    #[test]
+    fn normative_statements_should_have_unique_well_formed_ids() {
+        let mut ids = HashSet::new();
+        for statement in NORMATIVE_STATEMENTS {
+            let numbers = statement.id().strip_prefix("MQTT-").unwrap();
+            let (section, index) = numbers.rsplit_once('-').unwrap();
+            assert!(section.split('.').all(|part| part.parse::<u8>().is_ok()));
+            assert!(index.parse::<u8>().is_ok());
+            assert!(
+                ids.insert(statement.id()),
+                "{} is listed twice",
+                statement.id()
+            );
+        }
+    }
+
+    #[test]
+    fn find_should_look_statements_up_by_id() {
+        let statement = NormativeStatement::find("MQTT-3.1.2-3").unwrap();
+        assert_eq!(
+            statement.requirement(),
+            "The reserved CONNECT flag must be 0, otherwise the CONNECT is rejected"
+        );
+        assert!(NormativeStatement::find("MQTT-9.9.9-9").is_none());
+    }
+}
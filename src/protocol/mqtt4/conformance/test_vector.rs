@@ -0,0 +1,186 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::codec::{Decoder, Encoder};
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use crate::utils::hex::hex_handler;
+use bytes::BytesMut;
+
+/// What a conforming codec does with the bytes of a `TestVector`.
+#[allow(dead_code)]
+pub(crate) enum Expectation {
+    /// The bytes decode, and encoding the packet again gives back exactly the same bytes.
+    RoundTrip,
+    /// The bytes decode into a packet the check accepts.
+    Decodes(fn(&Packet) -> bool),
+    /// Decoding fails with an error the check accepts.
+    Rejected(fn(&MqttProtocolError) -> bool),
+}
+
+/// One complete control packet on the wire, checked against a normative statement with
+/// `Packet::decode` and `Packet::encode`.
+#[allow(dead_code)]
+pub(crate) struct TestVector {
+    statement: &'static str,
+    description: &'static str,
+    bytes: &'static [u8],
+    expectation: Expectation,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) enum Outcome {
+    Passed,
+    Failed(String),
+}
+
+#[allow(dead_code)]
+impl TestVector {
+    pub(crate) const fn round_trip(
+        statement: &'static str,
+        description: &'static str,
+        bytes: &'static [u8],
+    ) -> Self {
+        TestVector {
+            statement,
+            description,
+            bytes,
+            expectation: Expectation::RoundTrip,
+        }
+    }
+
+    pub(crate) const fn decodes(
+        statement: &'static str,
+        description: &'static str,
+        bytes: &'static [u8],
+        check: fn(&Packet) -> bool,
+    ) -> Self {
+        TestVector {
+            statement,
+            description,
+            bytes,
+            expectation: Expectation::Decodes(check),
+        }
+    }
+
+    pub(crate) const fn rejected(
+        statement: &'static str,
+        description: &'static str,
+        bytes: &'static [u8],
+        check: fn(&MqttProtocolError) -> bool,
+    ) -> Self {
+        TestVector {
+            statement,
+            description,
+            bytes,
+            expectation: Expectation::Rejected(check),
+        }
+    }
+
+    pub(crate) fn statement(&self) -> &'static str {
+        self.statement
+    }
+
+    pub(crate) fn description(&self) -> &'static str {
+        self.description
+    }
+
+    pub(crate) fn bytes(&self) -> &'static [u8] {
+        self.bytes
+    }
+
+    pub(crate) fn expectation(&self) -> &Expectation {
+        &self.expectation
+    }
+
+    pub(crate) fn run(&self) -> Outcome {
+        let decoded = Packet::decode(&mut BytesMut::from(self.bytes));
+        match (&self.expectation, decoded) {
+            (Expectation::RoundTrip, Ok(mut packet)) => match packet.encode() {
+                Ok(encoded) if encoded == self.bytes => Outcome::Passed,
+                Ok(encoded) => {
+                    Outcome::Failed(format!("re-encoded as {}", hex_handler::encode(&encoded)))
+                }
+                Err(error) => Outcome::Failed(format!("re-encoding failed: {}", error)),
+            },
+            (Expectation::Decodes(check), Ok(packet)) => {
+                if check(&packet) {
+                    Outcome::Passed
+                } else {
+                    Outcome::Failed(format!(
+                        "decoded {} does not meet the statement",
+                        packet.fixed_header().control_packet_type().name()
+                    ))
+                }
+            }
+            (Expectation::Rejected(check), Err(error)) => {
+                if check(&error) {
+                    Outcome::Passed
+                } else {
+                    Outcome::Failed(format!("rejected with an unexpected error: {}", error))
+                }
+            }
+            (Expectation::Rejected(_), Ok(packet)) => Outcome::Failed(format!(
+                "decoded as {} instead of being rejected",
+                packet.fixed_header().control_packet_type().name()
+            )),
+            (_, Err(error)) => Outcome::Failed(format!("decoding failed: {}", error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_vector_tests {
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::mqtt4::conformance::test_vector::{Outcome, TestVector};
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+
+    #[test]
+    fn run_should_pass_when_the_codec_behaves_as_expected() {
+        let vector = TestVector::round_trip("MQTT-2.2.2-1", "PINGREQ", &[0xC0, 0x00]);
+        assert_eq!(vector.run(), Outcome::Passed);
+
+        let vector = TestVector::decodes("MQTT-2.2.2-1", "PINGREQ", &[0xC0, 0x00], |packet| {
+            matches!(packet, Packet::PingReq { .. })
+        });
+        assert_eq!(vector.run(), Outcome::Passed);
+
+        let vector = TestVector::rejected("MQTT-2.2.2-1", "PINGREQ", &[0xC1, 0x00], |error| {
+            matches!(error, MqttProtocolError::InvalidFixedHeaderFlags)
+        });
+        assert_eq!(vector.run(), Outcome::Passed);
+    }
+
+    #[test]
+    fn run_should_explain_why_a_vector_failed() {
+        let vector = TestVector::rejected("MQTT-2.2.2-1", "PINGREQ", &[0xC0, 0x00], |_| true);
+        assert_eq!(
+            vector.run(),
+            Outcome::Failed("decoded as PINGREQ instead of being rejected".to_string())
+        );
+
+        let vector = TestVector::round_trip("MQTT-2.2.2-1", "PINGREQ", &[0xC1, 0x00]);
+        assert!(
+            matches!(vector.run(), Outcome::Failed(reason) if reason.starts_with("decoding failed"))
+        );
+
+        let vector = TestVector::rejected("MQTT-2.2.2-1", "PINGREQ", &[0xC1, 0x00], |error| {
+            matches!(error, MqttProtocolError::PacketTooShort)
+        });
+        assert!(
+            matches!(vector.run(), Outcome::Failed(reason) if reason.starts_with("rejected with an unexpected error"))
+        );
+    }
+}
@@ -0,0 +1,555 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::topic_error::TopicError;
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::conformance::test_vector::TestVector;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use crate::utils::code_error::CodeError;
+
+/// Test vectors for `NORMATIVE_STATEMENTS`, grouped by statement. A statement without vectors
+/// shows up in the report as not implemented, which is how rules the codec does not check yet
+/// are tracked. Unless stated otherwise CONNECT packets use keep alive 60 and client id "c1",
+/// and PUBLISH packets carry the message "hi".
+pub(crate) static TEST_VECTORS: &[TestVector] = &[
+    // MQTT-1.5.3-1
+    TestVector::rejected(
+        "MQTT-1.5.3-1",
+        "CONNECT client id with the ill-formed sequence 0xC3 0x28",
+        &[
+            0x10, 0x0E, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x02, 0x00, 0x3C, 0x00, 0x02,
+            0xC3, 0x28,
+        ],
+        |error| matches!(error, MqttProtocolError::CodeError(_)),
+    ),
+    TestVector::rejected(
+        "MQTT-1.5.3-1",
+        "PUBLISH topic encoding the surrogate U+D800",
+        &[0x30, 0x07, 0x00, 0x03, 0xED, 0xA0, 0x80, b'h', b'i'],
+        |error| matches!(error, MqttProtocolError::CodeError(_)),
+    ),
+    // MQTT-1.5.3-2
+    TestVector::rejected(
+        "MQTT-1.5.3-2",
+        "PUBLISH topic \"a\\0\"",
+        &[0x30, 0x06, 0x00, 0x02, b'a', 0x00, b'h', b'i'],
+        |error| {
+            matches!(
+                error,
+                MqttProtocolError::CodeError(CodeError::MQTTInvalidCode(0))
+                    | MqttProtocolError::InvalidTopicName(TopicError::NullCharacter(1))
+            )
+        },
+    ),
+    // MQTT-1.5.3-3
+    TestVector::round_trip(
+        "MQTT-1.5.3-3",
+        "PUBLISH topic starting with 0xEF 0xBB 0xBF",
+        &[0x30, 0x08, 0x00, 0x04, 0xEF, 0xBB, 0xBF, b'a', b'h', b'i'],
+    ),
+    // MQTT-2.2.2-1
+    TestVector::round_trip(
+        "MQTT-2.2.2-1",
+        "CONNACK with flags 0000",
+        &[0x20, 0x02, 0x00, 0x00],
+    ),
+    TestVector::round_trip(
+        "MQTT-2.2.2-1",
+        "PUBACK with flags 0000",
+        &[0x40, 0x02, 0x00, 0x01],
+    ),
+    TestVector::round_trip("MQTT-2.2.2-1", "PINGRESP with flags 0000", &[0xD0, 0x00]),
+    // MQTT-2.2.2-2
+    TestVector::rejected(
+        "MQTT-2.2.2-2",
+        "CONNACK with flags 0001",
+        &[0x21, 0x02, 0x00, 0x00],
+        |error| matches!(error, MqttProtocolError::InvalidFixedHeaderFlags),
+    ),
+    TestVector::rejected(
+        "MQTT-2.2.2-2",
+        "PUBACK with flags 0010",
+        &[0x42, 0x02, 0x00, 0x01],
+        |error| matches!(error, MqttProtocolError::InvalidFixedHeaderFlags),
+    ),
+    TestVector::rejected(
+        "MQTT-2.2.2-2",
+        "PINGREQ with flags 1000",
+        &[0xC8, 0x00],
+        |error| matches!(error, MqttProtocolError::InvalidFixedHeaderFlags),
+    ),
+    // MQTT-2.3.1-1
+    TestVector::rejected(
+        "MQTT-2.3.1-1",
+        "PUBLISH QoS 1 with packet id 0",
+        &[
+            0x32, 0x09, 0x00, 0x03, b'a', b'/', b'b', 0x00, 0x00, b'h', b'i',
+        ],
+        |error| matches!(error, MqttProtocolError::ZeroPacketIdentifier),
+    ),
+    TestVector::rejected(
+        "MQTT-2.3.1-1",
+        "SUBSCRIBE with packet id 0",
+        &[0x82, 0x06, 0x00, 0x00, 0x00, 0x01, b'a', 0x00],
+        |error| matches!(error, MqttProtocolError::ZeroPacketIdentifier),
+    ),
+    TestVector::rejected(
+        "MQTT-2.3.1-1",
+        "UNSUBSCRIBE with packet id 0",
+        &[0xA2, 0x05, 0x00, 0x00, 0x00, 0x01, b'a'],
+        |error| matches!(error, MqttProtocolError::ZeroPacketIdentifier),
+    ),
+    TestVector::round_trip(
+        "MQTT-2.3.1-1",
+        "PUBLISH QoS 2 with packet id 65535",
+        &[
+            0x34, 0x09, 0x00, 0x03, b'a', b'/', b'b', 0xFF, 0xFF, b'h', b'i',
+        ],
+    ),
+    // MQTT-2.3.1-5
+    TestVector::decodes(
+        "MQTT-2.3.1-5",
+        "PUBLISH QoS 0 reads no packet id",
+        &[0x30, 0x07, 0x00, 0x03, b'a', b'/', b'b', b'h', b'i'],
+        |packet| matches!(packet, Packet::Publish { variable, .. } if variable.packet_identifier().is_none()),
+    ),
+    // MQTT-3.1.2-1
+    TestVector::rejected(
+        "MQTT-3.1.2-1",
+        "CONNECT with protocol name \"MQTX\"",
+        &[
+            0x10, 0x0E, 0x00, 0x04, b'M', b'Q', b'T', b'X', 0x04, 0x02, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1',
+        ],
+        |error| matches!(error, MqttProtocolError::ProtocolNameError(_)),
+    ),
+    // MQTT-3.1.2-2
+    TestVector::rejected(
+        "MQTT-3.1.2-2",
+        "CONNECT with protocol level 2",
+        &[
+            0x10, 0x0E, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x02, 0x02, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1',
+        ],
+        |error| matches!(error, MqttProtocolError::ProtocolLevelNoSupport(2)),
+    ),
+    // MQTT-3.1.2-3
+    TestVector::rejected(
+        "MQTT-3.1.2-3",
+        "CONNECT with the reserved flag set",
+        &[
+            0x10, 0x0E, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x03, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1',
+        ],
+        |error| matches!(error, MqttProtocolError::ConnectReservedFlagSet),
+    ),
+    // MQTT-3.1.2-9
+    TestVector::round_trip(
+        "MQTT-3.1.2-9",
+        "CONNECT with will topic \"w\" and will message 0x01 0xFF",
+        &[
+            0x10, 0x15, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x0E, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1', 0x00, 0x01, b'w', 0x00, 0x02, 0x01, 0xFF,
+        ],
+    ),
+    TestVector::rejected(
+        "MQTT-3.1.2-9",
+        "CONNECT with the Will Flag but no will fields",
+        &[
+            0x10, 0x0E, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x06, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1',
+        ],
+        |error| {
+            matches!(
+                error,
+                MqttProtocolError::CodeError(CodeError::CodeLengthError(2, 0))
+            )
+        },
+    ),
+    // MQTT-3.1.2-11
+    TestVector::rejected(
+        "MQTT-3.1.2-11",
+        "CONNECT with Will QoS 1 but no Will Flag",
+        &[
+            0x10, 0x0E, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x0A, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1',
+        ],
+        |error| matches!(error, MqttProtocolError::WillQoSWithoutWillFlag(1)),
+    ),
+    // MQTT-3.1.2-13
+    TestVector::rejected(
+        "MQTT-3.1.2-13",
+        "CONNECT with Will QoS 2 but no Will Flag",
+        &[
+            0x10, 0x0E, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x12, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1',
+        ],
+        |error| matches!(error, MqttProtocolError::WillQoSWithoutWillFlag(2)),
+    ),
+    // MQTT-3.1.2-14
+    TestVector::rejected(
+        "MQTT-3.1.2-14",
+        "CONNECT with Will QoS 3",
+        &[
+            0x10, 0x15, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x1E, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1', 0x00, 0x01, b'w', 0x00, 0x02, 0x01, 0xFF,
+        ],
+        |error| matches!(error, MqttProtocolError::InvalidWillQoS(3)),
+    ),
+    TestVector::round_trip(
+        "MQTT-3.1.2-14",
+        "CONNECT with Will QoS 2",
+        &[
+            0x10, 0x15, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x16, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1', 0x00, 0x01, b'w', 0x00, 0x02, 0x01, 0xFF,
+        ],
+    ),
+    // MQTT-3.1.2-15
+    TestVector::rejected(
+        "MQTT-3.1.2-15",
+        "CONNECT with Will Retain but no Will Flag",
+        &[
+            0x10, 0x0E, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x22, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1',
+        ],
+        |error| matches!(error, MqttProtocolError::WillRetainWithoutWillFlag),
+    ),
+    // MQTT-3.1.2-19
+    TestVector::rejected(
+        "MQTT-3.1.2-19",
+        "CONNECT with the User Name Flag but no user name",
+        &[
+            0x10, 0x0E, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x82, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1',
+        ],
+        |error| {
+            matches!(
+                error,
+                MqttProtocolError::CodeError(CodeError::CodeLengthError(2, 0))
+            )
+        },
+    ),
+    // MQTT-3.1.2-21
+    TestVector::rejected(
+        "MQTT-3.1.2-21",
+        "CONNECT with the Password Flag but no password",
+        &[
+            0x10, 0x11, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0xC2, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1', 0x00, 0x01, b'u',
+        ],
+        |error| {
+            matches!(
+                error,
+                MqttProtocolError::CodeError(CodeError::CodeLengthError(2, 0))
+            )
+        },
+    ),
+    TestVector::round_trip(
+        "MQTT-3.1.2-21",
+        "CONNECT with user name \"u\" and password \"p\"",
+        &[
+            0x10, 0x14, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0xC2, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1', 0x00, 0x01, b'u', 0x00, 0x01, b'p',
+        ],
+    ),
+    // MQTT-3.1.2-22
+    TestVector::rejected(
+        "MQTT-3.1.2-22",
+        "CONNECT with the Password Flag but no User Name Flag",
+        &[
+            0x10, 0x11, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x42, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1', 0x00, 0x01, b'p',
+        ],
+        |error| matches!(error, MqttProtocolError::PasswordWithoutUsername),
+    ),
+    // MQTT-3.1.3-4
+    TestVector::rejected(
+        "MQTT-3.1.3-4",
+        "CONNECT client id that is not UTF-8",
+        &[
+            0x10, 0x0E, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x02, 0x00, 0x3C, 0x00, 0x02,
+            0xFF, b'1',
+        ],
+        |error| matches!(error, MqttProtocolError::CodeError(_)),
+    ),
+    // MQTT-3.1.3-5
+    TestVector::round_trip(
+        "MQTT-3.1.3-5",
+        "CONNECT client id \"0123456789abcdefghijKLM\"",
+        &[
+            0x10, 0x23, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x02, 0x00, 0x3C, 0x00, 0x17,
+            b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', b'8', b'9', b'a', b'b', b'c', b'd',
+            b'e', b'f', b'g', b'h', b'i', b'j', b'K', b'L', b'M',
+        ],
+    ),
+    // MQTT-3.1.3-8
+    TestVector::rejected(
+        "MQTT-3.1.3-8",
+        "CONNECT with an empty client id and CleanSession 0",
+        &[
+            0x10, 0x0C, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x00, 0x00, 0x3C, 0x00, 0x00,
+        ],
+        |error| matches!(error, MqttProtocolError::InvalidClientId),
+    ),
+    // MQTT-3.3.1-4
+    TestVector::rejected(
+        "MQTT-3.3.1-4",
+        "PUBLISH with QoS bits 11",
+        &[
+            0x36, 0x09, 0x00, 0x03, b'a', b'/', b'b', 0x00, 0x01, b'h', b'i',
+        ],
+        |error| matches!(error, MqttProtocolError::QoSLevelNotSupported(3)),
+    ),
+    // MQTT-3.3.2-1
+    TestVector::rejected(
+        "MQTT-3.3.2-1",
+        "PUBLISH topic that is not UTF-8",
+        &[0x30, 0x06, 0x00, 0x02, 0xC3, 0x28, b'h', b'i'],
+        |error| matches!(error, MqttProtocolError::CodeError(_)),
+    ),
+    TestVector::rejected(
+        "MQTT-3.3.2-1",
+        "PUBLISH ending inside the topic length",
+        &[0x30, 0x01, 0x00],
+        |error| {
+            matches!(
+                error,
+                MqttProtocolError::CodeError(CodeError::CodeLengthError(2, 1))
+            )
+        },
+    ),
+    // MQTT-3.3.2-2
+    TestVector::rejected(
+        "MQTT-3.3.2-2",
+        "PUBLISH topic \"a/+\"",
+        &[0x30, 0x07, 0x00, 0x03, b'a', b'/', b'+', b'h', b'i'],
+        |error| {
+            matches!(
+                error,
+                MqttProtocolError::InvalidTopicName(TopicError::WildcardInTopicName(2, '+'))
+            )
+        },
+    ),
+    TestVector::rejected(
+        "MQTT-3.3.2-2",
+        "PUBLISH topic \"a/#\"",
+        &[0x30, 0x07, 0x00, 0x03, b'a', b'/', b'#', b'h', b'i'],
+        |error| {
+            matches!(
+                error,
+                MqttProtocolError::InvalidTopicName(TopicError::WildcardInTopicName(2, '#'))
+            )
+        },
+    ),
+    // MQTT-3.6.1-1
+    TestVector::round_trip(
+        "MQTT-3.6.1-1",
+        "PUBREL with flags 0010",
+        &[0x62, 0x02, 0x00, 0x01],
+    ),
+    TestVector::rejected(
+        "MQTT-3.6.1-1",
+        "PUBREL with flags 0000",
+        &[0x60, 0x02, 0x00, 0x01],
+        |error| matches!(error, MqttProtocolError::InvalidFixedHeaderFlags),
+    ),
+    // MQTT-3.8.1-1
+    TestVector::round_trip(
+        "MQTT-3.8.1-1",
+        "SUBSCRIBE with flags 0010",
+        &[0x82, 0x06, 0x00, 0x01, 0x00, 0x01, b'a', 0x01],
+    ),
+    TestVector::rejected(
+        "MQTT-3.8.1-1",
+        "SUBSCRIBE with flags 0000",
+        &[0x80, 0x06, 0x00, 0x01, 0x00, 0x01, b'a', 0x01],
+        |error| matches!(error, MqttProtocolError::InvalidFixedHeaderFlags),
+    ),
+    // MQTT-3.8.3-1
+    TestVector::rejected(
+        "MQTT-3.8.3-1",
+        "SUBSCRIBE topic filter that is not UTF-8",
+        &[0x82, 0x07, 0x00, 0x01, 0x00, 0x02, 0xC3, 0x28, 0x00],
+        |error| matches!(error, MqttProtocolError::CodeError(_)),
+    ),
+    // MQTT-3.8.3-3
+    TestVector::rejected(
+        "MQTT-3.8.3-3",
+        "SUBSCRIBE without topic filters",
+        &[0x82, 0x02, 0x00, 0x01],
+        |error| matches!(error, MqttProtocolError::MalformedPacket),
+    ),
+    // MQTT-3.8.3-4
+    TestVector::rejected(
+        "MQTT-3.8.3-4",
+        "SUBSCRIBE requesting QoS 3",
+        &[0x82, 0x06, 0x00, 0x01, 0x00, 0x01, b'a', 0x03],
+        |error| matches!(error, MqttProtocolError::QoSLevelNotSupported(3)),
+    ),
+    TestVector::rejected(
+        "MQTT-3.8.3-4",
+        "SUBSCRIBE with reserved bits set in the requested QoS byte",
+        &[0x82, 0x06, 0x00, 0x01, 0x00, 0x01, b'a', 0x41],
+        |error| matches!(error, MqttProtocolError::QoSLevelNotSupported(0x41)),
+    ),
+    // MQTT-3.9.3-2
+    TestVector::round_trip(
+        "MQTT-3.9.3-2",
+        "SUBACK granting QoS 0, 1 and 2 and a failure",
+        &[0x90, 0x06, 0x00, 0x01, 0x00, 0x01, 0x02, 0x80],
+    ),
+    TestVector::rejected(
+        "MQTT-3.9.3-2",
+        "SUBACK with return code 0x03",
+        &[0x90, 0x03, 0x00, 0x01, 0x03],
+        |error| matches!(error, MqttProtocolError::MalformedPacket),
+    ),
+    // MQTT-3.10.1-1
+    TestVector::round_trip(
+        "MQTT-3.10.1-1",
+        "UNSUBSCRIBE with flags 0010",
+        &[0xA2, 0x05, 0x00, 0x01, 0x00, 0x01, b'a'],
+    ),
+    TestVector::rejected(
+        "MQTT-3.10.1-1",
+        "UNSUBSCRIBE with flags 0000",
+        &[0xA0, 0x05, 0x00, 0x01, 0x00, 0x01, b'a'],
+        |error| matches!(error, MqttProtocolError::InvalidFixedHeaderFlags),
+    ),
+    // MQTT-3.10.3-2
+    TestVector::rejected(
+        "MQTT-3.10.3-2",
+        "UNSUBSCRIBE without topic filters",
+        &[0xA2, 0x02, 0x00, 0x01],
+        |error| matches!(error, MqttProtocolError::MalformedPacket),
+    ),
+    // MQTT-3.14.1-1
+    TestVector::round_trip("MQTT-3.14.1-1", "DISCONNECT with flags 0000", &[0xE0, 0x00]),
+    TestVector::rejected(
+        "MQTT-3.14.1-1",
+        "DISCONNECT with flags 0001",
+        &[0xE1, 0x00],
+        |error| matches!(error, MqttProtocolError::InvalidFixedHeaderFlags),
+    ),
+    // MQTT-4.7.1-2
+    TestVector::rejected(
+        "MQTT-4.7.1-2",
+        "SUBSCRIBE topic filter \"a/#/b\"",
+        &[
+            0x82, 0x0A, 0x00, 0x01, 0x00, 0x05, b'a', b'/', b'#', b'/', b'b', 0x00,
+        ],
+        |error| {
+            matches!(
+                error,
+                MqttProtocolError::InvalidTopicFilter(TopicError::MultiLevelWildcardNotLast(2))
+            )
+        },
+    ),
+    // MQTT-4.7.1-3
+    TestVector::rejected(
+        "MQTT-4.7.1-3",
+        "SUBSCRIBE topic filter \"a+\"",
+        &[0x82, 0x07, 0x00, 0x01, 0x00, 0x02, b'a', b'+', 0x00],
+        |error| {
+            matches!(
+                error,
+                MqttProtocolError::InvalidTopicFilter(TopicError::WildcardNotWholeLevel(1, '+'))
+            )
+        },
+    ),
+    TestVector::round_trip(
+        "MQTT-4.7.1-3",
+        "SUBSCRIBE topic filter \"+/b/#\"",
+        &[
+            0x82, 0x0A, 0x00, 0x01, 0x00, 0x05, b'+', b'/', b'b', b'/', b'#', 0x01,
+        ],
+    ),
+    // MQTT-4.7.3-1
+    TestVector::rejected(
+        "MQTT-4.7.3-1",
+        "PUBLISH with an empty topic",
+        &[0x30, 0x04, 0x00, 0x00, b'h', b'i'],
+        |error| {
+            matches!(
+                error,
+                MqttProtocolError::InvalidTopicName(TopicError::Empty)
+            )
+        },
+    ),
+    TestVector::rejected(
+        "MQTT-4.7.3-1",
+        "SUBSCRIBE with an empty topic filter",
+        &[0x82, 0x05, 0x00, 0x01, 0x00, 0x00, 0x00],
+        |error| {
+            matches!(
+                error,
+                MqttProtocolError::InvalidTopicFilter(TopicError::Empty)
+            )
+        },
+    ),
+    TestVector::rejected(
+        "MQTT-4.7.3-1",
+        "UNSUBSCRIBE with an empty topic filter",
+        &[0xA2, 0x04, 0x00, 0x01, 0x00, 0x00],
+        |error| {
+            matches!(
+                error,
+                MqttProtocolError::InvalidTopicFilter(TopicError::Empty)
+            )
+        },
+    ),
+    // MQTT-4.7.3-2
+    TestVector::rejected(
+        "MQTT-4.7.3-2",
+        "SUBSCRIBE topic filter \"a\\0\"",
+        &[0x82, 0x07, 0x00, 0x01, 0x00, 0x02, b'a', 0x00, 0x00],
+        |error| {
+            matches!(
+                error,
+                MqttProtocolError::CodeError(CodeError::MQTTInvalidCode(0))
+                    | MqttProtocolError::InvalidTopicFilter(TopicError::NullCharacter(1))
+            )
+        },
+    ),
+];
+
+#[cfg(test)]
+mod test_vectors_tests {
+    use crate::protocol::mqtt4::conformance::normative_statement::NormativeStatement;
+    use crate::protocol::mqtt4::conformance::test_vectors::TEST_VECTORS;
+
+    #[test]
+    fn test_vectors_should_name_catalogued_statements() {
+        for vector in TEST_VECTORS {
+            assert!(
+                NormativeStatement::find(vector.statement()).is_some(),
+                "{} names the unknown statement {}",
+                vector.description(),
+                vector.statement()
+            );
+        }
+    }
+
+    #[test]
+    fn test_vectors_should_be_single_complete_packets() {
+        for vector in TEST_VECTORS {
+            assert_eq!(
+                vector.bytes().len(),
+                2 + vector.bytes()[1] as usize,
+                "{} does not match its Remaining Length",
+                vector.description()
+            );
+        }
+    }
+}
@@ -0,0 +1,421 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::byte_adapter::byte_operations::ByteOperations;
+use crate::protocol::common::control_packet_type::ControlPacketType;
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::packet_parser::decode_error::DecodeError;
+use crate::protocol::mqtt4::payload_parser::connect_parser::client_id_policy::ClientIdPolicy;
+use crate::utils::utf::utf_8_handler;
+use std::sync::LazyLock;
+
+/// Largest packet the Remaining Length encoding can describe: one type byte, four length bytes
+/// and 268,435,455 bytes of body.
+pub(crate) const MAX_PACKET_SIZE: usize = 1 + 4 + 268_435_455;
+
+/// Longest UTF-8 string field the two byte length prefix allows.
+pub(crate) const MAX_STRING_LENGTH: usize = u16::MAX as usize;
+
+static STRICT: LazyLock<DecoderConfig> = LazyLock::new(DecoderConfig::strict);
+
+/// What decoding does when a packet breaks one of the checks `DecoderConfig` switches on.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViolationHandling {
+    /// Fail decoding with the violation as the error.
+    Reject,
+    /// Decode anyway and attach the violation to the packet as a warning.
+    Warn,
+}
+
+/// Which protocol rules decoding enforces and how hard.
+///
+/// Only rules a packet can break while staying parseable are configurable. Truncated packets,
+/// invalid UTF-8, unknown packet types and the like always fail.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct DecoderConfig {
+    violation_handling: ViolationHandling,
+    verify_reserved_flags: bool,
+    verify_forbidden_code_points: bool,
+    client_id_policy: ClientIdPolicy,
+    reject_empty_username: bool,
+    max_string_length: usize,
+    max_packet_size: usize,
+}
+
+#[allow(dead_code)]
+impl DecoderConfig {
+    /// Every check on, every violation an error. What a broker should use.
+    pub fn strict() -> Self {
+        DecoderConfig {
+            violation_handling: ViolationHandling::Reject,
+            verify_reserved_flags: true,
+            verify_forbidden_code_points: true,
+            client_id_policy: ClientIdPolicy::strict(),
+            reject_empty_username: true,
+            max_string_length: MAX_STRING_LENGTH,
+            max_packet_size: MAX_PACKET_SIZE,
+        }
+    }
+
+    /// Every check on, every violation a warning. For tools that look at other peers' traffic.
+    pub fn lenient() -> Self {
+        DecoderConfig {
+            violation_handling: ViolationHandling::Warn,
+            ..Self::strict()
+        }
+    }
+
+    pub(crate) fn strict_ref() -> &'static DecoderConfig {
+        &STRICT
+    }
+
+    pub fn with_violation_handling(mut self, violation_handling: ViolationHandling) -> Self {
+        self.violation_handling = violation_handling;
+        self
+    }
+
+    /// Reserved fixed header flags must hold the value the packet type requires.
+    pub fn with_reserved_flags_check(mut self, enabled: bool) -> Self {
+        self.verify_reserved_flags = enabled;
+        self
+    }
+
+    /// Strings must not contain control characters or Unicode non-characters.
+    pub fn with_forbidden_code_points_check(mut self, enabled: bool) -> Self {
+        self.verify_forbidden_code_points = enabled;
+        self
+    }
+
+    pub fn with_client_id_policy(mut self, client_id_policy: ClientIdPolicy) -> Self {
+        self.client_id_policy = client_id_policy;
+        self
+    }
+
+    /// A CONNECT with the username flag set must not carry an empty username.
+    pub fn with_empty_username_check(mut self, enabled: bool) -> Self {
+        self.reject_empty_username = enabled;
+        self
+    }
+
+    pub fn with_max_string_length(mut self, max_string_length: usize) -> Self {
+        self.max_string_length = max_string_length;
+        self
+    }
+
+    /// Largest packet accepted, fixed header included. Unlike the other checks this one fails
+    /// under `Warn` as well.
+    pub fn with_max_packet_size(mut self, max_packet_size: usize) -> Self {
+        self.max_packet_size = max_packet_size;
+        self
+    }
+
+    pub fn violation_handling(&self) -> ViolationHandling {
+        self.violation_handling
+    }
+
+    pub fn verify_reserved_flags(&self) -> bool {
+        self.verify_reserved_flags
+    }
+
+    pub fn verify_forbidden_code_points(&self) -> bool {
+        self.verify_forbidden_code_points
+    }
+
+    pub fn client_id_policy(&self) -> &ClientIdPolicy {
+        &self.client_id_policy
+    }
+
+    pub fn reject_empty_username(&self) -> bool {
+        self.reject_empty_username
+    }
+
+    pub fn max_string_length(&self) -> usize {
+        self.max_string_length
+    }
+
+    pub fn max_packet_size(&self) -> usize {
+        self.max_packet_size
+    }
+}
+
+impl Default for DecoderConfig {
+    fn default() -> Self {
+        Self::strict()
+    }
+}
+
+/// State threaded through the fixed header, variable header and payload decoders of one packet.
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) struct DecodeContext<'a> {
+    config: &'a DecoderConfig,
+    warnings: Vec<MqttProtocolError>,
+    frame_length: usize,
+    packet_type: Option<ControlPacketType>,
+    field: &'static str,
+    field_index: Option<usize>,
+    field_start: usize,
+}
+
+#[allow(dead_code)]
+impl<'a> DecodeContext<'a> {
+    pub(crate) fn new(config: &'a DecoderConfig) -> Self {
+        DecodeContext {
+            config,
+            warnings: Vec::new(),
+            frame_length: 0,
+            packet_type: None,
+            field: "",
+            field_index: None,
+            field_start: 0,
+        }
+    }
+
+    pub(crate) fn strict() -> DecodeContext<'static> {
+        DecodeContext::new(DecoderConfig::strict_ref())
+    }
+
+    pub(crate) fn config(&self) -> &'a DecoderConfig {
+        self.config
+    }
+
+    /// Reports a broken rule: an error under `Reject`, a warning to keep going under `Warn`.
+    pub(crate) fn violation(&mut self, error: MqttProtocolError) -> Result<(), MqttProtocolError> {
+        match self.config.violation_handling {
+            ViolationHandling::Reject => Err(error),
+            ViolationHandling::Warn => {
+                self.warnings.push(error);
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn warnings(&self) -> &[MqttProtocolError] {
+        &self.warnings
+    }
+
+    pub(crate) fn into_warnings(self) -> Vec<MqttProtocolError> {
+        self.warnings
+    }
+
+    /// Starts tracking positions in the packet at the start of `bytes`. Only offsets are kept;
+    /// `decode_error` takes the bytes of the field that failed from the packet it is given.
+    pub(crate) fn start_packet(&mut self, bytes: &impl ByteOperations) {
+        self.frame_length = bytes.bytes_len();
+        self.packet_type = None;
+        self.field = "";
+        self.field_index = None;
+        self.field_start = 0;
+    }
+
+    pub(crate) fn set_packet_type(&mut self, packet_type: ControlPacketType) {
+        self.packet_type = Some(packet_type);
+    }
+
+    /// Marks `bytes` as positioned at the start of `field`, a path below the packet such as
+    /// `payload.will_topic`.
+    pub(crate) fn enter_field(&mut self, field: &'static str, bytes: &impl ByteOperations) {
+        self.field = field;
+        self.field_index = None;
+        self.field_start = self.frame_length.saturating_sub(bytes.bytes_len());
+    }
+
+    /// `enter_field` for item `index` of a list, written as `[]` in `field`, such as
+    /// `payload.subscriptions[].qos`.
+    pub(crate) fn enter_list_field(
+        &mut self,
+        field: &'static str,
+        index: usize,
+        bytes: &impl ByteOperations,
+    ) {
+        self.enter_field(field, bytes);
+        self.field_index = Some(index);
+    }
+
+    /// Renames the current field without moving its start, for fields sharing a byte.
+    pub(crate) fn set_field(&mut self, field: &'static str) {
+        self.field = field;
+    }
+
+    /// Places `error` at the field decoding was in, with `bytes` being what is left of
+    /// `frame`, the packet given to `start_packet`. The offending bytes, copied from `frame`,
+    /// are those read from the field so far, or the rest of the packet when the field failed
+    /// before reading anything.
+    pub(crate) fn decode_error(
+        &self,
+        error: MqttProtocolError,
+        bytes: &impl ByteOperations,
+        frame: &[u8],
+    ) -> DecodeError {
+        let position = self.frame_length.saturating_sub(bytes.bytes_len());
+        let start = self.field_start.min(frame.len());
+        let end = if position > start {
+            position.min(frame.len())
+        } else {
+            frame.len()
+        };
+        let field = match self.field_index {
+            Some(index) => self.field.replacen("[]", &format!("[{}]", index), 1),
+            None => self.field.to_string(),
+        };
+        let field = match &self.packet_type {
+            Some(packet_type) if !field.is_empty() => {
+                format!("{}.{}", packet_type.name().to_lowercase(), field)
+            }
+            _ => field,
+        };
+        DecodeError::new(
+            error,
+            self.field_start,
+            self.packet_type.clone(),
+            field,
+            frame[start..end].to_vec(),
+        )
+    }
+
+    /// Reads a length-prefixed UTF-8 string, applying the configured string checks.
+    pub(crate) fn read_string(
+        &mut self,
+        bytes: &mut impl ByteOperations,
+    ) -> Result<String, MqttProtocolError> {
+        let length = utf_8_handler::decode_length(bytes)? as usize;
+        if length > self.config.max_string_length {
+            self.violation(MqttProtocolError::StringTooLong(
+                length,
+                self.config.max_string_length,
+            ))?;
+        }
+
+        if bytes.bytes_len() < length {
+            return Err(MqttProtocolError::PacketTooShort);
+        }
+        let string_bytes = bytes.read_bytes(length);
+        if self.config.verify_forbidden_code_points
+            && let Err(error) = utf_8_handler::verify_for_mqtt(&string_bytes)
+        {
+            match error {
+                invalid @ crate::utils::code_error::CodeError::MQTTInvalidCode(_) => {
+                    self.violation(invalid.into())?
+                }
+                error => return Err(error.into()),
+            }
+        }
+        Ok(utf_8_handler::decode_utf8(string_bytes)?)
+    }
+}
+
+/// Fails with `PacketTooLarge` when a packet announcing `packet_size` bytes, fixed header
+/// included, is over `max_packet_size`.
+pub(crate) fn verify_packet_size(
+    packet_size: usize,
+    max_packet_size: usize,
+) -> Result<(), MqttProtocolError> {
+    if packet_size > max_packet_size {
+        return Err(MqttProtocolError::PacketTooLarge(
+            packet_size,
+            max_packet_size,
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod decoder_config_tests {
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::mqtt4::decoder_config::{DecodeContext, DecoderConfig, ViolationHandling};
+    use crate::utils::code_error::CodeError;
+    use bytes::BytesMut;
+
+    fn string_bytes(value: &str) -> BytesMut {
+        let mut bytes = BytesMut::new();
+        bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(value.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn strict_config_should_reject_forbidden_code_points_and_long_strings() {
+        let config = DecoderConfig::strict().with_max_string_length(4);
+        let mut context = DecodeContext::new(&config);
+
+        assert!(matches!(
+            context.read_string(&mut string_bytes("a\u{1}")),
+            Err(MqttProtocolError::CodeError(CodeError::MQTTInvalidCode(1)))
+        ));
+        assert!(matches!(
+            context.read_string(&mut string_bytes("hello")),
+            Err(MqttProtocolError::StringTooLong(5, 4))
+        ));
+        assert_eq!(
+            context.read_string(&mut string_bytes("abcd")).unwrap(),
+            "abcd"
+        );
+    }
+
+    #[test]
+    fn lenient_config_should_turn_violations_into_warnings() {
+        let config = DecoderConfig::lenient().with_max_string_length(4);
+        let mut context = DecodeContext::new(&config);
+
+        assert_eq!(
+            context.read_string(&mut string_bytes("a\u{1}")).unwrap(),
+            "a\u{1}"
+        );
+        assert_eq!(
+            context.read_string(&mut string_bytes("hello")).unwrap(),
+            "hello"
+        );
+        let warnings = context.into_warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(
+            warnings[1],
+            MqttProtocolError::StringTooLong(5, 4)
+        ));
+    }
+
+    #[test]
+    fn disabled_checks_should_neither_fail_nor_warn() {
+        let config = DecoderConfig::strict().with_forbidden_code_points_check(false);
+        let mut context = DecodeContext::new(&config);
+
+        assert_eq!(
+            context.read_string(&mut string_bytes("a\u{7F}")).unwrap(),
+            "a\u{7F}"
+        );
+        assert!(context.warnings().is_empty());
+        assert_eq!(config.violation_handling(), ViolationHandling::Reject);
+    }
+
+    #[test]
+    fn truncated_string_should_fail_even_when_lenient() {
+        for config in [DecoderConfig::strict(), DecoderConfig::lenient()] {
+            let mut context = DecodeContext::new(&config);
+            let mut bytes = BytesMut::from(&[0x00, 0x03, b'a', b'b'][..]);
+            assert!(matches!(
+                context.read_string(&mut bytes),
+                Err(MqttProtocolError::PacketTooShort)
+            ));
+        }
+    }
+
+    #[test]
+    fn invalid_utf8_should_fail_even_when_lenient() {
+        let config = DecoderConfig::lenient();
+        let mut context = DecodeContext::new(&config);
+        let mut bytes = BytesMut::from(&[0x00, 0x02, 0xC3, 0x28][..]);
+        assert!(context.read_string(&mut bytes).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::mqtt4::delivery::delivery_error::DeliveryError;
+use crate::protocol::mqtt4::delivery::inflight_publish::InflightPublish;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+use crate::protocol::mqtt4::variable_header_parser::pub_ack_parser::variable_header::PubAckVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::PublishVariableHeader;
+use std::collections::{HashSet, VecDeque};
+
+/// The sender side of QoS 1 delivery [MQTT-4.3.2]: every PUBLISH stays stored until the PUBACK
+/// with its packet identifier arrives. At most `max_inflight` messages wait at once, and they are
+/// kept in the order they were sent so a resumed session can send them again in that order.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub(crate) struct AtLeastOnceTracker {
+    max_inflight: usize,
+    inflight: VecDeque<InflightPublish>,
+    acknowledged: HashSet<PacketId>,
+}
+
+#[allow(dead_code)]
+impl AtLeastOnceTracker {
+    pub(crate) fn new(max_inflight: usize) -> Self {
+        AtLeastOnceTracker {
+            max_inflight,
+            inflight: VecDeque::new(),
+            acknowledged: HashSet::new(),
+        }
+    }
+
+    /// Stores a QoS 1 PUBLISH and returns the packet to send.
+    pub(crate) fn publish(
+        &mut self,
+        retain: bool,
+        variable: PublishVariableHeader,
+        payload: PublishPayload,
+    ) -> Result<Packet, DeliveryError> {
+        let qos = variable.qos();
+        let message = match InflightPublish::new(retain, variable, payload) {
+            Some(message) if qos == QoSCode::Qos1 => message,
+            _ => return Err(DeliveryError::WrongQoS(1, qos.as_u8())),
+        };
+        let packet = message.to_packet(false);
+        self.restore(message)?;
+        Ok(packet)
+    }
+
+    /// Puts back a message of a persisted session without sending it.
+    pub(crate) fn restore(&mut self, message: InflightPublish) -> Result<(), DeliveryError> {
+        let packet_identifier = message.packet_identifier();
+        if self.is_inflight(packet_identifier) {
+            return Err(DeliveryError::PacketIdentifierInUse(packet_identifier));
+        }
+        if self.inflight.len() >= self.max_inflight {
+            return Err(DeliveryError::InflightWindowFull(self.max_inflight));
+        }
+        self.acknowledged.remove(&packet_identifier);
+        self.inflight.push_back(message);
+        Ok(())
+    }
+
+    /// Matches a PUBACK with the PUBLISH it acknowledges and hands that PUBLISH back. A PUBACK
+    /// for an identifier acknowledged before and not reused since is a duplicate; one for an
+    /// identifier never sent is unexpected.
+    pub(crate) fn acknowledge(
+        &mut self,
+        variable: &PubAckVariableHeader,
+    ) -> Result<InflightPublish, DeliveryError> {
+        let packet_identifier = variable.packet_identifier();
+        let acknowledged = self
+            .inflight
+            .iter()
+            .position(|message| message.packet_identifier() == packet_identifier)
+            .and_then(|index| self.inflight.remove(index));
+        match acknowledged {
+            Some(message) => {
+                self.acknowledged.insert(packet_identifier);
+                Ok(message)
+            }
+            None if self.acknowledged.contains(&packet_identifier) => {
+                Err(DeliveryError::DuplicateAck("PUBACK", packet_identifier))
+            }
+            None => Err(DeliveryError::UnexpectedAck("PUBACK", packet_identifier)),
+        }
+    }
+
+    pub(crate) fn is_inflight(&self, packet_identifier: PacketId) -> bool {
+        self.get(packet_identifier).is_some()
+    }
+
+    pub(crate) fn get(&self, packet_identifier: PacketId) -> Option<&InflightPublish> {
+        self.inflight
+            .iter()
+            .find(|message| message.packet_identifier() == packet_identifier)
+    }
+
+    /// The unacknowledged messages, oldest first.
+    pub(crate) fn inflight(&self) -> impl Iterator<Item = &InflightPublish> {
+        self.inflight.iter()
+    }
+
+    pub(crate) fn inflight_len(&self) -> usize {
+        self.inflight.len()
+    }
+
+    pub(crate) fn max_inflight(&self) -> usize {
+        self.max_inflight
+    }
+
+    /// How many more messages `publish` accepts before the window is full.
+    pub(crate) fn available_window(&self) -> usize {
+        self.max_inflight.saturating_sub(self.inflight.len())
+    }
+
+    /// The PUBACK a receiver answers a QoS 1 PUBLISH with [MQTT-4.3.2-2].
+    pub(crate) fn pub_ack_for(variable: &PublishVariableHeader) -> Result<Packet, DeliveryError> {
+        match (variable.qos(), variable.packet_identifier()) {
+            (QoSCode::Qos1, Some(packet_identifier)) => Ok(Packet::pub_ack(packet_identifier)),
+            (qos, _) => Err(DeliveryError::WrongQoS(1, qos.as_u8())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod at_least_once_tests {
+    use crate::protocol::codec::Encoder;
+    use crate::protocol::common::packet_id::PacketId;
+    use crate::protocol::mqtt4::delivery::at_least_once::AtLeastOnceTracker;
+    use crate::protocol::mqtt4::delivery::delivery_error::DeliveryError;
+    use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+    use crate::protocol::mqtt4::variable_header_parser::pub_ack_parser::variable_header::PubAckVariableHeader;
+    use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+        PublishQoS, PublishVariableHeader,
+    };
+    use bytes::Bytes;
+
+    fn id(value: u16) -> PacketId {
+        PacketId::new(value).unwrap()
+    }
+
+    fn variable(packet_identifier: u16) -> PublishVariableHeader {
+        PublishVariableHeader::new(
+            "a/b".to_string(),
+            PublishQoS::AtLeastOnce(id(packet_identifier)),
+        )
+    }
+
+    fn publish(tracker: &mut AtLeastOnceTracker, packet_identifier: u16) -> Vec<u8> {
+        tracker
+            .publish(
+                false,
+                variable(packet_identifier),
+                PublishPayload::new(Bytes::from_static(b"x")),
+            )
+            .unwrap()
+            .encode()
+            .unwrap()
+    }
+
+    fn pub_ack(packet_identifier: u16) -> PubAckVariableHeader {
+        PubAckVariableHeader::new(id(packet_identifier))
+    }
+
+    #[test]
+    fn publish_should_be_kept_until_its_puback_arrives() {
+        let mut tracker = AtLeastOnceTracker::new(10);
+        assert_eq!(
+            publish(&mut tracker, 7),
+            vec![0x32, 0x08, 0x00, 0x03, b'a', b'/', b'b', 0x00, 0x07, b'x']
+        );
+        publish(&mut tracker, 8);
+        publish(&mut tracker, 9);
+        assert_eq!(tracker.inflight_len(), 3);
+        assert_eq!(tracker.available_window(), 7);
+
+        let acknowledged = tracker.acknowledge(&pub_ack(8)).unwrap();
+        assert_eq!(acknowledged.packet_identifier(), id(8));
+        assert!(!tracker.is_inflight(id(8)));
+        let remaining: Vec<u16> = tracker
+            .inflight()
+            .map(|message| message.packet_identifier().get())
+            .collect();
+        assert_eq!(remaining, vec![7, 9]);
+    }
+
+    #[test]
+    fn acknowledge_should_flag_duplicate_and_unexpected_pubacks() {
+        let mut tracker = AtLeastOnceTracker::new(10);
+        publish(&mut tracker, 1);
+        tracker.acknowledge(&pub_ack(1)).unwrap();
+        assert_eq!(
+            tracker.acknowledge(&pub_ack(1)),
+            Err(DeliveryError::DuplicateAck("PUBACK", id(1)))
+        );
+        assert_eq!(
+            tracker.acknowledge(&pub_ack(2)),
+            Err(DeliveryError::UnexpectedAck("PUBACK", id(2)))
+        );
+
+        // reusing the identifier makes its next PUBACK valid again
+        publish(&mut tracker, 1);
+        assert!(tracker.acknowledge(&pub_ack(1)).is_ok());
+    }
+
+    #[test]
+    fn publish_should_respect_the_window_and_identifiers_in_flight() {
+        let mut tracker = AtLeastOnceTracker::new(2);
+        publish(&mut tracker, 1);
+        assert_eq!(
+            tracker
+                .publish(
+                    false,
+                    variable(1),
+                    PublishPayload::new(Bytes::from_static(b"y"))
+                )
+                .err(),
+            Some(DeliveryError::PacketIdentifierInUse(id(1)))
+        );
+        publish(&mut tracker, 2);
+        assert_eq!(tracker.available_window(), 0);
+        assert_eq!(
+            tracker
+                .publish(
+                    false,
+                    variable(3),
+                    PublishPayload::new(Bytes::from_static(b"y"))
+                )
+                .err(),
+            Some(DeliveryError::InflightWindowFull(2))
+        );
+        assert_eq!(tracker.inflight_len(), 2);
+    }
+
+    #[test]
+    fn publish_should_only_take_qos_1() {
+        let mut tracker = AtLeastOnceTracker::new(2);
+        let qos0 = PublishVariableHeader::new("a".to_string(), PublishQoS::AtMostOnce);
+        let qos2 = PublishVariableHeader::new("a".to_string(), PublishQoS::ExactlyOnce(id(1)));
+        assert_eq!(
+            tracker
+                .publish(false, qos0.clone(), PublishPayload::new(Bytes::new()))
+                .err(),
+            Some(DeliveryError::WrongQoS(1, 0))
+        );
+        assert_eq!(
+            tracker
+                .publish(false, qos2.clone(), PublishPayload::new(Bytes::new()))
+                .err(),
+            Some(DeliveryError::WrongQoS(1, 2))
+        );
+        assert_eq!(
+            AtLeastOnceTracker::pub_ack_for(&qos2).err(),
+            Some(DeliveryError::WrongQoS(1, 2))
+        );
+        assert_eq!(
+            AtLeastOnceTracker::pub_ack_for(&qos0).err(),
+            Some(DeliveryError::WrongQoS(1, 0))
+        );
+    }
+
+    #[test]
+    fn pub_ack_for_should_answer_with_the_publish_packet_identifier() {
+        let mut pub_ack = AtLeastOnceTracker::pub_ack_for(&variable(0x0107)).unwrap();
+        assert_eq!(pub_ack.encode().unwrap(), vec![0x40, 0x02, 0x01, 0x07]);
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::packet_id::PacketId;
+
+/// Why a QoS 1 or QoS 2 delivery flow refused a packet.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq, Clone, thiserror::Error)]
+pub enum DeliveryError {
+    #[error("PUBLISH has QoS {1}, this flow handles QoS {0}")]
+    WrongQoS(u8, u8),
+
+    #[error("packet identifier {0} is already in flight")]
+    PacketIdentifierInUse(PacketId),
+
+    #[error("all 65535 packet identifiers are in use")]
+    PacketIdentifiersExhausted,
+
+    #[error("the inflight window of {0} messages is full")]
+    InflightWindowFull(usize),
+
+    #[error("{0} carries packet identifier {1}, which is not in flight")]
+    UnexpectedAck(&'static str, PacketId),
+
+    #[error("{0} carries packet identifier {1}, which has already been acknowledged")]
+    DuplicateAck(&'static str, PacketId),
+
+    #[error("{0} for packet identifier {1} arrived before {2}")]
+    OutOfOrderAck(&'static str, PacketId, &'static str),
+}
@@ -0,0 +1,556 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::mqtt4::delivery::delivery_error::DeliveryError;
+use crate::protocol::mqtt4::delivery::inflight_publish::InflightPublish;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+use crate::protocol::mqtt4::variable_header_parser::pub_comp_parser::variable_header::PubCompVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::pub_rec_parser::variable_header::PubRecVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::pub_rel_parser::variable_header::PubRelVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::PublishVariableHeader;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+/// How far the sender has got with one QoS 2 message.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum ExactlyOnceState {
+    /// The PUBLISH is sent and its PUBREC has not arrived.
+    AwaitingPubRec(InflightPublish),
+    /// The PUBREL is sent and its PUBCOMP has not arrived.
+    AwaitingPubComp(PacketId),
+}
+
+#[allow(dead_code)]
+impl ExactlyOnceState {
+    pub(crate) fn packet_identifier(&self) -> PacketId {
+        match self {
+            ExactlyOnceState::AwaitingPubRec(message) => message.packet_identifier(),
+            ExactlyOnceState::AwaitingPubComp(packet_identifier) => *packet_identifier,
+        }
+    }
+
+    /// What a resumed session sends again: the PUBLISH with DUP set, or the PUBREL
+    /// [MQTT-4.4.0-1].
+    pub(crate) fn retransmission(&self) -> Packet {
+        match self {
+            ExactlyOnceState::AwaitingPubRec(message) => message.to_packet(true),
+            ExactlyOnceState::AwaitingPubComp(packet_identifier) => {
+                Packet::pub_rel(*packet_identifier)
+            }
+        }
+    }
+}
+
+/// The sender side of QoS 2 delivery [MQTT-4.3.3]: PUBLISH, PUBREC, PUBREL, PUBCOMP. Messages
+/// are kept in the order they were published and move to the back once their PUBREC arrives, so
+/// PUBRELs keep the order of the PUBRECs [MQTT-4.6.0-4]. A PUBREC that arrives again is answered
+/// with the PUBREL again, while acks that skip a step or belong to a finished flow are refused.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub(crate) struct ExactlyOnceSender {
+    max_inflight: usize,
+    inflight: VecDeque<ExactlyOnceState>,
+    completed: HashSet<PacketId>,
+}
+
+#[allow(dead_code)]
+impl ExactlyOnceSender {
+    pub(crate) fn new(max_inflight: usize) -> Self {
+        ExactlyOnceSender {
+            max_inflight,
+            inflight: VecDeque::new(),
+            completed: HashSet::new(),
+        }
+    }
+
+    /// Stores a QoS 2 PUBLISH and returns the packet to send.
+    pub(crate) fn publish(
+        &mut self,
+        retain: bool,
+        variable: PublishVariableHeader,
+        payload: PublishPayload,
+    ) -> Result<Packet, DeliveryError> {
+        let qos = variable.qos();
+        let message = match InflightPublish::new(retain, variable, payload) {
+            Some(message) if qos == QoSCode::Qos2 => message,
+            _ => return Err(DeliveryError::WrongQoS(2, qos.as_u8())),
+        };
+        let packet = message.to_packet(false);
+        self.restore(ExactlyOnceState::AwaitingPubRec(message))?;
+        Ok(packet)
+    }
+
+    /// Puts back a flow of a persisted session without sending anything.
+    pub(crate) fn restore(&mut self, state: ExactlyOnceState) -> Result<(), DeliveryError> {
+        let packet_identifier = state.packet_identifier();
+        if self.is_inflight(packet_identifier) {
+            return Err(DeliveryError::PacketIdentifierInUse(packet_identifier));
+        }
+        if self.inflight.len() >= self.max_inflight {
+            return Err(DeliveryError::InflightWindowFull(self.max_inflight));
+        }
+        self.completed.remove(&packet_identifier);
+        self.inflight.push_back(state);
+        Ok(())
+    }
+
+    /// Answers a PUBREC with the PUBREL [MQTT-4.3.3-1]. From then on the PUBLISH is discarded
+    /// and never sent again, and the flow sits behind every PUBREL sent before.
+    pub(crate) fn handle_pub_rec(
+        &mut self,
+        variable: &PubRecVariableHeader,
+    ) -> Result<Packet, DeliveryError> {
+        let packet_identifier = variable.packet_identifier();
+        match self.position(packet_identifier) {
+            Some(index) => {
+                if let ExactlyOnceState::AwaitingPubRec(_) = self.inflight[index] {
+                    self.inflight.remove(index);
+                    self.inflight
+                        .push_back(ExactlyOnceState::AwaitingPubComp(packet_identifier));
+                }
+                Ok(Packet::pub_rel(packet_identifier))
+            }
+            None if self.completed.contains(&packet_identifier) => {
+                Err(DeliveryError::DuplicateAck("PUBREC", packet_identifier))
+            }
+            None => Err(DeliveryError::UnexpectedAck("PUBREC", packet_identifier)),
+        }
+    }
+
+    /// Finishes the flow of the PUBCOMP's packet identifier, which is free for reuse afterwards.
+    pub(crate) fn handle_pub_comp(
+        &mut self,
+        variable: &PubCompVariableHeader,
+    ) -> Result<PacketId, DeliveryError> {
+        let packet_identifier = variable.packet_identifier();
+        match self.position(packet_identifier) {
+            Some(index) => match self.inflight[index] {
+                ExactlyOnceState::AwaitingPubComp(_) => {
+                    self.inflight.remove(index);
+                    self.completed.insert(packet_identifier);
+                    Ok(packet_identifier)
+                }
+                ExactlyOnceState::AwaitingPubRec(_) => Err(DeliveryError::OutOfOrderAck(
+                    "PUBCOMP",
+                    packet_identifier,
+                    "PUBREC",
+                )),
+            },
+            None if self.completed.contains(&packet_identifier) => {
+                Err(DeliveryError::DuplicateAck("PUBCOMP", packet_identifier))
+            }
+            None => Err(DeliveryError::UnexpectedAck("PUBCOMP", packet_identifier)),
+        }
+    }
+
+    pub(crate) fn is_inflight(&self, packet_identifier: PacketId) -> bool {
+        self.position(packet_identifier).is_some()
+    }
+
+    pub(crate) fn get(&self, packet_identifier: PacketId) -> Option<&ExactlyOnceState> {
+        self.position(packet_identifier)
+            .map(|index| &self.inflight[index])
+    }
+
+    /// The unfinished flows: PUBLISH packets in the order they were sent, then PUBRELs in the
+    /// order their PUBRECs arrived.
+    pub(crate) fn inflight(&self) -> impl Iterator<Item = &ExactlyOnceState> {
+        self.inflight.iter()
+    }
+
+    pub(crate) fn inflight_len(&self) -> usize {
+        self.inflight.len()
+    }
+
+    pub(crate) fn max_inflight(&self) -> usize {
+        self.max_inflight
+    }
+
+    pub(crate) fn available_window(&self) -> usize {
+        self.max_inflight.saturating_sub(self.inflight.len())
+    }
+
+    /// The packets a resumed session sends again, in the order of `inflight`.
+    pub(crate) fn retransmissions(&self) -> Vec<Packet> {
+        self.inflight
+            .iter()
+            .map(ExactlyOnceState::retransmission)
+            .collect()
+    }
+
+    fn position(&self, packet_identifier: PacketId) -> Option<usize> {
+        self.inflight
+            .iter()
+            .position(|state| state.packet_identifier() == packet_identifier)
+    }
+}
+
+/// What the receiver makes of a QoS 2 PUBLISH.
+#[allow(dead_code)]
+pub(crate) struct ReceivedPublish {
+    /// Whether the application message goes to the application; false for a PUBLISH whose
+    /// packet identifier is still waiting for its PUBREL.
+    pub deliver: bool,
+    /// The PUBREC to send, which every PUBLISH gets, including repeated ones.
+    pub pub_rec: Packet,
+}
+
+/// The receiver side of QoS 2 delivery. A packet identifier is remembered from the first
+/// PUBLISH until the PUBREL releases it, and a PUBLISH arriving in between, with DUP set or not,
+/// is acknowledged without being delivered again [MQTT-4.3.3-2].
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExactlyOnceReceiver {
+    received: BTreeSet<PacketId>,
+}
+
+#[allow(dead_code)]
+impl ExactlyOnceReceiver {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// A receiver still waiting for the PUBRELs of `pending`, e.g. taken from a stored session.
+    pub(crate) fn restore(pending: impl IntoIterator<Item = PacketId>) -> Self {
+        ExactlyOnceReceiver {
+            received: pending.into_iter().collect(),
+        }
+    }
+
+    pub(crate) fn handle_publish(
+        &mut self,
+        variable: &PublishVariableHeader,
+    ) -> Result<ReceivedPublish, DeliveryError> {
+        match (variable.qos(), variable.packet_identifier()) {
+            (QoSCode::Qos2, Some(packet_identifier)) => Ok(ReceivedPublish {
+                deliver: self.received.insert(packet_identifier),
+                pub_rec: Packet::pub_rec(packet_identifier),
+            }),
+            (qos, _) => Err(DeliveryError::WrongQoS(2, qos.as_u8())),
+        }
+    }
+
+    /// Releases the packet identifier and returns the PUBCOMP. A PUBREL for an identifier
+    /// already released is the sender repeating it after a lost PUBCOMP, so it gets a PUBCOMP
+    /// too.
+    pub(crate) fn handle_pub_rel(&mut self, variable: &PubRelVariableHeader) -> Packet {
+        let packet_identifier = variable.packet_identifier();
+        self.received.remove(&packet_identifier);
+        Packet::pub_comp(packet_identifier)
+    }
+
+    pub(crate) fn is_pending(&self, packet_identifier: PacketId) -> bool {
+        self.received.contains(&packet_identifier)
+    }
+
+    /// The packet identifiers waiting for their PUBREL, in ascending order.
+    pub(crate) fn pending(&self) -> impl Iterator<Item = PacketId> + '_ {
+        self.received.iter().copied()
+    }
+}
+
+#[cfg(test)]
+mod exactly_once_tests {
+    use crate::protocol::codec::{Decoder, Encoder};
+    use crate::protocol::common::packet_id::PacketId;
+    use crate::protocol::mqtt4::delivery::delivery_error::DeliveryError;
+    use crate::protocol::mqtt4::delivery::exactly_once::{
+        ExactlyOnceReceiver, ExactlyOnceSender, ExactlyOnceState,
+    };
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+    use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+    use crate::protocol::mqtt4::variable_header_parser::pub_comp_parser::variable_header::PubCompVariableHeader;
+    use crate::protocol::mqtt4::variable_header_parser::pub_rec_parser::variable_header::PubRecVariableHeader;
+    use crate::protocol::mqtt4::variable_header_parser::pub_rel_parser::variable_header::PubRelVariableHeader;
+    use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+        PublishQoS, PublishVariableHeader,
+    };
+    use bytes::{Bytes, BytesMut};
+    use std::collections::VecDeque;
+
+    fn id(value: u16) -> PacketId {
+        PacketId::new(value).unwrap()
+    }
+
+    fn variable(packet_identifier: u16) -> PublishVariableHeader {
+        PublishVariableHeader::new(
+            "t".to_string(),
+            PublishQoS::ExactlyOnce(id(packet_identifier)),
+        )
+    }
+
+    fn payload() -> PublishPayload {
+        PublishPayload::new(Bytes::from_static(b"x"))
+    }
+
+    fn encode(mut packet: Packet) -> Vec<u8> {
+        packet.encode().unwrap()
+    }
+
+    fn decode(bytes: &[u8]) -> Packet {
+        Packet::decode(&mut BytesMut::from(bytes)).unwrap()
+    }
+
+    fn pub_rec(packet_identifier: u16) -> PubRecVariableHeader {
+        PubRecVariableHeader::new(id(packet_identifier))
+    }
+
+    fn pub_comp(packet_identifier: u16) -> PubCompVariableHeader {
+        PubCompVariableHeader::new(id(packet_identifier))
+    }
+
+    fn pub_rel(packet_identifier: u16) -> PubRelVariableHeader {
+        PubRelVariableHeader::new(id(packet_identifier))
+    }
+
+    #[test]
+    fn sender_should_walk_publish_pubrec_pubrel_pubcomp() {
+        let mut sender = ExactlyOnceSender::new(4);
+        let publish = sender.publish(false, variable(1), payload()).unwrap();
+        assert_eq!(
+            encode(publish),
+            vec![0x34, 0x06, 0x00, 0x01, b't', 0x00, 0x01, b'x']
+        );
+
+        let pub_rel = sender.handle_pub_rec(&pub_rec(1)).unwrap();
+        assert_eq!(encode(pub_rel), vec![0x62, 0x02, 0x00, 0x01]);
+        assert_eq!(
+            sender.inflight().collect::<Vec<_>>(),
+            vec![&ExactlyOnceState::AwaitingPubComp(id(1))]
+        );
+
+        // a repeated PUBREC gets the PUBREL again
+        let pub_rel = sender.handle_pub_rec(&pub_rec(1)).unwrap();
+        assert_eq!(encode(pub_rel), vec![0x62, 0x02, 0x00, 0x01]);
+
+        assert_eq!(sender.handle_pub_comp(&pub_comp(1)), Ok(id(1)));
+        assert_eq!(sender.inflight_len(), 0);
+    }
+
+    #[test]
+    fn sender_should_refuse_acks_out_of_order_or_after_completion() {
+        let mut sender = ExactlyOnceSender::new(4);
+        sender.publish(false, variable(1), payload()).unwrap();
+        assert_eq!(
+            sender.handle_pub_comp(&pub_comp(1)),
+            Err(DeliveryError::OutOfOrderAck("PUBCOMP", id(1), "PUBREC"))
+        );
+        assert!(sender.is_inflight(id(1)));
+
+        sender.handle_pub_rec(&pub_rec(1)).unwrap();
+        sender.handle_pub_comp(&pub_comp(1)).unwrap();
+        assert_eq!(
+            sender.handle_pub_comp(&pub_comp(1)),
+            Err(DeliveryError::DuplicateAck("PUBCOMP", id(1)))
+        );
+        assert_eq!(
+            sender.handle_pub_rec(&pub_rec(1)).err(),
+            Some(DeliveryError::DuplicateAck("PUBREC", id(1)))
+        );
+        assert_eq!(
+            sender.handle_pub_rec(&pub_rec(2)).err(),
+            Some(DeliveryError::UnexpectedAck("PUBREC", id(2)))
+        );
+        assert_eq!(
+            sender.handle_pub_comp(&pub_comp(2)),
+            Err(DeliveryError::UnexpectedAck("PUBCOMP", id(2)))
+        );
+    }
+
+    #[test]
+    fn sender_should_respect_the_window_and_qos() {
+        let mut sender = ExactlyOnceSender::new(1);
+        sender.publish(false, variable(1), payload()).unwrap();
+        assert_eq!(
+            sender.publish(false, variable(1), payload()).err(),
+            Some(DeliveryError::PacketIdentifierInUse(id(1)))
+        );
+        assert_eq!(
+            sender.publish(false, variable(2), payload()).err(),
+            Some(DeliveryError::InflightWindowFull(1))
+        );
+        let qos1 = PublishVariableHeader::new("t".to_string(), PublishQoS::AtLeastOnce(id(3)));
+        assert_eq!(
+            ExactlyOnceSender::new(1)
+                .publish(false, qos1.clone(), payload())
+                .err(),
+            Some(DeliveryError::WrongQoS(2, 1))
+        );
+        assert_eq!(
+            ExactlyOnceReceiver::new().handle_publish(&qos1).err(),
+            Some(DeliveryError::WrongQoS(2, 1))
+        );
+    }
+
+    #[test]
+    fn sender_retransmissions_should_keep_order_and_identifiers() {
+        let mut sender = ExactlyOnceSender::new(4);
+        sender.publish(false, variable(3), payload()).unwrap();
+        sender.publish(true, variable(1), payload()).unwrap();
+        sender.publish(false, variable(2), payload()).unwrap();
+        sender.handle_pub_rec(&pub_rec(2)).unwrap();
+        sender.handle_pub_rec(&pub_rec(3)).unwrap();
+        // a repeated PUBREC keeps its place
+        sender.handle_pub_rec(&pub_rec(2)).unwrap();
+        let bytes: Vec<Vec<u8>> = sender.retransmissions().into_iter().map(encode).collect();
+        assert_eq!(
+            bytes,
+            vec![
+                vec![0x3D, 0x06, 0x00, 0x01, b't', 0x00, 0x01, b'x'],
+                vec![0x62, 0x02, 0x00, 0x02],
+                vec![0x62, 0x02, 0x00, 0x03],
+            ]
+        );
+    }
+
+    #[test]
+    fn receiver_should_deliver_once_until_pubrel() {
+        let mut receiver = ExactlyOnceReceiver::new();
+        let first = receiver.handle_publish(&variable(9)).unwrap();
+        assert!(first.deliver);
+        assert_eq!(encode(first.pub_rec), vec![0x50, 0x02, 0x00, 0x09]);
+
+        let repeated = receiver.handle_publish(&variable(9)).unwrap();
+        assert!(!repeated.deliver);
+        assert_eq!(encode(repeated.pub_rec), vec![0x50, 0x02, 0x00, 0x09]);
+        assert!(receiver.is_pending(id(9)));
+
+        assert_eq!(
+            encode(receiver.handle_pub_rel(&pub_rel(9))),
+            vec![0x70, 0x02, 0x00, 0x09]
+        );
+        assert!(!receiver.is_pending(id(9)));
+        // a repeated PUBREL is still completed
+        assert_eq!(
+            encode(receiver.handle_pub_rel(&pub_rel(9))),
+            vec![0x70, 0x02, 0x00, 0x09]
+        );
+        // once released the identifier carries a new message
+        assert!(receiver.handle_publish(&variable(9)).unwrap().deliver);
+    }
+
+    const MESSAGES: u16 = 2;
+    const MAX_CONNECTION_LOSSES: usize = 3;
+
+    /// A sender and a receiver joined by a connection that may be lost at any point; after a
+    /// loss the packets in transit are gone and the sender resumes its session.
+    #[derive(Clone)]
+    struct World {
+        sender: ExactlyOnceSender,
+        receiver: ExactlyOnceReceiver,
+        to_receiver: VecDeque<Vec<u8>>,
+        to_sender: VecDeque<Vec<u8>>,
+        delivered: [usize; MESSAGES as usize],
+        connection_losses: usize,
+    }
+
+    impl World {
+        fn deliver_to_receiver(&mut self) {
+            let bytes = self.to_receiver.pop_front().unwrap();
+            let answer = match decode(&bytes) {
+                Packet::Publish { variable, .. } => {
+                    let received = self.receiver.handle_publish(&variable).unwrap();
+                    if received.deliver {
+                        let index = variable.packet_identifier().unwrap().get() - 1;
+                        self.delivered[usize::from(index)] += 1;
+                    }
+                    received.pub_rec
+                }
+                Packet::PubRel { variable, .. } => self.receiver.handle_pub_rel(&variable),
+                _ => unreachable!(),
+            };
+            self.to_sender.push_back(encode(answer));
+        }
+
+        fn deliver_to_sender(&mut self) {
+            let bytes = self.to_sender.pop_front().unwrap();
+            match decode(&bytes) {
+                Packet::PubRec { variable, .. } => {
+                    let pub_rel = self.sender.handle_pub_rec(&variable).unwrap();
+                    self.to_receiver.push_back(encode(pub_rel));
+                }
+                Packet::PubComp { variable, .. } => {
+                    self.sender.handle_pub_comp(&variable).unwrap();
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        fn lose_connection(&mut self) {
+            self.connection_losses += 1;
+            self.to_receiver = self
+                .sender
+                .retransmissions()
+                .into_iter()
+                .map(encode)
+                .collect();
+            self.to_sender.clear();
+        }
+    }
+
+    fn explore(world: World, finished: &mut usize) {
+        assert!(world.delivered.iter().all(|&count| count <= 1));
+        let mut moved = false;
+        if !world.to_receiver.is_empty() {
+            moved = true;
+            let mut next = world.clone();
+            next.deliver_to_receiver();
+            explore(next, finished);
+        }
+        if !world.to_sender.is_empty() {
+            moved = true;
+            let mut next = world.clone();
+            next.deliver_to_sender();
+            explore(next, finished);
+        }
+        if world.connection_losses < MAX_CONNECTION_LOSSES && world.sender.inflight_len() > 0 {
+            moved = true;
+            let mut next = world.clone();
+            next.lose_connection();
+            explore(next, finished);
+        }
+        if !moved {
+            assert_eq!(world.delivered, [1; MESSAGES as usize]);
+            assert_eq!(world.sender.inflight_len(), 0);
+            assert_eq!(world.receiver.pending().count(), 0);
+            *finished += 1;
+        }
+    }
+
+    #[test]
+    fn every_interleaving_should_deliver_each_message_exactly_once() {
+        let mut world = World {
+            sender: ExactlyOnceSender::new(usize::from(MESSAGES)),
+            receiver: ExactlyOnceReceiver::new(),
+            to_receiver: VecDeque::new(),
+            to_sender: VecDeque::new(),
+            delivered: [0; MESSAGES as usize],
+            connection_losses: 0,
+        };
+        for packet_identifier in 1..=MESSAGES {
+            let publish = world
+                .sender
+                .publish(false, variable(packet_identifier), payload())
+                .unwrap();
+            world.to_receiver.push_back(encode(publish));
+        }
+
+        let mut finished = 0;
+        explore(world, &mut finished);
+        assert_eq!(finished, 3095);
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::PublishVariableHeader;
+
+/// A QoS 1 or QoS 2 PUBLISH kept by its sender until the receiver acknowledges it.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct InflightPublish {
+    packet_identifier: PacketId,
+    retain: bool,
+    variable: PublishVariableHeader,
+    payload: PublishPayload,
+}
+
+#[allow(dead_code)]
+impl InflightPublish {
+    /// `None` for a QoS 0 PUBLISH, which is never acknowledged.
+    pub(crate) fn new(
+        retain: bool,
+        variable: PublishVariableHeader,
+        payload: PublishPayload,
+    ) -> Option<Self> {
+        Some(InflightPublish {
+            packet_identifier: variable.packet_identifier()?,
+            retain,
+            variable,
+            payload,
+        })
+    }
+
+    pub(crate) fn packet_identifier(&self) -> PacketId {
+        self.packet_identifier
+    }
+
+    pub(crate) fn retain(&self) -> bool {
+        self.retain
+    }
+
+    pub(crate) fn variable(&self) -> &PublishVariableHeader {
+        &self.variable
+    }
+
+    pub(crate) fn payload(&self) -> &PublishPayload {
+        &self.payload
+    }
+
+    /// The PUBLISH to put on the wire; `dup` is set when it is sent again [MQTT-3.3.1-1].
+    pub(crate) fn to_packet(&self, dup: bool) -> Packet {
+        Packet::publish(
+            dup,
+            self.retain,
+            self.variable.clone(),
+            self.payload.clone(),
+        )
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::mqtt4::delivery::delivery_error::DeliveryError;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use std::collections::BTreeSet;
+
+/// Hands out the packet identifiers a client or session puts on PUBLISH (QoS > 0), SUBSCRIBE and
+/// UNSUBSCRIBE. Identifiers are taken in turn from 1 to 65535 and then from 1 again, skipping
+/// every one still in use, so a released identifier is not reused straight away
+/// [MQTT-2.3.1-2].
+///
+/// With the `serde` feature the allocator serializes to
+/// `{"next_packet_identifier":3,"in_use":[1,2]}`, so it can be persisted with its session.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct PacketIdAllocator {
+    next_packet_identifier: PacketId,
+    in_use: BTreeSet<PacketId>,
+}
+
+impl Default for PacketIdAllocator {
+    fn default() -> Self {
+        PacketIdAllocator {
+            next_packet_identifier: PacketId::MIN,
+            in_use: BTreeSet::new(),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl PacketIdAllocator {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn allocate(&mut self) -> Result<PacketId, DeliveryError> {
+        if self.in_use.len() == usize::from(u16::MAX) {
+            return Err(DeliveryError::PacketIdentifiersExhausted);
+        }
+        let mut candidate = self.next_packet_identifier;
+        while self.in_use.contains(&candidate) {
+            candidate = Self::following(candidate);
+        }
+        self.in_use.insert(candidate);
+        self.next_packet_identifier = Self::following(candidate);
+        Ok(candidate)
+    }
+
+    /// Marks an identifier restored from a persisted session as in use.
+    pub(crate) fn reserve(&mut self, packet_identifier: PacketId) -> Result<(), DeliveryError> {
+        if !self.in_use.insert(packet_identifier) {
+            return Err(DeliveryError::PacketIdentifierInUse(packet_identifier));
+        }
+        Ok(())
+    }
+
+    /// Returns whether the identifier was in use.
+    pub(crate) fn release(&mut self, packet_identifier: PacketId) -> bool {
+        self.in_use.remove(&packet_identifier)
+    }
+
+    /// Releases the identifier of the ack that ends a flow: PUBACK, PUBCOMP, SUBACK or UNSUBACK.
+    /// A PUBREC keeps its identifier, which stays in use until the PUBCOMP. Returns the released
+    /// identifier, or `None` for other packets and identifiers not in use.
+    pub(crate) fn release_for(&mut self, packet: &Packet) -> Option<PacketId> {
+        let packet_identifier = match packet {
+            Packet::PubAck { variable, .. } | Packet::UnsubAck { variable, .. } => {
+                variable.packet_identifier()
+            }
+            Packet::PubComp { variable, .. } => variable.packet_identifier(),
+            Packet::SubAck { variable, .. } => variable.packet_identifier(),
+            _ => return None,
+        };
+        self.release(packet_identifier).then_some(packet_identifier)
+    }
+
+    pub(crate) fn is_in_use(&self, packet_identifier: PacketId) -> bool {
+        self.in_use.contains(&packet_identifier)
+    }
+
+    pub(crate) fn in_use_len(&self) -> usize {
+        self.in_use.len()
+    }
+
+    pub(crate) fn available(&self) -> usize {
+        usize::from(u16::MAX) - self.in_use.len()
+    }
+
+    fn following(packet_identifier: PacketId) -> PacketId {
+        PacketId::new(packet_identifier.get().wrapping_add(1)).unwrap_or(PacketId::MIN)
+    }
+}
+
+#[cfg(test)]
+mod packet_id_allocator_tests {
+    use crate::protocol::common::packet_id::PacketId;
+    use crate::protocol::common::return_code::ReturnCode;
+    use crate::protocol::mqtt4::delivery::delivery_error::DeliveryError;
+    use crate::protocol::mqtt4::delivery::packet_id_allocator::PacketIdAllocator;
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+
+    fn id(value: u16) -> PacketId {
+        PacketId::new(value).unwrap()
+    }
+
+    #[test]
+    fn allocate_should_count_up_and_wrap_past_identifiers_in_use() {
+        let mut allocator = PacketIdAllocator::new();
+        assert_eq!(allocator.allocate(), Ok(id(1)));
+        assert_eq!(allocator.allocate(), Ok(id(2)));
+        allocator.release(id(2));
+        // a released identifier waits for its turn
+        assert_eq!(allocator.allocate(), Ok(id(3)));
+
+        for expected in 4..=u16::MAX {
+            assert_eq!(allocator.allocate(), Ok(id(expected)));
+        }
+        // 1 and 3 are still in use
+        assert_eq!(allocator.allocate(), Ok(id(2)));
+        allocator.release(id(1));
+        assert_eq!(allocator.allocate(), Ok(id(1)));
+    }
+
+    #[test]
+    fn allocate_should_report_exhaustion() {
+        let mut allocator = PacketIdAllocator::new();
+        for _ in 0..u16::MAX {
+            allocator.allocate().unwrap();
+        }
+        assert_eq!(allocator.available(), 0);
+        assert_eq!(
+            allocator.allocate(),
+            Err(DeliveryError::PacketIdentifiersExhausted)
+        );
+        allocator.release(id(40_000));
+        assert_eq!(allocator.allocate(), Ok(id(40_000)));
+    }
+
+    #[test]
+    fn reserve_should_keep_restored_identifiers_from_being_handed_out() {
+        let mut allocator = PacketIdAllocator::new();
+        allocator.reserve(id(1)).unwrap();
+        allocator.reserve(id(3)).unwrap();
+        assert_eq!(
+            allocator.reserve(id(3)),
+            Err(DeliveryError::PacketIdentifierInUse(id(3)))
+        );
+        assert_eq!(allocator.allocate(), Ok(id(2)));
+        assert_eq!(allocator.allocate(), Ok(id(4)));
+        assert_eq!(allocator.in_use_len(), 4);
+    }
+
+    #[test]
+    fn release_for_should_only_release_on_flow_ending_acks() {
+        let mut allocator = PacketIdAllocator::new();
+        for _ in 0..4 {
+            allocator.allocate().unwrap();
+        }
+        assert_eq!(allocator.release_for(&Packet::pub_rec(id(1))), None);
+        assert!(allocator.is_in_use(id(1)));
+        assert_eq!(allocator.release_for(&Packet::pub_comp(id(1))), Some(id(1)));
+        assert_eq!(allocator.release_for(&Packet::pub_ack(id(2))), Some(id(2)));
+        assert_eq!(allocator.release_for(&Packet::pub_ack(id(2))), None);
+        assert_eq!(
+            allocator.release_for(&Packet::conn_ack(false, ReturnCode::ConnectionAccepted)),
+            None
+        );
+        assert_eq!(allocator.in_use_len(), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn allocator_should_round_trip_through_serde() {
+        let mut allocator = PacketIdAllocator::new();
+        allocator.allocate().unwrap();
+        allocator.allocate().unwrap();
+        allocator.release(id(1));
+
+        let json = serde_json::to_string(&allocator).unwrap();
+        assert_eq!(json, r#"{"next_packet_identifier":3,"in_use":[2]}"#);
+        let mut restored: PacketIdAllocator = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, allocator);
+        assert_eq!(restored.allocate(), Ok(id(3)));
+
+        assert!(
+            serde_json::from_str::<PacketIdAllocator>(
+                r#"{"next_packet_identifier":0,"in_use":[]}"#
+            )
+            .is_err()
+        );
+    }
+}
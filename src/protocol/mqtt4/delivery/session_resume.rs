@@ -0,0 +1,336 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::mqtt4::delivery::at_least_once::AtLeastOnceTracker;
+use crate::protocol::mqtt4::delivery::delivery_error::DeliveryError;
+use crate::protocol::mqtt4::delivery::exactly_once::{ExactlyOnceSender, ExactlyOnceState};
+use crate::protocol::mqtt4::delivery::inflight_publish::InflightPublish;
+use crate::protocol::mqtt4::delivery::packet_id_allocator::PacketIdAllocator;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use std::collections::HashSet;
+
+/// An outbound message whose flow has not finished, as a session persists it.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum InflightMessage {
+    /// A QoS 1 or QoS 2 PUBLISH whose PUBACK or PUBREC has not arrived.
+    Publish(InflightPublish),
+    /// A QoS 2 message whose PUBREL has no PUBCOMP yet.
+    PubRel(PacketId),
+}
+
+#[allow(dead_code)]
+impl InflightMessage {
+    pub(crate) fn packet_identifier(&self) -> PacketId {
+        match self {
+            InflightMessage::Publish(message) => message.packet_identifier(),
+            InflightMessage::PubRel(packet_identifier) => *packet_identifier,
+        }
+    }
+
+    /// The PUBLISH with DUP set, or the PUBREL.
+    pub(crate) fn retransmission(&self) -> Packet {
+        match self {
+            InflightMessage::Publish(message) => message.to_packet(true),
+            InflightMessage::PubRel(packet_identifier) => Packet::pub_rel(*packet_identifier),
+        }
+    }
+}
+
+/// The trackers and allocator `SessionResume::restore` rebuilds.
+#[allow(dead_code)]
+pub(crate) struct RestoredSession {
+    pub at_least_once: AtLeastOnceTracker,
+    pub exactly_once: ExactlyOnceSender,
+    pub packet_identifiers: PacketIdAllocator,
+    /// The packet identifiers in the order the messages are stored in.
+    pub order: Vec<PacketId>,
+}
+
+/// The outbound messages of a session that is resumed with CleanSession 0. Resuming re-sends
+/// every unacknowledged PUBLISH and PUBREL with its original packet identifier [MQTT-4.4.0-1],
+/// PUBLISH packets with DUP set in the order they were first sent [MQTT-4.6.0-1] [MQTT-4.6.0-2]
+/// and PUBRELs in the order their PUBRECs arrived [MQTT-4.6.0-4].
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone, Default)]
+pub(crate) struct SessionResume {
+    messages: Vec<InflightMessage>,
+}
+
+#[allow(dead_code)]
+impl SessionResume {
+    /// Persisted messages, oldest first. Each packet identifier may appear once.
+    pub(crate) fn new(messages: Vec<InflightMessage>) -> Result<Self, DeliveryError> {
+        let mut seen = HashSet::new();
+        for message in &messages {
+            if !seen.insert(message.packet_identifier()) {
+                return Err(DeliveryError::PacketIdentifierInUse(
+                    message.packet_identifier(),
+                ));
+            }
+        }
+        Ok(SessionResume { messages })
+    }
+
+    /// The messages the trackers hold, taken in `order`, the order the PUBLISH packets were
+    /// first sent in. The PUBRELs fill the places of their flows in the order their PUBRECs
+    /// arrived, which `exactly_once` keeps. Identifiers neither tracker holds any more are
+    /// skipped.
+    pub(crate) fn capture(
+        order: impl IntoIterator<Item = PacketId>,
+        at_least_once: &AtLeastOnceTracker,
+        exactly_once: &ExactlyOnceSender,
+    ) -> Self {
+        let mut seen = HashSet::new();
+        let order: Vec<PacketId> = order
+            .into_iter()
+            .filter(|packet_identifier| seen.insert(*packet_identifier))
+            .collect();
+        let mut pub_rels = exactly_once.inflight().filter_map(|state| match state {
+            ExactlyOnceState::AwaitingPubComp(packet_identifier)
+                if seen.contains(packet_identifier) =>
+            {
+                Some(*packet_identifier)
+            }
+            _ => None,
+        });
+        let messages = order
+            .into_iter()
+            .filter_map(|packet_identifier| {
+                if let Some(message) = at_least_once.get(packet_identifier) {
+                    return Some(InflightMessage::Publish(message.clone()));
+                }
+                match exactly_once.get(packet_identifier)? {
+                    ExactlyOnceState::AwaitingPubRec(message) => {
+                        Some(InflightMessage::Publish(message.clone()))
+                    }
+                    ExactlyOnceState::AwaitingPubComp(_) => {
+                        pub_rels.next().map(InflightMessage::PubRel)
+                    }
+                }
+            })
+            .collect();
+        SessionResume { messages }
+    }
+
+    pub(crate) fn messages(&self) -> &[InflightMessage] {
+        &self.messages
+    }
+
+    /// The packets to send once the new connection is accepted, in order.
+    pub(crate) fn retransmissions(&self) -> Vec<Packet> {
+        self.messages
+            .iter()
+            .map(InflightMessage::retransmission)
+            .collect()
+    }
+
+    /// Trackers holding the messages again, with `max_inflight` as the window of each, and an
+    /// allocator that will not hand out their packet identifiers.
+    pub(crate) fn restore(&self, max_inflight: usize) -> Result<RestoredSession, DeliveryError> {
+        let mut restored = RestoredSession {
+            at_least_once: AtLeastOnceTracker::new(max_inflight),
+            exactly_once: ExactlyOnceSender::new(max_inflight),
+            packet_identifiers: PacketIdAllocator::new(),
+            order: Vec::with_capacity(self.messages.len()),
+        };
+        for message in &self.messages {
+            match message {
+                InflightMessage::Publish(message) if message.variable().qos() == QoSCode::Qos1 => {
+                    restored.at_least_once.restore(message.clone())?
+                }
+                InflightMessage::Publish(message) => restored
+                    .exactly_once
+                    .restore(ExactlyOnceState::AwaitingPubRec(message.clone()))?,
+                InflightMessage::PubRel(packet_identifier) => restored
+                    .exactly_once
+                    .restore(ExactlyOnceState::AwaitingPubComp(*packet_identifier))?,
+            }
+            restored
+                .packet_identifiers
+                .reserve(message.packet_identifier())?;
+            restored.order.push(message.packet_identifier());
+        }
+        Ok(restored)
+    }
+}
+
+#[cfg(test)]
+mod session_resume_tests {
+    use crate::protocol::codec::Encoder;
+    use crate::protocol::common::packet_id::PacketId;
+    use crate::protocol::mqtt4::delivery::at_least_once::AtLeastOnceTracker;
+    use crate::protocol::mqtt4::delivery::delivery_error::DeliveryError;
+    use crate::protocol::mqtt4::delivery::exactly_once::ExactlyOnceSender;
+    use crate::protocol::mqtt4::delivery::inflight_publish::InflightPublish;
+    use crate::protocol::mqtt4::delivery::session_resume::{InflightMessage, SessionResume};
+    use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+    use crate::protocol::mqtt4::variable_header_parser::pub_rec_parser::variable_header::PubRecVariableHeader;
+    use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+        PublishQoS, PublishVariableHeader,
+    };
+    use bytes::Bytes;
+
+    fn id(value: u16) -> PacketId {
+        PacketId::new(value).unwrap()
+    }
+
+    fn message(retain: bool, qos: PublishQoS) -> InflightPublish {
+        InflightPublish::new(
+            retain,
+            PublishVariableHeader::new("t".to_string(), qos),
+            PublishPayload::new(Bytes::from_static(b"x")),
+        )
+        .unwrap()
+    }
+
+    fn wire(resume: &SessionResume) -> Vec<Vec<u8>> {
+        resume
+            .retransmissions()
+            .into_iter()
+            .map(|mut packet| packet.encode().unwrap())
+            .collect()
+    }
+
+    fn persisted() -> SessionResume {
+        SessionResume::new(vec![
+            InflightMessage::Publish(message(true, PublishQoS::AtLeastOnce(id(10)))),
+            InflightMessage::PubRel(id(4)),
+            InflightMessage::Publish(message(false, PublishQoS::ExactlyOnce(id(7)))),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn retransmissions_should_set_dup_and_keep_order_and_identifiers() {
+        assert_eq!(
+            wire(&persisted()),
+            vec![
+                vec![0x3B, 0x06, 0x00, 0x01, b't', 0x00, 0x0A, b'x'],
+                vec![0x62, 0x02, 0x00, 0x04],
+                vec![0x3C, 0x06, 0x00, 0x01, b't', 0x00, 0x07, b'x'],
+            ]
+        );
+        assert!(SessionResume::default().retransmissions().is_empty());
+    }
+
+    #[test]
+    fn new_should_reject_a_packet_identifier_used_twice() {
+        assert_eq!(
+            SessionResume::new(vec![
+                InflightMessage::Publish(message(false, PublishQoS::AtLeastOnce(id(3)))),
+                InflightMessage::PubRel(id(3)),
+            ]),
+            Err(DeliveryError::PacketIdentifierInUse(id(3)))
+        );
+    }
+
+    #[test]
+    fn capture_should_follow_the_send_order_across_both_trackers() {
+        let mut at_least_once = AtLeastOnceTracker::new(10);
+        let mut exactly_once = ExactlyOnceSender::new(10);
+        let qos1 = |packet_identifier| {
+            PublishVariableHeader::new(
+                "t".to_string(),
+                PublishQoS::AtLeastOnce(id(packet_identifier)),
+            )
+        };
+        let qos2 = |packet_identifier| {
+            PublishVariableHeader::new(
+                "t".to_string(),
+                PublishQoS::ExactlyOnce(id(packet_identifier)),
+            )
+        };
+        let payload = || PublishPayload::new(Bytes::from_static(b"x"));
+        exactly_once.publish(false, qos2(5), payload()).unwrap();
+        at_least_once.publish(false, qos1(6), payload()).unwrap();
+        exactly_once.publish(false, qos2(8), payload()).unwrap();
+        at_least_once.publish(false, qos1(9), payload()).unwrap();
+        exactly_once
+            .handle_pub_rec(&PubRecVariableHeader::new(id(5)))
+            .unwrap();
+
+        // 2 has been acknowledged already
+        let order = [5, 2, 6, 8, 9].map(id);
+        let resume = SessionResume::capture(order, &at_least_once, &exactly_once);
+        assert_eq!(
+            wire(&resume),
+            vec![
+                vec![0x62, 0x02, 0x00, 0x05],
+                vec![0x3A, 0x06, 0x00, 0x01, b't', 0x00, 0x06, b'x'],
+                vec![0x3C, 0x06, 0x00, 0x01, b't', 0x00, 0x08, b'x'],
+                vec![0x3A, 0x06, 0x00, 0x01, b't', 0x00, 0x09, b'x'],
+            ]
+        );
+    }
+
+    #[test]
+    fn capture_should_send_pub_rels_in_the_order_of_their_pub_recs() {
+        let at_least_once = AtLeastOnceTracker::new(10);
+        let mut exactly_once = ExactlyOnceSender::new(10);
+        let qos2 = |packet_identifier| {
+            PublishVariableHeader::new(
+                "t".to_string(),
+                PublishQoS::ExactlyOnce(id(packet_identifier)),
+            )
+        };
+        let payload = || PublishPayload::new(Bytes::from_static(b"x"));
+        exactly_once.publish(false, qos2(5), payload()).unwrap();
+        exactly_once.publish(false, qos2(2), payload()).unwrap();
+        exactly_once.publish(false, qos2(7), payload()).unwrap();
+        exactly_once
+            .handle_pub_rec(&PubRecVariableHeader::new(id(2)))
+            .unwrap();
+        exactly_once
+            .handle_pub_rec(&PubRecVariableHeader::new(id(5)))
+            .unwrap();
+
+        let order = [5, 2, 7].map(id);
+        let resume = SessionResume::capture(order, &at_least_once, &exactly_once);
+        assert_eq!(
+            wire(&resume),
+            vec![
+                vec![0x62, 0x02, 0x00, 0x02],
+                vec![0x62, 0x02, 0x00, 0x05],
+                vec![0x3C, 0x06, 0x00, 0x01, b't', 0x00, 0x07, b'x'],
+            ]
+        );
+    }
+
+    #[test]
+    fn restore_should_rebuild_trackers_and_reserve_identifiers() {
+        let resume = persisted();
+        let mut restored = resume.restore(10).unwrap();
+        assert_eq!(restored.order, vec![id(10), id(4), id(7)]);
+        assert!(restored.at_least_once.is_inflight(id(10)));
+        assert!(restored.exactly_once.is_inflight(id(4)));
+        assert!(restored.exactly_once.is_inflight(id(7)));
+        assert_eq!(restored.packet_identifiers.in_use_len(), 3);
+        assert_eq!(restored.packet_identifiers.allocate(), Ok(id(1)));
+
+        let captured = SessionResume::capture(
+            restored.order.iter().copied(),
+            &restored.at_least_once,
+            &restored.exactly_once,
+        );
+        assert_eq!(captured, resume);
+
+        assert_eq!(
+            resume.restore(1).err(),
+            Some(DeliveryError::InflightWindowFull(1))
+        );
+    }
+}
@@ -15,14 +15,18 @@
 use crate::byte_adapter::byte_operations::ByteOperations;
 use crate::protocol::common::control_packet_type::ControlPacketType;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::decoder_config::{DecodeContext, verify_packet_size};
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header_codec::MqttFixedHeaderCodec;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
 use crate::protocol::mqtt4::fixed_header_parser::remaining_length::remaining_length_parser;
 
 impl MqttFixedHeaderCodec for FixedHeader {
-    fn decode(bytes: &mut impl ByteOperations) -> Result<Self, MqttProtocolError> {
-        Self::decode(bytes)
+    fn decode(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<Self, MqttProtocolError> {
+        Self::decode_with_context(bytes, context)
     }
 }
 
@@ -31,15 +35,37 @@ impl FixedHeader {
     pub(crate) fn decode(
         bytes: &mut impl ByteOperations,
     ) -> Result<FixedHeader, MqttProtocolError> {
+        Self::decode_with_context(bytes, &mut DecodeContext::strict())
+    }
+
+    pub(crate) fn decode_with_context(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<FixedHeader, MqttProtocolError> {
+        context.enter_field("fixed_header.packet_type", bytes);
         let first_byte = bytes
             .read_a_byte()
             .ok_or(MqttProtocolError::PacketTooShort)?;
         let control_packet_type = ControlPacketType::parse(first_byte)?;
+        context.set_packet_type(control_packet_type.clone());
+        context.set_field("fixed_header.flags");
 
+        if context.config().verify_reserved_flags()
+            && let Err(error) = FixedHeaderFlags::verify(control_packet_type.clone(), first_byte)
+        {
+            context.violation(error)?;
+        }
         let fixed_header_reserve_flags =
-            FixedHeaderFlags::parse(control_packet_type.clone(), first_byte)?;
+            FixedHeaderFlags::create_factory(control_packet_type.clone(), first_byte)?;
 
-        let remaining_length = remaining_length_parser::parse(bytes)?;
+        context.enter_field("fixed_header.remaining_length", bytes);
+        let (remaining_length, length_bytes) = remaining_length_parser::parse_with_length(bytes)?;
+        // a size limit protects resources rather than the protocol, so lenient decoding
+        // does not relax it
+        verify_packet_size(
+            1 + length_bytes + remaining_length as usize,
+            context.config().max_packet_size(),
+        )?;
 
         Ok(FixedHeader::self_create(
             control_packet_type,
@@ -16,6 +16,14 @@ use crate::protocol::common::control_packet_type::ControlPacketType;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        try_from = "crate::protocol::mqtt4::fixed_header_parser::fixed_header_serde::FixedHeaderRepr",
+        into = "crate::protocol::mqtt4::fixed_header_parser::fixed_header_serde::FixedHeaderRepr"
+    )
+)]
 pub struct FixedHeader {
     control_packet_type: ControlPacketType,
     fixed_header_reserved_flags: FixedHeaderFlags,
@@ -14,10 +14,14 @@
 
 use crate::byte_adapter::byte_operations::ByteOperations;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::decoder_config::DecodeContext;
 
 #[allow(dead_code)]
 pub(crate) trait MqttFixedHeaderCodec {
-    fn decode(bytes: &mut impl ByteOperations) -> Result<Self, MqttProtocolError>
+    fn decode(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<Self, MqttProtocolError>
     where
         Self: Sized;
 }
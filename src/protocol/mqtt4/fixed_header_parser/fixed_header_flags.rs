@@ -171,7 +171,7 @@ impl FixedHeaderFlags {
         Ok(())
     }
 
-    pub(self) fn create_factory(
+    pub(crate) fn create_factory(
         control_packet_type: ControlPacketType,
         binary_byte: u8,
     ) -> Result<Self, MqttProtocolError> {
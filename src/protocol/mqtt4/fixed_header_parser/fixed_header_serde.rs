@@ -0,0 +1,195 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::control_packet_type::ControlPacketType;
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
+use serde::{Deserialize, Serialize};
+
+/// The `fixed_header` object of the JSON representation. `flags` is the low nibble of the first
+/// byte; PUBLISH also spells it out as `dup`, `qos` and `retain`. On input every field is
+/// optional: missing flags take the value the packet type requires and `remaining_length` is only
+/// informational, encoding computes it again.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct FixedHeaderFields {
+    #[serde(default)]
+    flags: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dup: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    qos: Option<QoSCode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retain: Option<bool>,
+    #[serde(default)]
+    remaining_length: u32,
+}
+
+impl FixedHeaderFields {
+    pub(crate) fn from_fixed_header(fixed_header: &FixedHeader) -> FixedHeaderFields {
+        let flags = fixed_header.fixed_header_reserved_flags();
+        let mut fields = FixedHeaderFields {
+            flags: Some(flags.encode() & 0x0F),
+            remaining_length: fixed_header.remaining_length(),
+            ..FixedHeaderFields::default()
+        };
+        if let FixedHeaderFlags::Publish { dup, qos, retain } = flags {
+            fields.dup = Some(*dup);
+            fields.qos = Some(*qos);
+            fields.retain = Some(*retain);
+        }
+        fields
+    }
+
+    pub(crate) fn into_fixed_header(
+        self,
+        control_packet_type: ControlPacketType,
+    ) -> Result<FixedHeader, MqttProtocolError> {
+        let nibble = match self.flags {
+            Some(flags) if flags > 0x0F => return Err(MqttProtocolError::InvalidFixedHeaderFlags),
+            Some(flags) => flags,
+            None => Self::required_flags(&control_packet_type),
+        };
+        let parsed = FixedHeaderFlags::parse(control_packet_type.clone(), nibble)?;
+
+        let flags = match parsed {
+            FixedHeaderFlags::Publish { dup, qos, retain } => {
+                let spelled_out = FixedHeaderFlags::Publish {
+                    dup: self.dup.unwrap_or(dup),
+                    qos: self.qos.unwrap_or(qos),
+                    retain: self.retain.unwrap_or(retain),
+                };
+                if self.flags.is_some() && spelled_out != parsed {
+                    return Err(MqttProtocolError::InvalidFixedHeaderFlags);
+                }
+                spelled_out
+            }
+            other => {
+                if self.dup.is_some() || self.qos.is_some() || self.retain.is_some() {
+                    return Err(MqttProtocolError::InvalidFixedHeaderFlags);
+                }
+                other
+            }
+        };
+
+        Ok(FixedHeader::self_create(
+            control_packet_type,
+            flags,
+            self.remaining_length,
+        ))
+    }
+
+    fn required_flags(control_packet_type: &ControlPacketType) -> u8 {
+        match control_packet_type {
+            ControlPacketType::PubRel
+            | ControlPacketType::Subscribe
+            | ControlPacketType::Unsubscribe => 0b0000_0010,
+            _ => 0b0000_0000,
+        }
+    }
+}
+
+/// A standalone fixed header carries its packet type next to the fields.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct FixedHeaderRepr {
+    #[serde(rename = "type")]
+    control_packet_type: ControlPacketType,
+    #[serde(flatten)]
+    fields: FixedHeaderFields,
+}
+
+impl From<FixedHeader> for FixedHeaderRepr {
+    fn from(fixed_header: FixedHeader) -> Self {
+        FixedHeaderRepr {
+            fields: FixedHeaderFields::from_fixed_header(&fixed_header),
+            control_packet_type: fixed_header.control_packet_type().clone(),
+        }
+    }
+}
+
+impl TryFrom<FixedHeaderRepr> for FixedHeader {
+    type Error = MqttProtocolError;
+
+    fn try_from(repr: FixedHeaderRepr) -> Result<Self, Self::Error> {
+        repr.fields.into_fixed_header(repr.control_packet_type)
+    }
+}
+
+#[cfg(test)]
+mod fixed_header_serde_tests {
+    use crate::protocol::common::control_packet_type::ControlPacketType;
+    use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
+    use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
+
+    #[test]
+    fn fixed_header_should_serialize_publish_flags_spelled_out() {
+        let fixed_header = FixedHeader::new(
+            ControlPacketType::Publish,
+            FixedHeaderFlags::Publish {
+                dup: false,
+                qos: QoSCode::Qos2,
+                retain: true,
+            },
+        );
+        let json = serde_json::to_string(&fixed_header).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"PUBLISH","flags":5,"dup":false,"qos":2,"retain":true,"remaining_length":0}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<FixedHeader>(&json).unwrap(),
+            fixed_header
+        );
+    }
+
+    #[test]
+    fn fixed_header_should_fill_in_required_flags() {
+        let fixed_header: FixedHeader = serde_json::from_str(r#"{"type":"SUBSCRIBE"}"#).unwrap();
+        assert_eq!(
+            fixed_header.fixed_header_reserved_flags(),
+            &FixedHeaderFlags::Subscribe
+        );
+
+        let fixed_header: FixedHeader =
+            serde_json::from_str(r#"{"type":"PUBLISH","qos":1}"#).unwrap();
+        assert_eq!(
+            fixed_header.fixed_header_reserved_flags(),
+            &FixedHeaderFlags::Publish {
+                dup: false,
+                qos: QoSCode::Qos1,
+                retain: false
+            }
+        );
+    }
+
+    #[test]
+    fn fixed_header_should_reject_invalid_flags() {
+        for json in [
+            r#"{"type":"SUBSCRIBE","flags":0}"#,
+            r#"{"type":"CONNECT","retain":true}"#,
+            r#"{"type":"PUBLISH","flags":6}"#,
+            r#"{"type":"PUBLISH","flags":1,"retain":false}"#,
+            r#"{"type":"PUBLISH","qos":3}"#,
+            r#"{"type":"RESERVED"}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<FixedHeader>(json).is_err(),
+                "{}",
+                json
+            );
+        }
+    }
+}
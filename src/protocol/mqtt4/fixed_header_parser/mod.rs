@@ -17,4 +17,6 @@ mod encoder;
 pub(crate) mod fixed_header;
 pub(crate) mod fixed_header_codec;
 pub(crate) mod fixed_header_flags;
+#[cfg(feature = "serde")]
+pub(crate) mod fixed_header_serde;
 pub(crate) mod remaining_length;
@@ -19,6 +19,13 @@ pub(crate) mod remaining_length_parser {
     const MAX_MULTIPLIER: u32 = 128 * 128 * 128;
 
     pub(crate) fn parse(bytes_ops: &mut impl ByteOperations) -> Result<u32, MqttProtocolError> {
+        parse_with_length(bytes_ops).map(|(value, _)| value)
+    }
+
+    /// Like `parse`, also returning how many bytes encoded the value.
+    pub(crate) fn parse_with_length(
+        bytes_ops: &mut impl ByteOperations,
+    ) -> Result<(u32, usize), MqttProtocolError> {
         let mut value: u32 = 0;
         let mut multiplier: u32 = 1;
         let mut bytes_read = 0;
@@ -33,7 +40,7 @@ pub(crate) mod remaining_length_parser {
             value += calculate_current_value(current_byte, multiplier);
 
             if is_end_byte(current_byte) {
-                return Ok(value);
+                return Ok((value, bytes_read));
             }
 
             if exceeds_max_multiplier(multiplier) {
@@ -48,6 +55,29 @@ pub(crate) mod remaining_length_parser {
         }
     }
 
+    /// Like `parse`, but on a borrowed slice so nothing is consumed. Returns the value together
+    /// with the number of bytes that encoded it, or `None` if the slice ends before the last byte.
+    pub(crate) fn peek(bytes: &[u8]) -> Result<Option<(u32, usize)>, MqttProtocolError> {
+        let mut value: u32 = 0;
+        let mut multiplier: u32 = 1;
+
+        for (index, current_byte) in bytes.iter().enumerate() {
+            value += calculate_current_value(*current_byte, multiplier);
+
+            if is_end_byte(*current_byte) {
+                return Ok(Some((value, index + 1)));
+            }
+
+            if exceeds_max_bytes(index + 1) {
+                return Err(MqttProtocolError::MalformedRemainingLength);
+            }
+
+            multiplier *= 128;
+        }
+
+        Ok(None)
+    }
+
     fn calculate_current_value(encoded_byte: u8, multiplier: u32) -> u32 {
         (encoded_byte & 0x7F) as u32 * multiplier
     }
@@ -256,4 +286,27 @@ mod remaining_length_tests {
         assert_eq!(value, 77);
         assert_eq!(bytes_mut.read_a_byte().unwrap(), 1);
     }
+
+    #[test]
+    fn remaining_length_peek_should_not_consume_bytes() {
+        let vec = remaining_length_parser::encode(321).unwrap();
+        let value = remaining_length_parser::peek(&vec).unwrap();
+        assert_eq!(value, Some((321, 2)));
+    }
+
+    #[test]
+    fn remaining_length_peek_should_return_none_when_incomplete() {
+        let value = remaining_length_parser::peek(&[0x80, 0x80]).unwrap();
+        assert_eq!(value, None);
+        assert_eq!(remaining_length_parser::peek(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn remaining_length_peek_more_than_four_bytes_should_error() {
+        let result = remaining_length_parser::peek(&[0xFF, 0xFF, 0xFF, 0xFF, 0x01]);
+        assert!(matches!(
+            result,
+            Err(MqttProtocolError::MalformedRemainingLength)
+        ));
+    }
 }
@@ -0,0 +1,90 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Where time-driven components read the current time from.
+#[allow(dead_code)]
+pub(crate) trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The monotonic system clock.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when `advance` is called. Clones share their time, so a test can
+/// keep one and hand another to the component under test.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub(crate) struct ManualClock {
+    now: Rc<Cell<Instant>>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        ManualClock {
+            now: Rc::new(Cell::new(Instant::now())),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl ManualClock {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+#[cfg(test)]
+mod clock_tests {
+    use crate::protocol::mqtt4::keep_alive::clock::{Clock, ManualClock, SystemClock};
+    use std::time::Duration;
+
+    #[test]
+    fn manual_clock_clones_should_share_their_time() {
+        let clock = ManualClock::new();
+        let shared = clock.clone();
+        let start = clock.now();
+        shared.advance(Duration::from_secs(3));
+        assert_eq!(clock.now() - start, Duration::from_secs(3));
+        assert_eq!(shared.now(), clock.now());
+    }
+
+    #[test]
+    fn system_clock_should_not_go_backwards() {
+        let clock = SystemClock;
+        let first = clock.now();
+        assert!(clock.now() >= first);
+    }
+}
@@ -0,0 +1,229 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::control_packet_type::ControlPacketType;
+use crate::protocol::mqtt4::keep_alive::clock::Clock;
+use std::time::{Duration, Instant};
+
+/// Which end of the connection a `KeepAliveSupervisor` watches for.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepAliveRole {
+    Client,
+    Server,
+}
+
+/// Why the supervisor gave up on the connection.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepAliveTimeout {
+    /// The server heard nothing from the client for one and a half Keep Alive periods
+    /// [MQTT-3.1.2-24].
+    ClientSilent,
+    /// The client sent a PINGREQ and no PINGRESP came back in time.
+    PingRespMissing,
+}
+
+/// What `KeepAliveSupervisor::poll` asks the connection to do.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepAliveAction {
+    /// The client has sent nothing for a Keep Alive period [MQTT-3.1.2-23].
+    SendPingReq,
+    /// Close the network connection.
+    Close(KeepAliveTimeout),
+}
+
+/// Acts on the Keep Alive of a CONNECT for either end of the connection. The connection reports
+/// every packet it sends and receives, and calls `poll` whenever `next_deadline` has passed. A
+/// Keep Alive of 0 turns the mechanism off.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub(crate) struct KeepAliveSupervisor<C: Clock> {
+    clock: C,
+    role: KeepAliveRole,
+    keep_alive: Duration,
+    ping_timeout: Duration,
+    last_received: Instant,
+    last_sent: Instant,
+    ping_sent: Option<Instant>,
+}
+
+#[allow(dead_code)]
+impl<C: Clock> KeepAliveSupervisor<C> {
+    /// A client that waits one Keep Alive period for each PINGRESP.
+    pub(crate) fn client(clock: C, keep_alive: u16) -> Self {
+        Self::new(clock, KeepAliveRole::Client, keep_alive)
+    }
+
+    pub(crate) fn server(clock: C, keep_alive: u16) -> Self {
+        Self::new(clock, KeepAliveRole::Server, keep_alive)
+    }
+
+    fn new(clock: C, role: KeepAliveRole, keep_alive: u16) -> Self {
+        let now = clock.now();
+        let keep_alive = Duration::from_secs(u64::from(keep_alive));
+        KeepAliveSupervisor {
+            clock,
+            role,
+            keep_alive,
+            ping_timeout: keep_alive,
+            last_received: now,
+            last_sent: now,
+            ping_sent: None,
+        }
+    }
+
+    /// How long a client waits for the PINGRESP before it treats the connection as dead.
+    pub(crate) fn with_ping_timeout(mut self, ping_timeout: Duration) -> Self {
+        self.ping_timeout = ping_timeout;
+        self
+    }
+
+    pub(crate) fn role(&self) -> KeepAliveRole {
+        self.role
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        !self.keep_alive.is_zero()
+    }
+
+    pub(crate) fn packet_received(&mut self, packet_type: &ControlPacketType) {
+        self.last_received = self.clock.now();
+        if *packet_type == ControlPacketType::PingResp {
+            self.ping_sent = None;
+        }
+    }
+
+    pub(crate) fn packet_sent(&mut self) {
+        self.last_sent = self.clock.now();
+    }
+
+    /// When `poll` may have something to do next, or `None` while Keep Alive is off.
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        if !self.is_enabled() {
+            return None;
+        }
+        Some(match (self.role, self.ping_sent) {
+            (KeepAliveRole::Server, _) => self.last_received + self.keep_alive * 3 / 2,
+            (KeepAliveRole::Client, Some(ping_sent)) => ping_sent + self.ping_timeout,
+            (KeepAliveRole::Client, None) => self.last_sent + self.keep_alive,
+        })
+    }
+
+    /// Checks the deadline against the clock. A returned `SendPingReq` counts as sent.
+    pub(crate) fn poll(&mut self) -> Option<KeepAliveAction> {
+        let now = self.clock.now();
+        if now < self.next_deadline()? {
+            return None;
+        }
+        match (self.role, self.ping_sent) {
+            (KeepAliveRole::Server, _) => {
+                Some(KeepAliveAction::Close(KeepAliveTimeout::ClientSilent))
+            }
+            (KeepAliveRole::Client, Some(_)) => {
+                Some(KeepAliveAction::Close(KeepAliveTimeout::PingRespMissing))
+            }
+            (KeepAliveRole::Client, None) => {
+                self.ping_sent = Some(now);
+                self.last_sent = now;
+                Some(KeepAliveAction::SendPingReq)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod keep_alive_supervisor_tests {
+    use crate::protocol::common::control_packet_type::ControlPacketType;
+    use crate::protocol::mqtt4::keep_alive::clock::{Clock, ManualClock};
+    use crate::protocol::mqtt4::keep_alive::keep_alive_supervisor::{
+        KeepAliveAction, KeepAliveSupervisor, KeepAliveTimeout,
+    };
+    use std::time::Duration;
+
+    fn millis(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    #[test]
+    fn server_should_close_after_one_and_a_half_keep_alive_periods_of_silence() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        let mut supervisor = KeepAliveSupervisor::server(clock.clone(), 10);
+        assert_eq!(supervisor.next_deadline(), Some(start + millis(15_000)));
+
+        clock.advance(millis(10_000));
+        supervisor.packet_received(&ControlPacketType::PingReq);
+        // sending does not count, only what the client sends does
+        supervisor.packet_sent();
+        clock.advance(millis(14_999));
+        assert_eq!(supervisor.poll(), None);
+        clock.advance(millis(1));
+        assert_eq!(
+            supervisor.poll(),
+            Some(KeepAliveAction::Close(KeepAliveTimeout::ClientSilent))
+        );
+    }
+
+    #[test]
+    fn client_should_ping_after_a_keep_alive_period_without_sending() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        let mut supervisor = KeepAliveSupervisor::client(clock.clone(), 10);
+
+        clock.advance(millis(4_000));
+        supervisor.packet_sent();
+        clock.advance(millis(9_999));
+        assert_eq!(supervisor.poll(), None);
+        clock.advance(millis(1));
+        assert_eq!(supervisor.poll(), Some(KeepAliveAction::SendPingReq));
+        assert_eq!(supervisor.poll(), None);
+
+        clock.advance(millis(2_000));
+        supervisor.packet_received(&ControlPacketType::PingResp);
+        assert_eq!(supervisor.next_deadline(), Some(start + millis(24_000)));
+    }
+
+    #[test]
+    fn client_should_treat_a_missing_pingresp_as_a_dead_connection() {
+        let clock = ManualClock::new();
+        let mut supervisor =
+            KeepAliveSupervisor::client(clock.clone(), 10).with_ping_timeout(millis(3_000));
+        clock.advance(millis(10_000));
+        assert_eq!(supervisor.poll(), Some(KeepAliveAction::SendPingReq));
+
+        // other packets do not answer the PINGREQ
+        clock.advance(millis(1_000));
+        supervisor.packet_received(&ControlPacketType::Publish);
+        clock.advance(millis(2_000));
+        assert_eq!(
+            supervisor.poll(),
+            Some(KeepAliveAction::Close(KeepAliveTimeout::PingRespMissing))
+        );
+    }
+
+    #[test]
+    fn zero_keep_alive_should_disable_supervision() {
+        let clock = ManualClock::new();
+        let mut client = KeepAliveSupervisor::client(clock.clone(), 0);
+        let mut server = KeepAliveSupervisor::server(clock.clone(), 0);
+        clock.advance(Duration::from_secs(1_000_000));
+        assert!(!client.is_enabled());
+        assert_eq!(client.next_deadline(), None);
+        assert_eq!(client.poll(), None);
+        assert_eq!(server.next_deadline(), None);
+        assert_eq!(server.poll(), None);
+    }
+}
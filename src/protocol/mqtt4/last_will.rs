@@ -0,0 +1,161 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use crate::protocol::mqtt4::payload_parser::connect_parser::payload::ConnectPayload;
+use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::ConnectVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+    PublishQoS, PublishVariableHeader,
+};
+use bytes::Bytes;
+
+/// The Will Message of a CONNECT, gathered from the Will Topic and Will Message in the payload
+/// and the Will QoS and Will Retain flags in the variable header. The server publishes it when
+/// the network connection closes without a DISCONNECT [MQTT-3.1.2-8].
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct LastWill {
+    topic_name: String,
+    message: Bytes,
+    qos: QoSCode,
+    retain: bool,
+}
+
+#[allow(dead_code)]
+impl LastWill {
+    /// `None` when the Will Flag is not set.
+    pub(crate) fn from_connect(
+        variable: &ConnectVariableHeader,
+        payload: &ConnectPayload,
+    ) -> Option<Self> {
+        let flags = variable.connect_flags();
+        if !flags.will_flag() {
+            return None;
+        }
+        Some(LastWill {
+            topic_name: payload.will_topic()?.to_string(),
+            message: Bytes::copy_from_slice(payload.will_message()?),
+            qos: *flags.will_qos(),
+            retain: flags.will_retain(),
+        })
+    }
+
+    /// The will of `packet` if it is a CONNECT with the Will Flag set.
+    pub(crate) fn from_packet(packet: &Packet) -> Option<Self> {
+        match packet {
+            Packet::Connect {
+                variable, payload, ..
+            } => Self::from_connect(variable, payload),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn topic_name(&self) -> &str {
+        &self.topic_name
+    }
+
+    pub(crate) fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    pub(crate) fn qos(&self) -> QoSCode {
+        self.qos
+    }
+
+    pub(crate) fn retain(&self) -> bool {
+        self.retain
+    }
+
+    /// The PUBLISH carrying the will with its Will QoS and Will Retain [MQTT-3.1.2-14]
+    /// [MQTT-3.1.2-15] [MQTT-3.1.2-17]. `packet_identifier` is only used at QoS 1 and 2. The
+    /// will message becomes the payload byte for byte.
+    pub(crate) fn to_packet(&self, packet_identifier: PacketId) -> Packet {
+        let qos = PublishQoS::with_packet_identifier(self.qos, packet_identifier);
+        Packet::publish(
+            false,
+            self.retain,
+            PublishVariableHeader::new(self.topic_name.clone(), qos),
+            PublishPayload::new(self.message.clone()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod last_will_tests {
+    use crate::protocol::codec::Encoder;
+    use crate::protocol::common::packet_id::PacketId;
+    use crate::protocol::common::protocol_level::ProtocolLevel;
+    use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::mqtt4::last_will::LastWill;
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+    use crate::protocol::mqtt4::payload_parser::connect_parser::payload::ConnectPayload;
+    use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::{
+        ConnectFlags, ConnectVariableHeader,
+    };
+    use bytes::Bytes;
+
+    fn connect(will: Option<(QoSCode, bool, &'static [u8])>) -> Packet {
+        let (will_qos, will_retain) = will.map_or((QoSCode::Qos0, false), |will| (will.0, will.1));
+        let flags =
+            ConnectFlags::new(false, false, will_retain, will_qos, will.is_some(), true).unwrap();
+        let variable = ConnectVariableHeader::new(ProtocolLevel::Mqtt3_1_1, flags, 60);
+        let payload = ConnectPayload::new(
+            "client".to_string(),
+            will.map(|_| "w".to_string()),
+            will.map(|will| Bytes::from_static(will.2)),
+            None,
+            None,
+        );
+        Packet::connect(variable, payload)
+    }
+
+    #[test]
+    fn will_should_combine_the_connect_flags_and_payload() {
+        let will = LastWill::from_packet(&connect(Some((QoSCode::Qos1, true, b"bye")))).unwrap();
+        assert_eq!(will.topic_name(), "w");
+        assert_eq!(will.message(), b"bye");
+        assert_eq!(will.qos(), QoSCode::Qos1);
+        assert!(will.retain());
+
+        let mut packet = will.to_packet(PacketId::new(5).unwrap());
+        assert_eq!(
+            packet.encode().unwrap(),
+            vec![0x33, 0x08, 0x00, 0x01, b'w', 0x00, 0x05, b'b', b'y', b'e']
+        );
+
+        let will = LastWill::from_packet(&connect(Some((QoSCode::Qos0, false, b"")))).unwrap();
+        let mut packet = will.to_packet(PacketId::MIN);
+        assert_eq!(packet.encode().unwrap(), vec![0x30, 0x03, 0x00, 0x01, b'w']);
+    }
+
+    #[test]
+    fn connect_without_will_flag_should_have_no_will() {
+        assert!(LastWill::from_packet(&connect(None)).is_none());
+        assert!(LastWill::from_packet(&Packet::disconnect()).is_none());
+    }
+
+    #[test]
+    fn will_message_that_is_not_utf8_should_become_the_publish_payload() {
+        let will =
+            LastWill::from_packet(&connect(Some((QoSCode::Qos0, false, &[0xFF, 0x00])))).unwrap();
+        let mut packet = will.to_packet(PacketId::MIN);
+        assert_eq!(
+            packet.encode().unwrap(),
+            vec![0x30, 0x05, 0x00, 0x01, b'w', 0xFF, 0x00]
+        );
+    }
+}
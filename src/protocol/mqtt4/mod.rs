@@ -12,8 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-mod fixed_header_parser;
+pub(crate) mod client;
+pub(crate) mod conformance;
+pub(crate) mod decoder_config;
+pub(crate) mod delivery;
+pub(crate) mod fixed_header_parser;
+pub(crate) mod keep_alive;
+pub(crate) mod last_will;
 
-mod packet_parser;
-mod payload_parser;
-mod variable_header_parser;
+pub(crate) mod packet_parser;
+pub(crate) mod payload_parser;
+pub(crate) mod retained;
+pub(crate) mod server_connection;
+pub(crate) mod server_reaction;
+pub(crate) mod session;
+pub(crate) mod variable_header_parser;
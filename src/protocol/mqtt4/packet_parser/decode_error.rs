@@ -0,0 +1,232 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::control_packet_type::ControlPacketType;
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::utils::hex::hex_handler;
+use std::fmt;
+
+/// Offending bytes shown by `Display`; `bytes` always holds all of them.
+const DISPLAYED_BYTES: usize = 16;
+
+/// A `MqttProtocolError` placed in the packet it came from: the byte offset where the failing
+/// field starts, the type of the packet if the fixed header got that far, the field path such
+/// as `connect.payload.will_topic` and the bytes of that field. Match on `error()` for the
+/// reason itself.
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) struct DecodeError {
+    error: MqttProtocolError,
+    offset: usize,
+    packet_type: Option<ControlPacketType>,
+    field: String,
+    bytes: Vec<u8>,
+}
+
+#[allow(dead_code)]
+impl DecodeError {
+    pub(crate) fn new(
+        error: MqttProtocolError,
+        offset: usize,
+        packet_type: Option<ControlPacketType>,
+        field: String,
+        bytes: Vec<u8>,
+    ) -> Self {
+        DecodeError {
+            error,
+            offset,
+            packet_type,
+            field,
+            bytes,
+        }
+    }
+
+    pub(crate) fn error(&self) -> &MqttProtocolError {
+        &self.error
+    }
+
+    pub(crate) fn into_error(self) -> MqttProtocolError {
+        self.error
+    }
+
+    /// Byte offset of the failing field, counted from the start of the packet unless moved
+    /// with `with_base_offset`.
+    pub(crate) fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub(crate) fn packet_type(&self) -> Option<&ControlPacketType> {
+        self.packet_type.as_ref()
+    }
+
+    /// Path of the failing field, empty when the error is not tied to one.
+    pub(crate) fn field(&self) -> &str {
+        &self.field
+    }
+
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Shifts the offset by where the packet starts in a larger stream.
+    pub(crate) fn with_base_offset(mut self, base_offset: usize) -> Self {
+        self.offset += base_offset;
+        self
+    }
+}
+
+impl From<MqttProtocolError> for DecodeError {
+    fn from(error: MqttProtocolError) -> Self {
+        DecodeError::new(error, 0, None, String::new(), Vec::new())
+    }
+}
+
+impl From<DecodeError> for MqttProtocolError {
+    fn from(error: DecodeError) -> Self {
+        error.error
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        if !self.field.is_empty() {
+            write!(f, " in {}", self.field)?;
+        }
+        write!(f, " at byte offset {}", self.offset)?;
+        if !self.bytes.is_empty() {
+            let shown = self.bytes.len().min(DISPLAYED_BYTES);
+            write!(f, " (bytes {}", hex_handler::encode(&self.bytes[..shown]))?;
+            if shown < self.bytes.len() {
+                write!(f, "...")?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod decode_error_tests {
+    use crate::protocol::common::control_packet_type::ControlPacketType;
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::mqtt4::decoder_config::DecoderConfig;
+    use crate::protocol::mqtt4::packet_parser::decode_error::DecodeError;
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+    use crate::utils::code_error::CodeError;
+    use bytes::BytesMut;
+
+    fn decode_error(bytes: &[u8]) -> DecodeError {
+        let result =
+            Packet::decode_with_config(&mut BytesMut::from(bytes), &DecoderConfig::strict());
+        match result {
+            Ok(_) => panic!("decoding should fail"),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn decode_error_should_locate_the_failing_connect_field() {
+        // CONNECT with the will flag, client id "c1" and a will topic that is not UTF-8
+        let error = decode_error(&[
+            0x10, 0x15, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x06, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1', 0x00, 0x02, 0xC3, 0x28, 0x00, 0x01, 0xFF,
+        ]);
+
+        assert!(matches!(
+            error.error(),
+            MqttProtocolError::CodeError(CodeError::UTF8DecodingError)
+        ));
+        assert_eq!(error.offset(), 16);
+        assert_eq!(error.packet_type(), Some(&ControlPacketType::Connect));
+        assert_eq!(error.field(), "connect.payload.will_topic");
+        assert_eq!(error.bytes(), &[0x00, 0x02, 0xC3, 0x28]);
+        assert_eq!(
+            error.to_string(),
+            concat!(
+                "from CodeError: UTF-8 decoding error in connect.payload.will_topic at byte ",
+                "offset 16 (bytes 0002c328)"
+            )
+        );
+    }
+
+    #[test]
+    fn decode_error_should_index_list_fields() {
+        // SUBSCRIBE to "a" at QoS 0 and "b" at QoS 3
+        let error = decode_error(&[
+            0x82, 0x0A, 0x00, 0x01, 0x00, 0x01, b'a', 0x00, 0x00, 0x01, b'b', 0x03,
+        ]);
+
+        assert!(matches!(
+            error.error(),
+            MqttProtocolError::QoSLevelNotSupported(3)
+        ));
+        assert_eq!(error.field(), "subscribe.payload.subscriptions[1].qos");
+        assert_eq!(error.offset(), 11);
+        assert_eq!(error.bytes(), &[0x03]);
+    }
+
+    #[test]
+    fn decode_error_should_show_the_rest_of_a_truncated_field() {
+        // CONNECT ending after the first keep alive byte
+        let error = decode_error(&[
+            0x10, 0x09, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x02, 0x00,
+        ]);
+
+        assert_eq!(error.field(), "connect.variable_header.keep_alive");
+        assert_eq!(error.offset(), 10);
+        assert_eq!(error.bytes(), &[0x00]);
+    }
+
+    #[test]
+    fn decode_error_should_report_an_unknown_packet_type_without_a_type() {
+        let error = decode_error(&[0x00, 0x00]);
+
+        assert_eq!(error.packet_type(), None);
+        assert_eq!(error.field(), "fixed_header.packet_type");
+        assert_eq!(error.offset(), 0);
+        assert_eq!(error.bytes(), &[0x00]);
+    }
+
+    #[test]
+    fn decode_error_should_shift_offsets_and_shorten_long_bytes() {
+        let error = DecodeError::new(
+            MqttProtocolError::MalformedPacket,
+            2,
+            Some(ControlPacketType::Publish),
+            "publish.payload.application_message".to_string(),
+            vec![0xAB; 20],
+        )
+        .with_base_offset(100);
+
+        assert_eq!(error.offset(), 102);
+        assert_eq!(
+            error.to_string(),
+            concat!(
+                "Malformed variable_header_parser in publish.payload.application_message at byte ",
+                "offset 102 (bytes abababababababababababababababab...)"
+            )
+        );
+        assert_eq!(
+            DecodeError::from(MqttProtocolError::PacketTooShort).to_string(),
+            "Packet does not have enough bytes at byte offset 0"
+        );
+    }
+}
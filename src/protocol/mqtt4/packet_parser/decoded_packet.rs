@@ -0,0 +1,51 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+
+/// A packet together with the violations a lenient `DecoderConfig` let through while decoding
+/// it. Under a strict config the warnings are always empty.
+#[allow(dead_code)]
+pub(crate) struct DecodedPacket {
+    packet: Packet,
+    warnings: Vec<MqttProtocolError>,
+}
+
+#[allow(dead_code)]
+impl DecodedPacket {
+    pub(crate) fn new(packet: Packet, warnings: Vec<MqttProtocolError>) -> Self {
+        DecodedPacket { packet, warnings }
+    }
+
+    pub(crate) fn packet(&self) -> &Packet {
+        &self.packet
+    }
+
+    pub(crate) fn warnings(&self) -> &[MqttProtocolError] {
+        &self.warnings
+    }
+
+    pub(crate) fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    pub(crate) fn into_packet(self) -> Packet {
+        self.packet
+    }
+
+    pub(crate) fn into_parts(self) -> (Packet, Vec<MqttProtocolError>) {
+        (self.packet, self.warnings)
+    }
+}
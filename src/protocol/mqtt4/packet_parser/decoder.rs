@@ -16,8 +16,14 @@ use crate::byte_adapter::byte_operations::ByteOperations;
 use crate::protocol::codec::Decoder;
 use crate::protocol::common::control_packet_type::ControlPacketType;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::decoder_config::{
+    DecodeContext, DecoderConfig, MAX_PACKET_SIZE, verify_packet_size,
+};
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header_codec::MqttFixedHeaderCodec;
+use crate::protocol::mqtt4::fixed_header_parser::remaining_length::remaining_length_parser;
+use crate::protocol::mqtt4::packet_parser::decode_error::DecodeError;
+use crate::protocol::mqtt4::packet_parser::decoded_packet::DecodedPacket;
 use crate::protocol::mqtt4::packet_parser::packet::Packet;
 use crate::protocol::mqtt4::payload_parser::connect_parser::payload::ConnectPayload;
 use crate::protocol::mqtt4::payload_parser::mqtt_payload_codec::MqttPayloadDecoder;
@@ -36,20 +42,55 @@ use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_hea
 use crate::protocol::mqtt4::variable_header_parser::sub_ack_parser::variable_header::SubAckVariableHeader;
 use crate::protocol::mqtt4::variable_header_parser::subscribe_parser::variable_header::SubscribeVariableHeader;
 use crate::protocol::mqtt4::variable_header_parser::unsubscribe_parser::variable_header::UnSubscribeVariableHeader;
+use bytes::{Bytes, BytesMut};
 
 impl Decoder for Packet {
     fn decode(bytes: &mut impl ByteOperations) -> Result<Self, MqttProtocolError>
     where
         Self: Sized,
     {
-        let fixed_header: FixedHeader = Self::read_fixed_header(bytes)?;
+        Self::decode_with_context(bytes, &mut DecodeContext::strict())
+    }
+}
+
+#[allow(dead_code)]
+impl Packet {
+    /// `decode` under `config` rather than the strict defaults. Violations the config turns into
+    /// warnings come back alongside the packet, and a failure says which field of the packet
+    /// it happened in.
+    ///
+    /// A whole packet is split off `bytes` without copying and decoded from a shared view, so
+    /// the bytes a `DecodeError` shows are only copied once decoding fails. Anything short of a
+    /// whole packet cannot decode and is copied, leaving `bytes` untouched.
+    pub(crate) fn decode_with_config(
+        bytes: &mut BytesMut,
+        config: &DecoderConfig,
+    ) -> Result<DecodedPacket, DecodeError> {
+        let frame = match Self::frame_length_with_max_size(bytes, config.max_packet_size()) {
+            Ok(Some(frame_length)) => bytes.split_to(frame_length).freeze(),
+            Ok(None) | Err(_) => Bytes::copy_from_slice(bytes),
+        };
+        let mut reader = frame.clone();
+        let mut context = DecodeContext::new(config);
+        context.start_packet(&reader);
+        match Self::decode_with_context(&mut reader, &mut context) {
+            Ok(packet) => Ok(DecodedPacket::new(packet, context.into_warnings())),
+            Err(error) => Err(context.decode_error(error, &reader, &frame)),
+        }
+    }
+
+    pub(crate) fn decode_with_context(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<Self, MqttProtocolError> {
+        let fixed_header: FixedHeader = Self::read_fixed_header(bytes, context)?;
 
         match fixed_header.control_packet_type() {
             ControlPacketType::Connect => {
                 let variable_header: ConnectVariableHeader =
-                    Self::read_variable_header(&fixed_header, bytes)?;
+                    Self::read_variable_header(&fixed_header, bytes, context)?;
                 let payload: ConnectPayload =
-                    Self::read_payload(&fixed_header, &variable_header, bytes)?;
+                    Self::read_payload(&fixed_header, &variable_header, bytes, context)?;
 
                 Ok(Packet::Connect {
                     fixed: fixed_header,
@@ -59,7 +100,7 @@ impl Decoder for Packet {
             }
             ControlPacketType::ConnAck => {
                 let variable_header: ConnAckVariableHeader =
-                    Self::read_variable_header(&fixed_header, bytes)?;
+                    Self::read_variable_header(&fixed_header, bytes, context)?;
 
                 Ok(Packet::ConnAck {
                     fixed: fixed_header,
@@ -68,9 +109,9 @@ impl Decoder for Packet {
             }
             ControlPacketType::Publish => {
                 let variable_header: PublishVariableHeader =
-                    Self::read_variable_header(&fixed_header, bytes)?;
+                    Self::read_variable_header(&fixed_header, bytes, context)?;
                 let payload: PublishPayload =
-                    Self::read_payload(&fixed_header, &variable_header, bytes)?;
+                    Self::read_payload(&fixed_header, &variable_header, bytes, context)?;
 
                 Ok(Packet::Publish {
                     fixed: fixed_header,
@@ -80,7 +121,7 @@ impl Decoder for Packet {
             }
             ControlPacketType::PubAck => {
                 let variable_header: PubAckVariableHeader =
-                    Self::read_variable_header(&fixed_header, bytes)?;
+                    Self::read_variable_header(&fixed_header, bytes, context)?;
 
                 Ok(Packet::PubAck {
                     fixed: fixed_header,
@@ -89,7 +130,7 @@ impl Decoder for Packet {
             }
             ControlPacketType::PubRec => {
                 let variable_header: PubRecVariableHeader =
-                    Self::read_variable_header(&fixed_header, bytes)?;
+                    Self::read_variable_header(&fixed_header, bytes, context)?;
 
                 Ok(Packet::PubRec {
                     fixed: fixed_header,
@@ -98,7 +139,7 @@ impl Decoder for Packet {
             }
             ControlPacketType::PubRel => {
                 let variable_header: PubRelVariableHeader =
-                    Self::read_variable_header(&fixed_header, bytes)?;
+                    Self::read_variable_header(&fixed_header, bytes, context)?;
 
                 Ok(Packet::PubRel {
                     fixed: fixed_header,
@@ -107,7 +148,7 @@ impl Decoder for Packet {
             }
             ControlPacketType::PubComp => {
                 let variable_header: PubCompVariableHeader =
-                    Self::read_variable_header(&fixed_header, bytes)?;
+                    Self::read_variable_header(&fixed_header, bytes, context)?;
 
                 Ok(Packet::PubComp {
                     fixed: fixed_header,
@@ -116,9 +157,9 @@ impl Decoder for Packet {
             }
             ControlPacketType::Subscribe => {
                 let variable_header: SubscribeVariableHeader =
-                    Self::read_variable_header(&fixed_header, bytes)?;
+                    Self::read_variable_header(&fixed_header, bytes, context)?;
                 let payload: SubscribePayload =
-                    Self::read_payload(&fixed_header, &variable_header, bytes)?;
+                    Self::read_payload(&fixed_header, &variable_header, bytes, context)?;
 
                 Ok(Packet::Subscribe {
                     fixed: fixed_header,
@@ -128,9 +169,9 @@ impl Decoder for Packet {
             }
             ControlPacketType::SubAck => {
                 let variable_header: SubAckVariableHeader =
-                    Self::read_variable_header(&fixed_header, bytes)?;
+                    Self::read_variable_header(&fixed_header, bytes, context)?;
                 let payload: SubAckPayload =
-                    Self::read_payload(&fixed_header, &variable_header, bytes)?;
+                    Self::read_payload(&fixed_header, &variable_header, bytes, context)?;
 
                 Ok(Packet::SubAck {
                     fixed: fixed_header,
@@ -140,9 +181,9 @@ impl Decoder for Packet {
             }
             ControlPacketType::Unsubscribe => {
                 let variable_header: UnSubscribeVariableHeader =
-                    Self::read_variable_header(&fixed_header, bytes)?;
+                    Self::read_variable_header(&fixed_header, bytes, context)?;
                 let payload: UnSubscribePayload =
-                    Self::read_payload(&fixed_header, &variable_header, bytes)?;
+                    Self::read_payload(&fixed_header, &variable_header, bytes, context)?;
 
                 Ok(Packet::Unsubscribe {
                     fixed: fixed_header,
@@ -152,7 +193,7 @@ impl Decoder for Packet {
             }
             ControlPacketType::UnsubAck => {
                 let variable_header: PubAckVariableHeader =
-                    Self::read_variable_header(&fixed_header, bytes)?;
+                    Self::read_variable_header(&fixed_header, bytes, context)?;
 
                 Ok(Packet::UnsubAck {
                     fixed: fixed_header,
@@ -170,27 +211,55 @@ impl Decoder for Packet {
             }),
         }
     }
-}
 
-#[allow(dead_code)]
-impl Packet {
+    /// Size of the control packet at the start of `bytes`, fixed header included, or `None`
+    /// while more bytes are needed. The payload decoders read to the end of the buffer, so a
+    /// byte stream has to be cut into single packets with this before calling `decode`.
+    pub(crate) fn frame_length(bytes: &[u8]) -> Result<Option<usize>, MqttProtocolError> {
+        Self::frame_length_with_max_size(bytes, MAX_PACKET_SIZE)
+    }
+
+    /// `frame_length`, failing with `PacketTooLarge` as soon as the fixed header announces
+    /// more than `max_packet_size` bytes, so the body never has to be buffered.
+    pub(crate) fn frame_length_with_max_size(
+        bytes: &[u8],
+        max_packet_size: usize,
+    ) -> Result<Option<usize>, MqttProtocolError> {
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+        let Some((remaining_length, length_bytes)) = remaining_length_parser::peek(&bytes[1..])?
+        else {
+            return Ok(None);
+        };
+        let frame_length = 1 + length_bytes + remaining_length as usize;
+        verify_packet_size(frame_length, max_packet_size)?;
+        if bytes.len() < frame_length {
+            return Ok(None);
+        }
+        Ok(Some(frame_length))
+    }
+
     pub(crate) fn read_fixed_header<T: MqttFixedHeaderCodec>(
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<T, MqttProtocolError> {
-        T::decode(bytes)
+        T::decode(bytes, context)
     }
     pub(crate) fn read_variable_header<T: MqttVariableHeaderDecoder>(
         fixed_header: &FixedHeader,
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<T, MqttProtocolError> {
-        T::decode(fixed_header, bytes)
+        T::decode(fixed_header, bytes, context)
     }
 
     pub(crate) fn read_payload<VariableHeader, T: MqttPayloadDecoder<VariableHeader>>(
         fixed_header: &FixedHeader,
         variable_header: &VariableHeader,
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<T, MqttProtocolError> {
-        T::decode(fixed_header, variable_header, bytes)
+        T::decode(fixed_header, variable_header, bytes, context)
     }
 }
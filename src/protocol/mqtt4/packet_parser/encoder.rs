@@ -1,11 +1,14 @@
 use crate::protocol::codec::Encoder;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header_codec::MqttFixedHeaderEncoder;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
 use crate::protocol::mqtt4::packet_parser::packet::Packet;
 use crate::protocol::mqtt4::payload_parser::mqtt_payload_codec::MqttPayloadEncoder;
 use crate::protocol::mqtt4::payload_parser::none_parser::encoder::NonePayload;
 use crate::protocol::mqtt4::variable_header_parser::mqtt_variable_header_codec::MqttVariableHeaderEncoder;
 use crate::protocol::mqtt4::variable_header_parser::none_variable_header_parser::variable_header::NoneVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::PublishVariableHeader;
 // Copyright 2023 RobustMQ Team
 //
 // Licensed under the Apache License, Version 2.0 (the "License");
@@ -36,7 +39,10 @@ impl Encoder for Packet {
                 fixed,
                 variable,
                 payload,
-            } => Self::encode(fixed, variable, payload),
+            } => {
+                Self::verify_publish_qos(fixed, variable)?;
+                Self::encode(fixed, variable, payload)
+            }
             Packet::Subscribe {
                 fixed,
                 variable,
@@ -88,6 +94,23 @@ impl Packet {
         fixed_header.encode(remaining_length)
     }
 
+    /// The fixed header carries the QoS on the wire while the variable header decides whether
+    /// a Packet Identifier follows the topic, so both have to agree.
+    fn verify_publish_qos(
+        fixed: &FixedHeader,
+        variable: &PublishVariableHeader,
+    ) -> Result<(), MqttProtocolError> {
+        if let FixedHeaderFlags::Publish { qos, .. } = fixed.fixed_header_reserved_flags()
+            && *qos != variable.qos()
+        {
+            return Err(MqttProtocolError::PublishQoSMismatch(
+                qos.as_u8(),
+                variable.qos().as_u8(),
+            ));
+        }
+        Ok(())
+    }
+
     fn encode<F: MqttFixedHeaderEncoder, V: MqttVariableHeaderEncoder, P: MqttPayloadEncoder>(
         fixed: &mut F,
         variable: &V,
@@ -12,6 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub(crate) mod decode_error;
+pub(crate) mod decoded_packet;
 mod decoder;
 mod encoder;
-mod packet;
+pub(crate) mod packet;
+#[cfg(feature = "serde")]
+mod packet_serde;
@@ -12,7 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::protocol::common::control_packet_type::ControlPacketType;
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::common::return_code::ReturnCode;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
 use crate::protocol::mqtt4::payload_parser::connect_parser::payload::ConnectPayload;
 use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
 use crate::protocol::mqtt4::payload_parser::sub_ack_parser::payload::SubAckPayload;
@@ -28,6 +33,31 @@ use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_hea
 use crate::protocol::mqtt4::variable_header_parser::sub_ack_parser::variable_header::SubAckVariableHeader;
 use crate::protocol::mqtt4::variable_header_parser::subscribe_parser::variable_header::SubscribeVariableHeader;
 use crate::protocol::mqtt4::variable_header_parser::unsubscribe_parser::variable_header::UnSubscribeVariableHeader;
+
+/// An MQTT 3.1.1 control packet.
+///
+/// With the `serde` feature a packet serializes to the object `mqtt-decode -o json` prints:
+///
+/// ```text
+/// {"type":"PUBLISH",
+///  "fixed_header":{"flags":2,"dup":false,"qos":1,"retain":false,"remaining_length":8},
+///  "variable_header":{"topic_name":"t","packet_identifier":9},
+///  "payload":{"application_message":"78"}}
+/// ```
+///
+/// - `type` is the upper case packet name (`CONNECT` ... `DISCONNECT`).
+/// - `variable_header` and `payload` are present only for packet types that have them. Packet
+///   identifiers are numbers, QoS levels and return codes are their numeric codes, a CONNECT
+///   `will_message` and a PUBLISH `application_message` are lower case hex strings and absent
+///   optional fields are `null`.
+/// - SUBSCRIBE payloads are `{"subscriptions":[{"topic_filter":..,"qos":..}]}`, UNSUBSCRIBE
+///   payloads `{"topic_filters":[..]}` and SUBACK payloads `{"return_codes":[..]}`.
+///
+/// Deserializing applies the checks decoding would: `ConnectFlags::new` runs on the CONNECT
+/// flags, reserved fixed header flags must hold their required value, the will, username and
+/// password fields must agree with their flags, topic names and filters must be valid and a
+/// PUBLISH carries a packet identifier exactly when its QoS is above 0. `fixed_header` may be
+/// left out, as may every field in it.
 #[allow(dead_code)]
 pub enum Packet {
     Connect {
@@ -90,16 +120,138 @@ pub enum Packet {
     },
 }
 
+#[allow(dead_code)]
+impl Packet {
+    /// A PUBLISH whose fixed header QoS is taken from `variable`, so the two always agree.
+    pub(crate) fn publish(
+        dup: bool,
+        retain: bool,
+        variable: PublishVariableHeader,
+        payload: PublishPayload,
+    ) -> Packet {
+        let flags = FixedHeaderFlags::Publish {
+            dup,
+            qos: variable.qos(),
+            retain,
+        };
+        Packet::Publish {
+            fixed: FixedHeader::new(ControlPacketType::Publish, flags),
+            variable,
+            payload,
+        }
+    }
+
+    pub(crate) fn connect(variable: ConnectVariableHeader, payload: ConnectPayload) -> Packet {
+        Packet::Connect {
+            fixed: FixedHeader::new(ControlPacketType::Connect, FixedHeaderFlags::Connect),
+            variable,
+            payload,
+        }
+    }
+
+    pub(crate) fn conn_ack(session_present: bool, return_code: ReturnCode) -> Packet {
+        Packet::ConnAck {
+            fixed: FixedHeader::new(ControlPacketType::ConnAck, FixedHeaderFlags::ConnAck),
+            variable: ConnAckVariableHeader::new(session_present, return_code),
+        }
+    }
+
+    pub(crate) fn pub_ack(packet_identifier: PacketId) -> Packet {
+        Packet::PubAck {
+            fixed: FixedHeader::new(ControlPacketType::PubAck, FixedHeaderFlags::PubAck),
+            variable: PubAckVariableHeader::new(packet_identifier),
+        }
+    }
+
+    pub(crate) fn pub_rec(packet_identifier: PacketId) -> Packet {
+        Packet::PubRec {
+            fixed: FixedHeader::new(ControlPacketType::PubRec, FixedHeaderFlags::PubRec),
+            variable: PubRecVariableHeader::new(packet_identifier),
+        }
+    }
+
+    pub(crate) fn pub_rel(packet_identifier: PacketId) -> Packet {
+        Packet::PubRel {
+            fixed: FixedHeader::new(ControlPacketType::PubRel, FixedHeaderFlags::PubRel),
+            variable: PubRelVariableHeader::new(packet_identifier),
+        }
+    }
+
+    pub(crate) fn pub_comp(packet_identifier: PacketId) -> Packet {
+        Packet::PubComp {
+            fixed: FixedHeader::new(ControlPacketType::PubComp, FixedHeaderFlags::PubComp),
+            variable: PubCompVariableHeader::new(packet_identifier),
+        }
+    }
+
+    pub(crate) fn subscribe(
+        packet_identifier: PacketId,
+        subscriptions: Vec<(String, QoSCode)>,
+    ) -> Packet {
+        Packet::Subscribe {
+            fixed: FixedHeader::new(ControlPacketType::Subscribe, FixedHeaderFlags::Subscribe),
+            variable: SubscribeVariableHeader::new(packet_identifier),
+            payload: SubscribePayload::new(subscriptions),
+        }
+    }
+
+    pub(crate) fn unsubscribe(packet_identifier: PacketId, topic_filters: Vec<String>) -> Packet {
+        Packet::Unsubscribe {
+            fixed: FixedHeader::new(
+                ControlPacketType::Unsubscribe,
+                FixedHeaderFlags::Unsubscribe,
+            ),
+            variable: UnSubscribeVariableHeader::new(packet_identifier),
+            payload: UnSubscribePayload::new(topic_filters),
+        }
+    }
+
+    pub(crate) fn ping_req() -> Packet {
+        Packet::PingReq {
+            fixed: FixedHeader::new(ControlPacketType::PingReq, FixedHeaderFlags::PingReq),
+        }
+    }
+
+    pub(crate) fn disconnect() -> Packet {
+        Packet::Disconnect {
+            fixed: FixedHeader::new(ControlPacketType::Disconnect, FixedHeaderFlags::Disconnect),
+        }
+    }
+
+    pub(crate) fn fixed_header(&self) -> &FixedHeader {
+        match self {
+            Packet::Connect { fixed, .. }
+            | Packet::ConnAck { fixed, .. }
+            | Packet::Publish { fixed, .. }
+            | Packet::PubAck { fixed, .. }
+            | Packet::PubRec { fixed, .. }
+            | Packet::PubRel { fixed, .. }
+            | Packet::PubComp { fixed, .. }
+            | Packet::Subscribe { fixed, .. }
+            | Packet::SubAck { fixed, .. }
+            | Packet::Unsubscribe { fixed, .. }
+            | Packet::UnsubAck { fixed, .. }
+            | Packet::PingReq { fixed }
+            | Packet::PingResp { fixed }
+            | Packet::Disconnect { fixed } => fixed,
+        }
+    }
+}
+
 #[cfg(test)]
 mod packet_tests {
     use crate::protocol::codec::{Decoder, Encoder};
     use crate::protocol::common::control_packet_type::ControlPacketType;
+    use crate::protocol::common::packet_id::PacketId;
     use crate::protocol::common::protocol_level::ProtocolLevel;
     use crate::protocol::common::qos::QoSCode;
     use crate::protocol::common::return_code::ReturnCode;
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::mqtt4::decoder_config::DecoderConfig;
     use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
     use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
     use crate::protocol::mqtt4::packet_parser::packet::Packet;
+    use crate::protocol::mqtt4::payload_parser::connect_parser::client_id_policy::ClientIdPolicy;
     use crate::protocol::mqtt4::payload_parser::connect_parser::payload::ConnectPayload;
     use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
     use crate::protocol::mqtt4::payload_parser::sub_ack_parser::payload::SubAckPayload;
@@ -114,11 +266,13 @@ mod packet_tests {
     use crate::protocol::mqtt4::variable_header_parser::pub_comp_parser::variable_header::PubCompVariableHeader;
     use crate::protocol::mqtt4::variable_header_parser::pub_rec_parser::variable_header::PubRecVariableHeader;
     use crate::protocol::mqtt4::variable_header_parser::pub_rel_parser::variable_header::PubRelVariableHeader;
-    use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::PublishVariableHeader;
+    use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+        PublishQoS, PublishVariableHeader,
+    };
     use crate::protocol::mqtt4::variable_header_parser::sub_ack_parser::variable_header::SubAckVariableHeader;
     use crate::protocol::mqtt4::variable_header_parser::subscribe_parser::variable_header::SubscribeVariableHeader;
     use crate::protocol::mqtt4::variable_header_parser::unsubscribe_parser::variable_header::UnSubscribeVariableHeader;
-    use bytes::BytesMut;
+    use bytes::{Bytes, BytesMut};
 
     #[test]
     fn test_packet_encode_decode_connect() {
@@ -242,8 +396,9 @@ mod packet_tests {
                 retain: false,
             },
         );
-        let expect_variable_header = PublishVariableHeader::new("test/topic".to_string(), None);
-        let expect_payload = PublishPayload::new("Hello MQTT!".to_string());
+        let expect_variable_header =
+            PublishVariableHeader::new("test/topic".to_string(), PublishQoS::AtMostOnce);
+        let expect_payload = PublishPayload::new(Bytes::from_static(b"Hello MQTT!"));
 
         let mut expect_packet = Packet::Publish {
             fixed: expect_fixed_header.clone(),
@@ -298,7 +453,7 @@ mod packet_tests {
     fn test_packet_encode_decode_pub_ack() {
         let expect_fixed_header =
             FixedHeader::new(ControlPacketType::PubAck, FixedHeaderFlags::PubAck);
-        let expect_variable_header = PubAckVariableHeader::new(0x1234);
+        let expect_variable_header = PubAckVariableHeader::new(PacketId::new(0x1234).unwrap());
         let mut expect_packet = Packet::PubAck {
             fixed: expect_fixed_header.clone(),
             variable: expect_variable_header.clone(),
@@ -339,7 +494,7 @@ mod packet_tests {
     fn test_packet_encode_decode_pub_rec() {
         let expect_fixed_header =
             FixedHeader::new(ControlPacketType::PubRec, FixedHeaderFlags::PubRec);
-        let expect_variable_header = PubRecVariableHeader::new(0x5678);
+        let expect_variable_header = PubRecVariableHeader::new(PacketId::new(0x5678).unwrap());
         let mut expect_packet = Packet::PubRec {
             fixed: expect_fixed_header.clone(),
             variable: expect_variable_header.clone(),
@@ -380,7 +535,7 @@ mod packet_tests {
     fn test_packet_encode_decode_pub_rel() {
         let expect_fixed_header =
             FixedHeader::new(ControlPacketType::PubRel, FixedHeaderFlags::PubRel);
-        let expect_variable_header = PubRelVariableHeader::new(0x9ABC);
+        let expect_variable_header = PubRelVariableHeader::new(PacketId::new(0x9ABC).unwrap());
         let mut expect_packet = Packet::PubRel {
             fixed: expect_fixed_header.clone(),
             variable: expect_variable_header.clone(),
@@ -421,7 +576,7 @@ mod packet_tests {
     fn test_packet_encode_decode_pub_comp() {
         let expect_fixed_header =
             FixedHeader::new(ControlPacketType::PubComp, FixedHeaderFlags::PubComp);
-        let expect_variable_header = PubCompVariableHeader::new(0xDEF0);
+        let expect_variable_header = PubCompVariableHeader::new(PacketId::new(0xDEF0).unwrap());
         let mut expect_packet = Packet::PubComp {
             fixed: expect_fixed_header.clone(),
             variable: expect_variable_header.clone(),
@@ -462,7 +617,7 @@ mod packet_tests {
     fn test_packet_encode_decode_subscribe() {
         let expect_fixed_header =
             FixedHeader::new(ControlPacketType::Subscribe, FixedHeaderFlags::Subscribe);
-        let expect_variable_header = SubscribeVariableHeader::new(10);
+        let expect_variable_header = SubscribeVariableHeader::new(PacketId::new(10).unwrap());
         let expect_payload = SubscribePayload::new(vec![
             ("sensor/temp".to_string(), QoSCode::Qos1),
             ("sensor/temp1".to_string(), QoSCode::Qos2),
@@ -539,7 +694,7 @@ mod packet_tests {
             ControlPacketType::Unsubscribe,
             FixedHeaderFlags::Unsubscribe,
         );
-        let expect_variable_header = UnSubscribeVariableHeader::new(11);
+        let expect_variable_header = UnSubscribeVariableHeader::new(PacketId::new(11).unwrap());
         let expect_payload = UnSubscribePayload::new(vec![
             "sensor/humidity".to_string(),
             "sensor/pressure".to_string(),
@@ -597,7 +752,7 @@ mod packet_tests {
     fn test_packet_encode_decode_sub_ack() {
         let expect_fixed_header =
             FixedHeader::new(ControlPacketType::SubAck, FixedHeaderFlags::SubAck);
-        let expect_variable_header = SubAckVariableHeader::new(12);
+        let expect_variable_header = SubAckVariableHeader::new(PacketId::new(12).unwrap());
         let expect_payload = SubAckPayload::new(vec![
             SubAckReturnCode::Qos0,
             SubAckReturnCode::Qos1,
@@ -760,4 +915,301 @@ mod packet_tests {
             panic!("Decoded packet is not of type Disconnect");
         }
     }
+
+    #[test]
+    fn frame_length_should_cover_fixed_header_and_remaining_length() {
+        let mut packet = Packet::Connect {
+            fixed: FixedHeader::new(ControlPacketType::Connect, FixedHeaderFlags::Connect),
+            variable: ConnectVariableHeader::new(
+                ProtocolLevel::Mqtt3_1_1,
+                ConnectFlags::new(false, false, false, QoSCode::Qos0, false, true).unwrap(),
+                60,
+            ),
+            payload: ConnectPayload::new("client123".to_string(), None, None, None, None),
+        };
+        let mut stream = packet.encode().unwrap();
+        let expect_frame_length = stream.len();
+        stream.extend_from_slice(&[0b1100_0000, 0x00]);
+
+        assert_eq!(
+            Packet::frame_length(&stream).unwrap(),
+            Some(expect_frame_length)
+        );
+        assert_eq!(
+            Packet::frame_length(&stream[..expect_frame_length - 1]).unwrap(),
+            None
+        );
+        assert_eq!(
+            Packet::frame_length(&stream[expect_frame_length..]).unwrap(),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn decode_with_config_should_accept_client_ids_the_policy_allows() {
+        let mut packet = Packet::Connect {
+            fixed: FixedHeader::new(ControlPacketType::Connect, FixedHeaderFlags::Connect),
+            variable: ConnectVariableHeader::new(
+                ProtocolLevel::Mqtt3_1_1,
+                ConnectFlags::new(false, false, false, QoSCode::Qos0, false, true).unwrap(),
+                60,
+            ),
+            payload: ConnectPayload::new("plant-7:pump_03".to_string(), None, None, None, None),
+        };
+        let bytes = packet.encode().unwrap();
+
+        assert!(matches!(
+            Packet::decode(&mut BytesMut::from(&bytes[..])),
+            Err(MqttProtocolError::InvalidClientId)
+        ));
+        let config = DecoderConfig::strict().with_client_id_policy(ClientIdPolicy::permissive());
+        let decoded = Packet::decode_with_config(&mut BytesMut::from(&bytes[..]), &config).unwrap();
+        assert!(decoded.is_clean());
+        let Packet::Connect { payload, .. } = decoded.packet() else {
+            panic!("Decoded packet is not of type Connect");
+        };
+        assert_eq!(payload.client_id(), "plant-7:pump_03");
+    }
+
+    #[test]
+    fn decode_with_config_should_take_one_packet_and_leave_a_partial_one() {
+        // PUBACK 7, PUBLISH "a/b" carrying "hi" and a PUBACK cut after three bytes
+        let mut bytes = BytesMut::from(
+            &[
+                0x40, 0x02, 0x00, 0x07, 0x30, 0x07, 0x00, 0x03, b'a', b'/', b'b', b'h', b'i', 0x40,
+                0x02, 0x00,
+            ][..],
+        );
+        let config = DecoderConfig::strict();
+        assert!(matches!(
+            Packet::decode_with_config(&mut bytes, &config)
+                .unwrap()
+                .packet(),
+            Packet::PubAck { .. }
+        ));
+        let decoded = Packet::decode_with_config(&mut bytes, &config).unwrap();
+        let Packet::Publish { payload, .. } = decoded.packet() else {
+            panic!("Decoded packet is not of type Publish");
+        };
+        assert_eq!(payload.application_message(), b"hi");
+        assert_eq!(&bytes[..], &[0x40, 0x02, 0x00]);
+
+        let error = Packet::decode_with_config(&mut bytes, &config)
+            .err()
+            .unwrap();
+        assert_eq!(error.field(), "puback.variable_header.packet_identifier");
+        assert_eq!(error.bytes(), &[0x00]);
+        assert_eq!(&bytes[..], &[0x40, 0x02, 0x00]);
+    }
+
+    #[test]
+    fn decode_with_lenient_config_should_attach_violations_as_warnings() {
+        // CONNECT with client id "plant-7" and an empty username
+        let connect = [
+            0x10, 0x15, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x82, 0x00, 0x3C, 0x00, 0x07,
+            b'p', b'l', b'a', b'n', b't', b'-', b'7', 0x00, 0x00,
+        ];
+        assert!(matches!(
+            Packet::decode(&mut BytesMut::from(&connect[..])),
+            Err(MqttProtocolError::InvalidClientId)
+        ));
+
+        let decoded = Packet::decode_with_config(
+            &mut BytesMut::from(&connect[..]),
+            &DecoderConfig::lenient(),
+        )
+        .unwrap();
+        let (packet, warnings) = decoded.into_parts();
+        assert!(matches!(
+            warnings.as_slice(),
+            [
+                MqttProtocolError::InvalidClientId,
+                MqttProtocolError::MalformedPacket
+            ]
+        ));
+        let Packet::Connect { payload, .. } = packet else {
+            panic!("Decoded packet is not of type Connect");
+        };
+        assert_eq!(payload.client_id(), "plant-7");
+        assert_eq!(payload.username(), Some(""));
+    }
+
+    #[test]
+    fn decode_with_lenient_config_should_accept_invalid_reserved_flags() {
+        // PUBACK with the reserved flags set to 0001
+        let pub_ack = [0x41, 0x02, 0x00, 0x07];
+        assert!(matches!(
+            Packet::decode(&mut BytesMut::from(&pub_ack[..])),
+            Err(MqttProtocolError::InvalidFixedHeaderFlags)
+        ));
+
+        let decoded = Packet::decode_with_config(
+            &mut BytesMut::from(&pub_ack[..]),
+            &DecoderConfig::lenient(),
+        )
+        .unwrap();
+        assert!(matches!(
+            decoded.warnings(),
+            [MqttProtocolError::InvalidFixedHeaderFlags]
+        ));
+        assert!(matches!(decoded.packet(), Packet::PubAck { .. }));
+
+        let unchecked = DecoderConfig::strict().with_reserved_flags_check(false);
+        let decoded =
+            Packet::decode_with_config(&mut BytesMut::from(&pub_ack[..]), &unchecked).unwrap();
+        assert!(decoded.is_clean());
+    }
+
+    #[test]
+    fn decode_should_reject_truncated_topic_and_payload_in_every_mode() {
+        // PUBLISH whose topic length announces 5 bytes where only "a/b" follows
+        let truncated_topic = [0x30, 0x05, 0x00, 0x05, b'a', b'/', b'b'];
+        // PUBLISH to "a/b" whose remaining length announces 3 more message bytes than follow
+        let truncated_payload = [0x30, 0x08, 0x00, 0x03, b'a', b'/', b'b', b'h', b'i'];
+        for bytes in [&truncated_topic[..], &truncated_payload[..]] {
+            for config in [DecoderConfig::strict(), DecoderConfig::lenient()] {
+                assert!(matches!(
+                    Packet::decode_with_config(&mut BytesMut::from(bytes), &config)
+                        .map_err(|error| error.into_error()),
+                    Err(MqttProtocolError::PacketTooShort)
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn decode_should_take_the_publish_payload_from_the_remaining_length() {
+        // PUBLISH QoS 1 to "a/b" with packet id 1 and message "hi!", then the next packet
+        let mut bytes = BytesMut::from(
+            &[
+                0x32, 0x0A, 0x00, 0x03, b'a', b'/', b'b', 0x00, 0x01, 0x68, 0x69, 0x21, 0xE0, 0x00,
+            ][..],
+        );
+        let Packet::Publish { payload, .. } = Packet::decode(&mut bytes).unwrap() else {
+            panic!("Decoded packet is not of type Publish");
+        };
+        assert_eq!(payload.application_message(), b"hi!");
+        assert_eq!(&bytes[..], &[0xE0, 0x00]);
+
+        // A remaining length too short for the topic name and packet identifier
+        assert!(matches!(
+            Packet::decode(&mut BytesMut::from(
+                &[0x32, 0x05, 0x00, 0x03, b'a', b'/', b'b', 0x00, 0x01][..]
+            )),
+            Err(MqttProtocolError::MalformedPacket)
+        ));
+    }
+
+    #[test]
+    fn decode_with_config_should_limit_packet_size() {
+        // PUBLISH QoS 0 to "a/b" with message "hello"
+        let publish = [
+            0x30, 0x0C, 0x00, 0x03, b'a', b'/', b'b', 0x00, 0x05, b'h', b'e', b'l', b'l', b'o',
+        ];
+        let config = DecoderConfig::strict().with_max_packet_size(13);
+        assert!(matches!(
+            Packet::decode_with_config(&mut BytesMut::from(&publish[..]), &config)
+                .map_err(|error| error.into_error()),
+            Err(MqttProtocolError::PacketTooLarge(14, 13))
+        ));
+
+        let config = DecoderConfig::lenient().with_max_packet_size(13);
+        assert!(matches!(
+            Packet::decode_with_config(&mut BytesMut::from(&publish[..]), &config)
+                .map_err(|error| error.into_error()),
+            Err(MqttProtocolError::PacketTooLarge(14, 13))
+        ));
+
+        let config = DecoderConfig::strict().with_max_packet_size(14);
+        assert!(Packet::decode_with_config(&mut BytesMut::from(&publish[..]), &config).is_ok());
+    }
+
+    #[test]
+    fn frame_length_with_max_size_should_reject_before_the_body_arrives() {
+        // PUBLISH announcing 268,435,455 bytes, of which only the fixed header has arrived
+        let header = [0x30, 0xFF, 0xFF, 0xFF, 0x7F];
+        assert_eq!(Packet::frame_length(&header).unwrap(), None);
+        assert!(matches!(
+            Packet::frame_length_with_max_size(&header, 1024),
+            Err(MqttProtocolError::PacketTooLarge(268_435_460, 1024))
+        ));
+
+        // the size is unknown until the last Remaining Length byte is in
+        assert_eq!(
+            Packet::frame_length_with_max_size(&header[..4], 1024).unwrap(),
+            None
+        );
+        assert_eq!(
+            Packet::frame_length_with_max_size(&[0xC0, 0x00], 2).unwrap(),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn publish_should_take_the_fixed_header_qos_from_the_variable_header() {
+        let packet_id = PacketId::new(9).unwrap();
+        let variable =
+            PublishVariableHeader::new("a/b".to_string(), PublishQoS::ExactlyOnce(packet_id));
+        let mut packet = Packet::publish(
+            true,
+            false,
+            variable,
+            PublishPayload::new(Bytes::from_static(b"hi")),
+        );
+        assert_eq!(
+            packet.fixed_header().fixed_header_reserved_flags(),
+            &FixedHeaderFlags::Publish {
+                dup: true,
+                qos: QoSCode::Qos2,
+                retain: false,
+            }
+        );
+
+        let encoded = packet.encode().unwrap();
+        assert_eq!(
+            &encoded[..],
+            &[
+                0x3C, 0x09, 0x00, 0x03, b'a', b'/', b'b', 0x00, 0x09, b'h', b'i'
+            ]
+        );
+    }
+
+    #[test]
+    fn encode_should_reject_publish_with_disagreeing_qos() {
+        let mut packet = Packet::Publish {
+            fixed: FixedHeader::new(
+                ControlPacketType::Publish,
+                FixedHeaderFlags::Publish {
+                    dup: false,
+                    qos: QoSCode::Qos0,
+                    retain: false,
+                },
+            ),
+            variable: PublishVariableHeader::new(
+                "a/b".to_string(),
+                PublishQoS::AtLeastOnce(PacketId::new(1).unwrap()),
+            ),
+            payload: PublishPayload::new(Bytes::from_static(b"hi")),
+        };
+        assert!(matches!(
+            packet.encode(),
+            Err(MqttProtocolError::PublishQoSMismatch(0, 1))
+        ));
+    }
+
+    #[test]
+    fn decode_should_reject_zero_packet_identifier() {
+        let mut puback = BytesMut::from(&[0x40, 0x02, 0x00, 0x00][..]);
+        assert!(matches!(
+            Packet::decode(&mut puback),
+            Err(MqttProtocolError::ZeroPacketIdentifier)
+        ));
+
+        // SUBSCRIBE with packet id 0 to "a" at QoS 0
+        let mut subscribe = BytesMut::from(&[0x82, 0x06, 0x00, 0x00, 0x00, 0x01, b'a', 0x00][..]);
+        assert!(matches!(
+            Packet::decode(&mut subscribe),
+            Err(MqttProtocolError::ZeroPacketIdentifier)
+        ));
+    }
 }
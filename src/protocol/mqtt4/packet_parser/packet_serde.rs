@@ -0,0 +1,572 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::common::topic_filter::TopicFilter;
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header_serde::FixedHeaderFields;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use crate::protocol::mqtt4::payload_parser::connect_parser::payload::ConnectPayload;
+use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+use crate::protocol::mqtt4::payload_parser::sub_ack_parser::payload::SubAckPayload;
+use crate::protocol::mqtt4::payload_parser::subscribe_parser::payload::SubscribePayload;
+use crate::protocol::mqtt4::payload_parser::unsubscribe_parser::payload::UnSubscribePayload;
+use crate::protocol::mqtt4::variable_header_parser::conn_ack_parser::variable_header::ConnAckVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::ConnectVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::pub_ack_parser::variable_header::PubAckVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::pub_comp_parser::variable_header::PubCompVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::pub_rec_parser::variable_header::PubRecVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::pub_rel_parser::variable_header::PubRelVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+    PublishQoS, PublishVariableHeader,
+};
+use crate::protocol::mqtt4::variable_header_parser::sub_ack_parser::variable_header::SubAckVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::subscribe_parser::variable_header::SubscribeVariableHeader;
+use crate::protocol::mqtt4::variable_header_parser::unsubscribe_parser::variable_header::UnSubscribeVariableHeader;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Cross-field rules a hand-written packet can break but a decoded one cannot.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum PacketSerdeError {
+    #[error("{0}")]
+    Protocol(#[from] MqttProtocolError),
+
+    #[error("will_flag is {0} but will_topic and will_message are not both {1}")]
+    WillMismatch(bool, &'static str),
+
+    #[error("username_flag is {0} but username is {1}")]
+    UsernameMismatch(bool, &'static str),
+
+    #[error("password_flag is {0} but password is {1}")]
+    PasswordMismatch(bool, &'static str),
+
+    #[error("PUBLISH with QoS {0} {1} a packet_identifier")]
+    PublishPacketIdentifier(u8, &'static str),
+}
+
+/// The PUBLISH variable header as written in JSON. Whether the packet identifier is required
+/// depends on the QoS in the fixed header, so it is only checked once both are known.
+#[derive(Serialize, Deserialize)]
+struct PublishVariableHeaderFields {
+    topic_name: String,
+    packet_identifier: Option<PacketId>,
+}
+
+/// Wire-independent shape of a packet, see `Packet` for the JSON it produces.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum PacketRepr {
+    #[serde(rename = "CONNECT")]
+    Connect {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+        variable_header: ConnectVariableHeader,
+        payload: ConnectPayload,
+    },
+    #[serde(rename = "CONNACK")]
+    ConnAck {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+        variable_header: ConnAckVariableHeader,
+    },
+    #[serde(rename = "PUBLISH")]
+    Publish {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+        variable_header: PublishVariableHeaderFields,
+        payload: PublishPayload,
+    },
+    #[serde(rename = "PUBACK")]
+    PubAck {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+        variable_header: PubAckVariableHeader,
+    },
+    #[serde(rename = "PUBREC")]
+    PubRec {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+        variable_header: PubRecVariableHeader,
+    },
+    #[serde(rename = "PUBREL")]
+    PubRel {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+        variable_header: PubRelVariableHeader,
+    },
+    #[serde(rename = "PUBCOMP")]
+    PubComp {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+        variable_header: PubCompVariableHeader,
+    },
+    #[serde(rename = "SUBSCRIBE")]
+    Subscribe {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+        variable_header: SubscribeVariableHeader,
+        payload: SubscribePayload,
+    },
+    #[serde(rename = "SUBACK")]
+    SubAck {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+        variable_header: SubAckVariableHeader,
+        payload: SubAckPayload,
+    },
+    #[serde(rename = "UNSUBSCRIBE")]
+    Unsubscribe {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+        variable_header: UnSubscribeVariableHeader,
+        payload: UnSubscribePayload,
+    },
+    #[serde(rename = "UNSUBACK")]
+    UnsubAck {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+        variable_header: PubAckVariableHeader,
+    },
+    #[serde(rename = "PINGREQ")]
+    PingReq {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+    },
+    #[serde(rename = "PINGRESP")]
+    PingResp {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+    },
+    #[serde(rename = "DISCONNECT")]
+    Disconnect {
+        #[serde(default)]
+        fixed_header: FixedHeaderFields,
+    },
+}
+
+impl From<&Packet> for PacketRepr {
+    fn from(packet: &Packet) -> Self {
+        let fixed_header = FixedHeaderFields::from_fixed_header(packet.fixed_header());
+        match packet {
+            Packet::Connect {
+                variable, payload, ..
+            } => PacketRepr::Connect {
+                fixed_header,
+                variable_header: variable.clone(),
+                payload: payload.clone(),
+            },
+            Packet::ConnAck { variable, .. } => PacketRepr::ConnAck {
+                fixed_header,
+                variable_header: variable.clone(),
+            },
+            Packet::Publish {
+                variable, payload, ..
+            } => PacketRepr::Publish {
+                fixed_header,
+                variable_header: PublishVariableHeaderFields {
+                    topic_name: variable.topic_name().to_string(),
+                    packet_identifier: variable.packet_identifier(),
+                },
+                payload: payload.clone(),
+            },
+            Packet::PubAck { variable, .. } => PacketRepr::PubAck {
+                fixed_header,
+                variable_header: variable.clone(),
+            },
+            Packet::PubRec { variable, .. } => PacketRepr::PubRec {
+                fixed_header,
+                variable_header: variable.clone(),
+            },
+            Packet::PubRel { variable, .. } => PacketRepr::PubRel {
+                fixed_header,
+                variable_header: variable.clone(),
+            },
+            Packet::PubComp { variable, .. } => PacketRepr::PubComp {
+                fixed_header,
+                variable_header: variable.clone(),
+            },
+            Packet::Subscribe {
+                variable, payload, ..
+            } => PacketRepr::Subscribe {
+                fixed_header,
+                variable_header: variable.clone(),
+                payload: payload.clone(),
+            },
+            Packet::SubAck {
+                variable, payload, ..
+            } => PacketRepr::SubAck {
+                fixed_header,
+                variable_header: variable.clone(),
+                payload: payload.clone(),
+            },
+            Packet::Unsubscribe {
+                variable, payload, ..
+            } => PacketRepr::Unsubscribe {
+                fixed_header,
+                variable_header: variable.clone(),
+                payload: payload.clone(),
+            },
+            Packet::UnsubAck { variable, .. } => PacketRepr::UnsubAck {
+                fixed_header,
+                variable_header: variable.clone(),
+            },
+            Packet::PingReq { .. } => PacketRepr::PingReq { fixed_header },
+            Packet::PingResp { .. } => PacketRepr::PingResp { fixed_header },
+            Packet::Disconnect { .. } => PacketRepr::Disconnect { fixed_header },
+        }
+    }
+}
+
+impl TryFrom<PacketRepr> for Packet {
+    type Error = PacketSerdeError;
+
+    fn try_from(repr: PacketRepr) -> Result<Self, Self::Error> {
+        use crate::protocol::common::control_packet_type::ControlPacketType as Type;
+
+        Ok(match repr {
+            PacketRepr::Connect {
+                fixed_header,
+                variable_header,
+                payload,
+            } => {
+                verify_connect(&variable_header, &payload)?;
+                Packet::Connect {
+                    fixed: fixed_header.into_fixed_header(Type::Connect)?,
+                    variable: variable_header,
+                    payload,
+                }
+            }
+            PacketRepr::ConnAck {
+                fixed_header,
+                variable_header,
+            } => Packet::ConnAck {
+                fixed: fixed_header.into_fixed_header(Type::ConnAck)?,
+                variable: variable_header,
+            },
+            PacketRepr::Publish {
+                fixed_header,
+                variable_header,
+                payload,
+            } => {
+                let fixed = fixed_header.into_fixed_header(Type::Publish)?;
+                let variable = verify_publish(&fixed, variable_header)?;
+                Packet::Publish {
+                    fixed,
+                    variable,
+                    payload,
+                }
+            }
+            PacketRepr::PubAck {
+                fixed_header,
+                variable_header,
+            } => Packet::PubAck {
+                fixed: fixed_header.into_fixed_header(Type::PubAck)?,
+                variable: variable_header,
+            },
+            PacketRepr::PubRec {
+                fixed_header,
+                variable_header,
+            } => Packet::PubRec {
+                fixed: fixed_header.into_fixed_header(Type::PubRec)?,
+                variable: variable_header,
+            },
+            PacketRepr::PubRel {
+                fixed_header,
+                variable_header,
+            } => Packet::PubRel {
+                fixed: fixed_header.into_fixed_header(Type::PubRel)?,
+                variable: variable_header,
+            },
+            PacketRepr::PubComp {
+                fixed_header,
+                variable_header,
+            } => Packet::PubComp {
+                fixed: fixed_header.into_fixed_header(Type::PubComp)?,
+                variable: variable_header,
+            },
+            PacketRepr::Subscribe {
+                fixed_header,
+                variable_header,
+                payload,
+            } => {
+                verify_topic_filters(
+                    payload
+                        .subscription_and_qos_tuples()
+                        .iter()
+                        .map(|(topic_filter, _)| topic_filter),
+                )?;
+                Packet::Subscribe {
+                    fixed: fixed_header.into_fixed_header(Type::Subscribe)?,
+                    variable: variable_header,
+                    payload,
+                }
+            }
+            PacketRepr::SubAck {
+                fixed_header,
+                variable_header,
+                payload,
+            } => Packet::SubAck {
+                fixed: fixed_header.into_fixed_header(Type::SubAck)?,
+                variable: variable_header,
+                payload,
+            },
+            PacketRepr::Unsubscribe {
+                fixed_header,
+                variable_header,
+                payload,
+            } => {
+                verify_topic_filters(payload.topics())?;
+                Packet::Unsubscribe {
+                    fixed: fixed_header.into_fixed_header(Type::Unsubscribe)?,
+                    variable: variable_header,
+                    payload,
+                }
+            }
+            PacketRepr::UnsubAck {
+                fixed_header,
+                variable_header,
+            } => Packet::UnsubAck {
+                fixed: fixed_header.into_fixed_header(Type::UnsubAck)?,
+                variable: variable_header,
+            },
+            PacketRepr::PingReq { fixed_header } => Packet::PingReq {
+                fixed: fixed_header.into_fixed_header(Type::PingReq)?,
+            },
+            PacketRepr::PingResp { fixed_header } => Packet::PingResp {
+                fixed: fixed_header.into_fixed_header(Type::PingResp)?,
+            },
+            PacketRepr::Disconnect { fixed_header } => Packet::Disconnect {
+                fixed: fixed_header.into_fixed_header(Type::Disconnect)?,
+            },
+        })
+    }
+}
+
+fn presence(present: bool) -> &'static str {
+    if present { "present" } else { "absent" }
+}
+
+fn verify_connect(
+    variable_header: &ConnectVariableHeader,
+    payload: &ConnectPayload,
+) -> Result<(), PacketSerdeError> {
+    let flags = variable_header.connect_flags();
+    if payload.will_topic().is_some() != flags.will_flag()
+        || payload.will_message().is_some() != flags.will_flag()
+    {
+        return Err(PacketSerdeError::WillMismatch(
+            flags.will_flag(),
+            presence(flags.will_flag()),
+        ));
+    }
+    if payload.username().is_some() != flags.username_flag() {
+        return Err(PacketSerdeError::UsernameMismatch(
+            flags.username_flag(),
+            presence(payload.username().is_some()),
+        ));
+    }
+    if payload.password().is_some() != flags.password_flag() {
+        return Err(PacketSerdeError::PasswordMismatch(
+            flags.password_flag(),
+            presence(payload.password().is_some()),
+        ));
+    }
+    Ok(())
+}
+
+fn verify_topic_filters<'a>(
+    topic_filters: impl IntoIterator<Item = &'a String>,
+) -> Result<(), PacketSerdeError> {
+    for topic_filter in topic_filters {
+        TopicFilter::verify(topic_filter).map_err(MqttProtocolError::InvalidTopicFilter)?;
+    }
+    Ok(())
+}
+
+fn verify_publish(
+    fixed_header: &FixedHeader,
+    variable_header: PublishVariableHeaderFields,
+) -> Result<PublishVariableHeader, PacketSerdeError> {
+    PublishVariableHeader::verify_topic_name(&variable_header.topic_name)?;
+    let qos = match fixed_header.fixed_header_reserved_flags() {
+        FixedHeaderFlags::Publish { qos, .. } => *qos,
+        _ => QoSCode::Qos0,
+    };
+    let publish_qos = PublishQoS::new(qos, variable_header.packet_identifier).map_err(|_| {
+        let requirement = if qos == QoSCode::Qos0 {
+            "must not have"
+        } else {
+            "needs"
+        };
+        PacketSerdeError::PublishPacketIdentifier(qos.as_u8(), requirement)
+    })?;
+    Ok(PublishVariableHeader::new(
+        variable_header.topic_name,
+        publish_qos,
+    ))
+}
+
+impl Serialize for Packet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PacketRepr::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Packet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = PacketRepr::deserialize(deserializer)?;
+        Packet::try_from(repr).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod packet_serde_tests {
+    use crate::cli::packet_json;
+    use crate::protocol::codec::{Decoder, Encoder};
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+    use bytes::BytesMut;
+
+    const WIRE_PACKETS: &[&[u8]] = &[
+        // CONNECT with will, username and password
+        &[
+            0x10, 0x1D, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0xCE, 0x00, 0x3C, 0x00, 0x02,
+            b'c', b'1', 0x00, 0x01, b'w', 0x00, 0x02, 0x01, 0xFF, 0x00, 0x01, b'u', 0x00, 0x03,
+            b'p', b'w', b'd',
+        ],
+        &[0x20, 0x02, 0x01, 0x00],
+        &[
+            0x3B, 0x0B, 0x00, 0x03, b'a', b'/', b'b', 0x00, 0x07, 0x00, 0x02, b'h', b'i',
+        ],
+        &[0x30, 0x05, 0x00, 0x01, b't', b'h', b'i'],
+        &[0x40, 0x02, 0x00, 0x01],
+        &[0x50, 0x02, 0x00, 0x02],
+        &[0x62, 0x02, 0x00, 0x03],
+        &[0x70, 0x02, 0x00, 0x04],
+        &[0x82, 0x08, 0x00, 0x05, 0x00, 0x03, b'a', b'/', b'#', 0x02],
+        &[0x90, 0x04, 0x00, 0x05, 0x02, 0x80],
+        &[0xA2, 0x07, 0x00, 0x06, 0x00, 0x03, b'a', b'/', b'+'],
+        &[0xB0, 0x02, 0x00, 0x06],
+        &[0xC0, 0x00],
+        &[0xD0, 0x00],
+        &[0xE0, 0x00],
+    ];
+
+    fn decode(bytes: &[u8]) -> Packet {
+        Packet::decode(&mut BytesMut::from(bytes)).unwrap()
+    }
+
+    #[test]
+    fn serialize_should_match_the_cli_json_of_every_packet_type() {
+        for bytes in WIRE_PACKETS {
+            let packet = decode(bytes);
+            assert_eq!(
+                serde_json::to_string(&packet).unwrap(),
+                packet_json::packet_to_json(&packet).to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn deserialize_should_round_trip_every_packet_type_to_the_same_bytes() {
+        for bytes in WIRE_PACKETS {
+            let json = serde_json::to_string(&decode(bytes)).unwrap();
+            let mut packet: Packet = serde_json::from_str(&json).unwrap();
+            assert_eq!(packet.encode().unwrap(), bytes.to_vec(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn deserialize_should_accept_minimal_hand_written_packets() {
+        let mut packet: Packet = serde_json::from_str(
+            r#"{"type":"CONNECT","variable_header":{"protocol_level":4,
+                "connect_flags":{"username_flag":false,"password_flag":false,"will_retain":false,
+                "will_qos":0,"will_flag":false,"clean_session":true},"keep_alive":30},
+                "payload":{"client_id":"c1"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            packet.encode().unwrap(),
+            vec![
+                0x10, 0x0E, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x02, 0x00, 0x1E, 0x00, 0x02,
+                b'c', b'1'
+            ]
+        );
+
+        let mut packet: Packet = serde_json::from_str(
+            r#"{"type":"PUBLISH","fixed_header":{"qos":1},
+                "variable_header":{"topic_name":"t","packet_identifier":9},
+                "payload":{"application_message":"78"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            packet.encode().unwrap(),
+            vec![0x32, 0x06, 0x00, 0x01, b't', 0x00, 0x09, b'x']
+        );
+    }
+
+    #[test]
+    fn deserialize_should_run_connect_flags_checks() {
+        let flags = |flags: &str| {
+            format!(
+                r#"{{"type":"CONNECT","variable_header":{{"protocol_level":4,
+                    "connect_flags":{},"keep_alive":0}},"payload":{{"client_id":"c"}}}}"#,
+                flags
+            )
+        };
+        // password without username
+        let json = flags(
+            r#"{"username_flag":false,"password_flag":true,"will_retain":false,"will_qos":0,"will_flag":false,"clean_session":true}"#,
+        );
+        assert!(serde_json::from_str::<Packet>(&json).is_err());
+        // will QoS without the will flag
+        let json = flags(
+            r#"{"username_flag":false,"password_flag":false,"will_retain":false,"will_qos":1,"will_flag":false,"clean_session":true}"#,
+        );
+        assert!(serde_json::from_str::<Packet>(&json).is_err());
+        // will QoS 3
+        let json = flags(
+            r#"{"username_flag":false,"password_flag":false,"will_retain":false,"will_qos":3,"will_flag":true,"clean_session":true}"#,
+        );
+        assert!(serde_json::from_str::<Packet>(&json).is_err());
+    }
+
+    #[test]
+    fn deserialize_should_reject_fields_that_disagree_with_flags() {
+        for json in [
+            // will flag set but no will topic
+            r#"{"type":"CONNECT","variable_header":{"protocol_level":4,"connect_flags":{"username_flag":false,"password_flag":false,"will_retain":false,"will_qos":0,"will_flag":true,"clean_session":true},"keep_alive":0},"payload":{"client_id":"c"}}"#,
+            // username without the username flag
+            r#"{"type":"CONNECT","variable_header":{"protocol_level":4,"connect_flags":{"username_flag":false,"password_flag":false,"will_retain":false,"will_qos":0,"will_flag":false,"clean_session":true},"keep_alive":0},"payload":{"client_id":"c","username":"u"}}"#,
+            // wrong protocol name
+            r#"{"type":"CONNECT","variable_header":{"protocol_name":"MQIsdp","protocol_level":4,"connect_flags":{"username_flag":false,"password_flag":false,"will_retain":false,"will_qos":0,"will_flag":false,"clean_session":true},"keep_alive":0},"payload":{"client_id":"c"}}"#,
+            // QoS 0 with a packet identifier
+            r#"{"type":"PUBLISH","variable_header":{"topic_name":"t","packet_identifier":1},"payload":{"application_message":""}}"#,
+            // QoS 2 without one
+            r#"{"type":"PUBLISH","fixed_header":{"qos":2},"variable_header":{"topic_name":"t"},"payload":{"application_message":""}}"#,
+            // reserved return code
+            r#"{"type":"CONNACK","variable_header":{"session_present":false,"return_code":6}}"#,
+            r#"{"type":"SUBACK","variable_header":{"packet_identifier":1},"payload":{"return_codes":[3]}}"#,
+            r#"{"type":"PINGREQ","fixed_header":{"flags":1}}"#,
+            // invalid topics
+            r#"{"type":"PUBLISH","variable_header":{"topic_name":"a/+"},"payload":{"application_message":""}}"#,
+            r#"{"type":"SUBSCRIBE","variable_header":{"packet_identifier":1},"payload":{"subscriptions":[{"topic_filter":"a/#/b","qos":0}]}}"#,
+            r#"{"type":"UNSUBSCRIBE","variable_header":{"packet_identifier":1},"payload":{"topic_filters":["sport+"]}}"#,
+        ] {
+            assert!(serde_json::from_str::<Packet>(json).is_err(), "{}", json);
+        }
+    }
+}
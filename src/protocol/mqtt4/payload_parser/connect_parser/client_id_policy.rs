@@ -0,0 +1,222 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use std::fmt;
+use std::sync::Arc;
+
+/// The 3.1.1 minimum a server must accept: 1 to 23 bytes of `0-9a-zA-Z`.
+const STRICT_MAX_LENGTH: usize = 23;
+
+/// Upper bound of any UTF-8 string field.
+const PERMISSIVE_MAX_LENGTH: usize = u16::MAX as usize;
+
+#[derive(Clone)]
+enum CharacterRule {
+    Alphanumeric,
+    Any,
+    Custom(Arc<dyn Fn(&str) -> bool + Send + Sync>),
+}
+
+/// Which non-empty Client Identifiers a CONNECT may carry, and what happens to an empty one.
+///
+/// The spec only requires servers to accept the strict form, but lets them accept longer IDs and
+/// other characters. An empty ID is allowed with clean session set; when an assigner is
+/// configured the decoded payload then carries the ID it returns instead.
+#[allow(dead_code)]
+#[derive(Clone)]
+pub struct ClientIdPolicy {
+    characters: CharacterRule,
+    max_length: usize,
+    assigner: Option<Arc<dyn Fn() -> String + Send + Sync>>,
+}
+
+#[allow(dead_code)]
+impl ClientIdPolicy {
+    pub fn strict() -> Self {
+        ClientIdPolicy {
+            characters: CharacterRule::Alphanumeric,
+            max_length: STRICT_MAX_LENGTH,
+            assigner: None,
+        }
+    }
+
+    /// Any UTF-8 string the string decoder accepts, up to 65535 bytes.
+    pub fn permissive() -> Self {
+        ClientIdPolicy {
+            characters: CharacterRule::Any,
+            max_length: PERMISSIVE_MAX_LENGTH,
+            assigner: None,
+        }
+    }
+
+    /// IDs up to `max_length` bytes for which `predicate` returns true.
+    pub fn custom(
+        max_length: usize,
+        predicate: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        ClientIdPolicy {
+            characters: CharacterRule::Custom(Arc::new(predicate)),
+            max_length,
+            assigner: None,
+        }
+    }
+
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Replaces an empty Client Identifier sent with clean session by `assigner()`.
+    pub fn with_assigned_client_ids(
+        mut self,
+        assigner: impl Fn() -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.assigner = Some(Arc::new(assigner));
+        self
+    }
+
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    pub fn verify(&self, client_id: &str) -> Result<(), MqttProtocolError> {
+        if client_id.len() > self.max_length {
+            return Err(MqttProtocolError::InvalidClientId);
+        }
+        let allowed = match &self.characters {
+            CharacterRule::Alphanumeric => client_id.chars().all(|c| c.is_ascii_alphanumeric()),
+            CharacterRule::Any => true,
+            CharacterRule::Custom(predicate) => predicate(client_id),
+        };
+        if !allowed {
+            return Err(MqttProtocolError::InvalidClientId);
+        }
+        Ok(())
+    }
+
+    /// The Client Identifier the session uses and whether the server assigned it.
+    pub(crate) fn resolve(
+        &self,
+        client_id: String,
+        clean_session: bool,
+    ) -> Result<(String, bool), MqttProtocolError> {
+        if !client_id.is_empty() {
+            self.verify(&client_id)?;
+            return Ok((client_id, false));
+        }
+        if !clean_session {
+            return Err(MqttProtocolError::InvalidClientId);
+        }
+        match &self.assigner {
+            Some(assigner) => Ok((assigner(), true)),
+            None => Ok((client_id, false)),
+        }
+    }
+}
+
+impl Default for ClientIdPolicy {
+    fn default() -> Self {
+        Self::strict()
+    }
+}
+
+impl fmt::Debug for ClientIdPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let characters = match self.characters {
+            CharacterRule::Alphanumeric => "alphanumeric",
+            CharacterRule::Any => "any",
+            CharacterRule::Custom(_) => "custom",
+        };
+        f.debug_struct("ClientIdPolicy")
+            .field("characters", &characters)
+            .field("max_length", &self.max_length)
+            .field("assigns_client_ids", &self.assigner.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod client_id_policy_tests {
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::mqtt4::payload_parser::connect_parser::client_id_policy::ClientIdPolicy;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn strict_policy_should_only_accept_the_spec_minimum() {
+        let policy = ClientIdPolicy::strict();
+        assert!(policy.verify("Client123").is_ok());
+        assert!(policy.verify(&"A".repeat(23)).is_ok());
+        assert!(matches!(
+            policy.verify(&"A".repeat(24)),
+            Err(MqttProtocolError::InvalidClientId)
+        ));
+        assert!(policy.verify("plant-7:pump_03").is_err());
+    }
+
+    #[test]
+    fn permissive_policy_should_accept_any_characters_and_long_ids() {
+        let policy = ClientIdPolicy::permissive();
+        assert!(policy.verify("plant-7:pump_03").is_ok());
+        assert!(
+            policy
+                .verify("6ba7b810-9dad-11d1-80b4-00c04fd430c8")
+                .is_ok()
+        );
+        assert!(policy.verify(&"A".repeat(65535)).is_ok());
+
+        let limited = ClientIdPolicy::permissive().with_max_length(8);
+        assert!(limited.verify("pump_03").is_ok());
+        assert!(limited.verify("plant-7:pump_03").is_err());
+    }
+
+    #[test]
+    fn custom_policy_should_apply_predicate_and_max_length() {
+        let policy = ClientIdPolicy::custom(16, |client_id| client_id.starts_with("plant-"));
+        assert!(policy.verify("plant-7:pump_03").is_ok());
+        assert!(policy.verify("pump_03").is_err());
+        assert!(policy.verify("plant-7:pump_03:valve").is_err());
+    }
+
+    #[test]
+    fn resolve_should_assign_client_id_only_to_empty_clean_sessions() {
+        let counter = AtomicU32::new(0);
+        let policy = ClientIdPolicy::strict().with_assigned_client_ids(move || {
+            format!("auto{}", counter.fetch_add(1, Ordering::Relaxed))
+        });
+
+        assert_eq!(
+            policy.resolve(String::new(), true).unwrap(),
+            ("auto0".to_string(), true)
+        );
+        assert_eq!(
+            policy.resolve(String::new(), true).unwrap(),
+            ("auto1".to_string(), true)
+        );
+        assert_eq!(
+            policy.resolve("given".to_string(), true).unwrap(),
+            ("given".to_string(), false)
+        );
+        assert!(matches!(
+            policy.resolve(String::new(), false),
+            Err(MqttProtocolError::InvalidClientId)
+        ));
+        assert_eq!(
+            ClientIdPolicy::strict()
+                .resolve(String::new(), true)
+                .unwrap(),
+            (String::new(), false)
+        );
+    }
+}
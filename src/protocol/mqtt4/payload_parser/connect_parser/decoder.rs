@@ -14,21 +14,26 @@
 
 use crate::byte_adapter::byte_operations::ByteOperations;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::decoder_config::{DecodeContext, DecoderConfig};
+use crate::protocol::mqtt4::payload_parser::connect_parser::client_id_policy::ClientIdPolicy;
 use crate::protocol::mqtt4::payload_parser::connect_parser::payload::ConnectPayload;
 use crate::protocol::mqtt4::payload_parser::mqtt_payload_codec::MqttPayloadDecoder;
 use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::ConnectVariableHeader;
+use crate::utils::mqtt_utils;
 use crate::utils::utf;
+use bytes::Bytes;
 
 impl MqttPayloadDecoder<ConnectVariableHeader> for ConnectPayload {
     fn decode(
         _fixed_header: &crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader,
         variable_header: &ConnectVariableHeader,
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<ConnectPayload, MqttProtocolError>
     where
         Self: Sized,
     {
-        Self::decode(bytes, variable_header)
+        Self::decode_with_context(bytes, variable_header, context)
     }
 }
 
@@ -38,45 +43,86 @@ impl ConnectPayload {
         bytes: &mut impl ByteOperations,
         connect_variable_header: &ConnectVariableHeader,
     ) -> Result<ConnectPayload, MqttProtocolError> {
-        let client_id = Self::parse_client_id(bytes)?;
+        Self::decode_with_context(bytes, connect_variable_header, &mut DecodeContext::strict())
+    }
 
-        if client_id.is_empty() && !connect_variable_header.connect_flags().clean_session() {
-            return Err(MqttProtocolError::InvalidClientId);
-        }
+    pub(crate) fn decode_with_policy(
+        bytes: &mut impl ByteOperations,
+        connect_variable_header: &ConnectVariableHeader,
+        client_id_policy: &ClientIdPolicy,
+    ) -> Result<ConnectPayload, MqttProtocolError> {
+        let config = DecoderConfig::strict().with_client_id_policy(client_id_policy.clone());
+        Self::decode_with_context(
+            bytes,
+            connect_variable_header,
+            &mut DecodeContext::new(&config),
+        )
+    }
+
+    pub(crate) fn decode_with_context(
+        bytes: &mut impl ByteOperations,
+        connect_variable_header: &ConnectVariableHeader,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<ConnectPayload, MqttProtocolError> {
+        context.enter_field("payload.client_id", bytes);
+        let client_id = context.read_string(bytes)?;
+        let clean_session = connect_variable_header.connect_flags().clean_session();
+        let (client_id, client_id_assigned) = match context
+            .config()
+            .client_id_policy()
+            .resolve(client_id.clone(), clean_session)
+        {
+            Ok(resolved) => resolved,
+            Err(error) => {
+                context.violation(error)?;
+                (client_id, false)
+            }
+        };
 
         let mut will_topic: Option<String> = None;
-        let mut will_message: Option<String> = None;
+        let mut will_message: Option<Bytes> = None;
         if connect_variable_header.connect_flags().will_flag() {
-            will_topic = Some(Self::parse_will_topic(bytes)?);
+            context.enter_field("payload.will_topic", bytes);
+            will_topic = Some(Self::parse_will_topic(bytes, context)?);
+            context.enter_field("payload.will_message", bytes);
             will_message = Some(Self::parse_will_message(bytes)?);
         }
 
         let mut username: Option<String> = None;
         let mut password: Option<String> = None;
         if connect_variable_header.connect_flags().username_flag() {
-            username = Some(Self::parse_username(bytes)?);
+            context.enter_field("payload.username", bytes);
+            username = Some(Self::parse_username(bytes, context)?);
         }
         if connect_variable_header.connect_flags().password_flag() {
-            password = Some(Self::parse_password(bytes)?);
+            context.enter_field("payload.password", bytes);
+            password = Some(Self::parse_password(bytes, context)?);
         }
 
-        Ok(ConnectPayload::new(
-            client_id,
-            will_topic,
-            will_message,
-            username,
-            password,
-        ))
+        let mut payload =
+            ConnectPayload::new(client_id, will_topic, will_message, username, password);
+        payload.set_client_id_assigned(client_id_assigned);
+        Ok(payload)
     }
 
-    fn parse_password(bytes: &mut impl ByteOperations) -> Result<String, MqttProtocolError> {
-        let password = utf::utf_8_handler::read(bytes)?;
+    fn parse_password(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<String, MqttProtocolError> {
+        let password = context.read_string(bytes)?;
         Ok(password)
     }
 
-    fn parse_username(bytes: &mut impl ByteOperations) -> Result<String, MqttProtocolError> {
-        let username = utf::utf_8_handler::read(bytes)?;
-        Self::verify_user_name(&username)?;
+    fn parse_username(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<String, MqttProtocolError> {
+        let username = context.read_string(bytes)?;
+        if context.config().reject_empty_username()
+            && let Err(error) = Self::verify_user_name(&username)
+        {
+            context.violation(error)?;
+        }
         Ok(username)
     }
 
@@ -87,37 +133,24 @@ impl ConnectPayload {
         Ok(())
     }
 
-    fn parse_will_message(bytes: &mut impl ByteOperations) -> Result<String, MqttProtocolError> {
-        let will_topic = utf::utf_8_handler::read(bytes)?;
-        Ok(will_topic)
+    fn parse_will_message(bytes: &mut impl ByteOperations) -> Result<Bytes, MqttProtocolError> {
+        let will_message = mqtt_utils::parse_binary_data(bytes)?;
+        Ok(will_message)
     }
 
-    fn parse_will_topic(bytes: &mut impl ByteOperations) -> Result<String, MqttProtocolError> {
-        let will_message = utf::utf_8_handler::read(bytes)?;
-        Ok(will_message)
+    fn parse_will_topic(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<String, MqttProtocolError> {
+        let will_topic = context.read_string(bytes)?;
+        Ok(will_topic)
     }
 
     pub(super) fn parse_client_id(
         bytes: &mut impl ByteOperations,
     ) -> Result<String, MqttProtocolError> {
         let client_id = utf::utf_8_handler::read(bytes)?;
-        Self::verify_string_is_ascii_alphanumeric(&client_id)?;
-        Self::verify_client_id_length(&client_id)?;
+        ClientIdPolicy::strict().verify(&client_id)?;
         Ok(client_id)
     }
-
-    fn verify_string_is_ascii_alphanumeric(client_id: &str) -> Result<(), MqttProtocolError> {
-        if !client_id.chars().all(|c| c.is_ascii_alphanumeric()) {
-            return Err(MqttProtocolError::InvalidClientId);
-        }
-        Ok(())
-    }
-
-    fn verify_client_id_length(client_id: &str) -> Result<(), MqttProtocolError> {
-        let length = client_id.len();
-        if length > 23 {
-            return Err(MqttProtocolError::InvalidClientId);
-        }
-        Ok(())
-    }
 }
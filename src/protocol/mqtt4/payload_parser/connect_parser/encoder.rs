@@ -42,7 +42,7 @@ impl MqttPayloadEncoder for ConnectPayload {
         if let Some(will_message) = self.will_message() {
             let encode_will_message_len =
                 radix_handler::u16_to_be_2_bytes(will_message.len())?.to_vec();
-            let encode_will_message = will_message.as_bytes().to_vec();
+            let encode_will_message = will_message.to_vec();
             encoded_bytes.extend(encode_will_message_len);
             encoded_bytes.extend(encode_will_message);
         }
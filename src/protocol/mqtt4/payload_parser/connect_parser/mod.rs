@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub(crate) mod client_id_policy;
 mod decoder;
 mod encoder;
 pub(crate) mod payload;
@@ -12,14 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use bytes::Bytes;
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct ConnectPayload {
     client_id: String,
     will_topic: Option<String>,
-    will_message: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, with = "crate::utils::hex::hex_handler::optional_hex")
+    )]
+    will_message: Option<Bytes>,
     username: Option<String>,
     password: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    client_id_assigned: bool,
 }
 
 #[allow(dead_code)]
@@ -27,7 +36,7 @@ impl ConnectPayload {
     pub fn new(
         client_id: String,
         will_topic: Option<String>,
-        will_message: Option<String>,
+        will_message: Option<Bytes>,
         username: Option<String>,
         password: Option<String>,
     ) -> Self {
@@ -37,6 +46,7 @@ impl ConnectPayload {
             will_message,
             username,
             password,
+            client_id_assigned: false,
         }
     }
 
@@ -44,11 +54,21 @@ impl ConnectPayload {
         &self.client_id
     }
 
+    /// Whether `client_id` was assigned by the `ClientIdPolicy` because the CONNECT carried an
+    /// empty one.
+    pub fn client_id_assigned(&self) -> bool {
+        self.client_id_assigned
+    }
+
+    pub(super) fn set_client_id_assigned(&mut self, client_id_assigned: bool) {
+        self.client_id_assigned = client_id_assigned;
+    }
+
     pub fn will_topic(&self) -> Option<&str> {
         self.will_topic.as_deref()
     }
 
-    pub fn will_message(&self) -> Option<&str> {
+    pub fn will_message(&self) -> Option<&[u8]> {
         self.will_message.as_deref()
     }
 
@@ -65,12 +85,14 @@ impl ConnectPayload {
 mod connect_payload_decode_tests {
     use crate::protocol::common::protocol_level::ProtocolLevel;
     use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::mqtt4::payload_parser::connect_parser::client_id_policy::ClientIdPolicy;
     use crate::protocol::mqtt4::payload_parser::connect_parser::payload::ConnectPayload;
     use crate::protocol::mqtt4::payload_parser::mqtt_payload_codec::MqttPayloadEncoder;
     use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::{
         ConnectFlags, ConnectVariableHeader,
     };
-    use bytes::BytesMut;
+    use bytes::{Bytes, BytesMut};
 
     #[test]
     fn client_id_should_contain_only_valid_characters() {
@@ -164,8 +186,62 @@ mod connect_payload_decode_tests {
         );
     }
 
-    // todo extend The Server MAY allow ClientId’s that contain more than 23 encoded bytes.
-    // The Server MAY allow ClientId’s that contain characters not included in the list given above.
+    #[test]
+    fn client_id_policy_can_allow_longer_ids_with_other_characters() {
+        let connect_flags =
+            ConnectFlags::new(false, false, false, QoSCode::Qos0, false, false).unwrap();
+        let connect_variable_header =
+            ConnectVariableHeader::new(ProtocolLevel::Mqtt3_1_1, connect_flags, 0);
+        let expect_client_id = "plant-7:pump_03/6ba7b810-9dad-11d1-80b4";
+        let connect_payload =
+            ConnectPayload::new(expect_client_id.to_string(), None, None, None, None);
+        let vec = connect_payload.encode().unwrap();
+
+        let result =
+            ConnectPayload::decode(&mut BytesMut::from(&vec[..]), &connect_variable_header);
+        assert!(matches!(result, Err(MqttProtocolError::InvalidClientId)));
+
+        let payload = ConnectPayload::decode_with_policy(
+            &mut BytesMut::from(&vec[..]),
+            &connect_variable_header,
+            &ClientIdPolicy::permissive(),
+        )
+        .unwrap();
+        assert_eq!(payload.client_id(), expect_client_id);
+        assert!(!payload.client_id_assigned());
+    }
+
+    #[test]
+    fn client_id_policy_can_assign_client_id_when_empty_with_clean_session() {
+        let policy = ClientIdPolicy::strict().with_assigned_client_ids(|| "assigned1".to_string());
+        let connect_payload = ConnectPayload::new(String::new(), None, None, None, None);
+        let vec = connect_payload.encode().unwrap();
+
+        let connect_flags =
+            ConnectFlags::new(false, false, false, QoSCode::Qos0, false, true).unwrap();
+        let connect_variable_header =
+            ConnectVariableHeader::new(ProtocolLevel::Mqtt3_1_1, connect_flags, 0);
+        let payload = ConnectPayload::decode_with_policy(
+            &mut BytesMut::from(&vec[..]),
+            &connect_variable_header,
+            &policy,
+        )
+        .unwrap();
+        assert_eq!(payload.client_id(), "assigned1");
+        assert!(payload.client_id_assigned());
+
+        let connect_flags =
+            ConnectFlags::new(false, false, false, QoSCode::Qos0, false, false).unwrap();
+        let connect_variable_header =
+            ConnectVariableHeader::new(ProtocolLevel::Mqtt3_1_1, connect_flags, 0);
+        let result = ConnectPayload::decode_with_policy(
+            &mut BytesMut::from(&vec[..]),
+            &connect_variable_header,
+            &policy,
+        );
+        assert!(matches!(result, Err(MqttProtocolError::InvalidClientId)));
+    }
+
     #[test]
     fn will_topic_and_will_message_must_be_present_when_will_flag_is_true() {
         let will_flag = true;
@@ -181,7 +257,7 @@ mod connect_payload_decode_tests {
         let connect_payload = ConnectPayload::new(
             client_id.to_string(),
             Some(expect_will_topic.to_string()),
-            Some(expect_will_message.to_string()),
+            Some(Bytes::from(expect_will_message)),
             None,
             None,
         );
@@ -192,7 +268,10 @@ mod connect_payload_decode_tests {
         assert!(result.is_ok());
         let payload = result.unwrap();
         assert_eq!(payload.will_topic().unwrap(), expect_will_topic);
-        assert_eq!(payload.will_message().unwrap(), expect_will_message);
+        assert_eq!(
+            payload.will_message().unwrap(),
+            expect_will_message.as_bytes()
+        );
     }
 
     #[test]
@@ -210,7 +289,7 @@ mod connect_payload_decode_tests {
         let connect_payload = ConnectPayload::new(
             expect_client_id.to_string(),
             Some(expect_will_topic.to_string()),
-            Some(expect_will_message.to_string()),
+            Some(Bytes::from(expect_will_message)),
             None,
             None,
         );
@@ -222,7 +301,10 @@ mod connect_payload_decode_tests {
         let payload = result.unwrap();
         assert_eq!(payload.client_id(), expect_client_id);
         assert_eq!(payload.will_topic().unwrap(), expect_will_topic);
-        assert_eq!(payload.will_message().unwrap(), expect_will_message);
+        assert_eq!(
+            payload.will_message().unwrap(),
+            expect_will_message.as_bytes()
+        );
     }
 
     #[test]
@@ -465,7 +547,7 @@ mod connect_payload_decode_tests {
         let connect_payload = ConnectPayload::new(
             expect_client_id.to_string(),
             Some(expect_will_topic.to_string()),
-            Some(expect_will_message.to_string()),
+            Some(Bytes::from(expect_will_message)),
             Some(expect_username.to_string()),
             Some(expect_password.to_string()),
         );
@@ -477,7 +559,10 @@ mod connect_payload_decode_tests {
         let payload = result.unwrap();
         assert_eq!(payload.client_id(), expect_client_id);
         assert_eq!(payload.will_topic().unwrap(), expect_will_topic);
-        assert_eq!(payload.will_message().unwrap(), expect_will_message);
+        assert_eq!(
+            payload.will_message().unwrap(),
+            expect_will_message.as_bytes()
+        );
         assert_eq!(payload.username().unwrap(), expect_username);
         assert_eq!(payload.password().unwrap(), expect_password);
     }
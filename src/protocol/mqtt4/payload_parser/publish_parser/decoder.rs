@@ -14,19 +14,22 @@
 
 use crate::byte_adapter::byte_operations::ByteOperations;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::decoder_config::DecodeContext;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
 use crate::protocol::mqtt4::payload_parser::mqtt_payload_codec::MqttPayloadDecoder;
 use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
 use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::PublishVariableHeader;
-use crate::utils::utf;
+use bytes::Bytes;
 
 impl MqttPayloadDecoder<PublishVariableHeader> for PublishPayload {
     fn decode(
-        _fixed_header: &FixedHeader,
-        _variable_header: &PublishVariableHeader,
+        fixed_header: &FixedHeader,
+        variable_header: &PublishVariableHeader,
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<PublishPayload, MqttProtocolError> {
-        Self::decode(bytes)
+        let length = Self::application_message_length(fixed_header, variable_header)?;
+        Self::decode_with_context(bytes, length, context)
     }
 }
 
@@ -35,14 +38,35 @@ impl PublishPayload {
     pub(super) fn decode(
         bytes: &mut impl ByteOperations,
     ) -> Result<PublishPayload, MqttProtocolError> {
-        let application_message = Self::parse_application_message(bytes)?;
-        Ok(PublishPayload::new(application_message))
+        let length = bytes.bytes_len();
+        Self::decode_with_context(bytes, length, &mut DecodeContext::strict())
     }
 
-    fn parse_application_message(
+    pub(crate) fn decode_with_context(
         bytes: &mut impl ByteOperations,
-    ) -> Result<String, MqttProtocolError> {
-        let application_message = utf::utf_8_handler::read(bytes)?;
-        Ok(application_message)
+        length: usize,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<PublishPayload, MqttProtocolError> {
+        context.enter_field("payload.application_message", bytes);
+        if bytes.bytes_len() < length {
+            return Err(MqttProtocolError::PacketTooShort);
+        }
+        Ok(PublishPayload::new(Bytes::from(bytes.read_bytes(length))))
+    }
+
+    /// What the remaining length leaves after the topic name and the packet identifier.
+    fn application_message_length(
+        fixed_header: &FixedHeader,
+        variable_header: &PublishVariableHeader,
+    ) -> Result<usize, MqttProtocolError> {
+        let packet_identifier_length = match variable_header.packet_identifier() {
+            Some(_) => 2,
+            None => 0,
+        };
+        let variable_header_length =
+            2 + variable_header.topic_name().len() + packet_identifier_length;
+        (fixed_header.remaining_length() as usize)
+            .checked_sub(variable_header_length)
+            .ok_or(MqttProtocolError::MalformedPacket)
     }
 }
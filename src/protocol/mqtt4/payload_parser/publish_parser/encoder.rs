@@ -15,22 +15,12 @@
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
 use crate::protocol::mqtt4::payload_parser::mqtt_payload_codec::MqttPayloadEncoder;
 use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
-use crate::utils::radix::radix_handler;
 
 impl MqttPayloadEncoder for PublishPayload {
     fn encode(&self) -> Result<Vec<u8>, MqttProtocolError>
     where
         Self: Sized,
     {
-        let mut encoded_bytes: Vec<u8> = Vec::new();
-
-        let encode_application_message_len =
-            radix_handler::u16_to_be_2_bytes(self.application_message().len())?.to_vec();
-
-        let encode_application_message = self.application_message().as_bytes().to_vec();
-        encoded_bytes.extend(encode_application_message_len);
-        encoded_bytes.extend(encode_application_message);
-
-        Ok(encoded_bytes)
+        Ok(self.application_message().to_vec())
     }
 }
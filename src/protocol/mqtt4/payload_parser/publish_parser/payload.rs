@@ -12,20 +12,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use bytes::Bytes;
+
+/// The application message of a PUBLISH: every byte the remaining length leaves after the
+/// variable header, with no length prefix and no encoding of its own.
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct PublishPayload {
-    application_message: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::hex::hex_handler::hex"))]
+    application_message: Bytes,
 }
 
 #[allow(dead_code)]
 impl PublishPayload {
-    pub fn new(application_message: String) -> Self {
+    pub fn new(application_message: Bytes) -> Self {
         PublishPayload {
             application_message,
         }
     }
-    pub fn application_message(&self) -> &str {
+    pub fn application_message(&self) -> &[u8] {
         &self.application_message
     }
 }
@@ -34,14 +40,15 @@ impl PublishPayload {
 mod publish_payload_tests {
     use crate::protocol::mqtt4::payload_parser::mqtt_payload_codec::MqttPayloadEncoder;
     use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
-    use bytes::BytesMut;
+    use bytes::{Bytes, BytesMut};
 
     #[test]
     fn publish_payload_parser_should_parse_payload_correctly() {
-        let expect_application_message = "Hello MQTT";
+        let expect_application_message = b"Hello MQTT";
 
-        let publish_payload = PublishPayload::new(expect_application_message.to_string());
+        let publish_payload = PublishPayload::new(Bytes::from_static(expect_application_message));
         let vec = publish_payload.encode().unwrap();
+        assert_eq!(vec, expect_application_message);
         let mut bytes = BytesMut::new();
         bytes.extend_from_slice(&vec);
 
@@ -55,25 +62,27 @@ mod publish_payload_tests {
 
     #[test]
     fn publish_payload_can_handle_empty_message() {
-        let expect_application_message = "";
         let mut bytes = BytesMut::new();
-        let publish_payload = PublishPayload::new(expect_application_message.to_string());
+        let publish_payload = PublishPayload::new(Bytes::new());
         let vec = publish_payload.encode().unwrap();
         bytes.extend_from_slice(&vec);
 
         let publish_payload = PublishPayload::decode(&mut bytes).unwrap();
 
-        assert_eq!(
-            publish_payload.application_message(),
-            expect_application_message
-        );
+        assert!(publish_payload.application_message().is_empty());
     }
 
     #[test]
-    fn publish_payload_can_not_exceed_max_length() {
-        let exceed_message = "A".repeat(70000);
-        let payload = PublishPayload::new(exceed_message.to_string());
-        let result = payload.encode();
-        assert!(result.is_err());
+    fn publish_payload_can_carry_binary_and_long_messages() {
+        let binary_message = vec![0x00, 0xFF, 0xC3, 0x28];
+        let long_message = vec![b'A'; 70000];
+        for message in [binary_message, long_message] {
+            let payload = PublishPayload::new(Bytes::from(message.clone()));
+            let mut bytes = BytesMut::from(&payload.encode().unwrap()[..]);
+
+            let payload = PublishPayload::decode(&mut bytes).unwrap();
+
+            assert_eq!(payload.application_message(), message);
+        }
     }
 }
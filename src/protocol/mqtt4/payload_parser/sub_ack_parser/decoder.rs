@@ -14,6 +14,7 @@
 
 use crate::byte_adapter::byte_operations::ByteOperations;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::decoder_config::DecodeContext;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
 use crate::protocol::mqtt4::payload_parser::mqtt_payload_codec::MqttPayloadDecoder;
 use crate::protocol::mqtt4::payload_parser::sub_ack_parser::payload::{
@@ -26,8 +27,9 @@ impl MqttPayloadDecoder<SubAckVariableHeader> for SubAckPayload {
         _fixed_header: &FixedHeader,
         _variable_header: &SubAckVariableHeader,
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<SubAckPayload, MqttProtocolError> {
-        Self::decode(bytes)
+        Self::decode_with_context(bytes, context)
     }
 }
 
@@ -35,9 +37,20 @@ impl MqttPayloadDecoder<SubAckVariableHeader> for SubAckPayload {
 impl SubAckPayload {
     pub(super) fn decode(
         bytes: &mut impl ByteOperations,
+    ) -> Result<SubAckPayload, MqttProtocolError> {
+        Self::decode_with_context(bytes, &mut DecodeContext::strict())
+    }
+
+    pub(crate) fn decode_with_context(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<SubAckPayload, MqttProtocolError> {
         let mut return_codes = Vec::new();
-        while let Some(code_byte) = bytes.read_a_byte() {
+        loop {
+            context.enter_list_field("payload.return_codes[]", return_codes.len(), bytes);
+            let Some(code_byte) = bytes.read_a_byte() else {
+                break;
+            };
             let return_code = SubAckReturnCode::parse(code_byte)?;
             return_codes.push(return_code);
         }
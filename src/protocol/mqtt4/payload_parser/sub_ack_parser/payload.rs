@@ -12,10 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::protocol::common::qos::QoSCode;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
 
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct SubAckPayload {
     return_codes: Vec<SubAckReturnCode>,
 }
@@ -33,6 +35,11 @@ impl SubAckPayload {
 
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u8", into = "u8")
+)]
 pub enum SubAckReturnCode {
     Qos0 = 0,
     Qos1 = 1,
@@ -42,7 +49,7 @@ pub enum SubAckReturnCode {
 
 #[allow(dead_code)]
 impl SubAckReturnCode {
-    pub(super) fn parse(byte: u8) -> Result<SubAckReturnCode, MqttProtocolError> {
+    pub(crate) fn parse(byte: u8) -> Result<SubAckReturnCode, MqttProtocolError> {
         match byte {
             0 => Ok(SubAckReturnCode::Qos0),
             1 => Ok(SubAckReturnCode::Qos1),
@@ -51,7 +58,7 @@ impl SubAckReturnCode {
             _ => Err(MqttProtocolError::MalformedPacket),
         }
     }
-    pub(super) fn as_u8(&self) -> u8 {
+    pub(crate) fn as_u8(&self) -> u8 {
         match self {
             SubAckReturnCode::Qos0 => 0,
             SubAckReturnCode::Qos1 => 1,
@@ -61,6 +68,31 @@ impl SubAckReturnCode {
     }
 }
 
+impl TryFrom<u8> for SubAckReturnCode {
+    type Error = MqttProtocolError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        SubAckReturnCode::parse(value)
+    }
+}
+
+/// The return code that grants a QoS.
+impl From<QoSCode> for SubAckReturnCode {
+    fn from(value: QoSCode) -> Self {
+        match value {
+            QoSCode::Qos0 => SubAckReturnCode::Qos0,
+            QoSCode::Qos1 => SubAckReturnCode::Qos1,
+            QoSCode::Qos2 => SubAckReturnCode::Qos2,
+        }
+    }
+}
+
+impl From<SubAckReturnCode> for u8 {
+    fn from(value: SubAckReturnCode) -> Self {
+        value.as_u8()
+    }
+}
+
 #[cfg(test)]
 mod sub_ack_payload_tests {
     use crate::byte_adapter::byte_operations::ByteOperations;
@@ -14,23 +14,25 @@
 
 use crate::byte_adapter::byte_operations::ByteOperations;
 use crate::protocol::common::qos::QoSCode;
+use crate::protocol::common::topic_filter::TopicFilter;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::decoder_config::DecodeContext;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
 use crate::protocol::mqtt4::payload_parser::mqtt_payload_codec::MqttPayloadDecoder;
 use crate::protocol::mqtt4::payload_parser::subscribe_parser::payload::SubscribePayload;
 use crate::protocol::mqtt4::variable_header_parser::subscribe_parser::variable_header::SubscribeVariableHeader;
-use crate::utils::utf;
 
 impl MqttPayloadDecoder<SubscribeVariableHeader> for SubscribePayload {
     fn decode(
         _fixed_header: &FixedHeader,
         _variable_header: &SubscribeVariableHeader,
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<Self, MqttProtocolError>
     where
         Self: Sized,
     {
-        Self::decode(bytes)
+        Self::decode_with_context(bytes, context)
     }
 }
 
@@ -38,10 +40,18 @@ impl MqttPayloadDecoder<SubscribeVariableHeader> for SubscribePayload {
 impl SubscribePayload {
     pub(super) fn decode(
         bytes: &mut impl ByteOperations,
+    ) -> Result<SubscribePayload, MqttProtocolError> {
+        Self::decode_with_context(bytes, &mut DecodeContext::strict())
+    }
+
+    pub(crate) fn decode_with_context(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<SubscribePayload, MqttProtocolError> {
         let mut topics: Vec<(String, QoSCode)> = Vec::new();
 
-        while let Some(topic) = Self::parse_topic_with_qos(bytes)? {
+        context.enter_field("payload.subscriptions", bytes);
+        while let Some(topic) = Self::parse_topic_with_qos(bytes, topics.len(), context)? {
             topics.push(topic);
         }
 
@@ -52,19 +62,27 @@ impl SubscribePayload {
 
     fn parse_topic_with_qos(
         bytes: &mut impl ByteOperations,
+        index: usize,
+        context: &mut DecodeContext<'_>,
     ) -> Result<Option<(String, QoSCode)>, MqttProtocolError> {
         if bytes.is_empty() {
             return Ok(None);
         }
 
-        let topic_filter = Self::parse_topic_filter(bytes)?;
+        context.enter_list_field("payload.subscriptions[].topic_filter", index, bytes);
+        let topic_filter = Self::parse_topic_filter(bytes, context)?;
+        context.enter_list_field("payload.subscriptions[].qos", index, bytes);
         let qos = Self::parse_qos(bytes)?;
 
         Ok(Some((topic_filter, qos)))
     }
 
-    fn parse_topic_filter(bytes: &mut impl ByteOperations) -> Result<String, MqttProtocolError> {
-        let topic_filter = utf::utf_8_handler::read(bytes)?;
+    fn parse_topic_filter(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<String, MqttProtocolError> {
+        let topic_filter = context.read_string(bytes)?;
+        TopicFilter::verify(&topic_filter).map_err(MqttProtocolError::InvalidTopicFilter)?;
         Ok(topic_filter)
     }
 
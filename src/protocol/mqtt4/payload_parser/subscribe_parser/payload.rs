@@ -15,7 +15,12 @@
 use crate::protocol::common::qos::QoSCode;
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct SubscribePayload {
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "subscriptions", with = "subscriptions")
+    )]
     subscription_and_qos_tuples: Vec<(String, QoSCode)>,
 }
 
@@ -31,10 +36,44 @@ impl SubscribePayload {
     }
 }
 
+/// Subscriptions are written as `{"topic_filter": ..., "qos": ...}` objects rather than tuples.
+#[cfg(feature = "serde")]
+mod subscriptions {
+    use crate::protocol::common::qos::QoSCode;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Subscription {
+        topic_filter: String,
+        qos: QoSCode,
+    }
+
+    pub(super) fn serialize<S: Serializer>(
+        tuples: &[(String, QoSCode)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(tuples.iter().map(|(topic_filter, qos)| Subscription {
+            topic_filter: topic_filter.clone(),
+            qos: *qos,
+        }))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, QoSCode)>, D::Error> {
+        let subscriptions = Vec::<Subscription>::deserialize(deserializer)?;
+        Ok(subscriptions
+            .into_iter()
+            .map(|subscription| (subscription.topic_filter, subscription.qos))
+            .collect())
+    }
+}
+
 #[cfg(test)]
 mod subscribe_payload_tests {
     use crate::byte_adapter::byte_operations::ByteOperations;
     use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::common::topic_error::TopicError;
     use crate::protocol::mqtt_protocol_error::MqttProtocolError;
     use crate::protocol::mqtt4::payload_parser::mqtt_payload_codec::MqttPayloadEncoder;
     use crate::protocol::mqtt4::payload_parser::subscribe_parser::payload::SubscribePayload;
@@ -138,6 +177,25 @@ mod subscribe_payload_tests {
         assert!(matches!(result, Err(MqttProtocolError::MalformedPacket)));
     }
 
+    #[test]
+    fn subscribe_payload_fails_on_invalid_topic_filter() {
+        for (filter, expect_error) in [
+            ("a/#/b", TopicError::MultiLevelWildcardNotLast(2)),
+            ("sport+", TopicError::WildcardNotWholeLevel(5, '+')),
+            ("", TopicError::Empty),
+        ] {
+            let mut bytes = BytesMut::new();
+            write(&mut bytes, filter).unwrap();
+            bytes.write_a_byte(0b0000_0001);
+            let result = SubscribePayload::decode(&mut bytes);
+            assert!(
+                matches!(&result, Err(MqttProtocolError::InvalidTopicFilter(error)) if *error == expect_error),
+                "{}",
+                filter
+            );
+        }
+    }
+
     #[test]
     fn subscribe_payload_can_handle_wildcard_topics() {
         let mut bytes = BytesMut::new();
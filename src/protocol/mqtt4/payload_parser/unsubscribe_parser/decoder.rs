@@ -13,23 +13,25 @@
 // limitations under the License.
 
 use crate::byte_adapter::byte_operations::ByteOperations;
+use crate::protocol::common::topic_filter::TopicFilter;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::decoder_config::DecodeContext;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
 use crate::protocol::mqtt4::payload_parser::mqtt_payload_codec::MqttPayloadDecoder;
 use crate::protocol::mqtt4::payload_parser::unsubscribe_parser::payload::UnSubscribePayload;
 use crate::protocol::mqtt4::variable_header_parser::unsubscribe_parser::variable_header::UnSubscribeVariableHeader;
-use crate::utils::utf;
 
 impl MqttPayloadDecoder<UnSubscribeVariableHeader> for UnSubscribePayload {
     fn decode(
         _fixed_header: &FixedHeader,
         _variable_header: &UnSubscribeVariableHeader,
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<Self, MqttProtocolError>
     where
         Self: Sized,
     {
-        Self::decode(bytes)
+        Self::decode_with_context(bytes, context)
     }
 }
 
@@ -37,10 +39,18 @@ impl MqttPayloadDecoder<UnSubscribeVariableHeader> for UnSubscribePayload {
 impl UnSubscribePayload {
     pub(super) fn decode(
         bytes: &mut impl ByteOperations,
+    ) -> Result<UnSubscribePayload, MqttProtocolError> {
+        Self::decode_with_context(bytes, &mut DecodeContext::strict())
+    }
+
+    pub(crate) fn decode_with_context(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<UnSubscribePayload, MqttProtocolError> {
         let mut topics = Vec::new();
 
-        while let Some(topic) = Self::parse_topic(bytes)? {
+        context.enter_field("payload.topic_filters", bytes);
+        while let Some(topic) = Self::parse_topic(bytes, topics.len(), context)? {
             topics.push(topic);
         }
 
@@ -49,18 +59,27 @@ impl UnSubscribePayload {
         Ok(UnSubscribePayload::new(topics))
     }
 
-    fn parse_topic(bytes: &mut impl ByteOperations) -> Result<Option<String>, MqttProtocolError> {
+    fn parse_topic(
+        bytes: &mut impl ByteOperations,
+        index: usize,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<Option<String>, MqttProtocolError> {
         if bytes.is_empty() {
             return Ok(None);
         }
 
-        let topic_filter = Self::parse_topic_filter(bytes)?;
+        context.enter_list_field("payload.topic_filters[]", index, bytes);
+        let topic_filter = Self::parse_topic_filter(bytes, context)?;
 
         Ok(Some(topic_filter))
     }
 
-    fn parse_topic_filter(bytes: &mut impl ByteOperations) -> Result<String, MqttProtocolError> {
-        let topic_filter = utf::utf_8_handler::read(bytes)?;
+    fn parse_topic_filter(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<String, MqttProtocolError> {
+        let topic_filter = context.read_string(bytes)?;
+        TopicFilter::verify(&topic_filter).map_err(MqttProtocolError::InvalidTopicFilter)?;
         Ok(topic_filter)
     }
 
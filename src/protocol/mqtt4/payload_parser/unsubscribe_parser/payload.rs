@@ -14,7 +14,9 @@
 
 #[allow(dead_code)]
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct UnSubscribePayload {
+    #[cfg_attr(feature = "serde", serde(rename = "topic_filters"))]
     topics: Vec<String>,
 }
 
@@ -30,9 +32,11 @@ impl UnSubscribePayload {
 
 #[cfg(test)]
 mod unsubscribe_payload_tests {
+    use crate::protocol::common::topic_error::TopicError;
     use crate::protocol::mqtt_protocol_error::MqttProtocolError;
     use crate::protocol::mqtt4::payload_parser::mqtt_payload_codec::MqttPayloadEncoder;
     use crate::protocol::mqtt4::payload_parser::unsubscribe_parser::payload::UnSubscribePayload;
+    use crate::utils::utf::utf_8_handler::write;
     use bytes::BytesMut;
 
     #[test]
@@ -89,6 +93,20 @@ mod unsubscribe_payload_tests {
         assert!(matches!(result, Err(MqttProtocolError::MalformedPacket)));
     }
 
+    #[test]
+    fn unsubscribe_payload_fails_on_invalid_topic_filter() {
+        let mut bytes = BytesMut::new();
+        write(&mut bytes, "valid/#").unwrap();
+        write(&mut bytes, "invalid/#/filter").unwrap();
+        let result = UnSubscribePayload::decode(&mut bytes);
+        assert!(matches!(
+            result,
+            Err(MqttProtocolError::InvalidTopicFilter(
+                TopicError::MultiLevelWildcardNotLast(8)
+            ))
+        ));
+    }
+
     #[test]
     fn unsubscribe_payload_can_handle_wildcard_topics() {
         let mut bytes = BytesMut::new();
@@ -0,0 +1,409 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::codec::{Decoder, Encoder};
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::common::topic_filter::TopicFilter;
+use crate::protocol::common::topic_name::TopicName;
+use crate::protocol::mqtt4::delivery::packet_id_allocator::PacketIdAllocator;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+use crate::protocol::mqtt4::retained::retained_store_error::RetainedStoreError;
+use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+    PublishQoS, PublishVariableHeader,
+};
+use crate::protocol::topic_matching::topic_matcher;
+use bytes::BytesMut;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+/// The last PUBLISH with RETAIN set that the server received for a topic.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct RetainedMessage {
+    topic_name: String,
+    qos: QoSCode,
+    payload: PublishPayload,
+}
+
+#[allow(dead_code)]
+impl RetainedMessage {
+    pub(crate) fn topic_name(&self) -> &str {
+        &self.topic_name
+    }
+
+    pub(crate) fn qos(&self) -> QoSCode {
+        self.qos
+    }
+
+    pub(crate) fn payload(&self) -> &PublishPayload {
+        &self.payload
+    }
+
+    /// The PUBLISH sending the message because of a new subscription granted `granted_qos`:
+    /// RETAIN is set [MQTT-3.3.1-8] and the QoS is the lower of the message's and the granted
+    /// one [MQTT-3.8.4-6]. `packet_identifier` is only used at QoS 1 and 2.
+    pub(crate) fn to_packet(&self, granted_qos: QoSCode, packet_identifier: PacketId) -> Packet {
+        let qos = granted_qos.min(self.qos);
+        Packet::publish(
+            false,
+            true,
+            PublishVariableHeader::new(
+                self.topic_name.clone(),
+                PublishQoS::with_packet_identifier(qos, packet_identifier),
+            ),
+            self.payload.clone(),
+        )
+    }
+}
+
+/// The retained messages of a server, one per topic name. The store lives in memory and can be
+/// written to and read back from a snapshot file of PUBLISH packets in MQTT wire format, whose
+/// packet identifiers carry no meaning.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct RetainedMessageStore {
+    messages: BTreeMap<String, RetainedMessage>,
+}
+
+#[allow(dead_code)]
+impl RetainedMessageStore {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub(crate) fn get(&self, topic_name: &str) -> Option<&RetainedMessage> {
+        self.messages.get(topic_name)
+    }
+
+    /// Keeps `packet` if it is a PUBLISH with RETAIN set, replacing the message retained for its
+    /// topic [MQTT-3.3.1-5]. An empty payload removes the retained message instead
+    /// [MQTT-3.3.1-10]. Returns whether the store changed.
+    pub(crate) fn handle_publish(&mut self, packet: &Packet) -> Result<bool, RetainedStoreError> {
+        let Packet::Publish {
+            fixed,
+            variable,
+            payload,
+        } = packet
+        else {
+            return Ok(false);
+        };
+        if !matches!(
+            fixed.fixed_header_reserved_flags(),
+            FixedHeaderFlags::Publish { retain: true, .. }
+        ) {
+            return Ok(false);
+        }
+        TopicName::verify(variable.topic_name()).map_err(RetainedStoreError::InvalidTopicName)?;
+        if payload.application_message().is_empty() {
+            return Ok(self.messages.remove(variable.topic_name()).is_some());
+        }
+        let message = RetainedMessage {
+            topic_name: variable.topic_name().to_string(),
+            qos: variable.qos(),
+            payload: payload.clone(),
+        };
+        self.messages.insert(message.topic_name.clone(), message);
+        Ok(true)
+    }
+
+    /// The retained messages whose topic name matches `topic_filter`, sorted by topic name.
+    pub(crate) fn matching(&self, topic_filter: &TopicFilter) -> Vec<&RetainedMessage> {
+        self.messages
+            .values()
+            .filter(|message| {
+                topic_matcher::matches_str(topic_filter.as_str(), &message.topic_name)
+            })
+            .collect()
+    }
+
+    /// The PUBLISH packets a new subscription to `topic_filter` granted `granted_qos` receives
+    /// [MQTT-3.3.1-6], with packet identifiers from `packet_identifiers` for those above QoS 0.
+    /// When identifiers run out, the ones taken so far are released again.
+    pub(crate) fn publishes_for(
+        &self,
+        topic_filter: &TopicFilter,
+        granted_qos: QoSCode,
+        packet_identifiers: &mut PacketIdAllocator,
+    ) -> Result<Vec<Packet>, RetainedStoreError> {
+        let mut packets = Vec::new();
+        let mut allocated = Vec::new();
+        for message in self.matching(topic_filter) {
+            let mut packet_identifier = PacketId::MIN;
+            if message.qos != QoSCode::Qos0 && granted_qos != QoSCode::Qos0 {
+                match packet_identifiers.allocate() {
+                    Ok(allocated_identifier) => packet_identifier = allocated_identifier,
+                    Err(error) => {
+                        for packet_identifier in allocated {
+                            packet_identifiers.release(packet_identifier);
+                        }
+                        return Err(error.into());
+                    }
+                }
+                allocated.push(packet_identifier);
+            }
+            packets.push(message.to_packet(granted_qos, packet_identifier));
+        }
+        Ok(packets)
+    }
+
+    /// Writes every retained message to `path`. The snapshot is written next to `path` and
+    /// renamed over it, so a crash leaves either the old or the new snapshot.
+    pub(crate) fn save_snapshot(&self, path: impl AsRef<Path>) -> Result<(), RetainedStoreError> {
+        let path = path.as_ref();
+        let mut bytes = Vec::new();
+        for message in self.messages.values() {
+            bytes.extend(message.to_packet(message.qos, PacketId::MIN).encode()?);
+        }
+        let mut written = path.to_path_buf().into_os_string();
+        written.push(".tmp");
+        let mut file = File::create(&written)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(&written, path)?;
+        Ok(())
+    }
+
+    /// Reads back a snapshot written by `save_snapshot`.
+    pub(crate) fn load_snapshot(path: impl AsRef<Path>) -> Result<Self, RetainedStoreError> {
+        let bytes = fs::read(path)?;
+        let mut store = RetainedMessageStore::new();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let invalid =
+                |reason: String| RetainedStoreError::InvalidSnapshot(offset as u64, reason);
+            let length = Packet::frame_length(&bytes[offset..])
+                .map_err(|error| invalid(error.to_string()))?
+                .ok_or_else(|| invalid("packet is cut short".to_string()))?;
+            let packet = Packet::decode(&mut BytesMut::from(&bytes[offset..offset + length]))
+                .map_err(|error| invalid(error.to_string()))?;
+            if !store.handle_publish(&packet)? {
+                return Err(invalid("not a retained message".to_string()));
+            }
+            offset += length;
+        }
+        Ok(store)
+    }
+}
+
+#[cfg(test)]
+mod retained_message_store_tests {
+    use crate::protocol::codec::{Decoder, Encoder};
+    use crate::protocol::common::packet_id::PacketId;
+    use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::common::topic_filter::TopicFilter;
+    use crate::protocol::mqtt4::delivery::delivery_error::DeliveryError;
+    use crate::protocol::mqtt4::delivery::packet_id_allocator::PacketIdAllocator;
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+    use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+    use crate::protocol::mqtt4::retained::retained_message_store::RetainedMessageStore;
+    use crate::protocol::mqtt4::retained::retained_store_error::RetainedStoreError;
+    use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+        PublishQoS, PublishVariableHeader,
+    };
+    use bytes::{Bytes, BytesMut};
+    use std::fs;
+
+    fn publish(topic_name: &str, qos: QoSCode, retain: bool, message: &str) -> Packet {
+        let qos = PublishQoS::with_packet_identifier(qos, PacketId::new(9).unwrap());
+        Packet::publish(
+            false,
+            retain,
+            PublishVariableHeader::new(topic_name.to_string(), qos),
+            PublishPayload::new(Bytes::copy_from_slice(message.as_bytes())),
+        )
+    }
+
+    fn store() -> RetainedMessageStore {
+        let mut store = RetainedMessageStore::new();
+        for (topic_name, qos, message) in [
+            ("sport/tennis", QoSCode::Qos2, "t"),
+            ("sport/golf", QoSCode::Qos0, "g"),
+            ("sport/tennis/player1", QoSCode::Qos1, "p"),
+            ("$SYS/uptime", QoSCode::Qos0, "1"),
+        ] {
+            assert!(
+                store
+                    .handle_publish(&publish(topic_name, qos, true, message))
+                    .unwrap()
+            );
+        }
+        store
+    }
+
+    fn filter(topic_filter: &str) -> TopicFilter {
+        TopicFilter::new(topic_filter).unwrap()
+    }
+
+    #[test]
+    fn retained_publish_should_replace_and_empty_payload_should_clear() {
+        let mut store = store();
+        assert!(
+            !store
+                .handle_publish(&publish("sport/golf", QoSCode::Qos1, false, "new"))
+                .unwrap()
+        );
+        assert_eq!(
+            store
+                .get("sport/golf")
+                .unwrap()
+                .payload()
+                .application_message(),
+            b"g"
+        );
+
+        store
+            .handle_publish(&publish("sport/golf", QoSCode::Qos1, true, "new"))
+            .unwrap();
+        let message = store.get("sport/golf").unwrap();
+        assert_eq!(message.payload().application_message(), b"new");
+        assert_eq!(message.qos(), QoSCode::Qos1);
+        assert_eq!(store.len(), 4);
+
+        assert!(
+            store
+                .handle_publish(&publish("sport/golf", QoSCode::Qos0, true, ""))
+                .unwrap()
+        );
+        assert!(store.get("sport/golf").is_none());
+        assert!(
+            !store
+                .handle_publish(&publish("sport/golf", QoSCode::Qos0, true, ""))
+                .unwrap()
+        );
+        assert!(!store.handle_publish(&Packet::ping_req()).unwrap());
+    }
+
+    #[test]
+    fn empty_retained_publish_from_the_wire_should_clear() {
+        let mut store = store();
+        store
+            .handle_publish(&publish("a/b", QoSCode::Qos1, true, "x"))
+            .unwrap();
+        assert!(store.get("a/b").is_some());
+
+        // PUBLISH QoS 0, retain, to "a/b" with a zero byte payload
+        let wire = [0x31, 0x05, 0x00, 0x03, b'a', b'/', b'b'];
+        let packet = Packet::decode(&mut BytesMut::from(&wire[..])).unwrap();
+
+        assert!(store.handle_publish(&packet).unwrap());
+        assert!(store.get("a/b").is_none());
+        assert_eq!(store.len(), 4);
+    }
+
+    #[test]
+    fn subscription_should_receive_matching_messages_retained_and_downgraded() {
+        let store = store();
+        let topics = |topic_filter: &str| -> Vec<&str> {
+            store
+                .matching(&filter(topic_filter))
+                .into_iter()
+                .map(|message| message.topic_name())
+                .collect()
+        };
+        assert_eq!(topics("sport/+"), vec!["sport/golf", "sport/tennis"]);
+        assert_eq!(
+            topics("sport/#"),
+            vec!["sport/golf", "sport/tennis", "sport/tennis/player1"]
+        );
+        assert_eq!(topics("#").len(), 3);
+        assert_eq!(topics("$SYS/#"), vec!["$SYS/uptime"]);
+
+        let mut packet_identifiers = PacketIdAllocator::new();
+        let packets: Vec<Vec<u8>> = store
+            .publishes_for(&filter("sport/#"), QoSCode::Qos1, &mut packet_identifiers)
+            .unwrap()
+            .into_iter()
+            .map(|mut packet| packet.encode().unwrap())
+            .collect();
+        assert_eq!(
+            packets,
+            vec![
+                // QoS 0 stays QoS 0 and takes no packet identifier
+                [&[0x31, 0x0D, 0x00, 0x0A][..], b"sport/golf", b"g"].concat(),
+                // QoS 2 is downgraded to the granted QoS 1
+                [
+                    &[0x33, 0x11, 0x00, 0x0C][..],
+                    b"sport/tennis",
+                    &[0x00, 0x01, b't']
+                ]
+                .concat(),
+                [
+                    &[0x33, 0x19, 0x00, 0x14][..],
+                    b"sport/tennis/player1",
+                    &[0x00, 0x02, b'p'],
+                ]
+                .concat(),
+            ]
+        );
+        assert_eq!(packet_identifiers.in_use_len(), 2);
+    }
+
+    #[test]
+    fn exhausted_packet_identifiers_should_be_released_again() {
+        let store = store();
+        let mut packet_identifiers = PacketIdAllocator::new();
+        for value in 2..=u16::MAX {
+            packet_identifiers
+                .reserve(PacketId::new(value).unwrap())
+                .unwrap();
+        }
+        assert!(matches!(
+            store.publishes_for(&filter("sport/#"), QoSCode::Qos2, &mut packet_identifiers),
+            Err(RetainedStoreError::Delivery(
+                DeliveryError::PacketIdentifiersExhausted
+            ))
+        ));
+        assert!(!packet_identifiers.is_in_use(PacketId::MIN));
+    }
+
+    #[test]
+    fn snapshot_should_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "mqtt_codec_{}_retained.snapshot",
+            std::process::id()
+        ));
+        let store = store();
+        store.save_snapshot(&path).unwrap();
+        assert_eq!(RetainedMessageStore::load_snapshot(&path).unwrap(), store);
+
+        let mut bytes = fs::read(&path).unwrap();
+        bytes.pop();
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            RetainedMessageStore::load_snapshot(&path),
+            Err(RetainedStoreError::InvalidSnapshot(_, _))
+        ));
+
+        let mut packet = publish("a", QoSCode::Qos0, false, "x");
+        fs::write(&path, packet.encode().unwrap()).unwrap();
+        assert!(matches!(
+            RetainedMessageStore::load_snapshot(&path),
+            Err(RetainedStoreError::InvalidSnapshot(0, _))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,36 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::topic_error::TopicError;
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::delivery::delivery_error::DeliveryError;
+
+#[allow(dead_code)]
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum RetainedStoreError {
+    #[error("from io::Error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("from MqttProtocolError: {0}")]
+    Codec(#[from] MqttProtocolError),
+
+    #[error("{0}")]
+    Delivery(#[from] DeliveryError),
+
+    #[error("Invalid topic name: {0}")]
+    InvalidTopicName(TopicError),
+
+    #[error("Retained message snapshot is invalid at offset {0}: {1}")]
+    InvalidSnapshot(u64, String),
+}
@@ -0,0 +1,401 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::control_packet_type::ControlPacketType;
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::keep_alive::clock::Clock;
+use crate::protocol::mqtt4::keep_alive::keep_alive_supervisor::{
+    KeepAliveAction, KeepAliveSupervisor,
+};
+use crate::protocol::mqtt4::last_will::LastWill;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use crate::protocol::mqtt4::server_reaction::{ConnectionState, ServerReaction};
+use std::time::Instant;
+
+/// Why a server closed the network connection to a client.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloseReason {
+    /// The first packet the client sent was not a CONNECT [MQTT-3.1.0-1].
+    ConnectExpected(ControlPacketType),
+    /// The client sent a second CONNECT [MQTT-3.1.0-2].
+    SecondConnect,
+    /// The client sent a packet only a server may send [MQTT-4.8.0-1].
+    ServerOnlyPacket(ControlPacketType),
+    /// A packet could not be decoded; holds the decoding error.
+    DecodeFailed(String),
+    /// The client was silent for one and a half Keep Alive periods [MQTT-3.1.2-24].
+    KeepAliveExpired,
+    /// The network connection went away underneath the server.
+    ConnectionLost,
+    /// The client sent a DISCONNECT [MQTT-3.14.4-2].
+    ClientDisconnected,
+}
+
+#[allow(dead_code)]
+impl CloseReason {
+    /// Whether the client ended the connection on purpose. Every other close is one the Will
+    /// Message is published for [MQTT-3.1.2-8].
+    pub(crate) fn is_clean(&self) -> bool {
+        *self == CloseReason::ClientDisconnected
+    }
+}
+
+/// A decision to close the network connection, with what to send before doing so and the
+/// will to publish.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CloseDecision {
+    reason: CloseReason,
+    reaction: ServerReaction,
+    will: Option<LastWill>,
+}
+
+#[allow(dead_code)]
+impl CloseDecision {
+    fn new(reason: CloseReason, will: Option<LastWill>) -> Self {
+        CloseDecision {
+            reason,
+            reaction: ServerReaction::Close,
+            will,
+        }
+    }
+
+    pub(crate) fn reason(&self) -> &CloseReason {
+        &self.reason
+    }
+
+    pub(crate) fn reaction(&self) -> &ServerReaction {
+        &self.reaction
+    }
+
+    /// The packet to send before closing, if any.
+    pub(crate) fn packet(&self) -> Option<Packet> {
+        self.reaction.packet()
+    }
+
+    /// The will of the CONNECT, unless there was none or the client sent a DISCONNECT.
+    pub(crate) fn will(&self) -> Option<&LastWill> {
+        self.will.as_ref()
+    }
+}
+
+/// What a server does with a packet that came in from the client.
+#[allow(dead_code)]
+pub(crate) enum ServerDecision {
+    /// The packet is allowed here and is handed on to the server.
+    Process(Packet),
+    /// Close the network connection.
+    Close(CloseDecision),
+    /// The connection is closed already, so the packet is dropped.
+    Ignore,
+}
+
+/// Enforces the order a client has to send its packets in, without touching the network.
+/// Decoded packets go to `handle_packet`, failed decodes to `handle_decode_error`; `poll` is
+/// called once `next_deadline` has passed. The Keep Alive of the CONNECT is supervised from
+/// the moment it is processed, and its will is handed out with any close but the one a
+/// DISCONNECT asks for, which discards it [MQTT-3.1.2-10].
+#[allow(dead_code)]
+pub(crate) struct ServerConnection<C: Clock + Clone> {
+    clock: C,
+    state: ConnectionState,
+    keep_alive: Option<KeepAliveSupervisor<C>>,
+    will: Option<LastWill>,
+}
+
+#[allow(dead_code)]
+impl<C: Clock + Clone> ServerConnection<C> {
+    pub(crate) fn new(clock: C) -> Self {
+        ServerConnection {
+            clock,
+            state: ConnectionState::AwaitingConnect,
+            keep_alive: None,
+            will: None,
+        }
+    }
+
+    pub(crate) fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    pub(crate) fn handle_packet(&mut self, packet: Packet) -> ServerDecision {
+        if self.state == ConnectionState::Closed {
+            return ServerDecision::Ignore;
+        }
+        let packet_type = packet.fixed_header().control_packet_type().clone();
+        if !packet_type.sent_by_client() {
+            return ServerDecision::Close(self.close(CloseReason::ServerOnlyPacket(packet_type)));
+        }
+        if let Some(keep_alive) = self.keep_alive.as_mut() {
+            keep_alive.packet_received(&packet_type);
+        }
+        match (self.state, &packet) {
+            (ConnectionState::AwaitingConnect, Packet::Connect { variable, .. }) => {
+                self.keep_alive = Some(KeepAliveSupervisor::server(
+                    self.clock.clone(),
+                    variable.keep_alive(),
+                ));
+                self.will = LastWill::from_packet(&packet);
+                self.state = ConnectionState::Connected;
+                ServerDecision::Process(packet)
+            }
+            (ConnectionState::AwaitingConnect, _) => {
+                ServerDecision::Close(self.close(CloseReason::ConnectExpected(packet_type)))
+            }
+            (_, Packet::Connect { .. }) => {
+                ServerDecision::Close(self.close(CloseReason::SecondConnect))
+            }
+            (_, Packet::Disconnect { .. }) => {
+                ServerDecision::Close(self.close(CloseReason::ClientDisconnected))
+            }
+            _ => ServerDecision::Process(packet),
+        }
+    }
+
+    /// Closes the connection for a packet that failed to decode, answering a rejected CONNECT
+    /// with a CONNACK where `ServerReaction::for_error` asks for one.
+    pub(crate) fn handle_decode_error(&mut self, error: &MqttProtocolError) -> ServerDecision {
+        if self.state == ConnectionState::Closed {
+            return ServerDecision::Ignore;
+        }
+        let reaction = ServerReaction::for_error(error, self.state);
+        let mut decision = self.close(CloseReason::DecodeFailed(error.to_string()));
+        decision.reaction = reaction;
+        ServerDecision::Close(decision)
+    }
+
+    /// When `poll` may have something to do next, or `None` while there is no deadline.
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        match self.state {
+            ConnectionState::Connected => self.keep_alive.as_ref()?.next_deadline(),
+            ConnectionState::AwaitingConnect | ConnectionState::Closed => None,
+        }
+    }
+
+    /// Closes the connection once the client has been silent for too long.
+    pub(crate) fn poll(&mut self) -> Option<CloseDecision> {
+        if self.state != ConnectionState::Connected {
+            return None;
+        }
+        match self.keep_alive.as_mut()?.poll()? {
+            KeepAliveAction::Close(_) => Some(self.close(CloseReason::KeepAliveExpired)),
+            KeepAliveAction::SendPingReq => None,
+        }
+    }
+
+    /// Records that the network connection has gone, unless it was closed already.
+    pub(crate) fn connection_lost(&mut self) -> Option<CloseDecision> {
+        if self.state == ConnectionState::Closed {
+            return None;
+        }
+        Some(self.close(CloseReason::ConnectionLost))
+    }
+
+    fn close(&mut self, reason: CloseReason) -> CloseDecision {
+        self.state = ConnectionState::Closed;
+        self.keep_alive = None;
+        let will = self.will.take().filter(|_| !reason.is_clean());
+        CloseDecision::new(reason, will)
+    }
+}
+
+#[cfg(test)]
+mod server_connection_tests {
+    use crate::protocol::codec::{Decoder, Encoder};
+    use crate::protocol::common::control_packet_type::ControlPacketType;
+    use crate::protocol::common::packet_id::PacketId;
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::mqtt4::keep_alive::clock::ManualClock;
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+    use crate::protocol::mqtt4::server_connection::{
+        CloseDecision, CloseReason, ServerConnection, ServerDecision,
+    };
+    use crate::protocol::mqtt4::server_reaction::{ConnectionState, ServerReaction};
+    use bytes::BytesMut;
+    use std::time::Duration;
+
+    // CONNECT from client "c" with CleanSession 1 and a Keep Alive of 10 seconds
+    const CONNECT: [u8; 15] = [
+        0x10, 0x0D, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x02, 0x00, 0x0A, 0x00, 0x01, b'c',
+    ];
+    const PUBLISH: [u8; 6] = [0x30, 0x04, 0x00, 0x01, b'a', b'x'];
+
+    fn decode(bytes: &[u8]) -> Packet {
+        Packet::decode(&mut BytesMut::from(bytes)).unwrap()
+    }
+
+    fn closed(decision: ServerDecision) -> CloseDecision {
+        match decision {
+            ServerDecision::Close(decision) => decision,
+            ServerDecision::Process(_) => panic!("packet was processed"),
+            ServerDecision::Ignore => panic!("packet was ignored"),
+        }
+    }
+
+    fn connected(clock: &ManualClock) -> ServerConnection<ManualClock> {
+        let mut server = ServerConnection::new(clock.clone());
+        assert!(matches!(
+            server.handle_packet(decode(&CONNECT)),
+            ServerDecision::Process(Packet::Connect { .. })
+        ));
+        assert_eq!(server.state(), ConnectionState::Connected);
+        server
+    }
+
+    #[test]
+    fn first_packet_should_have_to_be_a_connect() {
+        let mut server = ServerConnection::new(ManualClock::new());
+        let decision = closed(server.handle_packet(decode(&PUBLISH)));
+        assert_eq!(
+            decision.reason(),
+            &CloseReason::ConnectExpected(ControlPacketType::Publish)
+        );
+        assert!(decision.packet().is_none());
+        assert_eq!(server.state(), ConnectionState::Closed);
+
+        // nothing is processed once the connection is closed, not even a CONNECT
+        assert!(matches!(
+            server.handle_packet(decode(&CONNECT)),
+            ServerDecision::Ignore
+        ));
+    }
+
+    #[test]
+    fn second_connect_should_close_the_connection() {
+        let mut server = connected(&ManualClock::new());
+        assert!(matches!(
+            server.handle_packet(decode(&PUBLISH)),
+            ServerDecision::Process(Packet::Publish { .. })
+        ));
+        let decision = closed(server.handle_packet(decode(&CONNECT)));
+        assert_eq!(decision.reason(), &CloseReason::SecondConnect);
+        assert!(!decision.reason().is_clean());
+    }
+
+    #[test]
+    fn packets_only_a_server_sends_should_close_the_connection() {
+        for (bytes, packet_type) in [
+            (&[0x20, 0x02, 0x00, 0x00][..], ControlPacketType::ConnAck),
+            (
+                &[0x90, 0x03, 0x00, 0x01, 0x00][..],
+                ControlPacketType::SubAck,
+            ),
+            (&[0xB0, 0x02, 0x00, 0x01][..], ControlPacketType::UnsubAck),
+            (&[0xD0, 0x00][..], ControlPacketType::PingResp),
+        ] {
+            let mut server = connected(&ManualClock::new());
+            let decision = closed(server.handle_packet(decode(bytes)));
+            assert_eq!(
+                decision.reason(),
+                &CloseReason::ServerOnlyPacket(packet_type.clone())
+            );
+
+            let mut server = ServerConnection::new(ManualClock::new());
+            let decision = closed(server.handle_packet(decode(bytes)));
+            assert_eq!(
+                decision.reason(),
+                &CloseReason::ServerOnlyPacket(packet_type)
+            );
+        }
+    }
+
+    #[test]
+    fn disconnect_should_stop_processing() {
+        let mut server = connected(&ManualClock::new());
+        let decision = closed(server.handle_packet(decode(&[0xE0, 0x00])));
+        assert_eq!(decision.reason(), &CloseReason::ClientDisconnected);
+        assert!(decision.reason().is_clean());
+        assert!(decision.packet().is_none());
+
+        assert!(matches!(
+            server.handle_packet(decode(&PUBLISH)),
+            ServerDecision::Ignore
+        ));
+        assert!(server.connection_lost().is_none());
+        assert!(server.next_deadline().is_none());
+    }
+
+    #[test]
+    fn decode_errors_should_follow_the_server_reaction() {
+        let mut server = ServerConnection::new(ManualClock::new());
+        let decision =
+            closed(server.handle_decode_error(&MqttProtocolError::ProtocolLevelNoSupport(6)));
+        assert!(matches!(decision.reason(), CloseReason::DecodeFailed(_)));
+        assert_eq!(
+            decision.packet().unwrap().encode().unwrap(),
+            vec![0x20, 0x02, 0x00, 0x01]
+        );
+
+        let mut server = connected(&ManualClock::new());
+        let decision = closed(server.handle_decode_error(&MqttProtocolError::MalformedPacket));
+        assert_eq!(decision.reaction(), &ServerReaction::Close);
+        assert!(matches!(
+            server.handle_decode_error(&MqttProtocolError::MalformedPacket),
+            ServerDecision::Ignore
+        ));
+    }
+
+    #[test]
+    fn silent_client_should_be_closed_after_one_and_a_half_keep_alive_periods() {
+        let clock = ManualClock::new();
+        let mut server = connected(&clock);
+
+        clock.advance(Duration::from_secs(14));
+        assert!(server.poll().is_none());
+        assert!(matches!(
+            server.handle_packet(decode(&[0xC0, 0x00])),
+            ServerDecision::Process(Packet::PingReq { .. })
+        ));
+
+        clock.advance(Duration::from_secs(14));
+        assert!(server.poll().is_none());
+        clock.advance(Duration::from_secs(1));
+        let decision = server.poll().unwrap();
+        assert_eq!(decision.reason(), &CloseReason::KeepAliveExpired);
+        assert!(!decision.reason().is_clean());
+        assert!(server.poll().is_none());
+        assert!(server.connection_lost().is_none());
+    }
+
+    #[test]
+    fn will_should_be_handed_out_unless_the_client_disconnects() {
+        // CONNECT from client "c" with a QoS 1 retained will "bye" on topic "w"
+        let connect = [
+            0x10, 0x15, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x2E, 0x00, 0x0A, 0x00, 0x01,
+            b'c', 0x00, 0x01, b'w', 0x00, 0x03, b'b', b'y', b'e',
+        ];
+        let mut server = ServerConnection::new(ManualClock::new());
+        server.handle_packet(decode(&connect));
+        let decision = server.connection_lost().unwrap();
+        let will = decision.will().unwrap();
+        assert_eq!(
+            will.to_packet(PacketId::MIN).encode().unwrap(),
+            vec![0x33, 0x08, 0x00, 0x01, b'w', 0x00, 0x01, b'b', b'y', b'e']
+        );
+
+        let mut server = ServerConnection::new(ManualClock::new());
+        server.handle_packet(decode(&connect));
+        let decision = closed(server.handle_decode_error(&MqttProtocolError::MalformedPacket));
+        assert!(decision.will().is_some());
+
+        let mut server = ServerConnection::new(ManualClock::new());
+        server.handle_packet(decode(&connect));
+        let decision = closed(server.handle_packet(decode(&[0xE0, 0x00])));
+        assert!(decision.will().is_none());
+
+        let mut server = connected(&ManualClock::new());
+        assert!(server.connection_lost().unwrap().will().is_none());
+    }
+}
@@ -0,0 +1,170 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::return_code::ReturnCode;
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+
+/// How far a connection has got when one of its packets fails.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The server has not answered a CONNECT yet, so the failing packet is the first one.
+    AwaitingConnect,
+    /// The server has sent a CONNACK.
+    Connected,
+    /// The connection has been closed and no further packets are processed.
+    Closed,
+}
+
+/// What a server has to do about a packet it could not accept.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ServerReaction {
+    /// Answer the CONNECT with a CONNACK carrying this return code, then close the network
+    /// connection.
+    ConnAckThenClose(ReturnCode),
+    /// Close the network connection without sending anything.
+    Close,
+}
+
+#[allow(dead_code)]
+impl ServerReaction {
+    /// The reaction the specification asks for when decoding fails with `error` in `state`.
+    /// Only an unsupported Protocol Level [MQTT-3.1.2-2] and a rejected Client Identifier
+    /// [MQTT-3.1.3-9] are answered, and only while the CONNECT is unanswered; everything else
+    /// is a protocol violation that closes the connection [MQTT-4.8.0-1].
+    pub(crate) fn for_error(error: &MqttProtocolError, state: ConnectionState) -> ServerReaction {
+        if state != ConnectionState::AwaitingConnect {
+            return ServerReaction::Close;
+        }
+        match error {
+            MqttProtocolError::ProtocolLevelNoSupport(_) => {
+                ServerReaction::ConnAckThenClose(ReturnCode::UnacceptableProtocolVersion)
+            }
+            MqttProtocolError::InvalidClientId => {
+                ServerReaction::ConnAckThenClose(ReturnCode::IdentifierRejected)
+            }
+            // a server may answer a wrong Protocol Name by just closing [MQTT-3.1.2-1]
+            MqttProtocolError::ProtocolNameError(_)
+            | MqttProtocolError::MalformedPacket
+            | MqttProtocolError::InvalidPacketType
+            | MqttProtocolError::InvalidFixedHeaderFlags
+            | MqttProtocolError::QoSLevelNotSupported(_)
+            | MqttProtocolError::MalformedRemainingLength
+            | MqttProtocolError::PacketTooShort
+            | MqttProtocolError::CodeError(_)
+            | MqttProtocolError::InvalidQoS(_)
+            | MqttProtocolError::UnsupportedPacketType
+            | MqttProtocolError::ReservedReturnCode
+            | MqttProtocolError::InvalidTopicName(_)
+            | MqttProtocolError::InvalidTopicFilter(_)
+            | MqttProtocolError::ConnectReservedFlagSet
+            | MqttProtocolError::InvalidWillQoS(_)
+            | MqttProtocolError::WillQoSWithoutWillFlag(_)
+            | MqttProtocolError::WillRetainWithoutWillFlag
+            | MqttProtocolError::PasswordWithoutUsername
+            | MqttProtocolError::ZeroPacketIdentifier
+            | MqttProtocolError::MissingPacketIdentifier(_)
+            | MqttProtocolError::UnexpectedPacketIdentifier
+            | MqttProtocolError::PublishQoSMismatch(_, _)
+            | MqttProtocolError::StringTooLong(_, _)
+            | MqttProtocolError::PacketTooLarge(_, _) => ServerReaction::Close,
+        }
+    }
+
+    /// The packet to send before closing, if any. A rejecting CONNACK never has Session
+    /// Present set [MQTT-3.2.2-4].
+    pub(crate) fn packet(&self) -> Option<Packet> {
+        match self {
+            ServerReaction::ConnAckThenClose(return_code) => {
+                Some(Packet::conn_ack(false, return_code.clone()))
+            }
+            ServerReaction::Close => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod server_reaction_tests {
+    use crate::protocol::codec::Encoder;
+    use crate::protocol::common::return_code::ReturnCode;
+    use crate::protocol::common::topic_error::TopicError;
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::mqtt4::decoder_config::DecoderConfig;
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+    use crate::protocol::mqtt4::server_reaction::{ConnectionState, ServerReaction};
+    use bytes::BytesMut;
+
+    #[test]
+    fn unsupported_protocol_level_should_be_answered_with_connack_1() {
+        let reaction = ServerReaction::for_error(
+            &MqttProtocolError::ProtocolLevelNoSupport(6),
+            ConnectionState::AwaitingConnect,
+        );
+        assert_eq!(
+            reaction,
+            ServerReaction::ConnAckThenClose(ReturnCode::UnacceptableProtocolVersion)
+        );
+        assert_eq!(
+            reaction.packet().unwrap().encode().unwrap(),
+            vec![0x20, 0x02, 0x00, 0x01]
+        );
+    }
+
+    #[test]
+    fn rejected_client_id_should_be_answered_with_connack_2() {
+        // CONNECT with an empty client id and CleanSession 0
+        let connect = [
+            0x10, 0x0C, 0x00, 0x04, b'M', b'Q', b'T', b'T', 0x04, 0x00, 0x00, 0x3C, 0x00, 0x00,
+        ];
+        let error =
+            Packet::decode_with_config(&mut BytesMut::from(&connect[..]), &DecoderConfig::strict())
+                .err()
+                .unwrap();
+
+        let reaction = ServerReaction::for_error(error.error(), ConnectionState::AwaitingConnect);
+        assert_eq!(
+            reaction.packet().unwrap().encode().unwrap(),
+            vec![0x20, 0x02, 0x00, 0x02]
+        );
+    }
+
+    #[test]
+    fn other_errors_should_close_without_a_packet() {
+        for error in [
+            MqttProtocolError::ProtocolNameError("MQIsdp".to_string()),
+            MqttProtocolError::ConnectReservedFlagSet,
+            MqttProtocolError::InvalidTopicName(TopicError::Empty),
+            MqttProtocolError::PacketTooLarge(2048, 1024),
+        ] {
+            let reaction = ServerReaction::for_error(&error, ConnectionState::AwaitingConnect);
+            assert_eq!(reaction, ServerReaction::Close);
+            assert!(reaction.packet().is_none());
+        }
+    }
+
+    #[test]
+    fn errors_after_connack_should_only_close() {
+        for error in [
+            MqttProtocolError::ProtocolLevelNoSupport(6),
+            MqttProtocolError::InvalidClientId,
+        ] {
+            assert_eq!(
+                ServerReaction::for_error(&error, ConnectionState::Connected),
+                ServerReaction::Close
+            );
+        }
+    }
+}
@@ -0,0 +1,523 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::codec::{Decoder, Encoder};
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::delivery::inflight_publish::InflightPublish;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
+use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use crate::protocol::mqtt4::session::memory_session_store::apply_change;
+use crate::protocol::mqtt4::session::session_store::SessionStore;
+use crate::protocol::mqtt4::session::session_store_error::SessionStoreError;
+use crate::protocol::mqtt4::session::stored_session::{
+    QueuedMessage, SessionChange, StoredSession,
+};
+use bytes::BytesMut;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+// record kinds; every kind but DEQUEUE, DISCARD and CREATE is followed by the packet it names
+const SUBSCRIBE: u8 = 1;
+const UNSUBSCRIBE: u8 = 2;
+const PUBLISH: u8 = 3;
+const PUBREL: u8 = 4;
+const PUBACK: u8 = 5;
+const PUBCOMP: u8 = 6;
+const ENQUEUE: u8 = 7;
+const DEQUEUE: u8 = 8;
+const DISCARD: u8 = 9;
+const RECEIVED: u8 = 10;
+const RELEASED: u8 = 11;
+const CREATE: u8 = 12;
+
+/// A `SessionStore` that survives restarts by appending every change to a log file. A record
+/// is the kind of change in one byte, the client identifier as a length-prefixed UTF-8 string
+/// and, for every change but `Create`, `Dequeue` and `Discard`, the packet recording it in
+/// MQTT wire format. A received QoS 2 identifier is recorded as the PUBREC answering it and its
+/// release as the PUBREL. Packet identifiers of SUBSCRIBE, UNSUBSCRIBE and queued PUBLISH
+/// records carry no meaning and are always 1.
+///
+/// `apply` returns once its record is on disk. A crash can cut the last record short; `open`
+/// drops such a record. `compact` replaces the log with the records that rebuild the current
+/// sessions.
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) struct FileSessionStore {
+    path: PathBuf,
+    file: File,
+    sessions: HashMap<String, StoredSession>,
+}
+
+#[allow(dead_code)]
+impl FileSessionStore {
+    /// Replays the log at `path`, creating it when it does not exist.
+    pub(crate) fn open(path: impl AsRef<Path>) -> Result<Self, SessionStoreError> {
+        let path = path.as_ref().to_path_buf();
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error.into()),
+        };
+        let mut sessions = HashMap::new();
+        let mut offset = 0;
+        while let Some((length, client_id, change)) = decode_record(&bytes[offset..])
+            .map_err(|reason| SessionStoreError::CorruptRecord(offset as u64, reason))?
+        {
+            apply_change(&mut sessions, &client_id, change).map_err(|error| {
+                SessionStoreError::CorruptRecord(offset as u64, error.to_string())
+            })?;
+            offset += length;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        // drop a record cut short by a crash, so the next one is appended to a whole record
+        file.set_len(offset as u64)?;
+        Ok(FileSessionStore {
+            path,
+            file,
+            sessions,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rewrites the log with just the records that rebuild the current sessions. The new log
+    /// is written next to the old one and renamed over it, so a crash leaves one of the two;
+    /// the directory is synced after the rename so the new log is the one that stays.
+    pub(crate) fn compact(&mut self) -> Result<(), SessionStoreError> {
+        let mut bytes = Vec::new();
+        for client_id in self.client_ids() {
+            for change in self.sessions[&client_id].changes() {
+                bytes.extend(encode_record(&client_id, &change)?);
+            }
+        }
+        let mut compacted = self.path.clone().into_os_string();
+        compacted.push(".compact");
+        let mut file = File::create(&compacted)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(&compacted, &self.path)?;
+        sync_directory(&self.path)?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        Ok(())
+    }
+}
+
+/// Syncs the directory holding `path`, which makes a rename into it durable.
+#[cfg(unix)]
+fn sync_directory(path: &Path) -> Result<(), SessionStoreError> {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(directory)?.sync_all()?;
+    Ok(())
+}
+
+/// Directories cannot be opened for syncing here; the rename is as durable as it gets.
+#[cfg(not(unix))]
+fn sync_directory(_path: &Path) -> Result<(), SessionStoreError> {
+    Ok(())
+}
+
+impl SessionStore for FileSessionStore {
+    fn session(&self, client_id: &str) -> Option<&StoredSession> {
+        self.sessions.get(client_id)
+    }
+
+    fn client_ids(&self) -> Vec<String> {
+        let mut client_ids: Vec<String> = self.sessions.keys().cloned().collect();
+        client_ids.sort();
+        client_ids
+    }
+
+    /// Records the change in memory before appending it to the log and syncing the log to
+    /// disk, so a change reported as applied survives a crash of the host. After an I/O error
+    /// the store is ahead of its log and should be opened again.
+    fn apply(&mut self, client_id: &str, change: SessionChange) -> Result<(), SessionStoreError> {
+        let record = encode_record(client_id, &change)?;
+        apply_change(&mut self.sessions, client_id, change)?;
+        self.file.write_all(&record)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+fn encode_record(client_id: &str, change: &SessionChange) -> Result<Vec<u8>, SessionStoreError> {
+    let (kind, packet) = match change {
+        SessionChange::Create => (CREATE, None),
+        SessionChange::Subscribe(topic_filter, qos) => (
+            SUBSCRIBE,
+            Some(Packet::subscribe(
+                PacketId::MIN,
+                vec![(topic_filter.clone(), *qos)],
+            )),
+        ),
+        SessionChange::Unsubscribe(topic_filter) => (
+            UNSUBSCRIBE,
+            Some(Packet::unsubscribe(
+                PacketId::MIN,
+                vec![topic_filter.clone()],
+            )),
+        ),
+        SessionChange::Publish(message) => (PUBLISH, Some(message.to_packet(false))),
+        SessionChange::PubRel(packet_identifier) => {
+            (PUBREL, Some(Packet::pub_rel(*packet_identifier)))
+        }
+        SessionChange::PubAck(packet_identifier) => {
+            (PUBACK, Some(Packet::pub_ack(*packet_identifier)))
+        }
+        SessionChange::PubComp(packet_identifier) => {
+            (PUBCOMP, Some(Packet::pub_comp(*packet_identifier)))
+        }
+        SessionChange::Received(packet_identifier) => {
+            (RECEIVED, Some(Packet::pub_rec(*packet_identifier)))
+        }
+        SessionChange::Released(packet_identifier) => {
+            (RELEASED, Some(Packet::pub_rel(*packet_identifier)))
+        }
+        SessionChange::Enqueue(message) => (ENQUEUE, Some(message.to_packet(PacketId::MIN))),
+        SessionChange::Dequeue => (DEQUEUE, None),
+        SessionChange::Discard => (DISCARD, None),
+    };
+    let client_id_length = u16::try_from(client_id.len())
+        .map_err(|_| MqttProtocolError::StringTooLong(client_id.len(), u16::MAX as usize))?;
+    let mut record = vec![kind];
+    record.extend(client_id_length.to_be_bytes());
+    record.extend(client_id.as_bytes());
+    if let Some(mut packet) = packet {
+        record.extend(packet.encode()?);
+    }
+    Ok(record)
+}
+
+/// The length, client identifier and change of the record at the start of `bytes`, or `None`
+/// when `bytes` ends before the record does.
+fn decode_record(bytes: &[u8]) -> Result<Option<(usize, String, SessionChange)>, String> {
+    let Some(&kind) = bytes.first() else {
+        return Ok(None);
+    };
+    if !(SUBSCRIBE..=CREATE).contains(&kind) {
+        return Err(format!("unknown record kind {kind}"));
+    }
+    let Some(length_bytes) = bytes.get(1..3) else {
+        return Ok(None);
+    };
+    let client_id_end = 3 + usize::from(u16::from_be_bytes([length_bytes[0], length_bytes[1]]));
+    let Some(client_id) = bytes.get(3..client_id_end) else {
+        return Ok(None);
+    };
+    let client_id = String::from_utf8(client_id.to_vec())
+        .map_err(|_| "client identifier is not UTF-8".to_string())?;
+    match kind {
+        DEQUEUE => return Ok(Some((client_id_end, client_id, SessionChange::Dequeue))),
+        DISCARD => return Ok(Some((client_id_end, client_id, SessionChange::Discard))),
+        CREATE => return Ok(Some((client_id_end, client_id, SessionChange::Create))),
+        _ => {}
+    }
+    let packet_bytes = &bytes[client_id_end..];
+    let Some(packet_length) = Packet::frame_length(packet_bytes).map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    let packet = Packet::decode(&mut BytesMut::from(&packet_bytes[..packet_length]))
+        .map_err(|e| e.to_string())?;
+    let change = change_for(kind, packet)?;
+    Ok(Some((client_id_end + packet_length, client_id, change)))
+}
+
+fn change_for(kind: u8, packet: Packet) -> Result<SessionChange, String> {
+    match (kind, packet) {
+        (SUBSCRIBE, Packet::Subscribe { payload, .. }) => {
+            match payload.subscription_and_qos_tuples() {
+                [(topic_filter, qos)] => Ok(SessionChange::Subscribe(topic_filter.clone(), *qos)),
+                _ => Err("SUBSCRIBE record has to hold one topic filter".to_string()),
+            }
+        }
+        (UNSUBSCRIBE, Packet::Unsubscribe { payload, .. }) => match payload.topics() {
+            [topic_filter] => Ok(SessionChange::Unsubscribe(topic_filter.clone())),
+            _ => Err("UNSUBSCRIBE record has to hold one topic filter".to_string()),
+        },
+        (
+            PUBLISH,
+            Packet::Publish {
+                fixed,
+                variable,
+                payload,
+            },
+        ) => InflightPublish::new(retain(&fixed), variable, payload)
+            .map(SessionChange::Publish)
+            .ok_or_else(|| "PUBLISH record has QoS 0".to_string()),
+        (PUBREL, Packet::PubRel { variable, .. }) => {
+            Ok(SessionChange::PubRel(variable.packet_identifier()))
+        }
+        (PUBACK, Packet::PubAck { variable, .. }) => {
+            Ok(SessionChange::PubAck(variable.packet_identifier()))
+        }
+        (PUBCOMP, Packet::PubComp { variable, .. }) => {
+            Ok(SessionChange::PubComp(variable.packet_identifier()))
+        }
+        (RECEIVED, Packet::PubRec { variable, .. }) => {
+            Ok(SessionChange::Received(variable.packet_identifier()))
+        }
+        (RELEASED, Packet::PubRel { variable, .. }) => {
+            Ok(SessionChange::Released(variable.packet_identifier()))
+        }
+        (
+            ENQUEUE,
+            Packet::Publish {
+                fixed,
+                variable,
+                payload,
+            },
+        ) => Ok(SessionChange::Enqueue(QueuedMessage::new(
+            retain(&fixed),
+            variable.topic_name().to_string(),
+            variable.qos(),
+            payload,
+        ))),
+        (kind, packet) => Err(format!(
+            "record kind {kind} cannot hold a {} packet",
+            packet.fixed_header().control_packet_type().name()
+        )),
+    }
+}
+
+fn retain(fixed: &FixedHeader) -> bool {
+    match fixed.fixed_header_reserved_flags() {
+        FixedHeaderFlags::Publish { retain, .. } => *retain,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod file_session_store_tests {
+    use crate::protocol::common::packet_id::PacketId;
+    use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::mqtt4::delivery::inflight_publish::InflightPublish;
+    use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+    use crate::protocol::mqtt4::session::file_session_store::FileSessionStore;
+    use crate::protocol::mqtt4::session::memory_session_store::MemorySessionStore;
+    use crate::protocol::mqtt4::session::session_store::SessionStore;
+    use crate::protocol::mqtt4::session::session_store_error::SessionStoreError;
+    use crate::protocol::mqtt4::session::stored_session::{
+        QueuedMessage, SessionChange, StoredSession,
+    };
+    use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+        PublishQoS, PublishVariableHeader,
+    };
+    use bytes::Bytes;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn log_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("mqtt_codec_{}_{name}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn sessions(store: &impl SessionStore) -> Vec<(String, StoredSession)> {
+        store
+            .client_ids()
+            .into_iter()
+            .map(|client_id| {
+                let session = store.session(&client_id).unwrap().clone();
+                (client_id, session)
+            })
+            .collect()
+    }
+
+    fn inflight(value: u16, qos: QoSCode, retain: bool) -> SessionChange {
+        let qos = PublishQoS::new(qos, PacketId::new(value)).unwrap();
+        let variable = PublishVariableHeader::new("a/b".to_string(), qos);
+        let payload = PublishPayload::new(Bytes::from(format!("message {value}")));
+        SessionChange::Publish(InflightPublish::new(retain, variable, payload).unwrap())
+    }
+
+    fn enqueue(topic_name: &str, qos: QoSCode) -> SessionChange {
+        let payload = PublishPayload::new(Bytes::copy_from_slice(topic_name.as_bytes()));
+        SessionChange::Enqueue(QueuedMessage::new(
+            true,
+            topic_name.to_string(),
+            qos,
+            payload,
+        ))
+    }
+
+    fn changes() -> Vec<(&'static str, SessionChange)> {
+        let packet_id = |value| PacketId::new(value).unwrap();
+        vec![
+            ("a", SessionChange::Create),
+            ("e", SessionChange::Create),
+            (
+                "a",
+                SessionChange::Subscribe("a/#".to_string(), QoSCode::Qos1),
+            ),
+            (
+                "b",
+                SessionChange::Subscribe("+/b".to_string(), QoSCode::Qos2),
+            ),
+            ("a", inflight(1, QoSCode::Qos2, true)),
+            ("a", inflight(2, QoSCode::Qos1, false)),
+            ("b", enqueue("a/b", QoSCode::Qos2)),
+            ("a", SessionChange::PubRel(packet_id(1))),
+            ("b", enqueue("c/b", QoSCode::Qos0)),
+            (
+                "a",
+                SessionChange::Subscribe("a/#".to_string(), QoSCode::Qos0),
+            ),
+            ("b", SessionChange::Received(packet_id(7))),
+            ("a", SessionChange::PubAck(packet_id(2))),
+            ("b", SessionChange::Received(packet_id(9))),
+            ("b", SessionChange::Dequeue),
+            ("a", inflight(3, QoSCode::Qos1, false)),
+            ("b", SessionChange::Unsubscribe("+/b".to_string())),
+            ("a", SessionChange::PubComp(packet_id(1))),
+            ("b", SessionChange::Released(packet_id(7))),
+            (
+                "c",
+                SessionChange::Subscribe("#".to_string(), QoSCode::Qos0),
+            ),
+            ("c", SessionChange::Discard),
+        ]
+    }
+
+    #[test]
+    fn reopening_should_replay_the_log() {
+        let path = log_path("replay");
+        let mut store = FileSessionStore::open(&path).unwrap();
+        let mut expected = MemorySessionStore::new();
+        for (client_id, change) in changes() {
+            store.apply(client_id, change.clone()).unwrap();
+            expected.apply(client_id, change).unwrap();
+        }
+        assert!(matches!(
+            store.apply("a", SessionChange::PubComp(PacketId::MIN)),
+            Err(SessionStoreError::UnexpectedAck("PUBCOMP", _))
+        ));
+        drop(store);
+
+        let store = FileSessionStore::open(&path).unwrap();
+        assert_eq!(sessions(&store), sessions(&expected));
+        assert_eq!(store.client_ids(), vec!["a", "b", "e"]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn log_truncated_at_any_point_should_recover_the_last_whole_record() {
+        let path = log_path("truncated");
+        let mut store = FileSessionStore::open(&path).unwrap();
+        let mut expected = MemorySessionStore::new();
+        // the sessions after each whole record, keyed by the log length
+        let mut checkpoints = vec![(0, sessions(&expected))];
+        for (client_id, change) in changes() {
+            store.apply(client_id, change.clone()).unwrap();
+            expected.apply(client_id, change).unwrap();
+            checkpoints.push((fs::metadata(&path).unwrap().len(), sessions(&expected)));
+        }
+        drop(store);
+        let log = fs::read(&path).unwrap();
+
+        let crashed = log_path("crashed");
+        for cut in 0..=log.len() as u64 {
+            fs::write(&crashed, &log[..cut as usize]).unwrap();
+            let mut store = FileSessionStore::open(&crashed).unwrap();
+            let (length, recovered) = checkpoints
+                .iter()
+                .rev()
+                .find(|(length, _)| *length <= cut)
+                .unwrap();
+            assert_eq!(&sessions(&store), recovered, "log cut at {cut}");
+            assert_eq!(fs::metadata(&crashed).unwrap().len(), *length);
+
+            // new records follow the recovered ones
+            store
+                .apply(
+                    "d",
+                    SessionChange::Subscribe("d".to_string(), QoSCode::Qos1),
+                )
+                .unwrap();
+            let expected = sessions(&store);
+            drop(store);
+            assert_eq!(
+                sessions(&FileSessionStore::open(&crashed).unwrap()),
+                expected
+            );
+        }
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&crashed).unwrap();
+    }
+
+    #[test]
+    fn compaction_should_keep_the_sessions_in_a_shorter_log() {
+        let path = log_path("compaction");
+        let mut store = FileSessionStore::open(&path).unwrap();
+        for (client_id, change) in changes() {
+            store.apply(client_id, change).unwrap();
+        }
+        let before = sessions(&store);
+        let length = fs::metadata(&path).unwrap().len();
+
+        store.compact().unwrap();
+        assert!(fs::metadata(&path).unwrap().len() < length);
+        store.apply("b", SessionChange::Dequeue).unwrap();
+        store
+            .apply("b", SessionChange::Released(PacketId::new(9).unwrap()))
+            .unwrap();
+        drop(store);
+
+        let store = FileSessionStore::open(&path).unwrap();
+        let mut after = before.clone();
+        after[1].1 = StoredSession::default();
+        assert_eq!(sessions(&store), after);
+        assert!(store.session("e").unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn corrupt_record_should_fail_to_open() {
+        let path = log_path("corrupt");
+        let mut store = FileSessionStore::open(&path).unwrap();
+        store
+            .apply(
+                "a",
+                SessionChange::Subscribe("a".to_string(), QoSCode::Qos1),
+            )
+            .unwrap();
+        drop(store);
+        let mut log = fs::read(&path).unwrap();
+        let length = log.len();
+        // a whole PUBACK record for a message that was never sent
+        log.extend([5, 0x00, 0x01, b'a', 0x40, 0x02, 0x00, 0x09]);
+        fs::write(&path, &log).unwrap();
+        assert!(matches!(
+            FileSessionStore::open(&path),
+            Err(SessionStoreError::CorruptRecord(offset, _)) if offset == length as u64
+        ));
+
+        log.truncate(length);
+        log.push(0xFF);
+        fs::write(&path, &log).unwrap();
+        assert!(matches!(
+            FileSessionStore::open(&path),
+            Err(SessionStoreError::CorruptRecord(_, _))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+}
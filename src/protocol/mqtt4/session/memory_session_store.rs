@@ -0,0 +1,131 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::mqtt4::session::session_store::SessionStore;
+use crate::protocol::mqtt4::session::session_store_error::SessionStoreError;
+use crate::protocol::mqtt4::session::stored_session::{SessionChange, StoredSession};
+use std::collections::HashMap;
+
+/// A `SessionStore` that lives as long as the process does.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MemorySessionStore {
+    sessions: HashMap<String, StoredSession>,
+}
+
+#[allow(dead_code)]
+impl MemorySessionStore {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn session(&self, client_id: &str) -> Option<&StoredSession> {
+        self.sessions.get(client_id)
+    }
+
+    fn client_ids(&self) -> Vec<String> {
+        let mut client_ids: Vec<String> = self.sessions.keys().cloned().collect();
+        client_ids.sort();
+        client_ids
+    }
+
+    fn apply(&mut self, client_id: &str, change: SessionChange) -> Result<(), SessionStoreError> {
+        apply_change(&mut self.sessions, client_id, change)
+    }
+}
+
+/// Applies `change` to the session of `client_id` in `sessions`, dropping the session on
+/// `Discard`; shared by the stores, which all keep their sessions in memory.
+pub(crate) fn apply_change(
+    sessions: &mut HashMap<String, StoredSession>,
+    client_id: &str,
+    change: SessionChange,
+) -> Result<(), SessionStoreError> {
+    if change == SessionChange::Discard {
+        sessions.remove(client_id);
+        return Ok(());
+    }
+    match sessions.get_mut(client_id) {
+        Some(session) => session.apply(change),
+        None => {
+            let mut session = StoredSession::default();
+            session.apply(change)?;
+            sessions.insert(client_id.to_string(), session);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod memory_session_store_tests {
+    use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::mqtt4::session::memory_session_store::MemorySessionStore;
+    use crate::protocol::mqtt4::session::session_store::SessionStore;
+    use crate::protocol::mqtt4::session::stored_session::SessionChange;
+
+    #[test]
+    fn sessions_should_be_kept_per_client_until_discarded() {
+        let mut store = MemorySessionStore::new();
+        for client_id in ["b", "a"] {
+            store
+                .apply(
+                    client_id,
+                    SessionChange::Subscribe("t".to_string(), QoSCode::Qos1),
+                )
+                .unwrap();
+        }
+        assert_eq!(store.client_ids(), vec!["a".to_string(), "b".to_string()]);
+
+        // a change that fails leaves no session behind
+        assert!(store.apply("c", SessionChange::Dequeue).is_err());
+        assert!(store.session("c").is_none());
+
+        store.apply("a", SessionChange::Discard).unwrap();
+        assert!(store.session("a").is_none());
+        assert_eq!(
+            store.session("b").unwrap().subscriptions()["t"],
+            QoSCode::Qos1
+        );
+    }
+
+    #[test]
+    fn sessions_with_nothing_in_them_should_be_kept() {
+        let mut store = MemorySessionStore::new();
+        store.apply("a", SessionChange::Create).unwrap();
+        assert!(store.session("a").unwrap().is_empty());
+
+        store
+            .apply(
+                "b",
+                SessionChange::Subscribe("t".to_string(), QoSCode::Qos1),
+            )
+            .unwrap();
+        store
+            .apply("b", SessionChange::Unsubscribe("t".to_string()))
+            .unwrap();
+        assert!(store.session("b").unwrap().is_empty());
+        // a resumed session keeps what it holds
+        store
+            .apply(
+                "b",
+                SessionChange::Subscribe("t".to_string(), QoSCode::Qos1),
+            )
+            .unwrap();
+        store.apply("b", SessionChange::Create).unwrap();
+        assert_eq!(store.session("b").unwrap().subscriptions().len(), 1);
+        assert_eq!(store.client_ids(), vec!["a".to_string(), "b".to_string()]);
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::mqtt4::session::session_store_error::SessionStoreError;
+use crate::protocol::mqtt4::session::stored_session::{SessionChange, StoredSession};
+
+/// Where the sessions of CleanSession 0 clients are kept between connections [MQTT-3.1.2-4].
+/// Every change is validated against the stored session before it is recorded, so a change
+/// that fails leaves the store as it was.
+#[allow(dead_code)]
+pub(crate) trait SessionStore {
+    /// The session stored for `client_id`, if there is one.
+    fn session(&self, client_id: &str) -> Option<&StoredSession>;
+
+    /// The client identifiers with a stored session, sorted.
+    fn client_ids(&self) -> Vec<String>;
+
+    /// Records `change` for `client_id`, creating the session first if there is none. Only
+    /// `SessionChange::Discard` removes a session; one with nothing in it is still present.
+    fn apply(&mut self, client_id: &str, change: SessionChange) -> Result<(), SessionStoreError>;
+}
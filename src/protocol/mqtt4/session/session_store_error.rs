@@ -0,0 +1,45 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::common::topic_error::TopicError;
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+
+#[allow(dead_code)]
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum SessionStoreError {
+    #[error("from io::Error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("from MqttProtocolError: {0}")]
+    Codec(#[from] MqttProtocolError),
+
+    #[error("Invalid topic filter: {0}")]
+    InvalidTopicFilter(TopicError),
+
+    #[error("Invalid topic name: {0}")]
+    InvalidTopicName(TopicError),
+
+    #[error("Packet identifier {0} is already inflight")]
+    PacketIdentifierInUse(PacketId),
+
+    #[error("{0} does not match an inflight message with packet identifier {1}")]
+    UnexpectedAck(&'static str, PacketId),
+
+    #[error("The offline message queue is empty")]
+    QueueEmpty,
+
+    #[error("Session log record at offset {0} is invalid: {1}")]
+    CorruptRecord(u64, String),
+}
@@ -0,0 +1,465 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::common::topic_filter::TopicFilter;
+use crate::protocol::common::topic_name::TopicName;
+use crate::protocol::mqtt4::delivery::delivery_error::DeliveryError;
+use crate::protocol::mqtt4::delivery::exactly_once::ExactlyOnceReceiver;
+use crate::protocol::mqtt4::delivery::inflight_publish::InflightPublish;
+use crate::protocol::mqtt4::delivery::session_resume::{InflightMessage, SessionResume};
+use crate::protocol::mqtt4::packet_parser::packet::Packet;
+use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+use crate::protocol::mqtt4::session::session_store_error::SessionStoreError;
+use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+    PublishQoS, PublishVariableHeader,
+};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// A message that matched a subscription while its client was offline. It gets a packet
+/// identifier only once it is delivered.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct QueuedMessage {
+    retain: bool,
+    topic_name: String,
+    qos: QoSCode,
+    payload: PublishPayload,
+}
+
+#[allow(dead_code)]
+impl QueuedMessage {
+    pub(crate) fn new(
+        retain: bool,
+        topic_name: String,
+        qos: QoSCode,
+        payload: PublishPayload,
+    ) -> Self {
+        QueuedMessage {
+            retain,
+            topic_name,
+            qos,
+            payload,
+        }
+    }
+
+    pub(crate) fn retain(&self) -> bool {
+        self.retain
+    }
+
+    pub(crate) fn topic_name(&self) -> &str {
+        &self.topic_name
+    }
+
+    pub(crate) fn qos(&self) -> QoSCode {
+        self.qos
+    }
+
+    pub(crate) fn payload(&self) -> &PublishPayload {
+        &self.payload
+    }
+
+    /// The PUBLISH delivering the message. `packet_identifier` is only used at QoS 1 and 2.
+    pub(crate) fn to_packet(&self, packet_identifier: PacketId) -> Packet {
+        let qos = PublishQoS::with_packet_identifier(self.qos, packet_identifier);
+        Packet::publish(
+            false,
+            self.retain,
+            PublishVariableHeader::new(self.topic_name.clone(), qos),
+            self.payload.clone(),
+        )
+    }
+}
+
+/// One change to a stored session. The variants recording a packet flow are named after the
+/// packet that causes them.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum SessionChange {
+    /// A CleanSession 0 client connected. Its session exists from then on, even with nothing
+    /// in it, so the next CONNACK reports Session Present [MQTT-3.2.2-2].
+    Create,
+    /// Adds a subscription, replacing the QoS of an existing one with the same filter
+    /// [MQTT-3.8.4-3].
+    Subscribe(String, QoSCode),
+    Unsubscribe(String),
+    /// A QoS 1 or QoS 2 PUBLISH was sent to the client.
+    Publish(InflightPublish),
+    /// The PUBREC for a QoS 2 PUBLISH arrived, so the PUBLISH is replaced by its PUBREL, which
+    /// goes after the other inflight messages to keep PUBRELs in the order of their PUBRECs
+    /// [MQTT-4.6.0-4].
+    PubRel(PacketId),
+    /// The PUBACK for a QoS 1 PUBLISH arrived.
+    PubAck(PacketId),
+    /// The PUBCOMP for a PUBREL arrived.
+    PubComp(PacketId),
+    /// A QoS 2 PUBLISH from the client arrived; its packet identifier is held until the PUBREL
+    /// so a repeated PUBLISH is not delivered twice [MQTT-4.3.3-2].
+    Received(PacketId),
+    /// The PUBREL for a QoS 2 PUBLISH from the client arrived and released its identifier.
+    Released(PacketId),
+    /// A message is queued for the offline client.
+    Enqueue(QueuedMessage),
+    /// The oldest queued message was taken for delivery.
+    Dequeue,
+    /// The session ended, e.g. because the client connected with CleanSession 1.
+    Discard,
+}
+
+/// Everything kept for a CleanSession 0 client between connections [MQTT-3.1.2-4]: its
+/// subscriptions, the QoS 1 and QoS 2 messages sent to it that are not completely
+/// acknowledged, the QoS 2 messages received from it that are not released yet, and the
+/// messages queued while it was offline.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone, Default)]
+pub(crate) struct StoredSession {
+    subscriptions: BTreeMap<String, QoSCode>,
+    inflight: Vec<InflightMessage>,
+    received: BTreeSet<PacketId>,
+    queued: VecDeque<QueuedMessage>,
+}
+
+#[allow(dead_code)]
+impl StoredSession {
+    pub(crate) fn subscriptions(&self) -> &BTreeMap<String, QoSCode> {
+        &self.subscriptions
+    }
+
+    /// The unfinished outbound messages: PUBLISH packets in the order they were first sent,
+    /// each PUBREL where its PUBREC arrived.
+    pub(crate) fn inflight(&self) -> &[InflightMessage] {
+        &self.inflight
+    }
+
+    /// The packet identifiers of QoS 2 messages from the client still waiting for their PUBREL.
+    pub(crate) fn received(&self) -> &BTreeSet<PacketId> {
+        &self.received
+    }
+
+    /// The messages queued while the client was offline, oldest first.
+    pub(crate) fn queued(&self) -> &VecDeque<QueuedMessage> {
+        &self.queued
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+            && self.inflight.is_empty()
+            && self.received.is_empty()
+            && self.queued.is_empty()
+    }
+
+    /// What `SessionResume::restore` needs to re-send the inflight messages.
+    pub(crate) fn resume(&self) -> Result<SessionResume, DeliveryError> {
+        SessionResume::new(self.inflight.clone())
+    }
+
+    /// The receiver for QoS 2 messages from the client, still holding the unreleased ones.
+    pub(crate) fn receiver(&self) -> ExactlyOnceReceiver {
+        ExactlyOnceReceiver::restore(self.received.iter().copied())
+    }
+
+    /// Applies `change`, or leaves the session untouched when it does not fit.
+    pub(crate) fn apply(&mut self, change: SessionChange) -> Result<(), SessionStoreError> {
+        match change {
+            // resuming a session keeps what it holds
+            SessionChange::Create => {}
+            SessionChange::Subscribe(topic_filter, qos) => {
+                TopicFilter::verify(&topic_filter)
+                    .map_err(SessionStoreError::InvalidTopicFilter)?;
+                self.subscriptions.insert(topic_filter, qos);
+            }
+            SessionChange::Unsubscribe(topic_filter) => {
+                self.subscriptions.remove(&topic_filter);
+            }
+            SessionChange::Publish(message) => {
+                let packet_identifier = message.packet_identifier();
+                if self.position(packet_identifier).is_some() {
+                    return Err(SessionStoreError::PacketIdentifierInUse(packet_identifier));
+                }
+                TopicName::verify(message.variable().topic_name())
+                    .map_err(SessionStoreError::InvalidTopicName)?;
+                self.inflight.push(InflightMessage::Publish(message));
+            }
+            SessionChange::PubRel(packet_identifier) => match self.position(packet_identifier) {
+                Some(index) => match &self.inflight[index] {
+                    InflightMessage::Publish(message)
+                        if message.variable().qos() == QoSCode::Qos2 =>
+                    {
+                        self.inflight.remove(index);
+                        self.inflight
+                            .push(InflightMessage::PubRel(packet_identifier));
+                    }
+                    InflightMessage::Publish(_) | InflightMessage::PubRel(_) => {
+                        return Err(SessionStoreError::UnexpectedAck(
+                            "PUBREC",
+                            packet_identifier,
+                        ));
+                    }
+                },
+                None => self
+                    .inflight
+                    .push(InflightMessage::PubRel(packet_identifier)),
+            },
+            SessionChange::PubAck(packet_identifier) => {
+                let index = self
+                    .position(packet_identifier)
+                    .filter(|index| match &self.inflight[*index] {
+                        InflightMessage::Publish(message) => {
+                            message.variable().qos() == QoSCode::Qos1
+                        }
+                        InflightMessage::PubRel(_) => false,
+                    })
+                    .ok_or(SessionStoreError::UnexpectedAck(
+                        "PUBACK",
+                        packet_identifier,
+                    ))?;
+                self.inflight.remove(index);
+            }
+            SessionChange::PubComp(packet_identifier) => {
+                let index = self
+                    .position(packet_identifier)
+                    .filter(|index| matches!(self.inflight[*index], InflightMessage::PubRel(_)))
+                    .ok_or(SessionStoreError::UnexpectedAck(
+                        "PUBCOMP",
+                        packet_identifier,
+                    ))?;
+                self.inflight.remove(index);
+            }
+            // a repeated PUBLISH or PUBREL changes nothing, as for `ExactlyOnceReceiver`
+            SessionChange::Received(packet_identifier) => {
+                self.received.insert(packet_identifier);
+            }
+            SessionChange::Released(packet_identifier) => {
+                self.received.remove(&packet_identifier);
+            }
+            SessionChange::Enqueue(message) => {
+                TopicName::verify(message.topic_name())
+                    .map_err(SessionStoreError::InvalidTopicName)?;
+                self.queued.push_back(message);
+            }
+            SessionChange::Dequeue => {
+                self.queued
+                    .pop_front()
+                    .ok_or(SessionStoreError::QueueEmpty)?;
+            }
+            SessionChange::Discard => *self = StoredSession::default(),
+        }
+        Ok(())
+    }
+
+    /// The changes that rebuild this session from nothing, starting with `Create`, for
+    /// compacting a log of changes.
+    /// Pending PUBRELs are restored in their order without the PUBLISH they replaced.
+    pub(crate) fn changes(&self) -> Vec<SessionChange> {
+        let subscriptions = self
+            .subscriptions
+            .iter()
+            .map(|(topic_filter, qos)| SessionChange::Subscribe(topic_filter.clone(), *qos));
+        let inflight = self.inflight.iter().map(|message| match message {
+            InflightMessage::Publish(message) => SessionChange::Publish(message.clone()),
+            InflightMessage::PubRel(packet_identifier) => SessionChange::PubRel(*packet_identifier),
+        });
+        let received = self.received.iter().copied().map(SessionChange::Received);
+        let queued = self.queued.iter().cloned().map(SessionChange::Enqueue);
+        std::iter::once(SessionChange::Create)
+            .chain(subscriptions)
+            .chain(inflight)
+            .chain(received)
+            .chain(queued)
+            .collect()
+    }
+
+    fn position(&self, packet_identifier: PacketId) -> Option<usize> {
+        self.inflight
+            .iter()
+            .position(|message| message.packet_identifier() == packet_identifier)
+    }
+}
+
+#[cfg(test)]
+mod stored_session_tests {
+    use crate::protocol::codec::Encoder;
+    use crate::protocol::common::packet_id::PacketId;
+    use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::mqtt4::delivery::inflight_publish::InflightPublish;
+    use crate::protocol::mqtt4::delivery::session_resume::InflightMessage;
+    use crate::protocol::mqtt4::payload_parser::publish_parser::payload::PublishPayload;
+    use crate::protocol::mqtt4::session::session_store_error::SessionStoreError;
+    use crate::protocol::mqtt4::session::stored_session::{
+        QueuedMessage, SessionChange, StoredSession,
+    };
+    use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+        PublishQoS, PublishVariableHeader,
+    };
+    use bytes::Bytes;
+
+    fn packet_id(value: u16) -> PacketId {
+        PacketId::new(value).unwrap()
+    }
+
+    fn inflight(value: u16, qos: QoSCode) -> InflightPublish {
+        let qos = PublishQoS::new(qos, Some(packet_id(value))).unwrap();
+        let variable = PublishVariableHeader::new("a/b".to_string(), qos);
+        InflightPublish::new(
+            false,
+            variable,
+            PublishPayload::new(Bytes::from_static(b"x")),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn subscribing_again_should_replace_the_qos() {
+        let mut session = StoredSession::default();
+        session
+            .apply(SessionChange::Subscribe("a/#".to_string(), QoSCode::Qos0))
+            .unwrap();
+        session
+            .apply(SessionChange::Subscribe("a/#".to_string(), QoSCode::Qos2))
+            .unwrap();
+        assert_eq!(session.subscriptions().len(), 1);
+        assert_eq!(session.subscriptions()["a/#"], QoSCode::Qos2);
+
+        assert!(matches!(
+            session.apply(SessionChange::Subscribe("a/#/b".to_string(), QoSCode::Qos0)),
+            Err(SessionStoreError::InvalidTopicFilter(_))
+        ));
+        session
+            .apply(SessionChange::Unsubscribe("a/#".to_string()))
+            .unwrap();
+        assert!(session.is_empty());
+    }
+
+    #[test]
+    fn inflight_messages_should_follow_their_flows_in_send_order() {
+        let mut session = StoredSession::default();
+        session
+            .apply(SessionChange::Publish(inflight(1, QoSCode::Qos2)))
+            .unwrap();
+        session
+            .apply(SessionChange::Publish(inflight(2, QoSCode::Qos1)))
+            .unwrap();
+        session
+            .apply(SessionChange::Publish(inflight(3, QoSCode::Qos2)))
+            .unwrap();
+        assert!(matches!(
+            session.apply(SessionChange::Publish(inflight(2, QoSCode::Qos2))),
+            Err(SessionStoreError::PacketIdentifierInUse(_))
+        ));
+        // a PUBACK cannot finish a QoS 2 flow, nor a PUBREC a QoS 1 one
+        assert!(matches!(
+            session.apply(SessionChange::PubAck(packet_id(1))),
+            Err(SessionStoreError::UnexpectedAck("PUBACK", _))
+        ));
+        assert!(matches!(
+            session.apply(SessionChange::PubRel(packet_id(2))),
+            Err(SessionStoreError::UnexpectedAck("PUBREC", _))
+        ));
+
+        // PUBRELs take the order of their PUBRECs
+        session.apply(SessionChange::PubRel(packet_id(3))).unwrap();
+        session.apply(SessionChange::PubRel(packet_id(1))).unwrap();
+        assert_eq!(
+            session.inflight(),
+            &[
+                InflightMessage::Publish(inflight(2, QoSCode::Qos1)),
+                InflightMessage::PubRel(packet_id(3)),
+                InflightMessage::PubRel(packet_id(1)),
+            ]
+        );
+        let retransmissions: Vec<Vec<u8>> = session
+            .resume()
+            .unwrap()
+            .retransmissions()
+            .into_iter()
+            .map(|mut packet| packet.encode().unwrap())
+            .collect();
+        assert_eq!(
+            retransmissions,
+            vec![
+                vec![0x3A, 0x08, 0x00, 0x03, b'a', b'/', b'b', 0x00, 0x02, b'x'],
+                vec![0x62, 0x02, 0x00, 0x03],
+                vec![0x62, 0x02, 0x00, 0x01],
+            ]
+        );
+
+        session.apply(SessionChange::PubAck(packet_id(2))).unwrap();
+        session.apply(SessionChange::PubComp(packet_id(3))).unwrap();
+        session.apply(SessionChange::PubComp(packet_id(1))).unwrap();
+        assert!(session.is_empty());
+    }
+
+    #[test]
+    fn received_qos_2_identifiers_should_be_held_until_released() {
+        let mut session = StoredSession::default();
+        for value in [4, 2, 4] {
+            session
+                .apply(SessionChange::Received(packet_id(value)))
+                .unwrap();
+        }
+        assert!(!session.is_empty());
+        assert_eq!(
+            session.changes(),
+            vec![
+                SessionChange::Create,
+                SessionChange::Received(packet_id(2)),
+                SessionChange::Received(packet_id(4)),
+            ]
+        );
+        let receiver = session.receiver();
+        assert!(receiver.is_pending(packet_id(2)) && receiver.is_pending(packet_id(4)));
+
+        for value in [4, 4, 2] {
+            session
+                .apply(SessionChange::Released(packet_id(value)))
+                .unwrap();
+        }
+        assert!(session.is_empty());
+        assert_eq!(session.receiver().pending().count(), 0);
+    }
+
+    #[test]
+    fn queued_messages_should_be_delivered_oldest_first() {
+        let mut session = StoredSession::default();
+        for (topic_name, qos) in [("a", QoSCode::Qos1), ("b", QoSCode::Qos0)] {
+            let payload = PublishPayload::new(Bytes::copy_from_slice(topic_name.as_bytes()));
+            let message = QueuedMessage::new(false, topic_name.to_string(), qos, payload);
+            session.apply(SessionChange::Enqueue(message)).unwrap();
+        }
+        let invalid = QueuedMessage::new(
+            false,
+            "a/+".to_string(),
+            QoSCode::Qos0,
+            PublishPayload::new(Bytes::new()),
+        );
+        assert!(matches!(
+            session.apply(SessionChange::Enqueue(invalid)),
+            Err(SessionStoreError::InvalidTopicName(_))
+        ));
+
+        let mut packet = session.queued()[0].to_packet(packet_id(7));
+        assert_eq!(
+            packet.encode().unwrap(),
+            vec![0x32, 0x06, 0x00, 0x01, b'a', 0x00, 0x07, b'a']
+        );
+        session.apply(SessionChange::Dequeue).unwrap();
+        assert_eq!(session.queued()[0].topic_name(), "b");
+        session.apply(SessionChange::Dequeue).unwrap();
+        assert!(matches!(
+            session.apply(SessionChange::Dequeue),
+            Err(SessionStoreError::QueueEmpty)
+        ));
+    }
+}
@@ -15,6 +15,7 @@
 use crate::byte_adapter::byte_operations::ByteOperations;
 use crate::protocol::common::return_code::ReturnCode;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::decoder_config::DecodeContext;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
 use crate::protocol::mqtt4::variable_header_parser::conn_ack_parser::variable_header::ConnAckVariableHeader;
 use crate::protocol::mqtt4::variable_header_parser::mqtt_variable_header_codec::MqttVariableHeaderDecoder;
@@ -23,8 +24,9 @@ impl MqttVariableHeaderDecoder for ConnAckVariableHeader {
     fn decode(
         _fixed_header: &FixedHeader,
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<ConnAckVariableHeader, MqttProtocolError> {
-        Self::decode(bytes)
+        Self::decode_with_context(bytes, context)
     }
 }
 
@@ -33,8 +35,17 @@ impl ConnAckVariableHeader {
     pub(super) fn decode(
         bytes: &mut impl ByteOperations,
     ) -> Result<ConnAckVariableHeader, MqttProtocolError> {
+        Self::decode_with_context(bytes, &mut DecodeContext::strict())
+    }
+
+    pub(crate) fn decode_with_context(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<ConnAckVariableHeader, MqttProtocolError> {
+        context.enter_field("variable_header.acknowledge_flags", bytes);
         let session_present = Self::verify_reserved_byte_and_parse_session_present_flag(bytes)?;
 
+        context.enter_field("variable_header.return_code", bytes);
         let return_code = Self::parse_return_code(bytes)?;
 
         Ok(ConnAckVariableHeader::new(session_present, return_code))
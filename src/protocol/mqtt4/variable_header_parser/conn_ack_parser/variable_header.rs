@@ -16,6 +16,7 @@ use crate::protocol::common::return_code::ReturnCode;
 
 #[allow(dead_code)]
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct ConnAckVariableHeader {
     session_present: bool,
     return_code: ReturnCode,
@@ -16,20 +16,21 @@ use crate::byte_adapter::byte_operations::ByteOperations;
 use crate::protocol::common::protocol_level::ProtocolLevel;
 use crate::protocol::common::qos::QoSCode;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::decoder_config::DecodeContext;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
 use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::{
     ConnectFlags, ConnectVariableHeader,
 };
 use crate::protocol::mqtt4::variable_header_parser::mqtt_variable_header_codec::MqttVariableHeaderDecoder;
 use crate::utils::radix::radix_handler;
-use crate::utils::utf::utf_8_handler;
 
 impl MqttVariableHeaderDecoder for ConnectVariableHeader {
     fn decode(
         _fixed_header: &FixedHeader,
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<ConnectVariableHeader, MqttProtocolError> {
-        Self::decode(bytes)
+        Self::decode_with_context(bytes, context)
     }
 }
 
@@ -38,9 +39,20 @@ impl ConnectVariableHeader {
     pub(super) fn decode(
         bytes: &mut impl ByteOperations,
     ) -> Result<ConnectVariableHeader, MqttProtocolError> {
-        Self::verify_protocol_name(bytes)?;
+        Self::decode_with_context(bytes, &mut DecodeContext::strict())
+    }
+
+    pub(crate) fn decode_with_context(
+        bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<ConnectVariableHeader, MqttProtocolError> {
+        context.enter_field("variable_header.protocol_name", bytes);
+        Self::verify_protocol_name(bytes, context)?;
+        context.enter_field("variable_header.protocol_level", bytes);
         let protocol_level = Self::verify_and_return_protocol_level(bytes)?;
-        let connect_flags = Self::parser_connect_flags(bytes)?;
+        context.enter_field("variable_header.connect_flags", bytes);
+        let connect_flags = Self::parser_connect_flags(bytes, &protocol_level)?;
+        context.enter_field("variable_header.keep_alive", bytes);
         let keep_alive = Self::parse_keep_alive(bytes)?;
 
         Ok(ConnectVariableHeader::new(
@@ -51,8 +63,9 @@ impl ConnectVariableHeader {
     }
     pub(super) fn verify_protocol_name(
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<(), MqttProtocolError> {
-        let protocol_name = utf_8_handler::read(bytes)?;
+        let protocol_name = context.read_string(bytes)?;
         if protocol_name != "MQTT" {
             return Err(MqttProtocolError::ProtocolNameError(protocol_name));
         }
@@ -69,10 +82,12 @@ impl ConnectVariableHeader {
 
     pub(super) fn parser_connect_flags(
         bytes: &mut impl ByteOperations,
+        protocol_level: &ProtocolLevel,
     ) -> Result<ConnectFlags, MqttProtocolError> {
         let connect_flags_byte = bytes
             .read_a_byte()
             .ok_or(MqttProtocolError::PacketTooShort)?;
+        ConnectVariableHeader::verify_reserved_bit(connect_flags_byte)?;
 
         let user_name_flag = ConnectVariableHeader::parse_user_name_flag(connect_flags_byte);
         let password_flag = ConnectVariableHeader::parse_password_flag(connect_flags_byte);
@@ -80,9 +95,9 @@ impl ConnectVariableHeader {
         let will_qos = ConnectVariableHeader::parse_qos(connect_flags_byte)?;
         let will_flag = ConnectVariableHeader::parse_will_flag(connect_flags_byte);
         let clean_session = ConnectVariableHeader::parse_clean_session(connect_flags_byte);
-        ConnectVariableHeader::verify_reserved_bit(connect_flags_byte)?;
 
-        let connect_flags = ConnectFlags::new(
+        let connect_flags = ConnectFlags::new_for_level(
+            protocol_level,
             user_name_flag,
             password_flag,
             will_retain,
@@ -108,7 +123,7 @@ impl ConnectVariableHeader {
 
     pub(super) fn parse_qos(connect_flags_byte: u8) -> Result<QoSCode, MqttProtocolError> {
         let value = (connect_flags_byte & 0b0001_1000) >> 3;
-        QoSCode::parse(value)
+        QoSCode::parse(value).map_err(|_| MqttProtocolError::InvalidWillQoS(value))
     }
 
     pub(super) fn parse_will_flag(connect_flags_byte: u8) -> bool {
@@ -122,7 +137,7 @@ impl ConnectVariableHeader {
     pub(super) fn verify_reserved_bit(connect_flags_byte: u8) -> Result<(), MqttProtocolError> {
         let reserved_bit = connect_flags_byte & 0b0000_0001;
         if reserved_bit != 0 {
-            return Err(MqttProtocolError::MalformedPacket);
+            return Err(MqttProtocolError::ConnectReservedFlagSet);
         }
         Ok(())
     }
@@ -20,6 +20,11 @@ const PROTOCOL_NAME: &str = "MQTT";
 
 #[allow(dead_code)]
 #[derive(PartialEq, Debug, Clone, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "ConnectVariableHeaderFields")
+)]
 pub struct ConnectVariableHeader {
     protocol_name: String,
     protocol_level: ProtocolLevel,
@@ -29,6 +34,11 @@ pub struct ConnectVariableHeader {
 
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "ConnectFlagsFields")
+)]
 pub struct ConnectFlags {
     username_flag: bool,
     password_flag: bool,
@@ -40,6 +50,7 @@ pub struct ConnectFlags {
 
 #[allow(dead_code)]
 impl ConnectFlags {
+    /// Flags checked against the MQTT 3.1.1 rules.
     pub fn new(
         user_name_flag: bool,
         password_flag: bool,
@@ -47,6 +58,28 @@ impl ConnectFlags {
         will_qos: QoSCode,
         will_flag: bool,
         clean_session: bool,
+    ) -> Result<Self, MqttProtocolError> {
+        Self::new_for_level(
+            &ProtocolLevel::Mqtt3_1_1,
+            user_name_flag,
+            password_flag,
+            will_retain,
+            will_qos,
+            will_flag,
+            clean_session,
+        )
+    }
+
+    /// Flags checked against the rules of `protocol_level`. MQTT 5 is the only level that
+    /// allows a password without a user name.
+    pub fn new_for_level(
+        protocol_level: &ProtocolLevel,
+        user_name_flag: bool,
+        password_flag: bool,
+        will_retain: bool,
+        will_qos: QoSCode,
+        will_flag: bool,
+        clean_session: bool,
     ) -> Result<Self, MqttProtocolError> {
         let this = Self {
             username_flag: user_name_flag,
@@ -57,7 +90,7 @@ impl ConnectFlags {
             clean_session,
         };
 
-        this.verify()?;
+        this.verify(protocol_level)?;
 
         Ok(this)
     }
@@ -85,15 +118,18 @@ impl ConnectFlags {
         Ok(connect_flags_byte)
     }
 
-    fn verify(&self) -> Result<(), MqttProtocolError> {
-        self.verify_state_when_user_name_flag_is_0()?;
+    fn verify(&self, protocol_level: &ProtocolLevel) -> Result<(), MqttProtocolError> {
         self.verify_state_when_will_flag_is_0()?;
+        self.verify_state_when_user_name_flag_is_0(protocol_level)?;
         Ok(())
     }
 
-    fn verify_state_when_user_name_flag_is_0(&self) -> Result<(), MqttProtocolError> {
-        if !self.username_flag && self.password_flag {
-            return Err(MqttProtocolError::MalformedPacket);
+    fn verify_state_when_user_name_flag_is_0(
+        &self,
+        protocol_level: &ProtocolLevel,
+    ) -> Result<(), MqttProtocolError> {
+        if !self.username_flag && self.password_flag && *protocol_level != ProtocolLevel::Mqtt5 {
+            return Err(MqttProtocolError::PasswordWithoutUsername);
         }
         Ok(())
     }
@@ -101,10 +137,12 @@ impl ConnectFlags {
     fn verify_state_when_will_flag_is_0(&self) -> Result<(), MqttProtocolError> {
         if !self.will_flag {
             if self.will_qos != QoSCode::Qos0 {
-                return Err(MqttProtocolError::MalformedPacket);
+                return Err(MqttProtocolError::WillQoSWithoutWillFlag(
+                    self.will_qos.as_u8(),
+                ));
             }
             if self.will_retain {
-                return Err(MqttProtocolError::MalformedPacket);
+                return Err(MqttProtocolError::WillRetainWithoutWillFlag);
             }
         }
         Ok(())
@@ -167,12 +205,94 @@ impl ConnectVariableHeader {
     }
 }
 
+/// Unchecked field set read from serde input; converting it goes through `ConnectFlags::new` so
+/// the flag invariants hold for deserialized values too.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct ConnectFlagsFields {
+    username_flag: bool,
+    password_flag: bool,
+    will_retain: bool,
+    will_qos: QoSCode,
+    will_flag: bool,
+    clean_session: bool,
+}
+
+#[cfg(feature = "serde")]
+impl ConnectFlagsFields {
+    fn into_connect_flags(
+        self,
+        protocol_level: &ProtocolLevel,
+    ) -> Result<ConnectFlags, MqttProtocolError> {
+        ConnectFlags::new_for_level(
+            protocol_level,
+            self.username_flag,
+            self.password_flag,
+            self.will_retain,
+            self.will_qos,
+            self.will_flag,
+            self.clean_session,
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ConnectFlagsFields> for ConnectFlags {
+    type Error = MqttProtocolError;
+
+    fn try_from(fields: ConnectFlagsFields) -> Result<Self, Self::Error> {
+        ConnectFlags::new(
+            fields.username_flag,
+            fields.password_flag,
+            fields.will_retain,
+            fields.will_qos,
+            fields.will_flag,
+            fields.clean_session,
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct ConnectVariableHeaderFields {
+    #[serde(default = "default_protocol_name")]
+    protocol_name: String,
+    protocol_level: ProtocolLevel,
+    connect_flags: ConnectFlagsFields,
+    keep_alive: u16,
+}
+
+#[cfg(feature = "serde")]
+fn default_protocol_name() -> String {
+    PROTOCOL_NAME.to_string()
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ConnectVariableHeaderFields> for ConnectVariableHeader {
+    type Error = MqttProtocolError;
+
+    fn try_from(fields: ConnectVariableHeaderFields) -> Result<Self, Self::Error> {
+        if fields.protocol_name != PROTOCOL_NAME {
+            return Err(MqttProtocolError::ProtocolNameError(fields.protocol_name));
+        }
+        let connect_flags = fields
+            .connect_flags
+            .into_connect_flags(&fields.protocol_level)?;
+        Ok(ConnectVariableHeader::new(
+            fields.protocol_level,
+            connect_flags,
+            fields.keep_alive,
+        ))
+    }
+}
+
 #[cfg(test)]
 mod connect_variable_header_tests {
     use crate::byte_adapter::byte_operations::ByteOperations;
     use crate::protocol::common::protocol_level::ProtocolLevel;
     use crate::protocol::common::qos::QoSCode;
     use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::mqtt4::decoder_config::DecodeContext;
     use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::{
         ConnectFlags, ConnectVariableHeader,
     };
@@ -264,14 +384,18 @@ mod connect_variable_header_tests {
     fn connect_can_allowed_valid_protocol_name() {
         let mut bytes_mut = BytesMut::new();
         write(&mut bytes_mut, "MQTT").unwrap();
-        ConnectVariableHeader::verify_protocol_name(&mut bytes_mut).unwrap();
+        ConnectVariableHeader::verify_protocol_name(&mut bytes_mut, &mut DecodeContext::strict())
+            .unwrap();
     }
     #[test]
     fn connect_can_not_allowed_invalid_protocol_name() {
         let mut bytes_mut = BytesMut::new();
         let invalid_name = "hello";
         write(&mut bytes_mut, invalid_name).unwrap();
-        let result = ConnectVariableHeader::verify_protocol_name(&mut bytes_mut);
+        let result = ConnectVariableHeader::verify_protocol_name(
+            &mut bytes_mut,
+            &mut DecodeContext::strict(),
+        );
         assert!(result.is_err());
         assert!(matches!(
             result,
@@ -435,7 +559,7 @@ mod connect_variable_header_tests {
         assert!(reserved_bit.is_err());
         assert!(matches!(
             reserved_bit,
-            Err(MqttProtocolError::MalformedPacket)
+            Err(MqttProtocolError::ConnectReservedFlagSet)
         ));
     }
 
@@ -455,7 +579,9 @@ mod connect_variable_header_tests {
         let mut bytes_mut = BytesMut::new();
         bytes_mut.write_a_byte(connect_flags_byte);
 
-        let connect_flags = ConnectVariableHeader::parser_connect_flags(&mut bytes_mut).unwrap();
+        let connect_flags =
+            ConnectVariableHeader::parser_connect_flags(&mut bytes_mut, &ProtocolLevel::Mqtt3_1_1)
+                .unwrap();
 
         assert!(connect_flags.username_flag()); // 7th bit is 1
         assert!(connect_flags.password_flag()); // 6th bit is 1
@@ -479,6 +605,7 @@ mod connect_variable_header_tests {
 #[cfg(test)]
 mod connect_flags_verify_tests {
     use crate::byte_adapter::byte_operations::ByteOperations;
+    use crate::protocol::common::protocol_level::ProtocolLevel;
     use crate::protocol::common::qos::QoSCode;
     use crate::protocol::mqtt_protocol_error::MqttProtocolError;
     use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::{
@@ -507,7 +634,8 @@ mod connect_flags_verify_tests {
         let encoded = connect_flags.encode().unwrap();
         let mut bytes_mut = BytesMut::new();
         bytes_mut.write_a_byte(encoded);
-        let result = ConnectVariableHeader::parser_connect_flags(&mut bytes_mut);
+        let result =
+            ConnectVariableHeader::parser_connect_flags(&mut bytes_mut, &ProtocolLevel::Mqtt3_1_1);
         assert!(result.is_ok());
         let flags = result.unwrap();
         assert!(!flags.will_flag());
@@ -522,7 +650,10 @@ mod connect_flags_verify_tests {
         let result = ConnectFlags::new(false, false, will_retain, will_qos, will_flag, false);
 
         assert!(result.is_err());
-        assert!(matches!(result, Err(MqttProtocolError::MalformedPacket)))
+        assert!(matches!(
+            result,
+            Err(MqttProtocolError::WillQoSWithoutWillFlag(1))
+        ))
     }
 
     #[test]
@@ -533,7 +664,10 @@ mod connect_flags_verify_tests {
         let result = ConnectFlags::new(false, false, will_retain, will_qos, will_flag, false);
 
         assert!(result.is_err());
-        assert!(matches!(result, Err(MqttProtocolError::MalformedPacket)))
+        assert!(matches!(
+            result,
+            Err(MqttProtocolError::WillRetainWithoutWillFlag)
+        ))
     }
 
     #[test]
@@ -568,7 +702,8 @@ mod connect_flags_verify_tests {
         let encoded = connect_flags.encode().unwrap();
         let mut bytes_mut = BytesMut::new();
         bytes_mut.write_a_byte(encoded);
-        let result = ConnectVariableHeader::parser_connect_flags(&mut bytes_mut);
+        let result =
+            ConnectVariableHeader::parser_connect_flags(&mut bytes_mut, &ProtocolLevel::Mqtt3_1_1);
         assert!(result.is_ok());
         let flags = result.unwrap();
         assert!(!flags.username_flag());
@@ -589,7 +724,10 @@ mod connect_flags_verify_tests {
         );
 
         assert!(result.is_err());
-        assert!(matches!(result, Err(MqttProtocolError::MalformedPacket)))
+        assert!(matches!(
+            result,
+            Err(MqttProtocolError::PasswordWithoutUsername)
+        ))
     }
 
     #[test]
@@ -611,7 +749,10 @@ mod connect_flags_verify_tests {
             let encoded = connect_flags.encode().unwrap();
             let mut bytes_mut = BytesMut::new();
             bytes_mut.write_a_byte(encoded);
-            let result = ConnectVariableHeader::parser_connect_flags(&mut bytes_mut);
+            let result = ConnectVariableHeader::parser_connect_flags(
+                &mut bytes_mut,
+                &ProtocolLevel::Mqtt3_1_1,
+            );
             assert!(result.is_ok());
             let flags = result.unwrap();
             assert_eq!(flags.will_qos(), &expect_will_qos);
@@ -625,9 +766,63 @@ mod connect_flags_verify_tests {
         let encoded = connect_flags.encode().unwrap();
         let mut bytes_mut = BytesMut::new();
         bytes_mut.write_a_byte(encoded);
-        let result = ConnectVariableHeader::parser_connect_flags(&mut bytes_mut);
+        let result =
+            ConnectVariableHeader::parser_connect_flags(&mut bytes_mut, &ProtocolLevel::Mqtt3_1_1);
         assert!(result.is_ok());
         let flags = result.unwrap();
         assert!(flags.clean_session());
     }
+
+    fn parse_flags_byte(
+        connect_flags_byte: u8,
+        protocol_level: ProtocolLevel,
+    ) -> Result<ConnectFlags, MqttProtocolError> {
+        let mut bytes_mut = BytesMut::new();
+        bytes_mut.write_a_byte(connect_flags_byte);
+        ConnectVariableHeader::parser_connect_flags(&mut bytes_mut, &protocol_level)
+    }
+
+    #[test]
+    fn connect_flags_byte_with_reserved_bit_set_should_return_error() {
+        let result = parse_flags_byte(0b0000_0011, ProtocolLevel::Mqtt3_1_1);
+        assert!(matches!(
+            result,
+            Err(MqttProtocolError::ConnectReservedFlagSet)
+        ));
+    }
+
+    #[test]
+    fn connect_flags_byte_with_will_qos_3_should_return_error() {
+        let result = parse_flags_byte(0b0001_1110, ProtocolLevel::Mqtt3_1_1);
+        assert!(matches!(result, Err(MqttProtocolError::InvalidWillQoS(3))));
+    }
+
+    #[test]
+    fn connect_flags_byte_with_will_retain_without_will_flag_should_return_error() {
+        let result = parse_flags_byte(0b0010_0010, ProtocolLevel::Mqtt3_1_1);
+        assert!(matches!(
+            result,
+            Err(MqttProtocolError::WillRetainWithoutWillFlag)
+        ));
+        let result = parse_flags_byte(0b0001_0010, ProtocolLevel::Mqtt3_1_1);
+        assert!(matches!(
+            result,
+            Err(MqttProtocolError::WillQoSWithoutWillFlag(2))
+        ));
+    }
+
+    #[test]
+    fn password_without_user_name_should_only_be_allowed_in_mqtt_5() {
+        for protocol_level in [ProtocolLevel::Mqtt3, ProtocolLevel::Mqtt3_1_1] {
+            let result = parse_flags_byte(0b0100_0010, protocol_level);
+            assert!(matches!(
+                result,
+                Err(MqttProtocolError::PasswordWithoutUsername)
+            ));
+        }
+
+        let flags = parse_flags_byte(0b0100_0010, ProtocolLevel::Mqtt5).unwrap();
+        assert!(flags.password_flag());
+        assert!(!flags.username_flag());
+    }
 }
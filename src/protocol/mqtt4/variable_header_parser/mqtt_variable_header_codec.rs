@@ -14,6 +14,7 @@
 
 use crate::byte_adapter::byte_operations::ByteOperations;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::decoder_config::DecodeContext;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
 
 #[allow(dead_code)]
@@ -21,6 +22,7 @@ pub(crate) trait MqttVariableHeaderDecoder {
     fn decode(
         fixed_header: &FixedHeader,
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<Self, MqttProtocolError>
     where
         Self: Sized;
@@ -12,25 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::protocol::common::packet_id::PacketId;
+
 #[allow(dead_code)]
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct PubCompVariableHeader {
-    packet_identifier: u16,
+    packet_identifier: PacketId,
 }
 
 #[allow(dead_code)]
 impl PubCompVariableHeader {
-    pub fn new(packet_identifier: u16) -> Self {
+    pub fn new(packet_identifier: PacketId) -> Self {
         PubCompVariableHeader { packet_identifier }
     }
 
-    pub fn packet_identifier(&self) -> u16 {
+    pub fn packet_identifier(&self) -> PacketId {
         self.packet_identifier
     }
 }
 
 #[cfg(test)]
 mod pub_comp_variable_header_tests {
+    use crate::protocol::common::packet_id::PacketId;
     use crate::protocol::mqtt4::variable_header_parser::mqtt_variable_header_codec::MqttVariableHeaderEncoder;
     use crate::protocol::mqtt4::variable_header_parser::pub_comp_parser::variable_header::PubCompVariableHeader;
     use bytes::BytesMut;
@@ -38,7 +42,8 @@ mod pub_comp_variable_header_tests {
     #[test]
     fn pub_comp_variable_parser_should_parse_variable_header_correctly() {
         let mut bytes = BytesMut::new();
-        let expect_pub_comp_variable_header = PubCompVariableHeader::new(0b0000_1010_0010_1010);
+        let expect_pub_comp_variable_header =
+            PubCompVariableHeader::new(PacketId::new(0b0000_1010_0010_1010).unwrap());
         let encode_pub_comp_variable_header = expect_pub_comp_variable_header.encode().unwrap();
         bytes.extend(encode_pub_comp_variable_header);
 
@@ -12,25 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::protocol::common::packet_id::PacketId;
+
 #[allow(dead_code)]
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct PubRecVariableHeader {
-    packet_identifier: u16,
+    packet_identifier: PacketId,
 }
 
 #[allow(dead_code)]
 impl PubRecVariableHeader {
-    pub fn new(packet_identifier: u16) -> Self {
+    pub fn new(packet_identifier: PacketId) -> Self {
         PubRecVariableHeader { packet_identifier }
     }
 
-    pub fn packet_identifier(&self) -> u16 {
+    pub fn packet_identifier(&self) -> PacketId {
         self.packet_identifier
     }
 }
 
 #[cfg(test)]
 mod pub_rec_variable_header_tests {
+    use crate::protocol::common::packet_id::PacketId;
     use crate::protocol::mqtt4::variable_header_parser::mqtt_variable_header_codec::MqttVariableHeaderEncoder;
     use crate::protocol::mqtt4::variable_header_parser::pub_rec_parser::variable_header::PubRecVariableHeader;
     use bytes::BytesMut;
@@ -39,7 +43,8 @@ mod pub_rec_variable_header_tests {
     fn pub_rec_variable_parser_should_parse_variable_header_correctly() {
         let mut bytes = BytesMut::new();
 
-        let expect_pub_rec_variable_header = PubRecVariableHeader::new(0x1234);
+        let expect_pub_rec_variable_header =
+            PubRecVariableHeader::new(PacketId::new(0x1234).unwrap());
         let expect_encode_pub_rec_variable_header =
             expect_pub_rec_variable_header.encode().unwrap();
         bytes.extend(expect_encode_pub_rec_variable_header);
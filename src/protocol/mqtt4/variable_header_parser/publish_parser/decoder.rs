@@ -14,21 +14,25 @@
 
 use crate::byte_adapter::byte_operations::ByteOperations;
 use crate::protocol::common::qos::QoSCode;
+use crate::protocol::common::topic_name::TopicName;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::protocol::mqtt4::decoder_config::DecodeContext;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
 use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
 use crate::protocol::mqtt4::variable_header_parser::mqtt_variable_header_codec::MqttVariableHeaderDecoder;
-use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::PublishVariableHeader;
-use crate::utils::radix::radix_handler;
-use crate::utils::utf::utf_8_handler;
+use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+    PublishQoS, PublishVariableHeader,
+};
+use crate::utils::mqtt_utils;
 
 impl MqttVariableHeaderDecoder for PublishVariableHeader {
     fn decode(
         fixed_header: &FixedHeader,
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<PublishVariableHeader, MqttProtocolError> {
         if let FixedHeaderFlags::Publish { qos, .. } = fixed_header.fixed_header_reserved_flags() {
-            PublishVariableHeader::decode(bytes, qos)
+            PublishVariableHeader::decode_with_context(bytes, qos, context)
         } else {
             Err(MqttProtocolError::MalformedPacket)
         }
@@ -41,39 +45,45 @@ impl PublishVariableHeader {
         bytes: &mut impl ByteOperations,
         qos_level: &QoSCode,
     ) -> Result<PublishVariableHeader, MqttProtocolError> {
-        let topic_name = Self::parse_topic_name(bytes)?;
-        let packet_identifier = Self::parse_packet_identifier(bytes, qos_level)?;
-        Ok(PublishVariableHeader::new(topic_name, packet_identifier))
+        Self::decode_with_context(bytes, qos_level, &mut DecodeContext::strict())
+    }
+
+    pub(crate) fn decode_with_context(
+        bytes: &mut impl ByteOperations,
+        qos_level: &QoSCode,
+        context: &mut DecodeContext<'_>,
+    ) -> Result<PublishVariableHeader, MqttProtocolError> {
+        context.enter_field("variable_header.topic_name", bytes);
+        let topic_name = Self::parse_topic_name(bytes, context)?;
+        context.enter_field("variable_header.packet_identifier", bytes);
+        let qos = Self::parse_publish_qos(bytes, qos_level)?;
+        Ok(PublishVariableHeader::new(topic_name, qos))
     }
     pub(super) fn parse_topic_name(
         bytes: &mut impl ByteOperations,
+        context: &mut DecodeContext<'_>,
     ) -> Result<String, MqttProtocolError> {
-        let non_verify_topic_name = utf_8_handler::read(bytes)?;
+        let non_verify_topic_name = context.read_string(bytes)?;
         Self::verify_topic_name(&non_verify_topic_name)?;
         Ok(non_verify_topic_name)
     }
 
-    pub(super) fn verify_topic_name(topic_name: &str) -> Result<(), MqttProtocolError> {
-        if topic_name.is_empty() {
-            return Err(MqttProtocolError::MalformedPacket);
-        }
-        if topic_name.contains('#') || topic_name.contains('+') {
-            return Err(MqttProtocolError::MalformedPacket);
-        }
-        Ok(())
+    pub(crate) fn verify_topic_name(topic_name: &str) -> Result<(), MqttProtocolError> {
+        TopicName::verify(topic_name).map_err(MqttProtocolError::InvalidTopicName)
     }
 
-    pub(super) fn parse_packet_identifier(
+    pub(super) fn parse_publish_qos(
         bytes: &mut impl ByteOperations,
         qos_level: &QoSCode,
-    ) -> Result<Option<u16>, MqttProtocolError> {
+    ) -> Result<PublishQoS, MqttProtocolError> {
         match qos_level {
-            QoSCode::Qos0 => Ok(None),
-            QoSCode::Qos1 | QoSCode::Qos2 => {
-                let bytes = bytes.read_bytes(2);
-                let packet_identifier = radix_handler::be_bytes_to_u16(bytes.as_slice())?;
-                Ok(Some(packet_identifier))
-            }
+            QoSCode::Qos0 => Ok(PublishQoS::AtMostOnce),
+            QoSCode::Qos1 => Ok(PublishQoS::AtLeastOnce(
+                mqtt_utils::parse_packet_identifier(bytes)?,
+            )),
+            QoSCode::Qos2 => Ok(PublishQoS::ExactlyOnce(
+                mqtt_utils::parse_packet_identifier(bytes)?,
+            )),
         }
     }
 }
@@ -12,44 +12,117 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::protocol::common::packet_id::PacketId;
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+
+/// The QoS of a PUBLISH together with its Packet Identifier, which exists exactly when the QoS
+/// is above 0. The variants carry the delivery names the specification gives each QoS level.
+#[allow(dead_code, clippy::enum_variant_names)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum PublishQoS {
+    AtMostOnce,
+    AtLeastOnce(PacketId),
+    ExactlyOnce(PacketId),
+}
+
+#[allow(dead_code)]
+impl PublishQoS {
+    pub fn new(
+        qos: QoSCode,
+        packet_identifier: Option<PacketId>,
+    ) -> Result<PublishQoS, MqttProtocolError> {
+        match (qos, packet_identifier) {
+            (QoSCode::Qos0, None) => Ok(PublishQoS::AtMostOnce),
+            (QoSCode::Qos1, Some(packet_identifier)) => {
+                Ok(PublishQoS::AtLeastOnce(packet_identifier))
+            }
+            (QoSCode::Qos2, Some(packet_identifier)) => {
+                Ok(PublishQoS::ExactlyOnce(packet_identifier))
+            }
+            (QoSCode::Qos0, Some(_)) => Err(MqttProtocolError::UnexpectedPacketIdentifier),
+            (qos, None) => Err(MqttProtocolError::MissingPacketIdentifier(qos.as_u8())),
+        }
+    }
+
+    /// The QoS of a message about to be sent at `qos`, which only uses `packet_identifier` at
+    /// QoS 1 and 2.
+    pub fn with_packet_identifier(qos: QoSCode, packet_identifier: PacketId) -> PublishQoS {
+        match qos {
+            QoSCode::Qos0 => PublishQoS::AtMostOnce,
+            QoSCode::Qos1 => PublishQoS::AtLeastOnce(packet_identifier),
+            QoSCode::Qos2 => PublishQoS::ExactlyOnce(packet_identifier),
+        }
+    }
+
+    pub fn qos(&self) -> QoSCode {
+        match self {
+            PublishQoS::AtMostOnce => QoSCode::Qos0,
+            PublishQoS::AtLeastOnce(_) => QoSCode::Qos1,
+            PublishQoS::ExactlyOnce(_) => QoSCode::Qos2,
+        }
+    }
+
+    pub fn packet_identifier(&self) -> Option<PacketId> {
+        match self {
+            PublishQoS::AtMostOnce => None,
+            PublishQoS::AtLeastOnce(packet_identifier)
+            | PublishQoS::ExactlyOnce(packet_identifier) => Some(*packet_identifier),
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(PartialEq, Debug, Clone)]
 pub(crate) struct PublishVariableHeader {
     topic_name: String,
-    packet_identifier: Option<u16>,
+    qos: PublishQoS,
 }
 
 #[allow(dead_code)]
 impl PublishVariableHeader {
-    pub fn new(topic_name: String, packet_identifier: Option<u16>) -> Self {
-        PublishVariableHeader {
-            topic_name,
-            packet_identifier,
-        }
+    pub fn new(topic_name: String, qos: PublishQoS) -> Self {
+        PublishVariableHeader { topic_name, qos }
     }
 
     pub fn topic_name(&self) -> &str {
         &self.topic_name
     }
 
-    pub fn packet_identifier(&self) -> Option<u16> {
-        self.packet_identifier
+    pub fn publish_qos(&self) -> &PublishQoS {
+        &self.qos
+    }
+
+    pub fn qos(&self) -> QoSCode {
+        self.qos.qos()
+    }
+
+    pub fn packet_identifier(&self) -> Option<PacketId> {
+        self.qos.packet_identifier()
     }
 }
 
 #[cfg(test)]
 mod publish_variable_header_tests {
     use crate::byte_adapter::byte_operations::ByteOperations;
+    use crate::protocol::common::packet_id::PacketId;
     use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::common::topic_error::TopicError;
     use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::mqtt4::decoder_config::DecodeContext;
     use crate::protocol::mqtt4::variable_header_parser::mqtt_variable_header_codec::MqttVariableHeaderEncoder;
-    use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::PublishVariableHeader;
+    use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::{
+        PublishQoS, PublishVariableHeader,
+    };
     use crate::utils::utf::utf_8_handler::write;
     use bytes::BytesMut;
 
     #[test]
     fn publish_variable_header_can_encode_and_decode() {
-        let original_header = PublishVariableHeader::new("test/topic".to_string(), Some(0x1234));
+        let original_header = PublishVariableHeader::new(
+            "test/topic".to_string(),
+            PublishQoS::AtLeastOnce(PacketId::new(0x1234).unwrap()),
+        );
         let encoded_bytes = original_header.encode().unwrap();
 
         let mut bytes_mut = BytesMut::from(&encoded_bytes[..]);
@@ -63,7 +136,9 @@ mod publish_variable_header_tests {
         let mut bytes_mut = BytesMut::new();
         write(&mut bytes_mut, "a/b").unwrap();
 
-        let result = PublishVariableHeader::parse_topic_name(&mut bytes_mut).unwrap();
+        let result =
+            PublishVariableHeader::parse_topic_name(&mut bytes_mut, &mut DecodeContext::strict())
+                .unwrap();
 
         assert_eq!(result, "a/b");
     }
@@ -73,12 +148,13 @@ mod publish_variable_header_tests {
         let mut bytes_mut = BytesMut::new();
         write(&mut bytes_mut, "").unwrap();
 
-        let result = PublishVariableHeader::parse_topic_name(&mut bytes_mut);
+        let result =
+            PublishVariableHeader::parse_topic_name(&mut bytes_mut, &mut DecodeContext::strict());
 
         assert!(result.is_err());
         assert!(matches!(
             result.err().unwrap(),
-            MqttProtocolError::MalformedPacket
+            MqttProtocolError::InvalidTopicName(TopicError::Empty)
         ))
     }
 
@@ -87,23 +163,25 @@ mod publish_variable_header_tests {
         let mut bytes_mut = BytesMut::new();
         write(&mut bytes_mut, "a/+/c").unwrap();
 
-        let result = PublishVariableHeader::parse_topic_name(&mut bytes_mut);
+        let result =
+            PublishVariableHeader::parse_topic_name(&mut bytes_mut, &mut DecodeContext::strict());
 
         assert!(result.is_err());
         assert!(matches!(
             result.err().unwrap(),
-            MqttProtocolError::MalformedPacket
+            MqttProtocolError::InvalidTopicName(TopicError::WildcardInTopicName(2, '+'))
         ));
 
         let mut bytes_mut2 = BytesMut::new();
         write(&mut bytes_mut2, "a/b/#").unwrap();
 
-        let result2 = PublishVariableHeader::parse_topic_name(&mut bytes_mut2);
+        let result2 =
+            PublishVariableHeader::parse_topic_name(&mut bytes_mut2, &mut DecodeContext::strict());
 
         assert!(result2.is_err());
         assert!(matches!(
             result2.err().unwrap(),
-            MqttProtocolError::MalformedPacket
+            MqttProtocolError::InvalidTopicName(TopicError::WildcardInTopicName(4, '#'))
         ));
     }
 
@@ -111,8 +189,8 @@ mod publish_variable_header_tests {
     fn publish_variable_header_qos_0_no_packet_identifier() {
         let mut bytes_mut = BytesMut::new();
         let result =
-            PublishVariableHeader::parse_packet_identifier(&mut bytes_mut, &QoSCode::Qos0).unwrap();
-        assert_eq!(result, None);
+            PublishVariableHeader::parse_publish_qos(&mut bytes_mut, &QoSCode::Qos0).unwrap();
+        assert_eq!(result, PublishQoS::AtMostOnce);
     }
 
     #[test]
@@ -121,7 +199,45 @@ mod publish_variable_header_tests {
         bytes_mut.write_a_byte(0x12);
         bytes_mut.write_a_byte(0x34);
         let result =
-            PublishVariableHeader::parse_packet_identifier(&mut bytes_mut, &QoSCode::Qos1).unwrap();
-        assert_eq!(result, Some(0x1234));
+            PublishVariableHeader::parse_publish_qos(&mut bytes_mut, &QoSCode::Qos1).unwrap();
+        assert_eq!(
+            result,
+            PublishQoS::AtLeastOnce(PacketId::new(0x1234).unwrap())
+        );
+    }
+
+    #[test]
+    fn publish_variable_header_qos_2_packet_identifier_zero_should_fail() {
+        let mut bytes_mut = BytesMut::new();
+        bytes_mut.write_a_byte(0x00);
+        bytes_mut.write_a_byte(0x00);
+        let result = PublishVariableHeader::parse_publish_qos(&mut bytes_mut, &QoSCode::Qos2);
+        assert!(matches!(
+            result,
+            Err(MqttProtocolError::ZeroPacketIdentifier)
+        ));
+    }
+
+    #[test]
+    fn publish_qos_should_have_a_packet_identifier_exactly_when_qos_is_above_0() {
+        let packet_id = PacketId::new(7).unwrap();
+        assert_eq!(
+            PublishQoS::new(QoSCode::Qos0, None).unwrap(),
+            PublishQoS::AtMostOnce
+        );
+        assert_eq!(
+            PublishQoS::new(QoSCode::Qos2, Some(packet_id)).unwrap(),
+            PublishQoS::ExactlyOnce(packet_id)
+        );
+        assert!(matches!(
+            PublishQoS::new(QoSCode::Qos0, Some(packet_id)),
+            Err(MqttProtocolError::UnexpectedPacketIdentifier)
+        ));
+        assert!(matches!(
+            PublishQoS::new(QoSCode::Qos1, None),
+            Err(MqttProtocolError::MissingPacketIdentifier(1))
+        ));
+        assert_eq!(PublishQoS::ExactlyOnce(packet_id).qos(), QoSCode::Qos2);
+        assert_eq!(PublishQoS::AtMostOnce.packet_identifier(), None);
     }
 }
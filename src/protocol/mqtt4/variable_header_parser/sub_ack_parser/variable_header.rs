@@ -12,24 +12,28 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::protocol::common::packet_id::PacketId;
+
 #[allow(dead_code)]
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct SubAckVariableHeader {
-    packet_identifier: u16,
+    packet_identifier: PacketId,
 }
 
 #[allow(dead_code)]
 impl SubAckVariableHeader {
-    pub fn new(packet_identifier: u16) -> Self {
+    pub fn new(packet_identifier: PacketId) -> Self {
         SubAckVariableHeader { packet_identifier }
     }
-    pub fn packet_identifier(&self) -> u16 {
+    pub fn packet_identifier(&self) -> PacketId {
         self.packet_identifier
     }
 }
 
 #[cfg(test)]
 mod sub_ack_variable_header_tests {
+    use crate::protocol::common::packet_id::PacketId;
     use crate::protocol::mqtt4::variable_header_parser::mqtt_variable_header_codec::MqttVariableHeaderEncoder;
     use crate::protocol::mqtt4::variable_header_parser::sub_ack_parser::variable_header::SubAckVariableHeader;
     use bytes::BytesMut;
@@ -37,7 +41,8 @@ mod sub_ack_variable_header_tests {
     #[test]
     fn sub_ack_variable_parser_should_parse_variable_header_correctly() {
         let mut bytes = BytesMut::new();
-        let expect_sub_ack_variable_header = SubAckVariableHeader::new(0x2211);
+        let expect_sub_ack_variable_header =
+            SubAckVariableHeader::new(PacketId::new(0x2211).unwrap());
         let encode_sub_ack_variable_header = expect_sub_ack_variable_header.encode().unwrap();
         bytes.extend(encode_sub_ack_variable_header);
 
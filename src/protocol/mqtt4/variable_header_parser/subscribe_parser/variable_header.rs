@@ -12,25 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::protocol::common::packet_id::PacketId;
+
 #[allow(dead_code)]
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct SubscribeVariableHeader {
-    packet_identifier: u16,
+    packet_identifier: PacketId,
 }
 
 #[allow(dead_code)]
 impl SubscribeVariableHeader {
-    pub fn new(packet_identifier: u16) -> Self {
+    pub fn new(packet_identifier: PacketId) -> Self {
         SubscribeVariableHeader { packet_identifier }
     }
 
-    pub fn packet_identifier(&self) -> u16 {
+    pub fn packet_identifier(&self) -> PacketId {
         self.packet_identifier
     }
 }
 
 #[cfg(test)]
 mod subscribe_variable_header_tests {
+    use crate::protocol::common::packet_id::PacketId;
     use crate::protocol::mqtt4::variable_header_parser::mqtt_variable_header_codec::MqttVariableHeaderEncoder;
     use crate::protocol::mqtt4::variable_header_parser::subscribe_parser::variable_header::SubscribeVariableHeader;
     use bytes::BytesMut;
@@ -39,7 +43,8 @@ mod subscribe_variable_header_tests {
     fn subscribe_variable_parser_should_parse_variable_header_correctly() {
         let mut bytes = BytesMut::new();
 
-        let expect_subscribe_variable_header = SubscribeVariableHeader::new(0x2131);
+        let expect_subscribe_variable_header =
+            SubscribeVariableHeader::new(PacketId::new(0x2131).unwrap());
         let encode_expect_subscribe_variable_header =
             expect_subscribe_variable_header.encode().unwrap();
         bytes.extend(encode_expect_subscribe_variable_header);
@@ -12,25 +12,28 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::protocol::common::packet_id::PacketId;
+
 #[allow(dead_code)]
 #[derive(PartialEq, Debug)]
 pub(crate) struct UnSubAckVariableHeader {
-    packet_identifier: u16,
+    packet_identifier: PacketId,
 }
 
 #[allow(dead_code)]
 impl UnSubAckVariableHeader {
-    pub(crate) fn new(packet_identifier: u16) -> Self {
+    pub(crate) fn new(packet_identifier: PacketId) -> Self {
         UnSubAckVariableHeader { packet_identifier }
     }
 
-    pub fn packet_identifier(&self) -> u16 {
+    pub fn packet_identifier(&self) -> PacketId {
         self.packet_identifier
     }
 }
 
 #[cfg(test)]
 mod unsubscribe_variable_header_tests {
+    use crate::protocol::common::packet_id::PacketId;
     use crate::protocol::mqtt4::variable_header_parser::mqtt_variable_header_codec::MqttVariableHeaderEncoder;
     use crate::protocol::mqtt4::variable_header_parser::unsub_ack_parser::variable_header::UnSubAckVariableHeader;
     use bytes::BytesMut;
@@ -39,7 +42,8 @@ mod unsubscribe_variable_header_tests {
     fn unsub_ack_variable_parser_should_parse_variable_header_correctly() {
         let mut bytes = BytesMut::new();
 
-        let expect_un_sub_ack_variable_header = UnSubAckVariableHeader::new(0x2211);
+        let expect_un_sub_ack_variable_header =
+            UnSubAckVariableHeader::new(PacketId::new(0x2211).unwrap());
         let encode_expect_un_sub_ack_variable_header =
             expect_un_sub_ack_variable_header.encode().unwrap();
         bytes.extend(&encode_expect_un_sub_ack_variable_header);
@@ -12,25 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::protocol::common::packet_id::PacketId;
+
 #[allow(dead_code)]
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct UnSubscribeVariableHeader {
-    packet_identifier: u16,
+    packet_identifier: PacketId,
 }
 
 #[allow(dead_code)]
 impl UnSubscribeVariableHeader {
-    pub fn new(packet_identifier: u16) -> Self {
+    pub fn new(packet_identifier: PacketId) -> Self {
         UnSubscribeVariableHeader { packet_identifier }
     }
 
-    pub fn packet_identifier(&self) -> u16 {
+    pub fn packet_identifier(&self) -> PacketId {
         self.packet_identifier
     }
 }
 
 #[cfg(test)]
 mod unsubscribe_variable_header_tests {
+    use crate::protocol::common::packet_id::PacketId;
     use crate::protocol::mqtt4::variable_header_parser::mqtt_variable_header_codec::MqttVariableHeaderEncoder;
     use crate::protocol::mqtt4::variable_header_parser::unsubscribe_parser::variable_header::UnSubscribeVariableHeader;
     use bytes::BytesMut;
@@ -38,7 +42,8 @@ mod unsubscribe_variable_header_tests {
     #[test]
     fn unsubscribe_variable_parser_should_parse_variable_header_correctly() {
         let mut bytes = BytesMut::new();
-        let expect_unsubscribe_variable_header = UnSubscribeVariableHeader::new(0x2211);
+        let expect_unsubscribe_variable_header =
+            UnSubscribeVariableHeader::new(PacketId::new(0x2211).unwrap());
         let encode_expect_unsubscribe_variable_header =
             expect_unsubscribe_variable_header.encode().unwrap();
         bytes.extend(&encode_expect_unsubscribe_variable_header);
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::protocol::common::topic_error::TopicError;
 use crate::utils::code_error::CodeError;
 
 #[allow(dead_code)]
@@ -55,4 +56,43 @@ pub enum MqttProtocolError {
 
     #[error("Client Identifier is invalid")]
     InvalidClientId,
+
+    #[error("Invalid topic name: {0}")]
+    InvalidTopicName(TopicError),
+
+    #[error("Invalid topic filter: {0}")]
+    InvalidTopicFilter(TopicError),
+
+    #[error("CONNECT flags reserved bit must be 0")]
+    ConnectReservedFlagSet,
+
+    #[error("Will QoS {0} is not supported, it must be 0, 1 or 2")]
+    InvalidWillQoS(u8),
+
+    #[error("Will QoS {0} is set without the Will Flag")]
+    WillQoSWithoutWillFlag(u8),
+
+    #[error("Will Retain is set without the Will Flag")]
+    WillRetainWithoutWillFlag,
+
+    #[error("Password Flag is set without the User Name Flag")]
+    PasswordWithoutUsername,
+
+    #[error("Packet Identifier must not be 0")]
+    ZeroPacketIdentifier,
+
+    #[error("PUBLISH with QoS {0} needs a Packet Identifier")]
+    MissingPacketIdentifier(u8),
+
+    #[error("PUBLISH with QoS 0 must not have a Packet Identifier")]
+    UnexpectedPacketIdentifier,
+
+    #[error("PUBLISH fixed header QoS {0} disagrees with the variable header QoS {1}")]
+    PublishQoSMismatch(u8, u8),
+
+    #[error("String of {0} bytes exceeds the maximum string length of {1} bytes")]
+    StringTooLong(usize, usize),
+
+    #[error("Packet announces {0} bytes, more than the maximum packet size of {1} bytes")]
+    PacketTooLarge(usize, usize),
 }
@@ -0,0 +1,154 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::mqtt4::payload_parser::connect_parser::payload::ConnectPayload;
+use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::ConnectFlags;
+use crate::protocol::sparkplug_b::payload_parser::metric::Metric;
+use crate::protocol::sparkplug_b::payload_parser::payload::SparkplugPayload;
+use crate::protocol::sparkplug_b::sequence::SequenceNumber;
+use crate::protocol::sparkplug_b::sparkplug_error::SparkplugError;
+use crate::protocol::sparkplug_b::topic_namespace::{SparkplugMessageType, SparkplugTopic};
+use bytes::Bytes;
+
+/// The NDEATH an edge node registers as its MQTT will. It only carries the bdSeq metric, which
+/// must match the one published in the following NBIRTH.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct NodeDeathCertificate {
+    topic: SparkplugTopic,
+    bd_seq: SequenceNumber,
+}
+
+#[allow(dead_code)]
+impl NodeDeathCertificate {
+    pub fn new(
+        group_id: &str,
+        edge_node_id: &str,
+        bd_seq: SequenceNumber,
+    ) -> Result<Self, SparkplugError> {
+        let topic = SparkplugTopic::node(group_id, SparkplugMessageType::NDeath, edge_node_id)?;
+        Ok(NodeDeathCertificate { topic, bd_seq })
+    }
+
+    pub fn topic(&self) -> &SparkplugTopic {
+        &self.topic
+    }
+
+    pub fn bd_seq(&self) -> SequenceNumber {
+        self.bd_seq
+    }
+
+    pub fn payload(&self) -> SparkplugPayload {
+        SparkplugPayload::new(None, vec![Metric::bd_seq(self.bd_seq.value() as u64)], None)
+    }
+
+    /// The Sparkplug B specification requires the NDEATH will to use QoS 1 without retain.
+    pub fn connect_flags(
+        &self,
+        username_flag: bool,
+        password_flag: bool,
+        clean_session: bool,
+    ) -> Result<ConnectFlags, SparkplugError> {
+        Ok(ConnectFlags::new(
+            username_flag,
+            password_flag,
+            false,
+            QoSCode::Qos1,
+            true,
+            clean_session,
+        )?)
+    }
+
+    pub fn into_connect_payload(
+        self,
+        client_id: String,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> ConnectPayload {
+        let will_message = Bytes::from(self.payload().encode());
+        ConnectPayload::new(
+            client_id,
+            Some(self.topic.topic_name()),
+            Some(will_message),
+            username,
+            password,
+        )
+    }
+}
+
+#[cfg(test)]
+mod death_certificate_tests {
+    use crate::protocol::codec::{Decoder, Encoder};
+    use crate::protocol::common::control_packet_type::ControlPacketType;
+    use crate::protocol::common::protocol_level::ProtocolLevel;
+    use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::mqtt4::fixed_header_parser::fixed_header::FixedHeader;
+    use crate::protocol::mqtt4::fixed_header_parser::fixed_header_flags::FixedHeaderFlags;
+    use crate::protocol::mqtt4::packet_parser::packet::Packet;
+    use crate::protocol::mqtt4::variable_header_parser::connect_parser::variable_header::ConnectVariableHeader;
+    use crate::protocol::sparkplug_b::death_certificate::NodeDeathCertificate;
+    use crate::protocol::sparkplug_b::payload_parser::payload::SparkplugPayload;
+    use crate::protocol::sparkplug_b::sequence::SequenceNumber;
+    use crate::protocol::sparkplug_b::sparkplug_error::SparkplugError;
+    use bytes::BytesMut;
+
+    #[test]
+    fn node_death_certificate_should_use_ndeath_topic() {
+        let certificate =
+            NodeDeathCertificate::new("Plant1", "Edge1", SequenceNumber::new(3)).unwrap();
+        assert_eq!(
+            certificate.topic().topic_name(),
+            "spBv1.0/Plant1/NDEATH/Edge1"
+        );
+    }
+
+    #[test]
+    fn node_death_certificate_should_reject_invalid_identifiers() {
+        let result = NodeDeathCertificate::new("Plant/1", "Edge1", SequenceNumber::new(0));
+        assert!(matches!(result, Err(SparkplugError::InvalidIdentifier(_))));
+    }
+
+    #[test]
+    fn node_death_certificate_connect_flags_should_set_will_qos_1_without_retain() {
+        let certificate =
+            NodeDeathCertificate::new("Plant1", "Edge1", SequenceNumber::new(0)).unwrap();
+        let connect_flags = certificate.connect_flags(true, true, true).unwrap();
+        assert!(connect_flags.will_flag());
+        assert_eq!(connect_flags.will_qos(), &QoSCode::Qos1);
+        assert!(!connect_flags.will_retain());
+    }
+
+    #[test]
+    fn node_death_certificate_should_round_trip_through_connect_packet() {
+        let certificate =
+            NodeDeathCertificate::new("Plant1", "Edge1", SequenceNumber::new(42)).unwrap();
+        let connect_flags = certificate.connect_flags(false, false, true).unwrap();
+        let mut connect_packet = Packet::Connect {
+            fixed: FixedHeader::new(ControlPacketType::Connect, FixedHeaderFlags::Connect),
+            variable: ConnectVariableHeader::new(ProtocolLevel::Mqtt3_1_1, connect_flags, 60),
+            payload: certificate.into_connect_payload("Edge1".to_string(), None, None),
+        };
+
+        let mut bytes = BytesMut::from(&connect_packet.encode().unwrap()[..]);
+        let Packet::Connect { payload, .. } = Packet::decode(&mut bytes).unwrap() else {
+            panic!("Decoded packet is not of type Connect");
+        };
+
+        assert_eq!(payload.will_topic(), Some("spBv1.0/Plant1/NDEATH/Edge1"));
+        let mut will_message = BytesMut::from(payload.will_message().unwrap());
+        let death_payload = SparkplugPayload::decode(&mut will_message).unwrap();
+        assert_eq!(death_payload.bd_seq().unwrap(), 42);
+    }
+}
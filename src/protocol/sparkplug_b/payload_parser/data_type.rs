@@ -0,0 +1,128 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::sparkplug_b::sparkplug_error::SparkplugError;
+
+/// Sparkplug B datatype codes, arrays (22..=34) are not supported.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DataType {
+    Unknown = 0,
+    Int8 = 1,
+    Int16 = 2,
+    Int32 = 3,
+    Int64 = 4,
+    UInt8 = 5,
+    UInt16 = 6,
+    UInt32 = 7,
+    UInt64 = 8,
+    Float = 9,
+    Double = 10,
+    Boolean = 11,
+    String = 12,
+    DateTime = 13,
+    Text = 14,
+    Uuid = 15,
+    DataSet = 16,
+    Bytes = 17,
+    File = 18,
+    Template = 19,
+    PropertySet = 20,
+    PropertySetList = 21,
+}
+
+#[allow(dead_code)]
+impl DataType {
+    pub(crate) fn parse(code: u32) -> Result<DataType, SparkplugError> {
+        match code {
+            0 => Ok(DataType::Unknown),
+            1 => Ok(DataType::Int8),
+            2 => Ok(DataType::Int16),
+            3 => Ok(DataType::Int32),
+            4 => Ok(DataType::Int64),
+            5 => Ok(DataType::UInt8),
+            6 => Ok(DataType::UInt16),
+            7 => Ok(DataType::UInt32),
+            8 => Ok(DataType::UInt64),
+            9 => Ok(DataType::Float),
+            10 => Ok(DataType::Double),
+            11 => Ok(DataType::Boolean),
+            12 => Ok(DataType::String),
+            13 => Ok(DataType::DateTime),
+            14 => Ok(DataType::Text),
+            15 => Ok(DataType::Uuid),
+            16 => Ok(DataType::DataSet),
+            17 => Ok(DataType::Bytes),
+            18 => Ok(DataType::File),
+            19 => Ok(DataType::Template),
+            20 => Ok(DataType::PropertySet),
+            21 => Ok(DataType::PropertySetList),
+            _ => Err(SparkplugError::UnsupportedDataType(code)),
+        }
+    }
+
+    pub(crate) fn as_u32(&self) -> u32 {
+        *self as u32
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            DataType::Unknown => "Unknown",
+            DataType::Int8 => "Int8",
+            DataType::Int16 => "Int16",
+            DataType::Int32 => "Int32",
+            DataType::Int64 => "Int64",
+            DataType::UInt8 => "UInt8",
+            DataType::UInt16 => "UInt16",
+            DataType::UInt32 => "UInt32",
+            DataType::UInt64 => "UInt64",
+            DataType::Float => "Float",
+            DataType::Double => "Double",
+            DataType::Boolean => "Boolean",
+            DataType::String => "String",
+            DataType::DateTime => "DateTime",
+            DataType::Text => "Text",
+            DataType::Uuid => "UUID",
+            DataType::DataSet => "DataSet",
+            DataType::Bytes => "Bytes",
+            DataType::File => "File",
+            DataType::Template => "Template",
+            DataType::PropertySet => "PropertySet",
+            DataType::PropertySetList => "PropertySetList",
+        }
+    }
+}
+
+#[cfg(test)]
+mod data_type_tests {
+    use crate::protocol::sparkplug_b::payload_parser::data_type::DataType;
+    use crate::protocol::sparkplug_b::sparkplug_error::SparkplugError;
+
+    #[test]
+    fn data_type_parse_should_round_trip_all_codes() {
+        for code in 0..=21 {
+            let data_type = DataType::parse(code).unwrap();
+            assert_eq!(data_type.as_u32(), code);
+        }
+    }
+
+    #[test]
+    fn data_type_parse_should_reject_array_types() {
+        let result = DataType::parse(22);
+        assert!(matches!(
+            result,
+            Err(SparkplugError::UnsupportedDataType(22))
+        ));
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::byte_adapter::byte_operations::ByteOperations;
+use crate::protocol::sparkplug_b::payload_parser::data_type::DataType;
+use crate::protocol::sparkplug_b::payload_parser::metric::{Metric, MetricValue};
+use crate::protocol::sparkplug_b::payload_parser::payload::SparkplugPayload;
+use crate::protocol::sparkplug_b::sparkplug_error::SparkplugError;
+use crate::utils::protobuf::protobuf_handler::{
+    self, WIRE_TYPE_FIXED32, WIRE_TYPE_FIXED64, WIRE_TYPE_LENGTH_DELIMITED, WIRE_TYPE_VARINT,
+};
+use bytes::{Bytes, BytesMut};
+
+#[allow(dead_code)]
+impl SparkplugPayload {
+    pub(crate) fn decode(
+        bytes: &mut impl ByteOperations,
+    ) -> Result<SparkplugPayload, SparkplugError> {
+        let mut timestamp = None;
+        let mut metrics = Vec::new();
+        let mut seq = None;
+        let mut uuid = None;
+        let mut body = None;
+
+        while !bytes.is_empty() {
+            let (field_number, wire_type) = protobuf_handler::read_tag(bytes)?;
+            verify_wire_type(field_number, wire_type, Self::wire_type(field_number))?;
+            match field_number {
+                1 => timestamp = Some(protobuf_handler::read_varint(bytes)?),
+                2 => {
+                    let metric_bytes = protobuf_handler::read_length_delimited(bytes)?;
+                    metrics.push(Metric::decode(&mut BytesMut::from(&metric_bytes[..]))?);
+                }
+                3 => seq = Some(protobuf_handler::read_varint(bytes)?),
+                4 => uuid = Some(protobuf_handler::read_string(bytes)?),
+                5 => body = Some(Bytes::from(protobuf_handler::read_length_delimited(bytes)?)),
+                _ => protobuf_handler::skip_field(bytes, wire_type)?,
+            }
+        }
+
+        let mut payload = SparkplugPayload::new(timestamp, metrics, seq);
+        if let Some(uuid) = uuid {
+            payload.set_uuid(uuid);
+        }
+        if let Some(body) = body {
+            payload.set_body(body);
+        }
+        Ok(payload)
+    }
+
+    fn wire_type(field_number: u32) -> Option<u8> {
+        match field_number {
+            1 | 3 => Some(WIRE_TYPE_VARINT),
+            2 | 4 | 5 => Some(WIRE_TYPE_LENGTH_DELIMITED),
+            _ => None,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl Metric {
+    /// metadata (8), properties (9), dataset (17), template (18) and extension (19) are skipped.
+    pub(super) fn decode(bytes: &mut impl ByteOperations) -> Result<Metric, SparkplugError> {
+        let mut name = None;
+        let mut alias = None;
+        let mut timestamp = None;
+        let mut data_type = None;
+        let mut is_historical = false;
+        let mut is_transient = false;
+        let mut is_null = false;
+        let mut value = None;
+
+        while !bytes.is_empty() {
+            let (field_number, wire_type) = protobuf_handler::read_tag(bytes)?;
+            verify_wire_type(field_number, wire_type, Self::wire_type(field_number))?;
+            match field_number {
+                1 => name = Some(protobuf_handler::read_string(bytes)?),
+                2 => alias = Some(protobuf_handler::read_varint(bytes)?),
+                3 => timestamp = Some(protobuf_handler::read_varint(bytes)?),
+                4 => {
+                    let code = read_u32(bytes, field_number)?;
+                    data_type = Some(DataType::parse(code)?);
+                }
+                5 => is_historical = protobuf_handler::read_varint(bytes)? != 0,
+                6 => is_transient = protobuf_handler::read_varint(bytes)? != 0,
+                7 => is_null = protobuf_handler::read_varint(bytes)? != 0,
+                10 => value = Some(MetricValue::Int(read_u32(bytes, field_number)?)),
+                11 => value = Some(MetricValue::Long(protobuf_handler::read_varint(bytes)?)),
+                12 => {
+                    let bits = protobuf_handler::read_fixed32(bytes)?;
+                    value = Some(MetricValue::Float(f32::from_bits(bits)));
+                }
+                13 => {
+                    let bits = protobuf_handler::read_fixed64(bytes)?;
+                    value = Some(MetricValue::Double(f64::from_bits(bits)));
+                }
+                14 => {
+                    value = Some(MetricValue::Boolean(
+                        protobuf_handler::read_varint(bytes)? != 0,
+                    ))
+                }
+                15 => value = Some(MetricValue::String(protobuf_handler::read_string(bytes)?)),
+                16 => {
+                    let value_bytes = protobuf_handler::read_length_delimited(bytes)?;
+                    value = Some(MetricValue::Bytes(Bytes::from(value_bytes)));
+                }
+                _ => protobuf_handler::skip_field(bytes, wire_type)?,
+            }
+        }
+
+        Metric::self_create(
+            name,
+            alias,
+            timestamp,
+            data_type,
+            is_historical,
+            is_transient,
+            is_null,
+            value,
+        )
+    }
+
+    fn wire_type(field_number: u32) -> Option<u8> {
+        match field_number {
+            2..=7 | 10 | 11 | 14 => Some(WIRE_TYPE_VARINT),
+            1 | 15 | 16 => Some(WIRE_TYPE_LENGTH_DELIMITED),
+            12 => Some(WIRE_TYPE_FIXED32),
+            13 => Some(WIRE_TYPE_FIXED64),
+            _ => None,
+        }
+    }
+}
+
+/// A known field must arrive with the wire type of its declared type; fields this decoder
+/// skips (`expected` is `None`) may use any.
+fn verify_wire_type(
+    field_number: u32,
+    wire_type: u8,
+    expected: Option<u8>,
+) -> Result<(), SparkplugError> {
+    match expected {
+        Some(expected) if expected != wire_type => Err(SparkplugError::UnexpectedWireType(
+            field_number,
+            wire_type,
+            expected,
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// A varint declared `uint32`, which protobuf still lets a sender fill with up to 64 bits.
+fn read_u32(bytes: &mut impl ByteOperations, field_number: u32) -> Result<u32, SparkplugError> {
+    let value = protobuf_handler::read_varint(bytes)?;
+    u32::try_from(value).map_err(|_| SparkplugError::ValueOutOfRange(field_number, value))
+}
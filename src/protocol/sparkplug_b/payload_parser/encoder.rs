@@ -0,0 +1,122 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::sparkplug_b::payload_parser::metric::{Metric, MetricValue};
+use crate::protocol::sparkplug_b::payload_parser::payload::SparkplugPayload;
+use crate::utils::protobuf::protobuf_handler::{
+    self, WIRE_TYPE_FIXED32, WIRE_TYPE_FIXED64, WIRE_TYPE_LENGTH_DELIMITED, WIRE_TYPE_VARINT,
+};
+use bytes::BytesMut;
+
+#[allow(dead_code)]
+impl SparkplugPayload {
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut bytes = BytesMut::new();
+
+        if let Some(timestamp) = self.timestamp() {
+            protobuf_handler::write_tag(&mut bytes, 1, WIRE_TYPE_VARINT);
+            protobuf_handler::write_varint(&mut bytes, timestamp);
+        }
+        for metric in self.metrics() {
+            protobuf_handler::write_tag(&mut bytes, 2, WIRE_TYPE_LENGTH_DELIMITED);
+            protobuf_handler::write_length_delimited(&mut bytes, &metric.encode());
+        }
+        if let Some(seq) = self.seq() {
+            protobuf_handler::write_tag(&mut bytes, 3, WIRE_TYPE_VARINT);
+            protobuf_handler::write_varint(&mut bytes, seq);
+        }
+        if let Some(uuid) = self.uuid() {
+            protobuf_handler::write_tag(&mut bytes, 4, WIRE_TYPE_LENGTH_DELIMITED);
+            protobuf_handler::write_length_delimited(&mut bytes, uuid.as_bytes());
+        }
+        if let Some(body) = self.body() {
+            protobuf_handler::write_tag(&mut bytes, 5, WIRE_TYPE_LENGTH_DELIMITED);
+            protobuf_handler::write_length_delimited(&mut bytes, body);
+        }
+
+        bytes.to_vec()
+    }
+}
+
+#[allow(dead_code)]
+impl Metric {
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let mut bytes = BytesMut::new();
+
+        if let Some(name) = self.name() {
+            protobuf_handler::write_tag(&mut bytes, 1, WIRE_TYPE_LENGTH_DELIMITED);
+            protobuf_handler::write_length_delimited(&mut bytes, name.as_bytes());
+        }
+        if let Some(alias) = self.alias() {
+            protobuf_handler::write_tag(&mut bytes, 2, WIRE_TYPE_VARINT);
+            protobuf_handler::write_varint(&mut bytes, alias);
+        }
+        if let Some(timestamp) = self.timestamp() {
+            protobuf_handler::write_tag(&mut bytes, 3, WIRE_TYPE_VARINT);
+            protobuf_handler::write_varint(&mut bytes, timestamp);
+        }
+        if let Some(data_type) = self.data_type() {
+            protobuf_handler::write_tag(&mut bytes, 4, WIRE_TYPE_VARINT);
+            protobuf_handler::write_varint(&mut bytes, data_type.as_u32() as u64);
+        }
+        for (field_number, flag) in [
+            (5, self.is_historical()),
+            (6, self.is_transient()),
+            (7, self.is_null()),
+        ] {
+            if flag {
+                protobuf_handler::write_tag(&mut bytes, field_number, WIRE_TYPE_VARINT);
+                protobuf_handler::write_varint(&mut bytes, 1);
+            }
+        }
+        if let Some(value) = self.value() {
+            Self::encode_value(&mut bytes, value);
+        }
+
+        bytes.to_vec()
+    }
+
+    fn encode_value(bytes: &mut BytesMut, value: &MetricValue) {
+        match value {
+            MetricValue::Int(value) => {
+                protobuf_handler::write_tag(bytes, 10, WIRE_TYPE_VARINT);
+                protobuf_handler::write_varint(bytes, *value as u64);
+            }
+            MetricValue::Long(value) => {
+                protobuf_handler::write_tag(bytes, 11, WIRE_TYPE_VARINT);
+                protobuf_handler::write_varint(bytes, *value);
+            }
+            MetricValue::Float(value) => {
+                protobuf_handler::write_tag(bytes, 12, WIRE_TYPE_FIXED32);
+                protobuf_handler::write_fixed32(bytes, value.to_bits());
+            }
+            MetricValue::Double(value) => {
+                protobuf_handler::write_tag(bytes, 13, WIRE_TYPE_FIXED64);
+                protobuf_handler::write_fixed64(bytes, value.to_bits());
+            }
+            MetricValue::Boolean(value) => {
+                protobuf_handler::write_tag(bytes, 14, WIRE_TYPE_VARINT);
+                protobuf_handler::write_varint(bytes, *value as u64);
+            }
+            MetricValue::String(value) => {
+                protobuf_handler::write_tag(bytes, 15, WIRE_TYPE_LENGTH_DELIMITED);
+                protobuf_handler::write_length_delimited(bytes, value.as_bytes());
+            }
+            MetricValue::Bytes(value) => {
+                protobuf_handler::write_tag(bytes, 16, WIRE_TYPE_LENGTH_DELIMITED);
+                protobuf_handler::write_length_delimited(bytes, value);
+            }
+        }
+    }
+}
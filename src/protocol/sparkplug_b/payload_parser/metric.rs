@@ -0,0 +1,226 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::sparkplug_b::payload_parser::data_type::DataType;
+use crate::protocol::sparkplug_b::sparkplug_error::SparkplugError;
+use bytes::Bytes;
+
+pub(crate) const BD_SEQ_METRIC_NAME: &str = "bdSeq";
+
+/// The protobuf `oneof value` of a metric. Signed integers travel as their two's complement
+/// bit pattern, exactly as the Sparkplug B specification encodes them.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone)]
+pub enum MetricValue {
+    Int(u32),
+    Long(u64),
+    Float(f32),
+    Double(f64),
+    Boolean(bool),
+    String(String),
+    Bytes(Bytes),
+}
+
+#[allow(dead_code)]
+impl MetricValue {
+    pub(crate) fn is_compatible_with(&self, data_type: DataType) -> bool {
+        match self {
+            MetricValue::Int(_) => matches!(
+                data_type,
+                DataType::Int8
+                    | DataType::Int16
+                    | DataType::Int32
+                    | DataType::UInt8
+                    | DataType::UInt16
+                    | DataType::UInt32
+            ),
+            MetricValue::Long(_) => matches!(
+                data_type,
+                DataType::Int64 | DataType::UInt32 | DataType::UInt64 | DataType::DateTime
+            ),
+            MetricValue::Float(_) => data_type == DataType::Float,
+            MetricValue::Double(_) => data_type == DataType::Double,
+            MetricValue::Boolean(_) => data_type == DataType::Boolean,
+            MetricValue::String(_) => matches!(
+                data_type,
+                DataType::String | DataType::Text | DataType::Uuid
+            ),
+            MetricValue::Bytes(_) => matches!(data_type, DataType::Bytes | DataType::File),
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Metric {
+    name: Option<String>,
+    alias: Option<u64>,
+    timestamp: Option<u64>,
+    data_type: Option<DataType>,
+    is_historical: bool,
+    is_transient: bool,
+    is_null: bool,
+    value: Option<MetricValue>,
+}
+
+#[allow(dead_code)]
+impl Metric {
+    pub fn new(
+        name: &str,
+        data_type: DataType,
+        value: MetricValue,
+    ) -> Result<Metric, SparkplugError> {
+        Self::verify_value(data_type, &value)?;
+        Ok(Metric {
+            name: Some(name.to_string()),
+            alias: None,
+            timestamp: None,
+            data_type: Some(data_type),
+            is_historical: false,
+            is_transient: false,
+            is_null: false,
+            value: Some(value),
+        })
+    }
+
+    pub fn null(name: &str, data_type: DataType) -> Metric {
+        Metric {
+            name: Some(name.to_string()),
+            alias: None,
+            timestamp: None,
+            data_type: Some(data_type),
+            is_historical: false,
+            is_transient: false,
+            is_null: true,
+            value: None,
+        }
+    }
+
+    /// The birth/death sequence metric carried by NBIRTH and NDEATH.
+    pub fn bd_seq(bd_seq: u64) -> Metric {
+        Metric {
+            name: Some(BD_SEQ_METRIC_NAME.to_string()),
+            alias: None,
+            timestamp: None,
+            data_type: Some(DataType::UInt64),
+            is_historical: false,
+            is_transient: false,
+            is_null: false,
+            value: Some(MetricValue::Long(bd_seq)),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn self_create(
+        name: Option<String>,
+        alias: Option<u64>,
+        timestamp: Option<u64>,
+        data_type: Option<DataType>,
+        is_historical: bool,
+        is_transient: bool,
+        is_null: bool,
+        value: Option<MetricValue>,
+    ) -> Result<Metric, SparkplugError> {
+        if let (Some(data_type), Some(value)) = (data_type, &value) {
+            Self::verify_value(data_type, value)?;
+        }
+        Ok(Metric {
+            name,
+            alias,
+            timestamp,
+            data_type,
+            is_historical,
+            is_transient,
+            is_null,
+            value,
+        })
+    }
+
+    fn verify_value(data_type: DataType, value: &MetricValue) -> Result<(), SparkplugError> {
+        if !value.is_compatible_with(data_type) {
+            return Err(SparkplugError::DataTypeMismatch(data_type.name()));
+        }
+        Ok(())
+    }
+
+    pub fn set_alias(&mut self, alias: u64) {
+        self.alias = Some(alias);
+    }
+
+    pub fn set_timestamp(&mut self, timestamp: u64) {
+        self.timestamp = Some(timestamp);
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn alias(&self) -> Option<u64> {
+        self.alias
+    }
+
+    pub fn timestamp(&self) -> Option<u64> {
+        self.timestamp
+    }
+
+    pub fn data_type(&self) -> Option<DataType> {
+        self.data_type
+    }
+
+    pub fn is_historical(&self) -> bool {
+        self.is_historical
+    }
+
+    pub fn is_transient(&self) -> bool {
+        self.is_transient
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.is_null
+    }
+
+    pub fn value(&self) -> Option<&MetricValue> {
+        self.value.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod metric_tests {
+    use crate::protocol::sparkplug_b::payload_parser::data_type::DataType;
+    use crate::protocol::sparkplug_b::payload_parser::metric::{Metric, MetricValue};
+    use crate::protocol::sparkplug_b::sparkplug_error::SparkplugError;
+
+    #[test]
+    fn metric_should_accept_value_matching_data_type() {
+        let metric = Metric::new("Temperature", DataType::Double, MetricValue::Double(21.5));
+        assert!(metric.is_ok());
+    }
+
+    #[test]
+    fn metric_should_reject_value_not_matching_data_type() {
+        let result = Metric::new("Temperature", DataType::Double, MetricValue::Int(21));
+        assert!(matches!(
+            result,
+            Err(SparkplugError::DataTypeMismatch("Double"))
+        ));
+    }
+
+    #[test]
+    fn metric_bd_seq_should_be_uint64() {
+        let metric = Metric::bd_seq(3);
+        assert_eq!(metric.name(), Some("bdSeq"));
+        assert_eq!(metric.data_type(), Some(DataType::UInt64));
+        assert_eq!(metric.value(), Some(&MetricValue::Long(3)));
+    }
+}
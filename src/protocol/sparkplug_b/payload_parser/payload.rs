@@ -0,0 +1,225 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::sparkplug_b::payload_parser::metric::{
+    BD_SEQ_METRIC_NAME, Metric, MetricValue,
+};
+use crate::protocol::sparkplug_b::sequence::SequenceNumber;
+use crate::protocol::sparkplug_b::sparkplug_error::SparkplugError;
+use bytes::Bytes;
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct SparkplugPayload {
+    timestamp: Option<u64>,
+    metrics: Vec<Metric>,
+    seq: Option<u64>,
+    uuid: Option<String>,
+    body: Option<Bytes>,
+}
+
+#[allow(dead_code)]
+impl SparkplugPayload {
+    pub fn new(timestamp: Option<u64>, metrics: Vec<Metric>, seq: Option<u64>) -> Self {
+        SparkplugPayload {
+            timestamp,
+            metrics,
+            seq,
+            uuid: None,
+            body: None,
+        }
+    }
+
+    pub fn set_uuid(&mut self, uuid: String) {
+        self.uuid = Some(uuid);
+    }
+
+    pub fn set_body(&mut self, body: Bytes) {
+        self.body = Some(body);
+    }
+
+    pub fn timestamp(&self) -> Option<u64> {
+        self.timestamp
+    }
+
+    pub fn metrics(&self) -> &[Metric] {
+        &self.metrics
+    }
+
+    pub fn seq(&self) -> Option<u64> {
+        self.seq
+    }
+
+    pub fn uuid(&self) -> Option<&str> {
+        self.uuid.as_deref()
+    }
+
+    pub fn body(&self) -> Option<&[u8]> {
+        self.body.as_deref()
+    }
+
+    /// The payload sequence number, checked against the 0..=255 range of the specification.
+    pub fn sequence_number(&self) -> Result<Option<SequenceNumber>, SparkplugError> {
+        self.seq.map(SequenceNumber::try_from).transpose()
+    }
+
+    pub fn bd_seq(&self) -> Result<u64, SparkplugError> {
+        self.metrics
+            .iter()
+            .find(|metric| metric.name() == Some(BD_SEQ_METRIC_NAME))
+            .and_then(|metric| match metric.value() {
+                Some(MetricValue::Long(bd_seq)) => Some(*bd_seq),
+                Some(MetricValue::Int(bd_seq)) => Some(*bd_seq as u64),
+                _ => None,
+            })
+            .ok_or(SparkplugError::MissingBdSeq)
+    }
+}
+
+#[cfg(test)]
+mod sparkplug_payload_tests {
+    use crate::protocol::sparkplug_b::payload_parser::data_type::DataType;
+    use crate::protocol::sparkplug_b::payload_parser::metric::{Metric, MetricValue};
+    use crate::protocol::sparkplug_b::payload_parser::payload::SparkplugPayload;
+    use crate::protocol::sparkplug_b::sparkplug_error::SparkplugError;
+    use bytes::{Bytes, BytesMut};
+
+    fn birth_payload() -> SparkplugPayload {
+        let mut temperature =
+            Metric::new("Temperature", DataType::Float, MetricValue::Float(21.5)).unwrap();
+        temperature.set_alias(1);
+        temperature.set_timestamp(1_700_000_000_123);
+        let metrics = vec![
+            Metric::bd_seq(7),
+            temperature,
+            Metric::new("Running", DataType::Boolean, MetricValue::Boolean(true)).unwrap(),
+            Metric::new("Counter", DataType::Int32, MetricValue::Int((-5i32) as u32)).unwrap(),
+            Metric::new("Energy", DataType::Double, MetricValue::Double(1234.5)).unwrap(),
+            Metric::new(
+                "Serial",
+                DataType::String,
+                MetricValue::String("SN-42".to_string()),
+            )
+            .unwrap(),
+            Metric::new(
+                "Blob",
+                DataType::Bytes,
+                MetricValue::Bytes(Bytes::from_static(&[0, 1, 2])),
+            )
+            .unwrap(),
+            Metric::null("Pressure", DataType::Double),
+        ];
+        SparkplugPayload::new(Some(1_700_000_000_000), metrics, Some(0))
+    }
+
+    #[test]
+    fn sparkplug_payload_should_encode_and_decode() {
+        let mut expect_payload = birth_payload();
+        expect_payload.set_uuid("a-uuid".to_string());
+        expect_payload.set_body(Bytes::from_static(b"body"));
+
+        let mut bytes = BytesMut::from(&expect_payload.encode()[..]);
+        let payload = SparkplugPayload::decode(&mut bytes).unwrap();
+
+        assert_eq!(payload, expect_payload);
+    }
+
+    #[test]
+    fn sparkplug_payload_should_expose_bd_seq() {
+        assert_eq!(birth_payload().bd_seq().unwrap(), 7);
+    }
+
+    #[test]
+    fn sparkplug_payload_without_bd_seq_should_error() {
+        let payload = SparkplugPayload::new(None, vec![], Some(1));
+        assert!(matches!(
+            payload.bd_seq(),
+            Err(SparkplugError::MissingBdSeq)
+        ));
+    }
+
+    #[test]
+    fn sparkplug_payload_should_reject_seq_out_of_range() {
+        let payload = SparkplugPayload::new(None, vec![], Some(256));
+        assert!(matches!(
+            payload.sequence_number(),
+            Err(SparkplugError::SequenceOutOfRange(256))
+        ));
+    }
+
+    #[test]
+    fn sparkplug_payload_should_decode_known_wire_bytes() {
+        // timestamp = 1, one metric { name: "a", datatype: Int32, int_value: 5 }, seq = 2
+        let wire = [
+            0x08, 0x01, 0x12, 0x07, 0x0A, 0x01, b'a', 0x20, 0x03, 0x50, 0x05, 0x18, 0x02,
+        ];
+        let mut bytes = BytesMut::from(&wire[..]);
+        let payload = SparkplugPayload::decode(&mut bytes).unwrap();
+
+        assert_eq!(payload.timestamp(), Some(1));
+        assert_eq!(payload.seq(), Some(2));
+        assert_eq!(payload.metrics()[0].name(), Some("a"));
+        assert_eq!(payload.metrics()[0].data_type(), Some(DataType::Int32));
+        assert_eq!(payload.metrics()[0].value(), Some(&MetricValue::Int(5)));
+        assert_eq!(payload.encode(), wire.to_vec());
+    }
+
+    #[test]
+    fn sparkplug_payload_should_skip_unknown_fields() {
+        // field 99 (varint) followed by seq = 4
+        let wire = [0x98, 0x06, 0x2A, 0x18, 0x04];
+        let mut bytes = BytesMut::from(&wire[..]);
+        let payload = SparkplugPayload::decode(&mut bytes).unwrap();
+        assert_eq!(payload.seq(), Some(4));
+    }
+
+    #[test]
+    fn sparkplug_payload_should_reject_known_fields_with_the_wrong_wire_type() {
+        // seq = 2 sent as fixed32
+        let wire = [0x1D, 0x02, 0x00, 0x00, 0x00];
+        assert!(matches!(
+            SparkplugPayload::decode(&mut BytesMut::from(&wire[..])),
+            Err(SparkplugError::UnexpectedWireType(3, 5, 0))
+        ));
+
+        // metric { name: 1 as a varint }
+        let wire = [0x12, 0x02, 0x08, 0x01];
+        assert!(matches!(
+            SparkplugPayload::decode(&mut BytesMut::from(&wire[..])),
+            Err(SparkplugError::UnexpectedWireType(1, 0, 2))
+        ));
+    }
+
+    #[test]
+    fn sparkplug_payload_should_reject_int_value_above_32_bits() {
+        // metric { datatype: UInt32, int_value: 2^32 }
+        let wire = [0x12, 0x08, 0x20, 0x07, 0x50, 0x80, 0x80, 0x80, 0x80, 0x10];
+        assert!(matches!(
+            SparkplugPayload::decode(&mut BytesMut::from(&wire[..])),
+            Err(SparkplugError::ValueOutOfRange(10, 0x1_0000_0000))
+        ));
+    }
+
+    #[test]
+    fn sparkplug_payload_should_reject_mismatched_metric_value() {
+        // metric { datatype: Boolean, string_value: "x" }
+        let wire = [0x12, 0x05, 0x20, 0x0B, 0x7A, 0x01, b'x'];
+        let mut bytes = BytesMut::from(&wire[..]);
+        let result = SparkplugPayload::decode(&mut bytes);
+        assert!(matches!(
+            result,
+            Err(SparkplugError::DataTypeMismatch("Boolean"))
+        ));
+    }
+}
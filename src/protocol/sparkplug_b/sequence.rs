@@ -0,0 +1,140 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::sparkplug_b::sparkplug_error::SparkplugError;
+
+/// The `seq` of a Sparkplug payload. NBIRTH starts at 0 and every following message of the
+/// edge node increments it, wrapping from 255 back to 0.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub(crate) struct SequenceNumber(u8);
+
+#[allow(dead_code)]
+impl SequenceNumber {
+    pub fn new(value: u8) -> Self {
+        SequenceNumber(value)
+    }
+
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+
+    pub fn next(&self) -> SequenceNumber {
+        SequenceNumber(self.0.wrapping_add(1))
+    }
+
+    pub fn follows(&self, previous: SequenceNumber) -> bool {
+        previous.next() == *self
+    }
+}
+
+impl TryFrom<u64> for SequenceNumber {
+    type Error = SparkplugError;
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        u8::try_from(value)
+            .map(SequenceNumber)
+            .map_err(|_| SparkplugError::SequenceOutOfRange(value))
+    }
+}
+
+/// Hands out the `seq` values an edge node puts on its messages.
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+pub(crate) struct SequenceCounter {
+    next: SequenceNumber,
+}
+
+#[allow(dead_code)]
+impl SequenceCounter {
+    pub fn new() -> Self {
+        SequenceCounter::default()
+    }
+
+    /// NBIRTH always carries seq 0, so a rebirth restarts the counter.
+    pub fn reset(&mut self) {
+        self.next = SequenceNumber::default();
+    }
+
+    pub fn next_seq(&mut self) -> SequenceNumber {
+        let seq = self.next;
+        self.next = seq.next();
+        seq
+    }
+}
+
+/// Tracks the birth/death sequence shared by an NBIRTH and the NDEATH registered as its will.
+/// The value moves on once per MQTT session and wraps from 255 back to 0.
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+pub(crate) struct BdSeqCounter {
+    current: SequenceNumber,
+}
+
+#[allow(dead_code)]
+impl BdSeqCounter {
+    pub fn new(start: SequenceNumber) -> Self {
+        BdSeqCounter { current: start }
+    }
+
+    pub fn current(&self) -> SequenceNumber {
+        self.current
+    }
+
+    /// Called before each new CONNECT so the new NDEATH will and NBIRTH share a fresh value.
+    pub fn advance(&mut self) -> SequenceNumber {
+        self.current = self.current.next();
+        self.current
+    }
+}
+
+#[cfg(test)]
+mod sequence_tests {
+    use crate::protocol::sparkplug_b::sequence::{BdSeqCounter, SequenceCounter, SequenceNumber};
+    use crate::protocol::sparkplug_b::sparkplug_error::SparkplugError;
+
+    #[test]
+    fn sequence_number_should_wrap_from_255_to_0() {
+        let seq = SequenceNumber::new(255);
+        assert_eq!(seq.next(), SequenceNumber::new(0));
+        assert!(SequenceNumber::new(0).follows(seq));
+        assert!(!SequenceNumber::new(2).follows(SequenceNumber::new(0)));
+    }
+
+    #[test]
+    fn sequence_number_try_from_should_reject_values_above_255() {
+        assert_eq!(SequenceNumber::try_from(255).unwrap().value(), 255);
+        assert!(matches!(
+            SequenceNumber::try_from(256),
+            Err(SparkplugError::SequenceOutOfRange(256))
+        ));
+    }
+
+    #[test]
+    fn sequence_counter_should_start_at_zero_and_reset_on_rebirth() {
+        let mut counter = SequenceCounter::new();
+        assert_eq!(counter.next_seq().value(), 0);
+        assert_eq!(counter.next_seq().value(), 1);
+        counter.reset();
+        assert_eq!(counter.next_seq().value(), 0);
+    }
+
+    #[test]
+    fn bd_seq_counter_should_advance_and_wrap() {
+        let mut counter = BdSeqCounter::new(SequenceNumber::new(254));
+        assert_eq!(counter.advance().value(), 255);
+        assert_eq!(counter.advance().value(), 0);
+        assert_eq!(counter.current().value(), 0);
+    }
+}
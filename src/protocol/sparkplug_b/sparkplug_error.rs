@@ -0,0 +1,62 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+use crate::utils::code_error::CodeError;
+
+#[allow(dead_code)]
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum SparkplugError {
+    #[error("Topic namespace must be spBv1.0, got: {0}")]
+    InvalidNamespace(String),
+
+    #[error("Unknown Sparkplug message type: {0}")]
+    UnknownMessageType(String),
+
+    #[error("Sparkplug topic has the wrong number of levels: {0}")]
+    InvalidTopicLevels(String),
+
+    #[error("Sparkplug identifier is invalid: {0:?}")]
+    InvalidIdentifier(String),
+
+    #[error("Message type {0} requires a device id")]
+    MissingDeviceId(&'static str),
+
+    #[error("Message type {0} does not allow a device id")]
+    UnexpectedDeviceId(&'static str),
+
+    #[error("Sparkplug datatype {0} is not supported")]
+    UnsupportedDataType(u32),
+
+    #[error("Metric value does not match datatype {0:?}")]
+    DataTypeMismatch(&'static str),
+
+    #[error("Field {0} has wire type {1}, expected {2}")]
+    UnexpectedWireType(u32, u8, u8),
+
+    #[error("Field {0} value {1} does not fit in 32 bits")]
+    ValueOutOfRange(u32, u64),
+
+    #[error("Sequence number {0} is out of range 0..=255")]
+    SequenceOutOfRange(u64),
+
+    #[error("Payload does not carry a bdSeq metric")]
+    MissingBdSeq,
+
+    #[error("from MqttProtocolError: {0}")]
+    MqttProtocolError(#[from] MqttProtocolError),
+
+    #[error("from CodeError: {0}")]
+    CodeError(#[from] CodeError),
+}
@@ -0,0 +1,344 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::mqtt4::variable_header_parser::publish_parser::variable_header::PublishVariableHeader;
+use crate::protocol::sparkplug_b::sparkplug_error::SparkplugError;
+use std::fmt;
+
+pub(crate) const NAMESPACE: &str = "spBv1.0";
+const STATE: &str = "STATE";
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SparkplugMessageType {
+    NBirth,
+    NDeath,
+    DBirth,
+    DDeath,
+    NData,
+    DData,
+    NCmd,
+    DCmd,
+    State,
+}
+
+#[allow(dead_code)]
+impl SparkplugMessageType {
+    pub(crate) fn parse(value: &str) -> Result<SparkplugMessageType, SparkplugError> {
+        match value {
+            "NBIRTH" => Ok(SparkplugMessageType::NBirth),
+            "NDEATH" => Ok(SparkplugMessageType::NDeath),
+            "DBIRTH" => Ok(SparkplugMessageType::DBirth),
+            "DDEATH" => Ok(SparkplugMessageType::DDeath),
+            "NDATA" => Ok(SparkplugMessageType::NData),
+            "DDATA" => Ok(SparkplugMessageType::DData),
+            "NCMD" => Ok(SparkplugMessageType::NCmd),
+            "DCMD" => Ok(SparkplugMessageType::DCmd),
+            STATE => Ok(SparkplugMessageType::State),
+            _ => Err(SparkplugError::UnknownMessageType(value.to_string())),
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            SparkplugMessageType::NBirth => "NBIRTH",
+            SparkplugMessageType::NDeath => "NDEATH",
+            SparkplugMessageType::DBirth => "DBIRTH",
+            SparkplugMessageType::DDeath => "DDEATH",
+            SparkplugMessageType::NData => "NDATA",
+            SparkplugMessageType::DData => "DDATA",
+            SparkplugMessageType::NCmd => "NCMD",
+            SparkplugMessageType::DCmd => "DCMD",
+            SparkplugMessageType::State => STATE,
+        }
+    }
+
+    pub(crate) fn is_device_message(&self) -> bool {
+        matches!(
+            self,
+            SparkplugMessageType::DBirth
+                | SparkplugMessageType::DDeath
+                | SparkplugMessageType::DData
+                | SparkplugMessageType::DCmd
+        )
+    }
+}
+
+/// `spBv1.0/<group_id>/<message_type>/<edge_node_id>[/<device_id>]` or `spBv1.0/STATE/<host_id>`
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SparkplugTopic {
+    EdgeNode {
+        group_id: String,
+        message_type: SparkplugMessageType,
+        edge_node_id: String,
+        device_id: Option<String>,
+    },
+    State {
+        host_id: String,
+    },
+}
+
+#[allow(dead_code)]
+impl SparkplugTopic {
+    pub fn node(
+        group_id: &str,
+        message_type: SparkplugMessageType,
+        edge_node_id: &str,
+    ) -> Result<SparkplugTopic, SparkplugError> {
+        Self::edge_node(group_id, message_type, edge_node_id, None)
+    }
+
+    pub fn device(
+        group_id: &str,
+        message_type: SparkplugMessageType,
+        edge_node_id: &str,
+        device_id: &str,
+    ) -> Result<SparkplugTopic, SparkplugError> {
+        Self::edge_node(group_id, message_type, edge_node_id, Some(device_id))
+    }
+
+    pub fn state(host_id: &str) -> Result<SparkplugTopic, SparkplugError> {
+        Self::verify_identifier(host_id)?;
+        Ok(SparkplugTopic::State {
+            host_id: host_id.to_string(),
+        })
+    }
+
+    pub(crate) fn parse(topic_name: &str) -> Result<SparkplugTopic, SparkplugError> {
+        PublishVariableHeader::verify_topic_name(topic_name)?;
+
+        let levels: Vec<&str> = topic_name.split('/').collect();
+        if levels[0] != NAMESPACE {
+            return Err(SparkplugError::InvalidNamespace(levels[0].to_string()));
+        }
+
+        match levels.as_slice() {
+            [_, STATE, host_id] => Self::state(host_id),
+            [_, group_id, message_type, edge_node_id] => Self::node(
+                group_id,
+                SparkplugMessageType::parse(message_type)?,
+                edge_node_id,
+            ),
+            [_, group_id, message_type, edge_node_id, device_id] => Self::device(
+                group_id,
+                SparkplugMessageType::parse(message_type)?,
+                edge_node_id,
+                device_id,
+            ),
+            _ => Err(SparkplugError::InvalidTopicLevels(topic_name.to_string())),
+        }
+    }
+
+    pub(crate) fn topic_name(&self) -> String {
+        self.to_string()
+    }
+
+    pub fn message_type(&self) -> SparkplugMessageType {
+        match self {
+            SparkplugTopic::EdgeNode { message_type, .. } => *message_type,
+            SparkplugTopic::State { .. } => SparkplugMessageType::State,
+        }
+    }
+
+    fn edge_node(
+        group_id: &str,
+        message_type: SparkplugMessageType,
+        edge_node_id: &str,
+        device_id: Option<&str>,
+    ) -> Result<SparkplugTopic, SparkplugError> {
+        Self::verify_identifier(group_id)?;
+        Self::verify_identifier(edge_node_id)?;
+
+        match (message_type, device_id) {
+            (SparkplugMessageType::State, _) => {
+                return Err(SparkplugError::InvalidTopicLevels(
+                    message_type.as_str().to_string(),
+                ));
+            }
+            (message_type, None) if message_type.is_device_message() => {
+                return Err(SparkplugError::MissingDeviceId(message_type.as_str()));
+            }
+            (message_type, Some(_)) if !message_type.is_device_message() => {
+                return Err(SparkplugError::UnexpectedDeviceId(message_type.as_str()));
+            }
+            (_, Some(device_id)) => Self::verify_identifier(device_id)?,
+            (_, None) => {}
+        }
+
+        let topic = SparkplugTopic::EdgeNode {
+            group_id: group_id.to_string(),
+            message_type,
+            edge_node_id: edge_node_id.to_string(),
+            device_id: device_id.map(str::to_string),
+        };
+        PublishVariableHeader::verify_topic_name(&topic.topic_name())?;
+        Ok(topic)
+    }
+
+    fn verify_identifier(identifier: &str) -> Result<(), SparkplugError> {
+        if identifier.is_empty() || identifier.contains(['/', '+', '#']) {
+            return Err(SparkplugError::InvalidIdentifier(identifier.to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SparkplugTopic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SparkplugTopic::EdgeNode {
+                group_id,
+                message_type,
+                edge_node_id,
+                device_id,
+            } => {
+                write!(
+                    f,
+                    "{}/{}/{}/{}",
+                    NAMESPACE,
+                    group_id,
+                    message_type.as_str(),
+                    edge_node_id
+                )?;
+                if let Some(device_id) = device_id {
+                    write!(f, "/{}", device_id)?;
+                }
+                Ok(())
+            }
+            SparkplugTopic::State { host_id } => write!(f, "{}/{}/{}", NAMESPACE, STATE, host_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod topic_namespace_tests {
+    use crate::protocol::common::topic_error::TopicError;
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
+    use crate::protocol::sparkplug_b::sparkplug_error::SparkplugError;
+    use crate::protocol::sparkplug_b::topic_namespace::{SparkplugMessageType, SparkplugTopic};
+
+    #[test]
+    fn sparkplug_topic_should_parse_node_birth() {
+        let topic = SparkplugTopic::parse("spBv1.0/plant1/NBIRTH/edge7").unwrap();
+        assert_eq!(
+            topic,
+            SparkplugTopic::EdgeNode {
+                group_id: "plant1".to_string(),
+                message_type: SparkplugMessageType::NBirth,
+                edge_node_id: "edge7".to_string(),
+                device_id: None,
+            }
+        );
+    }
+
+    #[test]
+    fn sparkplug_topic_should_parse_device_data() {
+        let topic = SparkplugTopic::parse("spBv1.0/plant1/DDATA/edge7/pump03").unwrap();
+        assert_eq!(topic.message_type(), SparkplugMessageType::DData);
+        assert!(matches!(
+            topic,
+            SparkplugTopic::EdgeNode { device_id: Some(ref device_id), .. } if device_id == "pump03"
+        ));
+    }
+
+    #[test]
+    fn sparkplug_topic_should_parse_state() {
+        let topic = SparkplugTopic::parse("spBv1.0/STATE/scada01").unwrap();
+        assert_eq!(
+            topic,
+            SparkplugTopic::State {
+                host_id: "scada01".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn sparkplug_topic_should_round_trip_through_topic_name() {
+        for topic_name in [
+            "spBv1.0/g/NBIRTH/e",
+            "spBv1.0/g/NDEATH/e",
+            "spBv1.0/g/NDATA/e",
+            "spBv1.0/g/NCMD/e",
+            "spBv1.0/g/DBIRTH/e/d",
+            "spBv1.0/g/DDEATH/e/d",
+            "spBv1.0/g/DDATA/e/d",
+            "spBv1.0/g/DCMD/e/d",
+            "spBv1.0/STATE/h",
+        ] {
+            let topic = SparkplugTopic::parse(topic_name).unwrap();
+            assert_eq!(topic.topic_name(), topic_name);
+        }
+    }
+
+    #[test]
+    fn sparkplug_topic_should_reject_other_namespaces() {
+        let result = SparkplugTopic::parse("spAv1.0/g/NBIRTH/e");
+        assert!(matches!(result, Err(SparkplugError::InvalidNamespace(_))));
+    }
+
+    #[test]
+    fn sparkplug_topic_should_reject_unknown_message_type() {
+        let result = SparkplugTopic::parse("spBv1.0/g/NFOO/e");
+        assert!(matches!(result, Err(SparkplugError::UnknownMessageType(_))));
+    }
+
+    #[test]
+    fn sparkplug_topic_device_message_requires_device_id() {
+        let result = SparkplugTopic::parse("spBv1.0/g/DDATA/e");
+        assert!(matches!(
+            result,
+            Err(SparkplugError::MissingDeviceId("DDATA"))
+        ));
+    }
+
+    #[test]
+    fn sparkplug_topic_node_message_must_not_carry_device_id() {
+        let result = SparkplugTopic::node("g", SparkplugMessageType::NData, "e")
+            .and_then(|_| SparkplugTopic::device("g", SparkplugMessageType::NData, "e", "d"));
+        assert!(matches!(
+            result,
+            Err(SparkplugError::UnexpectedDeviceId("NDATA"))
+        ));
+    }
+
+    #[test]
+    fn sparkplug_topic_should_reject_wildcards_through_publish_topic_validation() {
+        let result = SparkplugTopic::parse("spBv1.0/g/NDATA/+");
+        assert!(matches!(
+            result,
+            Err(SparkplugError::MqttProtocolError(
+                MqttProtocolError::InvalidTopicName(TopicError::WildcardInTopicName(16, '+'))
+            ))
+        ));
+    }
+
+    #[test]
+    fn sparkplug_topic_should_reject_wildcards_in_constructed_identifiers() {
+        let result = SparkplugTopic::node("g", SparkplugMessageType::NBirth, "e#");
+        assert!(matches!(result, Err(SparkplugError::InvalidIdentifier(_))));
+    }
+
+    #[test]
+    fn sparkplug_topic_should_reject_wrong_level_count() {
+        let result = SparkplugTopic::parse("spBv1.0/g/DDATA/e/d/extra");
+        assert!(matches!(result, Err(SparkplugError::InvalidTopicLevels(_))));
+    }
+
+    #[test]
+    fn sparkplug_topic_should_reject_empty_levels() {
+        let result = SparkplugTopic::parse("spBv1.0//NBIRTH/e");
+        assert!(matches!(result, Err(SparkplugError::InvalidIdentifier(_))));
+    }
+}
@@ -0,0 +1,422 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::common::topic_error::TopicError;
+use crate::protocol::common::topic_filter::TopicFilter;
+use crate::protocol::common::topic_name::{TOPIC_LEVEL_SEPARATOR, TopicName};
+use crate::protocol::mqtt4::payload_parser::subscribe_parser::payload::SubscribePayload;
+use crate::protocol::topic_matching::topic_matcher::{MULTI_LEVEL, SINGLE_LEVEL};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// One subscription found by `SubscriptionIndex::matches`.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct SubscriptionMatch<'a, S, V> {
+    pub topic_filter: &'a TopicFilter,
+    pub subscriber: &'a S,
+    pub value: &'a V,
+}
+
+#[derive(Debug)]
+struct Node<S, V> {
+    children: HashMap<Box<str>, Node<S, V>>,
+    topic_filter: Option<TopicFilter>,
+    subscribers: HashMap<S, V>,
+}
+
+impl<S, V> Default for Node<S, V> {
+    fn default() -> Self {
+        Node {
+            children: HashMap::new(),
+            topic_filter: None,
+            subscribers: HashMap::new(),
+        }
+    }
+}
+
+impl<S, V> Node<S, V> {
+    fn is_empty(&self) -> bool {
+        self.children.is_empty() && self.subscribers.is_empty()
+    }
+}
+
+/// Topic filters of many subscribers in a trie with one node per filter level, so finding the
+/// subscriptions matching a topic name costs a few hash lookups per level of the name no matter
+/// how many filters are stored. `V` is whatever a subscription carries, the granted QoS usually.
+#[allow(dead_code)]
+#[derive(Debug)]
+pub struct SubscriptionIndex<S, V = QoSCode> {
+    root: Node<S, V>,
+    len: usize,
+}
+
+impl<S, V> Default for SubscriptionIndex<S, V> {
+    fn default() -> Self {
+        SubscriptionIndex {
+            root: Node::default(),
+            len: 0,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl<S: Eq + Hash + Clone, V> SubscriptionIndex<S, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of (topic filter, subscriber) pairs.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds a subscription, returning the value it replaces when the subscriber already had
+    /// this exact filter.
+    pub fn insert(&mut self, topic_filter: &TopicFilter, subscriber: S, value: V) -> Option<V> {
+        let mut node = &mut self.root;
+        for level in topic_filter.levels() {
+            node = node.children.entry(level.into()).or_default();
+        }
+        node.topic_filter
+            .get_or_insert_with(|| topic_filter.clone());
+        let replaced = node.subscribers.insert(subscriber, value);
+        if replaced.is_none() {
+            self.len += 1;
+        }
+        replaced
+    }
+
+    /// Removes a subscription and prunes the trie nodes nothing uses any more.
+    pub fn remove(&mut self, topic_filter: &TopicFilter, subscriber: &S) -> Option<V> {
+        let levels: Vec<&str> = topic_filter.levels().collect();
+        let removed = Self::remove_from(&mut self.root, &levels, subscriber);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    fn remove_from(node: &mut Node<S, V>, levels: &[&str], subscriber: &S) -> Option<V> {
+        let Some((level, rest)) = levels.split_first() else {
+            let removed = node.subscribers.remove(subscriber);
+            if node.subscribers.is_empty() {
+                node.topic_filter = None;
+            }
+            return removed;
+        };
+        let child = node.children.get_mut(*level)?;
+        let removed = Self::remove_from(child, rest, subscriber);
+        if child.is_empty() {
+            node.children.remove(*level);
+        }
+        removed
+    }
+
+    /// Removes every subscription of `subscriber`, e.g. when its session ends.
+    pub fn remove_subscriber(&mut self, subscriber: &S) -> usize {
+        let removed = Self::remove_subscriber_from(&mut self.root, subscriber);
+        self.len -= removed;
+        removed
+    }
+
+    fn remove_subscriber_from(node: &mut Node<S, V>, subscriber: &S) -> usize {
+        let mut removed = usize::from(node.subscribers.remove(subscriber).is_some());
+        if node.subscribers.is_empty() {
+            node.topic_filter = None;
+        }
+        node.children.retain(|_, child| {
+            removed += Self::remove_subscriber_from(child, subscriber);
+            !child.is_empty()
+        });
+        removed
+    }
+
+    pub fn get(&self, topic_filter: &TopicFilter, subscriber: &S) -> Option<&V> {
+        let mut node = &self.root;
+        for level in topic_filter.levels() {
+            node = node.children.get(level)?;
+        }
+        node.subscribers.get(subscriber)
+    }
+
+    /// Calls `visit` for every subscription whose filter matches `topic_name`. A subscriber with
+    /// several matching filters is visited once per filter.
+    pub fn for_each_match<'a>(
+        &'a self,
+        topic_name: &TopicName,
+        mut visit: impl FnMut(SubscriptionMatch<'a, S, V>),
+    ) {
+        let levels: Vec<&str> = topic_name.as_str().split(TOPIC_LEVEL_SEPARATOR).collect();
+        // wildcards in the first level never match topic names reserved by the server
+        let wildcards_allowed = !topic_name.is_system();
+        Self::visit_node(&self.root, &levels, wildcards_allowed, &mut visit);
+    }
+
+    fn visit_node<'a>(
+        node: &'a Node<S, V>,
+        levels: &[&str],
+        wildcards_allowed: bool,
+        visit: &mut impl FnMut(SubscriptionMatch<'a, S, V>),
+    ) {
+        if wildcards_allowed {
+            // `#` matches the remaining levels, including none at all
+            if let Some(multi_level) = node.children.get(MULTI_LEVEL) {
+                Self::visit_subscribers(multi_level, visit);
+            }
+        }
+
+        let Some((level, rest)) = levels.split_first() else {
+            Self::visit_subscribers(node, visit);
+            return;
+        };
+        if let Some(child) = node.children.get(*level) {
+            Self::visit_node(child, rest, true, visit);
+        }
+        if wildcards_allowed && let Some(single_level) = node.children.get(SINGLE_LEVEL) {
+            Self::visit_node(single_level, rest, true, visit);
+        }
+    }
+
+    fn visit_subscribers<'a>(
+        node: &'a Node<S, V>,
+        visit: &mut impl FnMut(SubscriptionMatch<'a, S, V>),
+    ) {
+        if let Some(topic_filter) = &node.topic_filter {
+            for (subscriber, value) in &node.subscribers {
+                visit(SubscriptionMatch {
+                    topic_filter,
+                    subscriber,
+                    value,
+                });
+            }
+        }
+    }
+
+    /// Every subscription whose filter matches `topic_name`.
+    pub fn matches<'a>(&'a self, topic_name: &TopicName) -> Vec<SubscriptionMatch<'a, S, V>> {
+        let mut matches = Vec::new();
+        self.for_each_match(topic_name, |found| matches.push(found));
+        matches
+    }
+}
+
+#[allow(dead_code)]
+impl<S: Eq + Hash + Clone> SubscriptionIndex<S, QoSCode> {
+    /// Adds every topic filter of a SUBSCRIBE with its requested QoS.
+    pub fn subscribe(
+        &mut self,
+        subscriber: &S,
+        payload: &SubscribePayload,
+    ) -> Result<(), TopicError> {
+        for (topic_filter, qos) in payload.subscription_and_qos_tuples() {
+            let topic_filter = TopicFilter::new(topic_filter.as_str())?;
+            self.insert(&topic_filter, subscriber.clone(), *qos);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod subscription_index_tests {
+    use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::common::topic_filter::TopicFilter;
+    use crate::protocol::common::topic_name::TopicName;
+    use crate::protocol::mqtt4::payload_parser::subscribe_parser::payload::SubscribePayload;
+    use crate::protocol::topic_matching::subscription_index::SubscriptionIndex;
+    use crate::protocol::topic_matching::topic_matcher;
+
+    fn filter(topic_filter: &str) -> TopicFilter {
+        TopicFilter::new(topic_filter).unwrap()
+    }
+
+    fn name(topic_name: &str) -> TopicName {
+        TopicName::new(topic_name).unwrap()
+    }
+
+    fn matching(index: &SubscriptionIndex<u32, ()>, topic_name: &str) -> Vec<(String, u32)> {
+        let mut matches: Vec<(String, u32)> = index
+            .matches(&name(topic_name))
+            .into_iter()
+            .map(|found| (found.topic_filter.to_string(), *found.subscriber))
+            .collect();
+        matches.sort();
+        matches
+    }
+
+    #[test]
+    fn index_should_return_every_matching_subscriber() {
+        let mut index = SubscriptionIndex::new();
+        for (subscriber, topic_filter) in [
+            "sport/tennis/player1",
+            "sport/tennis/+",
+            "sport/#",
+            "#",
+            "+/+/+",
+            "sport/+/player1/#",
+            "finance/#",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            index.insert(&filter(topic_filter), subscriber as u32, ());
+        }
+
+        assert_eq!(
+            matching(&index, "sport/tennis/player1"),
+            vec![
+                ("#".to_string(), 3),
+                ("+/+/+".to_string(), 4),
+                ("sport/#".to_string(), 2),
+                ("sport/+/player1/#".to_string(), 5),
+                ("sport/tennis/+".to_string(), 1),
+                ("sport/tennis/player1".to_string(), 0),
+            ]
+        );
+        assert_eq!(
+            matching(&index, "sport"),
+            vec![("#".to_string(), 3), ("sport/#".to_string(), 2)]
+        );
+        assert_eq!(matching(&index, "weather"), vec![("#".to_string(), 3)]);
+    }
+
+    #[test]
+    fn index_should_not_match_system_topics_with_leading_wildcards() {
+        let mut index = SubscriptionIndex::new();
+        index.insert(&filter("#"), 1, ());
+        index.insert(&filter("+/uptime"), 2, ());
+        index.insert(&filter("$SYS/#"), 3, ());
+        index.insert(&filter("$SYS/+"), 4, ());
+
+        assert_eq!(
+            matching(&index, "$SYS/uptime"),
+            vec![("$SYS/#".to_string(), 3), ("$SYS/+".to_string(), 4)]
+        );
+        assert_eq!(matching(&index, "a/$SYS").len(), 1);
+    }
+
+    #[test]
+    fn index_should_replace_and_remove_subscriptions() {
+        let mut index = SubscriptionIndex::new();
+        assert_eq!(index.insert(&filter("a/+"), "c1", QoSCode::Qos0), None);
+        assert_eq!(
+            index.insert(&filter("a/+"), "c1", QoSCode::Qos2),
+            Some(QoSCode::Qos0)
+        );
+        index.insert(&filter("a/+"), "c2", QoSCode::Qos1);
+        index.insert(&filter("a/b/#"), "c1", QoSCode::Qos1);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.get(&filter("a/+"), &"c1"), Some(&QoSCode::Qos2));
+
+        assert_eq!(index.remove(&filter("a/+"), &"c1"), Some(QoSCode::Qos2));
+        assert_eq!(index.remove(&filter("a/+"), &"c1"), None);
+        assert_eq!(index.matches(&name("a/b")).len(), 2);
+
+        assert_eq!(index.remove_subscriber(&"c1"), 1);
+        assert_eq!(index.remove_subscriber(&"c2"), 1);
+        assert!(index.is_empty());
+        assert!(index.root.is_empty());
+    }
+
+    #[test]
+    fn index_should_subscribe_from_subscribe_payload() {
+        let payload = SubscribePayload::new(vec![
+            ("a/#".to_string(), QoSCode::Qos1),
+            ("b/+".to_string(), QoSCode::Qos2),
+        ]);
+        let mut index = SubscriptionIndex::new();
+        index.subscribe(&7u16, &payload).unwrap();
+
+        let matches = index.matches(&name("b/c"));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].value, &QoSCode::Qos2);
+
+        let invalid = SubscribePayload::new(vec![("a/#/b".to_string(), QoSCode::Qos0)]);
+        assert!(index.subscribe(&7u16, &invalid).is_err());
+    }
+
+    #[test]
+    fn index_should_agree_with_the_single_pair_matcher_on_many_filters() {
+        let words = ["a", "b", "c", "", "$s", "+", "#"];
+        let mut filters = Vec::new();
+        for first in words {
+            for second in words {
+                for third in words {
+                    for topic_filter in [
+                        first.to_string(),
+                        format!("{}/{}", first, second),
+                        format!("{}/{}/{}", first, second, third),
+                    ] {
+                        if let Ok(topic_filter) = TopicFilter::new(topic_filter) {
+                            filters.push(topic_filter);
+                        }
+                    }
+                }
+            }
+        }
+        let mut index = SubscriptionIndex::new();
+        for (subscriber, topic_filter) in filters.iter().enumerate() {
+            index.insert(topic_filter, subscriber as u32, ());
+        }
+
+        for topic_name in [
+            "a", "a/b", "a/b/c", "/", "//", "a//c", "$s/b", "$s", "b/$s/a/c",
+        ] {
+            let topic_name = name(topic_name);
+            let mut expected: Vec<u32> = filters
+                .iter()
+                .enumerate()
+                .filter(|(_, topic_filter)| topic_matcher::matches(topic_filter, &topic_name))
+                .map(|(subscriber, _)| subscriber as u32)
+                .collect();
+            let mut actual: Vec<u32> = index
+                .matches(&topic_name)
+                .iter()
+                .map(|found| *found.subscriber)
+                .collect();
+            expected.sort();
+            actual.sort();
+            assert_eq!(actual, expected, "{}", topic_name);
+        }
+    }
+
+    #[test]
+    fn index_should_handle_hundreds_of_thousands_of_filters() {
+        let mut index = SubscriptionIndex::new();
+        for device in 0..100_000u32 {
+            index.insert(&filter(&format!("site/{}/temperature", device)), device, ());
+            index.insert(&filter(&format!("site/{}/#", device)), device, ());
+        }
+        index.insert(&filter("site/+/temperature"), u32::MAX, ());
+        assert_eq!(index.len(), 200_001);
+
+        let mut found = Vec::new();
+        for _ in 0..100 {
+            found = matching(&index, "site/4242/temperature");
+        }
+        assert_eq!(
+            found,
+            vec![
+                ("site/+/temperature".to_string(), u32::MAX),
+                ("site/4242/#".to_string(), 4242),
+                ("site/4242/temperature".to_string(), 4242),
+            ]
+        );
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::qos::QoSCode;
+use crate::protocol::common::topic_filter::TopicFilter;
+use crate::protocol::common::topic_name::TopicName;
+use crate::protocol::mqtt4::payload_parser::sub_ack_parser::payload::{
+    SubAckPayload, SubAckReturnCode,
+};
+use crate::protocol::mqtt4::payload_parser::subscribe_parser::payload::SubscribePayload;
+use crate::protocol::mqtt4::payload_parser::unsubscribe_parser::payload::UnSubscribePayload;
+use crate::protocol::topic_matching::topic_matcher;
+use std::collections::BTreeMap;
+
+/// The subscriptions of one client with the QoS granted to each. A server grants the requested
+/// QoS up to `maximum_qos` and refuses invalid topic filters with a Failure return code.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionRegistry {
+    maximum_qos: QoSCode,
+    subscriptions: BTreeMap<String, QoSCode>,
+}
+
+impl Default for SubscriptionRegistry {
+    fn default() -> Self {
+        SubscriptionRegistry {
+            maximum_qos: QoSCode::Qos2,
+            subscriptions: BTreeMap::new(),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl SubscriptionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry granting no more than `maximum_qos` [MQTT-3.9.3-2].
+    pub fn with_maximum_qos(maximum_qos: QoSCode) -> Self {
+        SubscriptionRegistry {
+            maximum_qos,
+            ..Self::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+
+    /// The QoS granted to `topic_filter`, if the client subscribed to exactly this filter.
+    pub fn granted_qos(&self, topic_filter: &str) -> Option<QoSCode> {
+        self.subscriptions.get(topic_filter).copied()
+    }
+
+    /// Every subscription with its granted QoS, sorted by topic filter.
+    pub fn subscriptions(&self) -> impl Iterator<Item = (&str, QoSCode)> {
+        self.subscriptions
+            .iter()
+            .map(|(topic_filter, qos)| (topic_filter.as_str(), *qos))
+    }
+
+    /// Adds every topic filter of a SUBSCRIBE and answers with one return code per filter, in
+    /// the same order [MQTT-3.9.3-1]. A filter the client is already subscribed to gets the new
+    /// QoS [MQTT-3.8.4-3].
+    pub fn subscribe(&mut self, payload: &SubscribePayload) -> SubAckPayload {
+        let return_codes = payload
+            .subscription_and_qos_tuples()
+            .iter()
+            .map(|(topic_filter, qos)| {
+                if TopicFilter::verify(topic_filter).is_err() {
+                    return SubAckReturnCode::Failure;
+                }
+                let granted = (*qos).min(self.maximum_qos);
+                self.subscriptions.insert(topic_filter.clone(), granted);
+                SubAckReturnCode::from(granted)
+            })
+            .collect();
+        SubAckPayload::new(return_codes)
+    }
+
+    /// Removes the topic filters of an UNSUBSCRIBE that match a subscription character for
+    /// character [MQTT-3.10.4-1], returning how many were removed.
+    pub fn unsubscribe(&mut self, payload: &UnSubscribePayload) -> usize {
+        payload
+            .topics()
+            .iter()
+            .filter(|topic_filter| self.subscriptions.remove(topic_filter.as_str()).is_some())
+            .count()
+    }
+
+    /// The QoS to deliver a PUBLISH on `topic_name` at, or `None` when no subscription matches.
+    /// Overlapping subscriptions deliver the message once, at the highest QoS any of them was
+    /// granted, but never above the QoS it was published with [MQTT-3.3.5-1].
+    pub fn delivery_qos(&self, topic_name: &TopicName, publish_qos: QoSCode) -> Option<QoSCode> {
+        self.subscriptions
+            .iter()
+            .filter(|(topic_filter, _)| {
+                topic_matcher::matches_str(topic_filter, topic_name.as_str())
+            })
+            .map(|(_, granted)| *granted)
+            .max()
+            .map(|granted| granted.min(publish_qos))
+    }
+}
+
+#[cfg(test)]
+mod subscription_registry_tests {
+    use crate::protocol::common::qos::QoSCode;
+    use crate::protocol::common::topic_name::TopicName;
+    use crate::protocol::mqtt4::payload_parser::sub_ack_parser::payload::SubAckReturnCode;
+    use crate::protocol::mqtt4::payload_parser::subscribe_parser::payload::SubscribePayload;
+    use crate::protocol::mqtt4::payload_parser::unsubscribe_parser::payload::UnSubscribePayload;
+    use crate::protocol::topic_matching::subscription_registry::SubscriptionRegistry;
+
+    fn subscribe(
+        registry: &mut SubscriptionRegistry,
+        subscriptions: &[(&str, QoSCode)],
+    ) -> Vec<SubAckReturnCode> {
+        let payload = SubscribePayload::new(
+            subscriptions
+                .iter()
+                .map(|(topic_filter, qos)| (topic_filter.to_string(), *qos))
+                .collect(),
+        );
+        registry.subscribe(&payload).return_codes().clone()
+    }
+
+    fn delivery_qos(
+        registry: &SubscriptionRegistry,
+        topic_name: &str,
+        publish_qos: QoSCode,
+    ) -> Option<QoSCode> {
+        registry.delivery_qos(&TopicName::new(topic_name).unwrap(), publish_qos)
+    }
+
+    #[test]
+    fn subscribe_should_answer_each_filter_in_order() {
+        let mut registry = SubscriptionRegistry::with_maximum_qos(QoSCode::Qos1);
+        assert_eq!(
+            subscribe(
+                &mut registry,
+                &[
+                    ("a/#", QoSCode::Qos0),
+                    ("a/#/b", QoSCode::Qos1),
+                    ("a/b", QoSCode::Qos2),
+                ]
+            ),
+            vec![
+                SubAckReturnCode::Qos0,
+                SubAckReturnCode::Failure,
+                SubAckReturnCode::Qos1,
+            ]
+        );
+        assert_eq!(registry.len(), 2);
+
+        // subscribing to the same filter again replaces the subscription
+        assert_eq!(
+            subscribe(&mut registry, &[("a/#", QoSCode::Qos1)]),
+            vec![SubAckReturnCode::Qos1]
+        );
+        assert_eq!(
+            registry.subscriptions().collect::<Vec<_>>(),
+            vec![("a/#", QoSCode::Qos1), ("a/b", QoSCode::Qos1)]
+        );
+    }
+
+    #[test]
+    fn unsubscribe_should_only_remove_identical_filters() {
+        let mut registry = SubscriptionRegistry::new();
+        subscribe(
+            &mut registry,
+            &[("a/+", QoSCode::Qos1), ("a/b", QoSCode::Qos2)],
+        );
+        let payload = UnSubscribePayload::new(vec!["a/#".to_string(), "a/+".to_string()]);
+        assert_eq!(registry.unsubscribe(&payload), 1);
+        assert_eq!(registry.granted_qos("a/+"), None);
+        assert_eq!(registry.granted_qos("a/b"), Some(QoSCode::Qos2));
+    }
+
+    #[test]
+    fn overlapping_subscriptions_should_deliver_once_at_the_highest_granted_qos() {
+        let mut registry = SubscriptionRegistry::new();
+        subscribe(
+            &mut registry,
+            &[("a/#", QoSCode::Qos0), ("a/b", QoSCode::Qos2)],
+        );
+
+        assert_eq!(
+            delivery_qos(&registry, "a/b", QoSCode::Qos2),
+            Some(QoSCode::Qos2)
+        );
+        assert_eq!(
+            delivery_qos(&registry, "a/b", QoSCode::Qos1),
+            Some(QoSCode::Qos1)
+        );
+        assert_eq!(
+            delivery_qos(&registry, "a/c", QoSCode::Qos2),
+            Some(QoSCode::Qos0)
+        );
+        assert_eq!(delivery_qos(&registry, "b", QoSCode::Qos0), None);
+
+        subscribe(&mut registry, &[("#", QoSCode::Qos1)]);
+        assert_eq!(delivery_qos(&registry, "$SYS/uptime", QoSCode::Qos1), None);
+        assert_eq!(
+            delivery_qos(&registry, "b", QoSCode::Qos2),
+            Some(QoSCode::Qos1)
+        );
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::protocol::common::topic_filter::TopicFilter;
+use crate::protocol::common::topic_name::{
+    MULTI_LEVEL_WILDCARD, SINGLE_LEVEL_WILDCARD, TOPIC_LEVEL_SEPARATOR, TopicName,
+};
+
+pub(crate) const SINGLE_LEVEL: &str = "+";
+pub(crate) const MULTI_LEVEL: &str = "#";
+
+/// Whether `topic_name` matches `topic_filter` under the MQTT 3.1.1 rules:
+///
+/// - `+` matches exactly one level, which may be empty;
+/// - `#` matches any number of levels including the parent, so `sport/#` matches `sport`;
+/// - a filter starting with a wildcard never matches a topic name starting with `$`.
+#[allow(dead_code)]
+pub fn matches(topic_filter: &TopicFilter, topic_name: &TopicName) -> bool {
+    matches_str(topic_filter.as_str(), topic_name.as_str())
+}
+
+/// `matches` for strings already known to be a valid filter and a valid topic name.
+pub(crate) fn matches_str(topic_filter: &str, topic_name: &str) -> bool {
+    if topic_name.starts_with('$')
+        && topic_filter.starts_with([SINGLE_LEVEL_WILDCARD, MULTI_LEVEL_WILDCARD])
+    {
+        return false;
+    }
+
+    let mut filter_levels = topic_filter.split(TOPIC_LEVEL_SEPARATOR);
+    let mut name_levels = topic_name.split(TOPIC_LEVEL_SEPARATOR);
+    loop {
+        match (filter_levels.next(), name_levels.next()) {
+            (Some(MULTI_LEVEL), _) => return true,
+            (Some(SINGLE_LEVEL), Some(_)) => continue,
+            (Some(filter_level), Some(name_level)) if filter_level == name_level => continue,
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl TopicFilter {
+    pub fn matches(&self, topic_name: &TopicName) -> bool {
+        matches(self, topic_name)
+    }
+}
+
+#[cfg(test)]
+mod topic_matcher_tests {
+    use crate::protocol::common::topic_filter::TopicFilter;
+    use crate::protocol::common::topic_name::TopicName;
+    use crate::protocol::topic_matching::topic_matcher;
+
+    fn matches(filter: &str, name: &str) -> bool {
+        topic_matcher::matches(
+            &TopicFilter::new(filter).unwrap(),
+            &TopicName::new(name).unwrap(),
+        )
+    }
+
+    #[test]
+    fn matcher_should_match_exact_topics() {
+        assert!(matches("sport/tennis", "sport/tennis"));
+        assert!(!matches("sport/tennis", "sport/tennis/player1"));
+        assert!(!matches("sport/tennis", "sport"));
+        assert!(!matches("sport", "Sport"));
+        assert!(matches("/", "/"));
+        assert!(!matches("a/", "a"));
+    }
+
+    #[test]
+    fn matcher_should_follow_multi_level_wildcard_rules() {
+        assert!(matches("sport/tennis/player1/#", "sport/tennis/player1"));
+        assert!(matches(
+            "sport/tennis/player1/#",
+            "sport/tennis/player1/ranking"
+        ));
+        assert!(matches(
+            "sport/tennis/player1/#",
+            "sport/tennis/player1/score/wimbledon"
+        ));
+        assert!(matches("sport/#", "sport"));
+        assert!(matches("#", "sport/tennis"));
+        assert!(matches("#", "/"));
+        assert!(!matches("sport/tennis/#", "sport/tenn"));
+    }
+
+    #[test]
+    fn matcher_should_follow_single_level_wildcard_rules() {
+        assert!(matches("sport/tennis/+", "sport/tennis/player1"));
+        assert!(!matches("sport/tennis/+", "sport/tennis/player1/ranking"));
+        assert!(!matches("sport/+", "sport"));
+        assert!(matches("sport/+", "sport/"));
+        assert!(matches("+", "sport"));
+        assert!(!matches("+", "/finance"));
+        assert!(matches("+/+", "/finance"));
+        assert!(matches("/+", "/finance"));
+        assert!(matches("+/tennis/#", "sport/tennis"));
+    }
+
+    #[test]
+    fn matcher_should_not_match_system_topics_with_leading_wildcards() {
+        assert!(!matches("#", "$SYS/uptime"));
+        assert!(!matches("+/monitor/Clients", "$SYS/monitor/Clients"));
+        assert!(matches("$SYS/#", "$SYS/monitor/Clients"));
+        assert!(matches("$SYS/monitor/+", "$SYS/monitor/Clients"));
+        assert!(matches("a/#", "a/$b"));
+    }
+}
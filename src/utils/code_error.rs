@@ -13,7 +13,7 @@
 // limitations under the License.
 
 #[derive(Debug, thiserror::Error)]
-pub(crate) enum CodeError {
+pub enum CodeError {
     #[error("Code length error: expected {0}, got {1}")]
     CodeLengthError(usize, usize),
 
@@ -25,4 +25,13 @@ pub(crate) enum CodeError {
 
     #[error("Invalid Code: {0} in MQTT Protocol")]
     MQTTInvalidCode(u32),
+
+    #[error("Varint is longer than 10 bytes")]
+    VarintOverflow,
+
+    #[error("Protobuf wire type {0} is not supported")]
+    UnsupportedWireType(u8),
+
+    #[error("Invalid hex digit at position {0}")]
+    InvalidHexDigit(usize),
 }
@@ -0,0 +1,118 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[allow(dead_code)]
+pub(crate) mod hex_handler {
+    use crate::utils::code_error::CodeError;
+
+    pub(crate) fn encode(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Strict counterpart of `encode`: an even number of hex digits in either case, nothing else.
+    pub(crate) fn decode(text: &str) -> Result<Vec<u8>, CodeError> {
+        if !text.len().is_multiple_of(2) {
+            return Err(CodeError::CodeLengthError(text.len() + 1, text.len()));
+        }
+        text.as_bytes()
+            .chunks(2)
+            .enumerate()
+            .map(|(index, pair)| {
+                let high = hex_digit(pair[0]).ok_or(CodeError::InvalidHexDigit(index * 2))?;
+                let low = hex_digit(pair[1]).ok_or(CodeError::InvalidHexDigit(index * 2 + 1))?;
+                Ok((high << 4) | low)
+            })
+            .collect()
+    }
+
+    fn hex_digit(character: u8) -> Option<u8> {
+        (character as char).to_digit(16).map(|digit| digit as u8)
+    }
+
+    /// `#[serde(with = ...)]` adapter writing binary fields as hex strings.
+    #[cfg(feature = "serde")]
+    pub(crate) mod hex {
+        use bytes::Bytes;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub(crate) fn serialize<S: Serializer>(
+            value: &Bytes,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&super::encode(value))
+        }
+
+        pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Bytes, D::Error> {
+            let text = String::deserialize(deserializer)?;
+            super::decode(&text)
+                .map(Bytes::from)
+                .map_err(serde::de::Error::custom)
+        }
+    }
+
+    /// `#[serde(with = ...)]` adapter writing optional binary fields as hex strings or `null`.
+    #[cfg(feature = "serde")]
+    pub(crate) mod optional_hex {
+        use bytes::Bytes;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub(crate) fn serialize<S: Serializer>(
+            value: &Option<Bytes>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match value {
+                Some(bytes) => serializer.serialize_str(&super::encode(bytes)),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Bytes>, D::Error> {
+            let text: Option<String> = Option::deserialize(deserializer)?;
+            text.map(|text| super::decode(&text).map(Bytes::from))
+                .transpose()
+                .map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod hex_tests {
+    use crate::utils::code_error::CodeError;
+    use crate::utils::hex::hex_handler;
+
+    #[test]
+    fn hex_should_round_trip_bytes() {
+        let bytes = [0x00, 0x10, 0xAB, 0xFF];
+        let text = hex_handler::encode(&bytes);
+        assert_eq!(text, "0010abff");
+        assert_eq!(hex_handler::decode(&text).unwrap(), bytes);
+        assert_eq!(hex_handler::decode("ABff").unwrap(), [0xAB, 0xFF]);
+    }
+
+    #[test]
+    fn hex_decode_should_reject_odd_length_and_bad_digits() {
+        assert!(matches!(
+            hex_handler::decode("abc"),
+            Err(CodeError::CodeLengthError(4, 3))
+        ));
+        assert!(matches!(
+            hex_handler::decode("0g"),
+            Err(CodeError::InvalidHexDigit(1))
+        ));
+    }
+}
@@ -13,6 +13,8 @@
 // limitations under the License.
 
 pub(crate) mod code_error;
+pub(crate) mod hex;
 pub(crate) mod mqtt_utils;
+pub(crate) mod protobuf;
 pub(crate) mod radix;
 pub(crate) mod utf;
@@ -13,21 +13,37 @@
 // limitations under the License.
 
 use crate::byte_adapter::byte_operations::ByteOperations;
+use crate::protocol::common::packet_id::PacketId;
 use crate::protocol::mqtt_protocol_error::MqttProtocolError;
 use crate::utils::radix::radix_handler;
+use bytes::Bytes;
 
 #[allow(dead_code)]
 #[inline]
 pub(crate) fn parse_packet_identifier(
     bytes: &mut impl ByteOperations,
-) -> Result<u16, MqttProtocolError> {
+) -> Result<PacketId, MqttProtocolError> {
     let byte = bytes.read_bytes(2);
-    Ok(radix_handler::be_bytes_to_u16(byte.as_slice())?)
+    PacketId::parse(radix_handler::be_bytes_to_u16(byte.as_slice())?)
+}
+
+/// Binary Data is a two byte length followed by that many bytes, with no UTF-8 rules applied.
+#[allow(dead_code)]
+pub(crate) fn parse_binary_data(
+    bytes: &mut impl ByteOperations,
+) -> Result<Bytes, MqttProtocolError> {
+    let length_bytes = bytes.read_bytes(2);
+    let length = radix_handler::be_bytes_to_u16(length_bytes.as_slice())? as usize;
+    if bytes.bytes_len() < length {
+        return Err(MqttProtocolError::PacketTooShort);
+    }
+    Ok(Bytes::from(bytes.read_bytes(length)))
 }
 
 #[cfg(test)]
 mod mqtt_utils_tests {
     use crate::byte_adapter::byte_operations::ByteOperations;
+    use crate::protocol::mqtt_protocol_error::MqttProtocolError;
     use crate::utils::mqtt_utils;
     use bytes::BytesMut;
 
@@ -39,6 +55,39 @@ mod mqtt_utils_tests {
 
         let packet_identifier = mqtt_utils::parse_packet_identifier(&mut bytes).unwrap();
 
-        assert_eq!(packet_identifier, 0x1234);
+        assert_eq!(packet_identifier.get(), 0x1234);
+    }
+
+    #[test]
+    fn mqtt_utils_should_reject_packet_identifier_zero() {
+        let mut bytes = BytesMut::new();
+        bytes.write_bytes(&[0x00, 0x00]);
+
+        let result = mqtt_utils::parse_packet_identifier(&mut bytes);
+
+        assert!(matches!(
+            result,
+            Err(MqttProtocolError::ZeroPacketIdentifier)
+        ));
+    }
+
+    #[test]
+    fn mqtt_utils_should_parse_binary_data_without_utf_8_checks() {
+        let mut bytes = BytesMut::new();
+        bytes.write_bytes(&[0x00, 0x03, 0x00, 0xFF, 0x08]);
+
+        let binary_data = mqtt_utils::parse_binary_data(&mut bytes).unwrap();
+
+        assert_eq!(binary_data.as_ref(), &[0x00, 0xFF, 0x08]);
+    }
+
+    #[test]
+    fn mqtt_utils_should_fail_when_binary_data_is_truncated() {
+        let mut bytes = BytesMut::new();
+        bytes.write_bytes(&[0x00, 0x03, 0x01]);
+
+        let result = mqtt_utils::parse_binary_data(&mut bytes);
+
+        assert!(matches!(result, Err(MqttProtocolError::PacketTooShort)));
     }
 }
@@ -0,0 +1,190 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[allow(dead_code)]
+pub(crate) mod protobuf_handler {
+    use crate::byte_adapter::byte_operations::ByteOperations;
+    use crate::utils::code_error::CodeError;
+
+    pub(crate) const WIRE_TYPE_VARINT: u8 = 0;
+    pub(crate) const WIRE_TYPE_FIXED64: u8 = 1;
+    pub(crate) const WIRE_TYPE_LENGTH_DELIMITED: u8 = 2;
+    pub(crate) const WIRE_TYPE_FIXED32: u8 = 5;
+
+    const MAX_VARINT_BYTES: usize = 10;
+
+    pub(crate) fn read_varint(byte_opts: &mut impl ByteOperations) -> Result<u64, CodeError> {
+        let mut value: u64 = 0;
+        for index in 0..MAX_VARINT_BYTES {
+            let byte = byte_opts
+                .read_a_byte()
+                .ok_or(CodeError::CodeLengthError(index + 1, index))?;
+            value |= ((byte & 0x7F) as u64) << (7 * index);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(CodeError::VarintOverflow)
+    }
+
+    pub(crate) fn write_varint(byte_opts: &mut impl ByteOperations, mut value: u64) {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                byte_opts.write_a_byte(byte);
+                return;
+            }
+            byte_opts.write_a_byte(byte | 0x80);
+        }
+    }
+
+    /// returns (field number, wire type)
+    pub(crate) fn read_tag(byte_opts: &mut impl ByteOperations) -> Result<(u32, u8), CodeError> {
+        let tag = read_varint(byte_opts)?;
+        let wire_type = (tag & 0x07) as u8;
+        Ok(((tag >> 3) as u32, wire_type))
+    }
+
+    pub(crate) fn write_tag(byte_opts: &mut impl ByteOperations, field_number: u32, wire_type: u8) {
+        write_varint(byte_opts, ((field_number as u64) << 3) | wire_type as u64);
+    }
+
+    pub(crate) fn read_fixed32(byte_opts: &mut impl ByteOperations) -> Result<u32, CodeError> {
+        let bytes = read_exact(byte_opts, 4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub(crate) fn write_fixed32(byte_opts: &mut impl ByteOperations, value: u32) {
+        byte_opts.write_bytes(&value.to_le_bytes());
+    }
+
+    pub(crate) fn read_fixed64(byte_opts: &mut impl ByteOperations) -> Result<u64, CodeError> {
+        let bytes = read_exact(byte_opts, 8)?;
+        let mut array = [0u8; 8];
+        array.copy_from_slice(&bytes);
+        Ok(u64::from_le_bytes(array))
+    }
+
+    pub(crate) fn write_fixed64(byte_opts: &mut impl ByteOperations, value: u64) {
+        byte_opts.write_bytes(&value.to_le_bytes());
+    }
+
+    pub(crate) fn read_length_delimited(
+        byte_opts: &mut impl ByteOperations,
+    ) -> Result<Vec<u8>, CodeError> {
+        let length = read_varint(byte_opts)?;
+        let length = usize::try_from(length)
+            .map_err(|_| CodeError::UsizeConversionError(usize::MAX, "usize"))?;
+        read_exact(byte_opts, length)
+    }
+
+    pub(crate) fn write_length_delimited(byte_opts: &mut impl ByteOperations, bytes: &[u8]) {
+        write_varint(byte_opts, bytes.len() as u64);
+        byte_opts.write_bytes(bytes);
+    }
+
+    pub(crate) fn read_string(byte_opts: &mut impl ByteOperations) -> Result<String, CodeError> {
+        let bytes = read_length_delimited(byte_opts)?;
+        String::from_utf8(bytes).map_err(|_| CodeError::UTF8DecodingError)
+    }
+
+    /// unknown fields are skipped so newer peers can add fields without breaking us
+    pub(crate) fn skip_field(
+        byte_opts: &mut impl ByteOperations,
+        wire_type: u8,
+    ) -> Result<(), CodeError> {
+        match wire_type {
+            WIRE_TYPE_VARINT => read_varint(byte_opts).map(|_| ()),
+            WIRE_TYPE_FIXED64 => read_exact(byte_opts, 8).map(|_| ()),
+            WIRE_TYPE_LENGTH_DELIMITED => read_length_delimited(byte_opts).map(|_| ()),
+            WIRE_TYPE_FIXED32 => read_exact(byte_opts, 4).map(|_| ()),
+            _ => Err(CodeError::UnsupportedWireType(wire_type)),
+        }
+    }
+
+    fn read_exact(
+        byte_opts: &mut impl ByteOperations,
+        length: usize,
+    ) -> Result<Vec<u8>, CodeError> {
+        let bytes = byte_opts.read_bytes(length);
+        if bytes.len() != length {
+            return Err(CodeError::CodeLengthError(length, bytes.len()));
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod protobuf_tests {
+    use crate::utils::code_error::CodeError;
+    use crate::utils::protobuf::protobuf_handler;
+    use bytes::BytesMut;
+
+    #[test]
+    fn protobuf_handler_should_encode_and_decode_varint() {
+        for value in [0u64, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut bytes_mut = BytesMut::new();
+            protobuf_handler::write_varint(&mut bytes_mut, value);
+            assert_eq!(
+                protobuf_handler::read_varint(&mut bytes_mut).unwrap(),
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn protobuf_handler_should_encode_300_as_two_bytes() {
+        let mut bytes_mut = BytesMut::new();
+        protobuf_handler::write_varint(&mut bytes_mut, 300);
+        assert_eq!(bytes_mut.as_ref(), &[0xAC, 0x02]);
+    }
+
+    #[test]
+    fn protobuf_handler_should_reject_varint_longer_than_10_bytes() {
+        let mut bytes_mut = BytesMut::from(&[0xFFu8; 11][..]);
+        let result = protobuf_handler::read_varint(&mut bytes_mut);
+        assert!(matches!(result, Err(CodeError::VarintOverflow)));
+    }
+
+    #[test]
+    fn protobuf_handler_should_read_tag() {
+        let mut bytes_mut = BytesMut::new();
+        protobuf_handler::write_tag(
+            &mut bytes_mut,
+            15,
+            protobuf_handler::WIRE_TYPE_LENGTH_DELIMITED,
+        );
+        let (field_number, wire_type) = protobuf_handler::read_tag(&mut bytes_mut).unwrap();
+        assert_eq!(field_number, 15);
+        assert_eq!(wire_type, protobuf_handler::WIRE_TYPE_LENGTH_DELIMITED);
+    }
+
+    #[test]
+    fn protobuf_handler_should_skip_unknown_fields() {
+        let mut bytes_mut = BytesMut::new();
+        protobuf_handler::write_length_delimited(&mut bytes_mut, b"skip me");
+        protobuf_handler::write_fixed32(&mut bytes_mut, 7);
+        protobuf_handler::skip_field(&mut bytes_mut, protobuf_handler::WIRE_TYPE_LENGTH_DELIMITED)
+            .unwrap();
+        assert_eq!(protobuf_handler::read_fixed32(&mut bytes_mut).unwrap(), 7);
+    }
+
+    #[test]
+    fn protobuf_handler_should_fail_on_truncated_length_delimited_field() {
+        let mut bytes_mut = BytesMut::from(&[0x05u8, b'a', b'b'][..]);
+        let result = protobuf_handler::read_length_delimited(&mut bytes_mut);
+        assert!(matches!(result, Err(CodeError::CodeLengthError(5, 2))));
+    }
+}
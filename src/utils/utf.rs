@@ -27,7 +27,7 @@ pub(crate) mod utf_8_handler {
         Ok(utf8_string)
     }
 
-    pub(super) fn decode_length(byte_opts: &mut impl ByteOperations) -> Result<u16, CodeError> {
+    pub(crate) fn decode_length(byte_opts: &mut impl ByteOperations) -> Result<u16, CodeError> {
         let length_bytes = byte_opts.read_bytes(2);
         let utf_8_length = calculate_mqtt_str_length(length_bytes)?;
         Ok(utf_8_length)
@@ -51,7 +51,7 @@ pub(crate) mod utf_8_handler {
     }
 
     /// we don't verify 0xD800..=0xDFFF, because rust string already do that
-    pub(super) fn verify_for_mqtt(string_bytes: &[u8]) -> Result<(), CodeError> {
+    pub(crate) fn verify_for_mqtt(string_bytes: &[u8]) -> Result<(), CodeError> {
         let str = std::str::from_utf8(string_bytes).map_err(|_| CodeError::UTF8DecodingError)?;
         const FORBIDDEN_CHAR_FOR_MQTT: &[RangeInclusive<u32>] = &[
             0x0000..=0x0000, // null
@@ -74,7 +74,7 @@ pub(crate) mod utf_8_handler {
         Ok(())
     }
 
-    pub(super) fn decode_utf8(string_bytes: Vec<u8>) -> Result<String, CodeError> {
+    pub(crate) fn decode_utf8(string_bytes: Vec<u8>) -> Result<String, CodeError> {
         let utf8_string =
             String::from_utf8(string_bytes).map_err(|_| CodeError::UTF8DecodingError)?;
         Ok(utf8_string)